@@ -64,6 +64,12 @@ parser.add_argument("-t", "--table_num", type=int,
                     default=DEFAULT_TABLE_NUM, help="The number of table to seed in catalog.")
 parser.add_argument("-r", "--rate", type=int,
                     default=DEFAULT_RATE, help="Request rate.")
+parser.add_argument("-c", "--codec", type=str, choices=["json", "msgpack"],
+                    default="json", help="Codec of stored values.")
+parser.add_argument("-z", "--compression", type=str, choices=["none", "lz4", "zstd"],
+                    default="none", help="Compression of stored values.")
+parser.add_argument("--columns", type=int, default=0,
+                    help="The number of columns of each seeded table, to grow table metadata.")
 parser.add_argument("-p", "--plot", action="store_true",
                     default=False, help="Generate a plot of this benchmark.")
 args = parser.parse_args()
@@ -76,11 +82,17 @@ run(f"rm -rf {TEST_ROOT_DIR} && mkdir {TEST_ROOT_DIR}",
     note="initializing test dir")
 run(f"cargo build --release && cp target/release/{args.binary_name} {TEST_ROOT_DIR}/{args.binary_name}",
     note="building catalog in release mode")
-catalog_server = run(f"{TEST_ROOT_DIR}/{args.binary_name} --db-root {args.db_root}",
+catalog_server = run(f"CATALOG_VALUE_CODEC={args.codec} CATALOG_VALUE_COMPRESSION={args.compression} "
+                     f"{TEST_ROOT_DIR}/{args.binary_name} --db-root {args.db_root}",
                      note="starting catalog server", bg=True, out=CATALOG_LOG)
 print("Waiting for catalog server to start...")
 time.sleep(1)
 
+# columns of every seeded table, empty unless --columns is given
+columns = [{'name': f"column_{i}", 'aggregates': {'count': i}, 'value_range': [0, i],
+            'is_strong_key': i == 0, 'is_weak_key': False, 'primary_key_col_name': "column_0"}
+           for i in range(args.columns)]
+
 # seeding the catalog, uniformly distribute tables to namespaces
 print(f"Seeding namespaces and tables...")
 NAMESPACE_ENDPOINT = "namespaces"
@@ -102,7 +114,7 @@ for i in range(args.namespace_num):
     for table in tables:
         response = requests.post(
             f"{args.base_url}/{NAMESPACE_ENDPOINT}/{namespace}/{TABLE_ENDPOINT}",
-            json={'name': table}
+            json={'name': table, 'columns': columns or None}
         )
         assert response.status_code == 201, f"Failed to create table in {namespace}"
 
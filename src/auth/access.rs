@@ -0,0 +1,154 @@
+use crate::auth::authenticator::Principal;
+use crate::config::config::AuthConfig;
+use crate::database::database::Database;
+use crate::dto::acl::{Grant, Privilege, Resource};
+use crate::repository::acl::AclRepository;
+use axum::http::StatusCode;
+use std::io;
+use std::sync::{Arc, Mutex};
+
+/// Caller is the principal of a request together with the grants it holds,
+/// available to handlers as a request extension.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Caller {
+    pub principal: Principal,
+    /// `None` when access control does not restrict the caller.
+    grants: Option<Vec<Grant>>,
+}
+
+impl Caller {
+    /// A caller allowed everything, as every caller is with RBAC disabled.
+    pub fn unrestricted(principal: Principal) -> Self {
+        Self {
+            principal,
+            grants: None,
+        }
+    }
+
+    pub fn with_grants(principal: Principal, grants: Vec<Grant>) -> Self {
+        Self {
+            principal,
+            grants: Some(grants),
+        }
+    }
+
+    pub fn allows(&self, privilege: Privilege, resource: Resource) -> bool {
+        match &self.grants {
+            None => true,
+            Some(grants) => grants
+                .iter()
+                .any(|grant| grant.privilege == privilege && grant.scope.covers(&resource)),
+        }
+    }
+
+    /// Fails with 403 Forbidden unless the caller holds the privilege.
+    pub fn require(
+        &self,
+        privilege: Privilege,
+        resource: Resource,
+    ) -> Result<(), (StatusCode, String)> {
+        if self.allows(privilege, resource) {
+            return Ok(());
+        }
+        Err((
+            StatusCode::FORBIDDEN,
+            format!(
+                "Error: {} lacks {:?} on {}",
+                self.principal,
+                privilege,
+                describe(&resource)
+            ),
+        ))
+    }
+}
+
+fn describe(resource: &Resource) -> String {
+    match resource {
+        Resource::Catalog => "the catalog".to_string(),
+        Resource::Namespace(namespace) => format!("namespace {}", namespace.0.join(".")),
+        Resource::Table(table) => {
+            format!("table {}.{}", table.namespace.0.join("."), table.name)
+        }
+    }
+}
+
+/// AccessControl resolves the grants of authenticated principals.
+pub struct AccessControl {
+    enabled: bool,
+    admins: Vec<String>,
+    repo: AclRepository,
+}
+
+impl AccessControl {
+    pub fn new(config: &AuthConfig, database: Arc<Mutex<Database>>) -> Self {
+        Self {
+            enabled: config.enabled && config.rbac,
+            admins: config.admins.clone(),
+            repo: AclRepository::new(database),
+        }
+    }
+
+    pub fn caller(&self, principal: Principal) -> io::Result<Caller> {
+        if !self.enabled || self.admins.contains(&principal.name) {
+            return Ok(Caller::unrestricted(principal));
+        }
+        let grants = self.repo.grants_of(&principal.name)?;
+        Ok(Caller::with_grants(principal, grants))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::authenticator::AuthMethod;
+    use crate::dto::acl::GrantScope;
+    use crate::dto::namespace_data::NamespaceIdent;
+    use tempfile::tempdir;
+
+    fn principal(name: &str) -> Principal {
+        Principal {
+            name: name.to_string(),
+            method: AuthMethod::ApiToken,
+        }
+    }
+
+    #[test]
+    fn test_caller_grants() {
+        let dir = tempdir().unwrap();
+        let db = Arc::new(Mutex::new(Database::open(dir.path()).unwrap()));
+        let repo = AclRepository::new(db.clone());
+        let sales = NamespaceIdent(vec!["sales".to_string()]);
+        repo.create_role("sales-reader").unwrap();
+        repo.grant(
+            "sales-reader",
+            Grant {
+                privilege: Privilege::ReadMetadata,
+                scope: GrantScope::Namespace {
+                    namespace: sales.clone(),
+                },
+            },
+        )
+        .unwrap();
+        repo.assign_role("alice", "sales-reader").unwrap();
+        let config = AuthConfig {
+            enabled: true,
+            rbac: true,
+            admins: vec!["root".to_string()],
+            ..Default::default()
+        };
+        let access = AccessControl::new(&config, db);
+
+        let alice = access.caller(principal("alice")).unwrap();
+        assert!(alice.allows(Privilege::ReadMetadata, Resource::Namespace(&sales)));
+        assert!(!alice.allows(Privilege::Drop, Resource::Namespace(&sales)));
+        let (status, _) = alice
+            .require(Privilege::ReadMetadata, Resource::Catalog)
+            .unwrap_err();
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let bob = access.caller(principal("bob")).unwrap();
+        assert!(!bob.allows(Privilege::ReadMetadata, Resource::Namespace(&sales)));
+        let root = access.caller(principal("root")).unwrap();
+        assert!(root.allows(Privilege::ManageGrants, Resource::Catalog));
+    }
+}
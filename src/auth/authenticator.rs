@@ -0,0 +1,293 @@
+use crate::config::config::AuthConfig;
+use crate::dto::oauth::{TokenRequest, TokenResponse};
+use crate::server::tls::ClientCertificate;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{self, ErrorKind};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Issuer of the JWTs signed by this server.
+pub const JWT_ISSUER: &str = "catalog1";
+
+/// How a caller proved its identity.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum AuthMethod {
+    /// Authentication is disabled.
+    Anonymous,
+    ApiToken,
+    Jwt,
+    ClientCertificate,
+}
+
+/// Principal is the authenticated caller of a request, available to
+/// handlers as a request extension.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    pub name: String,
+    pub method: AuthMethod,
+}
+
+impl Principal {
+    pub fn anonymous() -> Self {
+        Self {
+            name: "anonymous".to_string(),
+            method: AuthMethod::Anonymous,
+        }
+    }
+}
+
+impl fmt::Display for Principal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Claims {
+    sub: String,
+    iss: String,
+    iat: u64,
+    exp: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scope: Option<String>,
+}
+
+/// Authenticator checks bearer tokens and issues JWTs for the client
+/// credentials flow.
+pub struct Authenticator {
+    config: AuthConfig,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Compares secrets in time independent of where they differ.
+fn secrets_match(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+fn unauthorized(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::PermissionDenied, message.to_string())
+}
+
+impl Authenticator {
+    pub fn new(config: AuthConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Resolves the caller from the value of an `Authorization` header.
+    /// Every caller is anonymous while authentication is disabled.
+    pub fn authenticate(&self, authorization: Option<&str>) -> io::Result<Principal> {
+        if !self.config.enabled {
+            return Ok(Principal::anonymous());
+        }
+        let token = authorization
+            .and_then(|value| {
+                value
+                    .strip_prefix("Bearer ")
+                    .or_else(|| value.strip_prefix("bearer "))
+            })
+            .map(str::trim)
+            .ok_or_else(|| unauthorized("Missing bearer token"))?;
+
+        if let Some(api_token) = self
+            .config
+            .tokens
+            .iter()
+            .find(|api_token| secrets_match(&api_token.token, token))
+        {
+            return Ok(Principal {
+                name: api_token.name.clone(),
+                method: AuthMethod::ApiToken,
+            });
+        }
+
+        let secret = self
+            .config
+            .jwt_secret
+            .as_ref()
+            .ok_or_else(|| unauthorized("Invalid bearer token"))?;
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.set_issuer(&[JWT_ISSUER]);
+        let claims = jsonwebtoken::decode::<Claims>(
+            token,
+            &DecodingKey::from_secret(secret.as_bytes()),
+            &validation,
+        )
+        .map_err(|e| unauthorized(&format!("Invalid bearer token: {}", e)))?
+        .claims;
+        Ok(Principal {
+            name: claims.sub,
+            method: AuthMethod::Jwt,
+        })
+    }
+
+    /// Resolves a caller that presented a verified TLS client certificate
+    /// to the principal named by its subject.
+    pub fn authenticate_certificate(&self, certificate: &ClientCertificate) -> Principal {
+        if !self.config.enabled {
+            return Principal::anonymous();
+        }
+        Principal {
+            name: certificate.principal_name().to_string(),
+            method: AuthMethod::ClientCertificate,
+        }
+    }
+
+    /// Issues a signed JWT to a client presenting valid client credentials.
+    pub fn issue_token(&self, request: &TokenRequest) -> io::Result<TokenResponse> {
+        if request.grant_type != "client_credentials" {
+            return Err(io::Error::new(
+                ErrorKind::Unsupported,
+                format!("Unsupported grant type {}", request.grant_type),
+            ));
+        }
+        let secret = self.config.jwt_secret.as_ref().ok_or_else(|| {
+            io::Error::new(ErrorKind::InvalidInput, "Token issuing is not configured")
+        })?;
+        let known = self.config.clients.iter().any(|client| {
+            client.client_id == request.client_id
+                && secrets_match(&client.client_secret, &request.client_secret)
+        });
+        if !known {
+            return Err(unauthorized("Invalid client credentials"));
+        }
+
+        let iat = now();
+        let claims = Claims {
+            sub: request.client_id.clone(),
+            iss: JWT_ISSUER.to_string(),
+            iat,
+            exp: iat + self.config.token_ttl_secs,
+            scope: request.scope.clone(),
+        };
+        let token = jsonwebtoken::encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(secret.as_bytes()),
+        )
+        .map_err(io::Error::other)?;
+        Ok(TokenResponse::bearer(token, self.config.token_ttl_secs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::config::{ApiToken, OAuthClient};
+
+    fn authenticator() -> Authenticator {
+        Authenticator::new(AuthConfig {
+            enabled: true,
+            tokens: vec![ApiToken {
+                name: "ci".to_string(),
+                token: "static-token".to_string(),
+            }],
+            jwt_secret: Some("secret".to_string()),
+            clients: vec![OAuthClient {
+                client_id: "spark".to_string(),
+                client_secret: "spark-secret".to_string(),
+            }],
+            ..Default::default()
+        })
+    }
+
+    fn token_request(client_secret: &str) -> TokenRequest {
+        TokenRequest {
+            grant_type: "client_credentials".to_string(),
+            client_id: "spark".to_string(),
+            client_secret: client_secret.to_string(),
+            scope: Some("catalog".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_disabled_auth_is_anonymous() {
+        let auth = Authenticator::new(AuthConfig::default());
+
+        assert_eq!(auth.authenticate(None).unwrap(), Principal::anonymous());
+    }
+
+    #[test]
+    fn test_static_token() {
+        let auth = authenticator();
+
+        let principal = auth.authenticate(Some("Bearer static-token")).unwrap();
+        assert_eq!(principal.name, "ci");
+        assert_eq!(principal.method, AuthMethod::ApiToken);
+
+        let err = auth.authenticate(Some("Bearer wrong")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+        let err = auth.authenticate(None).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn test_client_credentials_jwt() {
+        let auth = authenticator();
+
+        let response = auth.issue_token(&token_request("spark-secret")).unwrap();
+        assert_eq!(response.expires_in, 3600);
+        let principal = auth
+            .authenticate(Some(&format!("Bearer {}", response.access_token)))
+            .unwrap();
+        assert_eq!(principal.name, "spark");
+        assert_eq!(principal.method, AuthMethod::Jwt);
+
+        let err = auth.issue_token(&token_request("wrong")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn test_client_certificate() {
+        let certificate = ClientCertificate {
+            subject: "CN=spark, O=Analytics".to_string(),
+            common_name: Some("spark".to_string()),
+        };
+
+        let principal = authenticator().authenticate_certificate(&certificate);
+        assert_eq!(principal.name, "spark");
+        assert_eq!(principal.method, AuthMethod::ClientCertificate);
+        assert_eq!(
+            Authenticator::new(AuthConfig::default()).authenticate_certificate(&certificate),
+            Principal::anonymous()
+        );
+    }
+
+    #[test]
+    fn test_expired_jwt_is_rejected() {
+        let auth = authenticator();
+        let claims = Claims {
+            sub: "spark".to_string(),
+            iss: JWT_ISSUER.to_string(),
+            iat: now() - 7200,
+            exp: now() - 3600,
+            scope: None,
+        };
+        let token = jsonwebtoken::encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap();
+
+        let err = auth
+            .authenticate(Some(&format!("Bearer {}", token)))
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    }
+}
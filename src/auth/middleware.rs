@@ -0,0 +1,50 @@
+use crate::auth::access::AccessControl;
+use crate::auth::authenticator::Authenticator;
+use crate::server::tls::ClientCertificate;
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+/// Authenticates every request, by its bearer token or else by its verified
+/// TLS client certificate, and stores the caller, as a
+/// [`Caller`](crate::auth::access::Caller) carrying its grants, in its
+/// extensions.
+pub async fn authenticate(
+    State((auth, access)): State<(Arc<Authenticator>, Arc<AccessControl>)>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let authorization = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+    let result = match (
+        authorization,
+        request.extensions().get::<ClientCertificate>(),
+    ) {
+        (None, Some(certificate)) => Ok(auth.authenticate_certificate(certificate)),
+        _ => auth.authenticate(authorization),
+    };
+    let principal = match result {
+        Ok(principal) => principal,
+        Err(e) => {
+            return (
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, "Bearer")],
+                format!("Error: {}", e),
+            )
+                .into_response()
+        }
+    };
+    match access.caller(principal) {
+        Ok(caller) => {
+            request.extensions_mut().insert(caller);
+            next.run(request).await
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e)).into_response(),
+    }
+}
@@ -0,0 +1,3 @@
+pub mod access;
+pub mod authenticator;
+pub mod middleware;
@@ -0,0 +1,14 @@
+use catalog1::cli::{self, CatalogCli};
+use clap::Parser;
+use std::process;
+
+fn main() {
+    let cli = CatalogCli::parse();
+    match cli::run(&cli) {
+        Ok(output) => print!("{}", output),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,544 @@
+use crate::config::config::{BackupConfig, Config};
+use crate::database::database::Database;
+use crate::database::dump::DumpSummary;
+use crate::database::migration;
+use crate::dto::backup::{BackupInfo, CheckpointResponse};
+use crate::dto::fsck::FsckReport;
+use crate::dto::migration::MigrationReport;
+use crate::dto::namespace_data::{NamespaceData, NamespaceIdent};
+use crate::dto::rename_request::TableRenameRequest;
+use crate::dto::set_namespace_properties_req::SetNamespacePropertiesRequest;
+use crate::dto::table_data::{Table, TableCreation, TableIdent};
+use crate::handlers::naming::NamingRules;
+use crate::repository::admin::AdminRepository;
+use crate::repository::namespace::NamespaceRepository;
+use crate::repository::table::TableRepository;
+use crate::telemetry::metrics::lock_database;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::io::{self, ErrorKind, Read};
+use std::sync::{Arc, Mutex};
+
+/// CatalogBackend is what the admin CLI needs from a catalog, whether it is
+/// reached over HTTP or opened directly on disk.
+pub trait CatalogBackend {
+    fn list_namespaces(&self) -> io::Result<Vec<NamespaceIdent>>;
+    fn create_namespace(&self, namespace: &NamespaceData) -> io::Result<()>;
+    fn load_namespace(&self, namespace: &NamespaceIdent) -> io::Result<NamespaceData>;
+    fn drop_namespace(&self, namespace: &NamespaceIdent) -> io::Result<()>;
+    fn set_namespace_properties(
+        &self,
+        namespace: &NamespaceIdent,
+        request: &SetNamespacePropertiesRequest,
+    ) -> io::Result<()>;
+    fn list_tables(&self, namespace: &NamespaceIdent) -> io::Result<Vec<TableIdent>>;
+    fn create_table(&self, namespace: &NamespaceIdent, table: &TableCreation) -> io::Result<()>;
+    fn load_table(&self, table: &TableIdent) -> io::Result<Table>;
+    fn drop_table(&self, table: &TableIdent) -> io::Result<()>;
+    fn rename_table(&self, request: &TableRenameRequest) -> io::Result<()>;
+    /// Returns an NDJSON dump of the whole catalog.
+    fn export_dump(&self) -> io::Result<Vec<u8>>;
+    /// Restores a dump into an empty catalog.
+    fn import_dump(&self, dump: &[u8]) -> io::Result<DumpSummary>;
+    fn create_backup(&self) -> io::Result<BackupInfo>;
+    fn list_backups(&self) -> io::Result<Vec<BackupInfo>>;
+    fn create_checkpoint(&self) -> io::Result<CheckpointResponse>;
+    fn fsck(&self, repair: bool) -> io::Result<FsckReport>;
+    fn migrate(&self, dry_run: bool) -> io::Result<MigrationReport>;
+}
+
+/// LocalBackend works on a RocksDB directory that no server has open, with
+/// the storage, naming and backup settings the server would use.
+pub struct LocalBackend {
+    database: Arc<Mutex<Database>>,
+    naming: NamingRules,
+    namespaces: NamespaceRepository,
+    tables: TableRepository,
+    admin: AdminRepository,
+}
+
+impl LocalBackend {
+    /// Opens the data directory of `config`.
+    pub fn open(config: &Config) -> io::Result<Self> {
+        let storage = &config.storage;
+        let db =
+            Database::open_with_config(&storage.data_dir, &storage.rocksdb, &storage.encoding)?;
+        let naming = NamingRules::new(&config.naming)?;
+        Ok(Self::new(
+            Arc::new(Mutex::new(db)),
+            config.backup.clone(),
+            naming,
+        ))
+    }
+
+    pub fn new(database: Arc<Mutex<Database>>, backup: BackupConfig, naming: NamingRules) -> Self {
+        let case = naming.case_policy();
+        Self {
+            naming,
+            namespaces: NamespaceRepository::new(database.clone()).with_case_policy(case),
+            tables: TableRepository::new(database.clone()).with_case_policy(case),
+            admin: AdminRepository::new(database.clone(), backup),
+            database,
+        }
+    }
+
+    /// Fails unless the database is in the current format, which the
+    /// server migrates to on startup; writes would otherwise mix formats.
+    fn writable(&self) -> io::Result<()> {
+        migration::ensure_current(&lock_database(&self.database, "cli"))
+    }
+}
+
+fn invalid_name(message: String) -> io::Error {
+    io::Error::new(ErrorKind::InvalidInput, message)
+}
+
+fn namespace_not_found(namespace: &NamespaceIdent) -> io::Error {
+    io::Error::new(
+        ErrorKind::NotFound,
+        format!("Namespace {} not found", namespace.0.join(".")),
+    )
+}
+
+impl CatalogBackend for LocalBackend {
+    fn list_namespaces(&self) -> io::Result<Vec<NamespaceIdent>> {
+        self.namespaces.list_all_namespaces()
+    }
+
+    fn create_namespace(&self, namespace: &NamespaceData) -> io::Result<()> {
+        self.writable()?;
+        self.naming
+            .check_new_namespace(namespace.get_name())
+            .map_err(invalid_name)?;
+        // Objects created here have no owner; drops still return quota.
+        self.namespaces.create_namespace(
+            namespace.get_name().clone(),
+            Some(namespace.get_properties().clone()),
+            None,
+        )
+    }
+
+    fn load_namespace(&self, namespace: &NamespaceIdent) -> io::Result<NamespaceData> {
+        self.namespaces
+            .load_namespace(namespace)?
+            .ok_or_else(|| namespace_not_found(namespace))
+    }
+
+    fn drop_namespace(&self, namespace: &NamespaceIdent) -> io::Result<()> {
+        self.writable()?;
+        if !self.namespaces.namespace_exists(namespace)? {
+            return Err(namespace_not_found(namespace));
+        }
+        self.namespaces.delete_namespace(namespace, None)
+    }
+
+    fn set_namespace_properties(
+        &self,
+        namespace: &NamespaceIdent,
+        request: &SetNamespacePropertiesRequest,
+    ) -> io::Result<()> {
+        self.writable()?;
+        self.namespaces
+            .set_namespace_properties(
+                namespace.clone(),
+                request.removals.clone(),
+                request.updates.clone(),
+                None,
+            )
+            .map(|_| ())
+    }
+
+    fn list_tables(&self, namespace: &NamespaceIdent) -> io::Result<Vec<TableIdent>> {
+        Ok(self.tables.list_all_tables(namespace)?.unwrap_or_default())
+    }
+
+    fn create_table(&self, namespace: &NamespaceIdent, table: &TableCreation) -> io::Result<()> {
+        self.writable()?;
+        self.naming
+            .check_new_table(&TableIdent::new(namespace.clone(), table.name.clone()))
+            .map_err(invalid_name)?;
+        self.tables.create_table(namespace, table, None)
+    }
+
+    fn load_table(&self, table: &TableIdent) -> io::Result<Table> {
+        self.tables
+            .load_table(&table.namespace, table.name.clone())?
+            .ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::NotFound,
+                    format!("Table {} not found", table_name(table)),
+                )
+            })
+    }
+
+    fn drop_table(&self, table: &TableIdent) -> io::Result<()> {
+        self.writable()?;
+        self.tables
+            .drop_table(&table.namespace, table.name.clone(), None)
+    }
+
+    fn rename_table(&self, request: &TableRenameRequest) -> io::Result<()> {
+        self.writable()?;
+        self.naming
+            .check_table(&request.source)
+            .map_err(invalid_name)?;
+        self.naming
+            .check_new_table(&request.destination)
+            .map_err(invalid_name)?;
+        self.tables.rename_table(request, None)
+    }
+
+    fn export_dump(&self) -> io::Result<Vec<u8>> {
+        self.admin.export_dump()
+    }
+
+    fn import_dump(&self, dump: &[u8]) -> io::Result<DumpSummary> {
+        self.writable()?;
+        self.admin.import_dump(dump)
+    }
+
+    fn create_backup(&self) -> io::Result<BackupInfo> {
+        self.admin.create_backup()
+    }
+
+    fn list_backups(&self) -> io::Result<Vec<BackupInfo>> {
+        self.admin.list_backups()
+    }
+
+    fn create_checkpoint(&self) -> io::Result<CheckpointResponse> {
+        self.admin.create_checkpoint()
+    }
+
+    fn fsck(&self, repair: bool) -> io::Result<FsckReport> {
+        self.admin.fsck(repair)
+    }
+
+    fn migrate(&self, dry_run: bool) -> io::Result<MigrationReport> {
+        self.admin.migrate(dry_run)
+    }
+}
+
+/// HttpBackend talks to a running catalog server.
+pub struct HttpBackend {
+    base_url: String,
+    agent: ureq::Agent,
+    token: Option<String>,
+}
+
+impl HttpBackend {
+    pub fn new(base_url: &str, token: Option<String>) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            agent: ureq::AgentBuilder::new().build(),
+            token,
+        }
+    }
+
+    /// Starts a request carrying the bearer token, if any.
+    fn request(&self, method: &str, url: &str) -> ureq::Request {
+        let request = self.agent.request(method, url);
+        match &self.token {
+            Some(token) => request.set("Authorization", &format!("Bearer {}", token)),
+            None => request,
+        }
+    }
+
+    fn namespace_url(&self, namespace: &NamespaceIdent) -> String {
+        format!(
+            "{}/namespaces/{}",
+            self.base_url,
+            encode_segment(&namespace.0.join("\u{1F}"))
+        )
+    }
+
+    fn table_url(&self, table: &TableIdent) -> String {
+        format!(
+            "{}/tables/{}",
+            self.namespace_url(&table.namespace),
+            encode_segment(&table.name)
+        )
+    }
+
+    fn get<T: DeserializeOwned>(&self, url: &str) -> io::Result<T> {
+        let response = self.request("GET", url).call().map_err(http_error)?;
+        response
+            .into_json()
+            .map_err(|e| io::Error::new(ErrorKind::InvalidData, e.to_string()))
+    }
+
+    fn post<T: DeserializeOwned>(&self, url: &str) -> io::Result<T> {
+        let response = self.request("POST", url).call().map_err(http_error)?;
+        response
+            .into_json()
+            .map_err(|e| io::Error::new(ErrorKind::InvalidData, e.to_string()))
+    }
+
+    fn send(&self, method: &str, url: &str, body: Option<Value>) -> io::Result<()> {
+        let request = self.request(method, url);
+        match body {
+            Some(body) => request.send_json(body),
+            None => request.call(),
+        }
+        .map(|_| ())
+        .map_err(http_error)
+    }
+}
+
+fn to_value<T: serde::Serialize>(value: &T) -> io::Result<Value> {
+    serde_json::to_value(value).map_err(|e| io::Error::new(ErrorKind::InvalidInput, e.to_string()))
+}
+
+impl CatalogBackend for HttpBackend {
+    fn list_namespaces(&self) -> io::Result<Vec<NamespaceIdent>> {
+        let response: Value = self.get(&format!("{}/namespaces", self.base_url))?;
+        serde_json::from_value(response["namespaces"].clone())
+            .map_err(|e| io::Error::new(ErrorKind::InvalidData, e.to_string()))
+    }
+
+    fn create_namespace(&self, namespace: &NamespaceData) -> io::Result<()> {
+        let url = format!("{}/namespaces", self.base_url);
+        self.send("POST", &url, Some(to_value(namespace)?))
+    }
+
+    fn load_namespace(&self, namespace: &NamespaceIdent) -> io::Result<NamespaceData> {
+        self.get(&self.namespace_url(namespace))
+    }
+
+    fn drop_namespace(&self, namespace: &NamespaceIdent) -> io::Result<()> {
+        self.send("DELETE", &self.namespace_url(namespace), None)
+    }
+
+    fn set_namespace_properties(
+        &self,
+        namespace: &NamespaceIdent,
+        request: &SetNamespacePropertiesRequest,
+    ) -> io::Result<()> {
+        let url = format!("{}/properties", self.namespace_url(namespace));
+        self.send("POST", &url, Some(to_value(request)?))
+    }
+
+    fn list_tables(&self, namespace: &NamespaceIdent) -> io::Result<Vec<TableIdent>> {
+        self.get(&format!("{}/tables", self.namespace_url(namespace)))
+    }
+
+    fn create_table(&self, namespace: &NamespaceIdent, table: &TableCreation) -> io::Result<()> {
+        let url = format!("{}/tables", self.namespace_url(namespace));
+        self.send("POST", &url, Some(to_value(table)?))
+    }
+
+    fn load_table(&self, table: &TableIdent) -> io::Result<Table> {
+        self.get(&self.table_url(table))
+    }
+
+    fn drop_table(&self, table: &TableIdent) -> io::Result<()> {
+        self.send("DELETE", &self.table_url(table), None)
+    }
+
+    fn rename_table(&self, request: &TableRenameRequest) -> io::Result<()> {
+        let url = format!("{}/tables/rename", self.base_url);
+        self.send("POST", &url, Some(to_value(request)?))
+    }
+
+    fn export_dump(&self) -> io::Result<Vec<u8>> {
+        let url = format!("{}/admin/export", self.base_url);
+        let response = self.request("GET", &url).call().map_err(http_error)?;
+        let mut dump = Vec::new();
+        response.into_reader().read_to_end(&mut dump)?;
+        Ok(dump)
+    }
+
+    fn import_dump(&self, dump: &[u8]) -> io::Result<DumpSummary> {
+        let url = format!("{}/admin/import", self.base_url);
+        let response = self
+            .agent
+            .post(&url)
+            .set("Content-Type", "application/x-ndjson")
+            .send_bytes(dump)
+            .map_err(http_error)?;
+        response
+            .into_json()
+            .map_err(|e| io::Error::new(ErrorKind::InvalidData, e.to_string()))
+    }
+
+    fn create_backup(&self) -> io::Result<BackupInfo> {
+        self.post(&format!("{}/admin/backups", self.base_url))
+    }
+
+    fn list_backups(&self) -> io::Result<Vec<BackupInfo>> {
+        self.get(&format!("{}/admin/backups", self.base_url))
+    }
+
+    fn create_checkpoint(&self) -> io::Result<CheckpointResponse> {
+        self.post(&format!("{}/admin/checkpoints", self.base_url))
+    }
+
+    fn fsck(&self, repair: bool) -> io::Result<FsckReport> {
+        let url = format!("{}/admin/fsck", self.base_url);
+        if repair {
+            self.post(&url)
+        } else {
+            self.get(&url)
+        }
+    }
+
+    /// A running server migrates on startup, so there is nothing to do remotely.
+    fn migrate(&self, _dry_run: bool) -> io::Result<MigrationReport> {
+        Err(io::Error::new(
+            ErrorKind::Unsupported,
+            "Migrations run on a local database, pass --data-dir",
+        ))
+    }
+}
+
+/// Maps HTTP failures to the io::Error kinds the handlers were mapped from.
+fn http_error(error: ureq::Error) -> io::Error {
+    match error {
+        ureq::Error::Status(status, response) => {
+            let kind = match status {
+                400 => ErrorKind::InvalidInput,
+                404 => ErrorKind::NotFound,
+                409 => ErrorKind::AlreadyExists,
+                _ => ErrorKind::Other,
+            };
+            let body = response.into_string().unwrap_or_default();
+            let message = if body.is_empty() {
+                format!("HTTP {}", status)
+            } else {
+                format!("HTTP {}: {}", status, body)
+            };
+            io::Error::new(kind, message)
+        }
+        ureq::Error::Transport(transport) => {
+            io::Error::new(ErrorKind::ConnectionRefused, transport.to_string())
+        }
+    }
+}
+
+/// Percent-encodes a path segment.
+pub fn encode_segment(segment: &str) -> String {
+    let mut encoded = String::new();
+    for byte in segment.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+pub fn table_name(table: &TableIdent) -> String {
+    format!("{}.{}", table.namespace.0.join("."), table.name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::config::{NamingConfig, StorageConfig};
+    use crate::database::format::{FORMAT_VERSION_KEY, META_CF};
+    use serde_json::json;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    fn config(data_dir: &Path) -> Config {
+        Config {
+            storage: StorageConfig {
+                data_dir: data_dir.to_path_buf(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_encode_segment() {
+        assert_eq!(encode_segment("sales"), "sales");
+        assert_eq!(encode_segment("a\u{1F}b c"), "a%1Fb%20c");
+    }
+
+    #[test]
+    fn test_http_backend_urls() {
+        let backend = HttpBackend::new("http://localhost:3000/", None);
+        let table = TableIdent::new(
+            NamespaceIdent(vec!["a".to_string(), "b".to_string()]),
+            "t".to_string(),
+        );
+
+        assert_eq!(
+            backend.table_url(&table),
+            "http://localhost:3000/namespaces/a%1Fb/tables/t"
+        );
+    }
+
+    #[test]
+    fn test_local_backend() {
+        let dir = tempdir().unwrap();
+        let backend = LocalBackend::open(&config(dir.path())).unwrap();
+        let namespace = NamespaceData {
+            name: NamespaceIdent(vec!["sales".to_string()]),
+            properties: json!({"owner": "etl"}),
+            // Created namespaces start at version 1.
+            version: 1,
+        };
+
+        backend.create_namespace(&namespace).unwrap();
+        let err = backend.create_namespace(&namespace).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+        assert_eq!(backend.load_namespace(&namespace.name).unwrap(), namespace);
+
+        let creation = TableCreation::builder().name("orders".to_string()).build();
+        backend.create_table(&namespace.name, &creation).unwrap();
+        let table = TableIdent::new(namespace.name.clone(), "orders".to_string());
+        assert_eq!(
+            backend.list_tables(&namespace.name).unwrap(),
+            vec![table.clone()]
+        );
+        assert_eq!(backend.load_table(&table).unwrap().id, table);
+
+        backend.drop_table(&table).unwrap();
+        let err = backend.load_table(&table).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        backend.drop_namespace(&namespace.name).unwrap();
+        assert!(backend.list_namespaces().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_local_backend_follows_the_server_config() {
+        let dir = tempdir().unwrap();
+        let backend = LocalBackend::open(&Config {
+            naming: NamingConfig {
+                reserved_names: vec!["system".to_string()],
+                ..Default::default()
+            },
+            ..config(dir.path())
+        })
+        .unwrap();
+        let system = NamespaceData {
+            name: NamespaceIdent(vec!["system".to_string()]),
+            properties: json!({}),
+            version: 1,
+        };
+        let err = backend.create_namespace(&system).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let sales = NamespaceIdent(vec!["sales".to_string()]);
+        backend
+            .create_namespace(&NamespaceData {
+                name: sales.clone(),
+                ..system
+            })
+            .unwrap();
+        let creation = TableCreation::builder().name("system".to_string()).build();
+        let err = backend.create_table(&sales, &creation).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+        // Databases the server has not migrated yet are not written to.
+        lock_database(&backend.database, "cli")
+            .delete_raw(META_CF, FORMAT_VERSION_KEY)
+            .unwrap();
+        let creation = TableCreation::builder().name("orders".to_string()).build();
+        let err = backend.create_table(&sales, &creation).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(backend.list_tables(&sales).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,652 @@
+pub mod backend;
+pub mod output;
+
+use crate::config::config::{Cli, Config};
+use crate::database::database::Database;
+use crate::dto::backup::BackupInfo;
+use crate::dto::column_data::ColumnData;
+use crate::dto::namespace_data::{NamespaceData, NamespaceIdent};
+use crate::dto::rename_request::TableRenameRequest;
+use crate::dto::set_namespace_properties_req::SetNamespacePropertiesRequest;
+use crate::dto::table_data::{TableCreation, TableIdent};
+use backend::{table_name, CatalogBackend, HttpBackend, LocalBackend};
+use clap::{Parser, Subcommand};
+use output::{cell, OutputFormat, Report};
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+/// Command line of the `catalog-cli` administration tool.
+#[derive(Parser, Debug)]
+#[command(name = "catalog-cli", about = "Administer an Iceberg REST catalog")]
+pub struct CatalogCli {
+    /// Base URL of a running catalog server.
+    #[arg(long, env = "CATALOG_URL", default_value = "http://localhost:3000")]
+    pub url: String,
+    /// Bearer token sent to the server.
+    #[arg(long, env = "CATALOG_TOKEN", hide_env_values = true)]
+    pub token: Option<String>,
+    /// Work directly on an offline RocksDB directory instead of a server.
+    #[arg(long, conflicts_with = "url")]
+    pub data_dir: Option<PathBuf>,
+    /// Backup directory used with --data-dir.
+    #[arg(long, requires = "data_dir")]
+    pub backup_dir: Option<PathBuf>,
+    /// Server config file whose storage, naming and backup settings apply
+    /// with --data-dir (env: CATALOG_CONFIG).
+    #[arg(long, requires = "data_dir")]
+    pub config: Option<PathBuf>,
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Table)]
+    pub output: OutputFormat,
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Manage namespaces.
+    #[command(subcommand)]
+    Namespace(NamespaceCommand),
+    /// Manage tables.
+    #[command(subcommand)]
+    Table(TableCommand),
+    /// Write an NDJSON dump of the whole catalog to a file or stdout.
+    Export {
+        #[arg(short, long)]
+        file: Option<PathBuf>,
+    },
+    /// Restore a dump into an empty catalog.
+    Import { file: PathBuf },
+    /// Manage backups and checkpoints.
+    #[command(subcommand)]
+    Backup(BackupCommand),
+    /// Check the catalog invariants.
+    Fsck {
+        /// Repair the issues found.
+        #[arg(long)]
+        repair: bool,
+    },
+    /// Upgrade a local database to the storage format of this build.
+    Migrate {
+        /// Report the migrations without writing anything.
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum BackupCommand {
+    /// Create a hot backup, dropping the oldest ones beyond retention.
+    Create,
+    /// List the backups.
+    List,
+    /// Create a checkpoint under the backup directory.
+    Checkpoint,
+    /// Restore a backup into a data directory; no server may be using it.
+    Restore {
+        /// Backup directory to restore from.
+        #[arg(long)]
+        from: PathBuf,
+        /// Data directory to restore into.
+        #[arg(long)]
+        to: PathBuf,
+        /// Backup to restore, the latest one by default.
+        #[arg(long)]
+        id: Option<u32>,
+        /// Overwrite a data directory that is not empty.
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+/// Namespaces are written dotted, e.g. `sales.eu`.
+#[derive(Subcommand, Debug, Clone)]
+pub enum NamespaceCommand {
+    /// List namespaces.
+    List,
+    /// Create a namespace.
+    Create {
+        namespace: String,
+        /// Property as `key=value`; repeatable.
+        #[arg(short, long = "property", value_parser = parse_property)]
+        properties: Vec<(String, String)>,
+    },
+    /// Show the properties of a namespace.
+    Show { namespace: String },
+    /// Drop a namespace.
+    Drop { namespace: String },
+    /// Set or remove namespace properties.
+    SetProperties {
+        namespace: String,
+        #[arg(long = "set", value_parser = parse_property)]
+        updates: Vec<(String, String)>,
+        #[arg(long = "remove")]
+        removals: Vec<String>,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum TableCommand {
+    /// List the tables of a namespace.
+    List { namespace: String },
+    /// Create a table.
+    Create {
+        namespace: String,
+        name: String,
+        /// JSON file holding the array of columns of the table.
+        #[arg(long)]
+        schema: Option<PathBuf>,
+    },
+    /// Show the metadata of a table.
+    Show { namespace: String, name: String },
+    /// Drop a table.
+    Drop { namespace: String, name: String },
+    /// Rename or move a table.
+    Rename {
+        namespace: String,
+        name: String,
+        to_namespace: String,
+        to_name: String,
+    },
+}
+
+/// Runs the command against the selected backend and renders its result.
+pub fn run(cli: &CatalogCli) -> io::Result<String> {
+    if let Command::Backup(BackupCommand::Restore {
+        from,
+        to,
+        id,
+        force,
+    }) = &cli.command
+    {
+        return restore_backup(from, to, *id, *force).map(|report| report.render(cli.output));
+    }
+    let report = match &cli.data_dir {
+        Some(dir) => {
+            // The same layers the server reads, pointed at the directory.
+            let mut config = Config::load(&Cli {
+                config: cli.config.clone(),
+                data_dir: Some(dir.clone()),
+                ..Default::default()
+            })?;
+            if let Some(backup_dir) = &cli.backup_dir {
+                config.backup.dir = Some(backup_dir.clone());
+            }
+            let backend = LocalBackend::open(&config)?;
+            execute(&backend, &cli.command)?
+        }
+        None => execute(&HttpBackend::new(&cli.url, cli.token.clone()), &cli.command)?,
+    };
+    Ok(report.render(cli.output))
+}
+
+pub fn execute(backend: &dyn CatalogBackend, command: &Command) -> io::Result<Report> {
+    match command {
+        Command::Namespace(command) => execute_namespace(backend, command),
+        Command::Table(command) => execute_table(backend, command),
+        Command::Export { file } => {
+            let dump = backend.export_dump()?;
+            match file {
+                Some(path) => {
+                    fs::write(path, &dump)?;
+                    Ok(Report::Message(format!(
+                        "Exported catalog to {}",
+                        path.display()
+                    )))
+                }
+                None => Ok(Report::Raw(String::from_utf8_lossy(&dump).into_owned())),
+            }
+        }
+        Command::Backup(command) => execute_backup(backend, command),
+        Command::Fsck { repair } => {
+            let report = backend.fsck(*repair)?;
+            let rows = report
+                .issues
+                .iter()
+                .map(|issue| {
+                    let value = json!(issue);
+                    let kind = cell(&value["kind"]);
+                    let mut details = value.as_object().cloned().unwrap_or_default();
+                    details.remove("kind");
+                    vec![kind, Value::Object(details).to_string()]
+                })
+                .collect();
+            Ok(Report::rows(&["issue", "details"], rows, json!(report)))
+        }
+        Command::Migrate { dry_run } => {
+            let report = backend.migrate(*dry_run)?;
+            let rows = report
+                .steps
+                .iter()
+                .map(|step| {
+                    vec![
+                        step.version.to_string(),
+                        step.description.clone(),
+                        step.entries.to_string(),
+                    ]
+                })
+                .collect();
+            Ok(Report::rows(
+                &["version", "migration", "entries"],
+                rows,
+                json!(report),
+            ))
+        }
+        Command::Import { file } => {
+            let summary = backend.import_dump(&fs::read(file)?)?;
+            let rows = summary
+                .entries
+                .iter()
+                .map(|(cf, count)| vec![cf.clone(), count.to_string()])
+                .collect();
+            Ok(Report::rows(
+                &["column family", "entries"],
+                rows,
+                json!(summary),
+            ))
+        }
+    }
+}
+
+fn execute_backup(backend: &dyn CatalogBackend, command: &BackupCommand) -> io::Result<Report> {
+    match command {
+        BackupCommand::Create => {
+            let backup = backend.create_backup()?;
+            Ok(backup_report(std::slice::from_ref(&backup), json!(backup)))
+        }
+        BackupCommand::List => {
+            let backups = backend.list_backups()?;
+            Ok(backup_report(&backups, json!(backups)))
+        }
+        BackupCommand::Checkpoint => {
+            let checkpoint = backend.create_checkpoint()?;
+            Ok(Report::Message(format!(
+                "Created checkpoint {}",
+                checkpoint.path
+            )))
+        }
+        BackupCommand::Restore {
+            from,
+            to,
+            id,
+            force,
+        } => restore_backup(from, to, *id, *force),
+    }
+}
+
+fn backup_report(backups: &[BackupInfo], json: Value) -> Report {
+    let rows = backups
+        .iter()
+        .map(|b| {
+            vec![
+                b.backup_id.to_string(),
+                b.timestamp.to_string(),
+                b.size_in_bytes.to_string(),
+                b.num_files.to_string(),
+            ]
+        })
+        .collect();
+    Report::rows(&["id", "timestamp", "bytes", "files"], rows, json)
+}
+
+/// Restores straight from the backup directory; this never goes through a server.
+fn restore_backup(from: &Path, to: &Path, id: Option<u32>, force: bool) -> io::Result<Report> {
+    let occupied = to.exists() && fs::read_dir(to)?.next().is_some();
+    if occupied && !force {
+        return Err(io::Error::new(
+            ErrorKind::AlreadyExists,
+            format!(
+                "{} is not empty, pass --force to overwrite it",
+                to.display()
+            ),
+        ));
+    }
+    Database::restore_backup(from, to, id)?;
+    Ok(Report::Message(format!(
+        "Restored backup into {}",
+        to.display()
+    )))
+}
+
+fn execute_namespace(
+    backend: &dyn CatalogBackend,
+    command: &NamespaceCommand,
+) -> io::Result<Report> {
+    match command {
+        NamespaceCommand::List => {
+            let namespaces = backend.list_namespaces()?;
+            let rows = namespaces.iter().map(|ns| vec![ns.0.join(".")]).collect();
+            Ok(Report::rows(&["namespace"], rows, json!(namespaces)))
+        }
+        NamespaceCommand::Create {
+            namespace,
+            properties,
+        } => {
+            let properties: Map<String, Value> = properties
+                .iter()
+                .map(|(k, v)| (k.clone(), Value::String(v.clone())))
+                .collect();
+            backend.create_namespace(&NamespaceData {
+                name: parse_namespace(namespace)?,
+                properties: Value::Object(properties),
+                version: 0,
+            })?;
+            Ok(Report::Message(format!("Created namespace {}", namespace)))
+        }
+        NamespaceCommand::Show { namespace } => {
+            let data = backend.load_namespace(&parse_namespace(namespace)?)?;
+            let rows = match data.get_properties() {
+                Value::Object(properties) => properties
+                    .iter()
+                    .map(|(k, v)| vec![k.clone(), cell(v)])
+                    .collect(),
+                _ => vec![],
+            };
+            Ok(Report::rows(&["property", "value"], rows, json!(data)))
+        }
+        NamespaceCommand::Drop { namespace } => {
+            backend.drop_namespace(&parse_namespace(namespace)?)?;
+            Ok(Report::Message(format!("Dropped namespace {}", namespace)))
+        }
+        NamespaceCommand::SetProperties {
+            namespace,
+            updates,
+            removals,
+        } => {
+            let request = SetNamespacePropertiesRequest {
+                removals: removals.clone(),
+                updates: updates
+                    .iter()
+                    .map(|(k, v)| (k.clone(), Value::String(v.clone())))
+                    .collect(),
+            };
+            backend.set_namespace_properties(&parse_namespace(namespace)?, &request)?;
+            Ok(Report::Message(format!(
+                "Updated properties of namespace {}",
+                namespace
+            )))
+        }
+    }
+}
+
+fn execute_table(backend: &dyn CatalogBackend, command: &TableCommand) -> io::Result<Report> {
+    match command {
+        TableCommand::List { namespace } => {
+            let tables = backend.list_tables(&parse_namespace(namespace)?)?;
+            let rows = tables.iter().map(|t| vec![t.name.clone()]).collect();
+            Ok(Report::rows(&["table"], rows, json!(tables)))
+        }
+        TableCommand::Create {
+            namespace,
+            name,
+            schema,
+        } => {
+            let columns = match schema {
+                Some(path) => {
+                    let contents = fs::read_to_string(path)?;
+                    let columns: Vec<ColumnData> = serde_json::from_str(&contents)
+                        .map_err(|e| io::Error::new(ErrorKind::InvalidData, e.to_string()))?;
+                    Some(columns)
+                }
+                None => None,
+            };
+            let creation = TableCreation::builder()
+                .name(name.clone())
+                .columns(columns)
+                .build();
+            backend.create_table(&parse_namespace(namespace)?, &creation)?;
+            Ok(Report::Message(format!(
+                "Created table {}.{}",
+                namespace, name
+            )))
+        }
+        TableCommand::Show { namespace, name } => {
+            let table =
+                backend.load_table(&TableIdent::new(parse_namespace(namespace)?, name.clone()))?;
+            let columns = table
+                .metadata
+                .columns
+                .iter()
+                .flatten()
+                .map(|c| c.name.clone())
+                .collect::<Vec<_>>()
+                .join(", ");
+            let primary_key = table
+                .metadata
+                .constraints
+                .primary_key
+                .as_ref()
+                .map(|pk| pk.columns.join(", "))
+                .unwrap_or_default();
+            let rows = vec![
+                vec!["table".to_string(), table_name(&table.id)],
+                vec!["uuid".to_string(), table.metadata.table_uuid.clone()],
+                vec!["columns".to_string(), columns],
+                vec!["primary key".to_string(), primary_key],
+            ];
+            Ok(Report::rows(&["field", "value"], rows, json!(table)))
+        }
+        TableCommand::Drop { namespace, name } => {
+            backend.drop_table(&TableIdent::new(parse_namespace(namespace)?, name.clone()))?;
+            Ok(Report::Message(format!(
+                "Dropped table {}.{}",
+                namespace, name
+            )))
+        }
+        TableCommand::Rename {
+            namespace,
+            name,
+            to_namespace,
+            to_name,
+        } => {
+            backend.rename_table(&TableRenameRequest {
+                source: TableIdent::new(parse_namespace(namespace)?, name.clone()),
+                destination: TableIdent::new(parse_namespace(to_namespace)?, to_name.clone()),
+            })?;
+            Ok(Report::Message(format!(
+                "Renamed table {}.{} to {}.{}",
+                namespace, name, to_namespace, to_name
+            )))
+        }
+    }
+}
+
+pub fn parse_namespace(namespace: &str) -> io::Result<NamespaceIdent> {
+    let parts: Vec<String> = namespace.split('.').map(|p| p.to_string()).collect();
+    if parts.iter().any(|p| p.is_empty()) {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Invalid namespace '{}'", namespace),
+        ));
+    }
+    Ok(NamespaceIdent::new(parts))
+}
+
+fn parse_property(property: &str) -> Result<(String, String), String> {
+    property
+        .split_once('=')
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .ok_or_else(|| format!("expected key=value, got '{}'", property))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::config::{BackupConfig, StorageConfig};
+    use crate::handlers::naming::NamingRules;
+    use std::sync::{Arc, Mutex};
+    use tempfile::tempdir;
+
+    fn config(data_dir: &Path) -> Config {
+        Config {
+            storage: StorageConfig {
+                data_dir: data_dir.to_path_buf(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn parse(args: &[&str]) -> CatalogCli {
+        CatalogCli::try_parse_from([&["catalog-cli"], args].concat()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_arguments() {
+        let cli = parse(&[
+            "--data-dir",
+            "/tmp/db",
+            "-o",
+            "json",
+            "namespace",
+            "create",
+            "sales.eu",
+            "-p",
+            "owner=etl",
+        ]);
+
+        assert_eq!(cli.data_dir, Some(PathBuf::from("/tmp/db")));
+        assert_eq!(cli.output, OutputFormat::Json);
+        match cli.command {
+            Command::Namespace(NamespaceCommand::Create {
+                namespace,
+                properties,
+            }) => {
+                assert_eq!(namespace, "sales.eu");
+                assert_eq!(properties, vec![("owner".to_string(), "etl".to_string())]);
+            }
+            other => panic!("unexpected command {:?}", other),
+        }
+        assert!(
+            CatalogCli::try_parse_from(["catalog-cli", "namespace", "create", "a", "-p", "x"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_namespace() {
+        assert_eq!(
+            parse_namespace("a.b").unwrap(),
+            NamespaceIdent(vec!["a".to_string(), "b".to_string()])
+        );
+        assert_eq!(
+            parse_namespace("a..b").unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn test_execute_and_export_import() {
+        let source_dir = tempdir().unwrap();
+        let source = LocalBackend::open(&config(source_dir.path())).unwrap();
+        let commands = [
+            parse(&["namespace", "create", "sales", "-p", "owner=etl"]),
+            parse(&["namespace", "create", "sales.eu"]),
+            parse(&["table", "create", "sales.eu", "orders"]),
+            parse(&[
+                "table",
+                "rename",
+                "sales.eu",
+                "orders",
+                "sales",
+                "orders_v2",
+            ]),
+            parse(&["namespace", "set-properties", "sales", "--set", "tier=gold"]),
+        ];
+        for cli in &commands {
+            execute(&source, &cli.command).unwrap();
+        }
+
+        let report = execute(&source, &parse(&["table", "list", "sales"]).command).unwrap();
+        assert_eq!(
+            report.render(OutputFormat::Table),
+            "table\n---------\norders_v2\n"
+        );
+
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("catalog.ndjson");
+        let export = Command::Export {
+            file: Some(file.clone()),
+        };
+        execute(&source, &export).unwrap();
+
+        let target_dir = tempdir().unwrap();
+        let target = LocalBackend::open(&config(target_dir.path())).unwrap();
+        let import = Command::Import { file };
+        execute(&target, &import).unwrap();
+        let sales = target
+            .load_namespace(&parse_namespace("sales").unwrap())
+            .unwrap();
+        assert_eq!(sales.properties["tier"], "gold");
+        let orders = TableIdent::new(sales.name.clone(), "orders_v2".to_string());
+        assert_eq!(
+            target.load_table(&orders).unwrap().metadata.table_uuid,
+            source.load_table(&orders).unwrap().metadata.table_uuid
+        );
+
+        let err = execute(&target, &import).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+    }
+
+    #[test]
+    fn test_backup_commands() {
+        let dir = tempdir().unwrap();
+        let backup_dir = dir.path().join("backups");
+        let backend = LocalBackend::open(&Config {
+            backup: BackupConfig {
+                dir: Some(backup_dir.clone()),
+                ..Default::default()
+            },
+            ..config(&dir.path().join("db"))
+        })
+        .unwrap();
+        execute(&backend, &parse(&["namespace", "create", "sales"]).command).unwrap();
+        execute(&backend, &parse(&["backup", "create"]).command).unwrap();
+
+        let report = execute(&backend, &parse(&["backup", "list"]).command).unwrap();
+        match report {
+            Report::Rows { rows, .. } => assert_eq!(rows.len(), 1),
+            other => panic!("unexpected report {:?}", other),
+        }
+
+        let target = dir.path().join("restored");
+        let backup_dir = backup_dir.to_str().unwrap();
+        let restore = parse(&[
+            "backup",
+            "restore",
+            "--from",
+            backup_dir,
+            "--to",
+            target.to_str().unwrap(),
+        ]);
+        execute(&backend, &restore.command).unwrap();
+        fs::write(target.join("LOCK"), "").unwrap();
+        let err = execute(&backend, &restore.command).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+    }
+
+    #[test]
+    fn test_fsck_command() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        db.write_raw(&[("NamespaceData", b"[".to_vec(), b"{}".to_vec())])
+            .unwrap();
+        let backend = LocalBackend::new(
+            Arc::new(Mutex::new(db)),
+            BackupConfig::default(),
+            NamingRules::new(&Default::default()).unwrap(),
+        );
+
+        let report = execute(&backend, &parse(&["fsck"]).command).unwrap();
+        match report {
+            Report::Rows { rows, .. } => assert_eq!(rows[0][0], "undecodable-entry"),
+            other => panic!("unexpected report {:?}", other),
+        }
+        execute(&backend, &parse(&["fsck", "--repair"]).command).unwrap();
+        match execute(&backend, &parse(&["fsck"]).command).unwrap() {
+            Report::Rows { rows, .. } => assert!(rows.is_empty()),
+            other => panic!("unexpected report {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use clap::ValueEnum;
+use serde_json::Value;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+
+/// Report is the result of a CLI command, printable in either output format.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Report {
+    /// Rows for the table format next to the JSON document they came from.
+    Rows {
+        headers: Vec<String>,
+        rows: Vec<Vec<String>>,
+        json: Value,
+    },
+    Message(String),
+    /// Text printed as is in every format.
+    Raw(String),
+}
+
+impl Report {
+    pub fn rows(headers: &[&str], rows: Vec<Vec<String>>, json: Value) -> Self {
+        Report::Rows {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            rows,
+            json,
+        }
+    }
+
+    pub fn render(&self, format: OutputFormat) -> String {
+        match (self, format) {
+            (Report::Rows { headers, rows, .. }, OutputFormat::Table) => {
+                render_table(headers, rows)
+            }
+            (Report::Rows { json, .. }, OutputFormat::Json) => {
+                format!(
+                    "{}\n",
+                    serde_json::to_string_pretty(json).unwrap_or_default()
+                )
+            }
+            (Report::Message(message), OutputFormat::Table) => format!("{}\n", message),
+            (Report::Message(message), OutputFormat::Json) => {
+                format!("{}\n", serde_json::json!({ "message": message }))
+            }
+            (Report::Raw(text), _) => text.clone(),
+        }
+    }
+}
+
+/// Renders rows as left aligned columns under an underlined header.
+pub fn render_table(headers: &[String], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (i, cell) in row.iter().enumerate() {
+            widths[i] = widths[i].max(cell.chars().count());
+        }
+    }
+
+    let line = |cells: &[String]| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect();
+        format!("{}\n", padded.join("  ").trim_end())
+    };
+
+    let mut out = line(headers);
+    let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+    out.push_str(&line(&rule));
+    for row in rows {
+        out.push_str(&line(row));
+    }
+    out
+}
+
+/// Formats a JSON value for a table cell; strings lose their quotes.
+pub fn cell(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_table() {
+        let report = Report::rows(
+            &["namespace", "tables"],
+            vec![
+                vec!["sales".to_string(), "12".to_string()],
+                vec!["hr".to_string(), "3".to_string()],
+            ],
+            json!([]),
+        );
+
+        assert_eq!(
+            report.render(OutputFormat::Table),
+            "namespace  tables\n---------  ------\nsales      12\nhr         3\n"
+        );
+    }
+
+    #[test]
+    fn test_render_json() {
+        let report = Report::rows(&["namespace"], vec![], json!(["sales"]));
+
+        assert_eq!(report.render(OutputFormat::Json), "[\n  \"sales\"\n]\n");
+        assert_eq!(
+            Report::Message("done".to_string()).render(OutputFormat::Json),
+            "{\"message\":\"done\"}\n"
+        );
+    }
+}
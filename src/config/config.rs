@@ -0,0 +1,1094 @@
+use crate::database::database::COLUMN_FAMILIES;
+use clap::Parser;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Config file read from the working directory when none is given explicitly.
+pub const DEFAULT_CONFIG_FILE: &str = "catalog.toml";
+
+pub const LOG_LEVELS: [&str; 5] = ["trace", "debug", "info", "warn", "error"];
+
+/// Command line flags; each one overrides the config file and environment.
+#[derive(Parser, Debug, Default, Clone)]
+#[command(name = "catalog1", about = "Iceberg REST catalog server")]
+pub struct Cli {
+    /// Path of the TOML config file (env: CATALOG_CONFIG).
+    #[arg(short, long)]
+    pub config: Option<PathBuf>,
+    #[arg(long)]
+    pub bind_address: Option<String>,
+    #[arg(short, long)]
+    pub port: Option<u16>,
+    #[arg(long)]
+    pub data_dir: Option<PathBuf>,
+    #[arg(long)]
+    pub log_level: Option<String>,
+    /// Serve HTTPS using --tls-cert and --tls-key.
+    #[arg(long)]
+    pub tls: bool,
+    #[arg(long)]
+    pub tls_cert: Option<PathBuf>,
+    #[arg(long)]
+    pub tls_key: Option<PathBuf>,
+    #[arg(long)]
+    pub tls_client_ca: Option<PathBuf>,
+}
+
+/// Config is the typed configuration of the catalog server.
+///
+/// Values are layered: defaults, then the TOML file, then `CATALOG_*`
+/// environment variables, then command line flags.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub storage: StorageConfig,
+    pub log: LogConfig,
+    pub tls: TlsConfig,
+    pub auth: AuthConfig,
+    pub backup: BackupConfig,
+    pub limits: LimitsConfig,
+    pub naming: NamingConfig,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind_address: String,
+    pub port: u16,
+    /// How long in-flight requests may run after SIGINT or SIGTERM before
+    /// the server stops waiting for them.
+    pub shutdown_timeout_secs: u64,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind_address: "0.0.0.0".to_string(),
+            port: 3000,
+            shutdown_timeout_secs: 30,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    pub data_dir: PathBuf,
+    pub rocksdb: RocksDbConfig,
+    pub encoding: EncodingConfig,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            data_dir: PathBuf::from("rocksdb"),
+            rocksdb: RocksDbConfig::default(),
+            encoding: EncodingConfig::default(),
+        }
+    }
+}
+
+/// RocksDbConfig holds the RocksDB tuning knobs applied to every column family.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct RocksDbConfig {
+    /// Maximum number of open files, -1 for unlimited.
+    pub max_open_files: i32,
+    pub write_buffer_size: usize,
+    pub max_write_buffer_number: i32,
+    pub block_cache_size: usize,
+    /// Background threads; RocksDB's default when unset.
+    pub parallelism: Option<i32>,
+    /// Collects the RocksDB statistics exported by `/metrics`, such as the
+    /// block cache hit rate, at a small cost per operation.
+    pub statistics: bool,
+}
+
+impl Default for RocksDbConfig {
+    fn default() -> Self {
+        Self {
+            max_open_files: -1,
+            write_buffer_size: 64 * 1024 * 1024,
+            max_write_buffer_number: 2,
+            block_cache_size: 8 * 1024 * 1024,
+            parallelism: None,
+            statistics: true,
+        }
+    }
+}
+
+/// Codec serializing stored values.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Codec {
+    #[default]
+    Json,
+    MsgPack,
+}
+
+/// Compression applied to encoded values.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    #[default]
+    None,
+    Lz4,
+    Zstd,
+}
+
+impl FromStr for Codec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(Codec::Json),
+            "msgpack" => Ok(Codec::MsgPack),
+            other => Err(format!("Unknown codec {}", other)),
+        }
+    }
+}
+
+impl FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(Compression::None),
+            "lz4" => Ok(Compression::Lz4),
+            "zstd" => Ok(Compression::Zstd),
+            other => Err(format!("Unknown compression {}", other)),
+        }
+    }
+}
+
+/// ValueEncoding is how the values of one column family are written.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ValueEncoding {
+    pub codec: Codec,
+    pub compression: Compression,
+}
+
+/// EncodingConfig picks the value encoding written to each column family.
+/// Values are always readable whatever encoding wrote them.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct EncodingConfig {
+    pub codec: Codec,
+    pub compression: Compression,
+    /// Overrides keyed by column family name.
+    pub column_families: BTreeMap<String, ValueEncoding>,
+}
+
+impl EncodingConfig {
+    pub fn for_cf(&self, cf: &str) -> ValueEncoding {
+        self.column_families
+            .get(cf)
+            .copied()
+            .unwrap_or(ValueEncoding {
+                codec: self.codec,
+                compression: self.compression,
+            })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    pub level: String,
+    pub format: LogFormat,
+    /// OTLP/HTTP traces endpoint of an OpenTelemetry collector, such as
+    /// `http://localhost:4318/v1/traces`; spans are only exported when set.
+    pub otlp_endpoint: Option<String>,
+    /// Service name reported with exported spans.
+    pub service_name: String,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            format: LogFormat::default(),
+            otlp_endpoint: None,
+            service_name: "catalog1".to_string(),
+        }
+    }
+}
+
+/// Output format of log lines.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!("Unknown log format {}", other)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    pub enabled: bool,
+    pub cert_path: Option<PathBuf>,
+    pub key_path: Option<PathBuf>,
+    /// CA bundle used to verify client certificates; a verified
+    /// certificate authenticates the client as its subject.
+    pub client_ca_path: Option<PathBuf>,
+    /// How often to check the certificate files for changes; new
+    /// connections use the reloaded certificate.
+    pub reload_interval_secs: Option<u64>,
+}
+
+/// AuthConfig controls bearer token authentication of the REST API.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    pub enabled: bool,
+    /// Static bearer tokens accepted by the server.
+    pub tokens: Vec<ApiToken>,
+    /// Secret signing the JWTs issued by `/v1/oauth/tokens`.
+    pub jwt_secret: Option<String>,
+    /// Clients allowed to use the client credentials flow.
+    pub clients: Vec<OAuthClient>,
+    /// Lifetime of issued JWTs.
+    pub token_ttl_secs: u64,
+    /// Enforces role-based access control on authenticated callers.
+    pub rbac: bool,
+    /// Principals holding every privilege, used to bootstrap the grants.
+    pub admins: Vec<String>,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            tokens: Vec::new(),
+            jwt_secret: None,
+            clients: Vec::new(),
+            token_ttl_secs: 3600,
+            rbac: false,
+            admins: Vec::new(),
+        }
+    }
+}
+
+/// ApiToken is a static bearer token; its name identifies the caller.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ApiToken {
+    pub name: String,
+    pub token: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct OAuthClient {
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+/// BackupConfig controls RocksDB backups and checkpoints.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackupConfig {
+    /// Directory holding backups and checkpoints; backups are off when unset.
+    pub dir: Option<PathBuf>,
+    /// Seconds between scheduled backups; only on-demand backups when unset.
+    pub interval_secs: Option<u64>,
+    /// Number of most recent backups kept.
+    pub keep: usize,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            interval_secs: None,
+            keep: 7,
+        }
+    }
+}
+
+/// LimitsConfig protects the catalog from clients that overload it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    /// Limits the request rate of each principal, or of each client address
+    /// for anonymous callers, and the overall rate of each client address.
+    pub rate_limit: bool,
+    /// Sustained GET and HEAD requests per second.
+    pub reads_per_second: f64,
+    /// GET and HEAD requests allowed at once after a quiet period.
+    pub read_burst: u32,
+    pub writes_per_second: f64,
+    pub write_burst: u32,
+    /// Sustained requests per second from one client address, whoever it
+    /// authenticates as, token requests and failed authentication included.
+    pub address_requests_per_second: f64,
+    pub address_burst: u32,
+    /// Namespaces a principal may own; unlimited when unset.
+    pub max_namespaces_per_principal: Option<u64>,
+    /// Tables a principal may own; unlimited when unset.
+    pub max_tables_per_principal: Option<u64>,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            rate_limit: false,
+            reads_per_second: 100.0,
+            read_burst: 200,
+            writes_per_second: 20.0,
+            write_burst: 40,
+            address_requests_per_second: 200.0,
+            address_burst: 400,
+            max_namespaces_per_principal: None,
+            max_tables_per_principal: None,
+        }
+    }
+}
+
+/// NamingConfig holds the rules namespace levels and table names follow.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct NamingConfig {
+    /// Characters names may use, as a regular expression character class
+    /// such as `[A-Za-z0-9_]`; any but control characters when unset.
+    pub allowed_characters: Option<String>,
+    /// Most levels a namespace may have.
+    pub max_depth: usize,
+    /// Longest namespace level or table name, in bytes.
+    pub max_length: usize,
+    /// Names no namespace level or table may take, in any case.
+    pub reserved_names: Vec<String>,
+    pub case: CasePolicy,
+}
+
+impl NamingConfig {
+    /// Compiles `allowed_characters` into a pattern matching one allowed
+    /// character.
+    pub fn allowed_regex(&self) -> Result<Option<Regex>, regex::Error> {
+        self.allowed_characters
+            .as_ref()
+            .map(|class| Regex::new(&format!("^(?:{})$", class)))
+            .transpose()
+    }
+}
+
+impl Default for NamingConfig {
+    fn default() -> Self {
+        Self {
+            allowed_characters: None,
+            max_depth: 16,
+            max_length: 255,
+            reserved_names: Vec::new(),
+            case: CasePolicy::Sensitive,
+        }
+    }
+}
+
+/// How names treat case.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CasePolicy {
+    /// Names keep their case, so `Sales` and `sales` are different objects.
+    #[default]
+    Sensitive,
+    /// Names must be lowercase, so no two differ only in case.
+    Lowercase,
+    /// Names keep their case, but a new name may not differ only in case
+    /// from an existing sibling, so `Sales` and `sales` cannot both exist.
+    Insensitive,
+}
+
+impl CasePolicy {
+    /// Whether the new name `name` clashes with the existing name `other`,
+    /// that is whether they differ only in case under `Insensitive`.
+    pub fn clashes(&self, name: &str, other: &str) -> bool {
+        *self == CasePolicy::Insensitive
+            && name != other
+            && name.to_lowercase() == other.to_lowercase()
+    }
+}
+
+impl FromStr for CasePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sensitive" => Ok(CasePolicy::Sensitive),
+            "lowercase" => Ok(CasePolicy::Lowercase),
+            "insensitive" => Ok(CasePolicy::Insensitive),
+            other => Err(format!("Unknown case policy {}", other)),
+        }
+    }
+}
+
+impl Config {
+    /// Loads the configuration for the given flags from the process environment.
+    pub fn load(cli: &Cli) -> Result<Self, io::Error> {
+        Self::load_with_env(cli, |key| std::env::var(key).ok())
+    }
+
+    pub fn load_with_env<F>(cli: &Cli, env: F) -> Result<Self, io::Error>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let path = cli
+            .config
+            .clone()
+            .or_else(|| env("CATALOG_CONFIG").map(PathBuf::from));
+        let mut config = match path {
+            Some(path) => Self::from_file(&path)?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                Self::from_file(Path::new(DEFAULT_CONFIG_FILE))?
+            }
+            None => Self::default(),
+        };
+        config.apply_env(&env)?;
+        config.apply_cli(cli);
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, io::Error> {
+        let contents = fs::read_to_string(path).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("Cannot read config file {}: {}", path.display(), e),
+            )
+        })?;
+        toml::from_str(&contents).map_err(|e| {
+            io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid config file {}: {}", path.display(), e),
+            )
+        })
+    }
+
+    fn apply_env<F>(&mut self, env: &F) -> Result<(), io::Error>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        // PORT is still honoured for existing .env files.
+        if let Some(port) = parse_env(env, "PORT")? {
+            self.server.port = port;
+        }
+        if let Some(port) = parse_env(env, "CATALOG_PORT")? {
+            self.server.port = port;
+        }
+        if let Some(address) = env("CATALOG_BIND_ADDRESS") {
+            self.server.bind_address = address;
+        }
+        if let Some(secs) = parse_env(env, "CATALOG_SHUTDOWN_TIMEOUT_SECS")? {
+            self.server.shutdown_timeout_secs = secs;
+        }
+        if let Some(dir) = env("CATALOG_DATA_DIR") {
+            self.storage.data_dir = PathBuf::from(dir);
+        }
+        if let Some(value) = parse_env(env, "CATALOG_ROCKSDB_MAX_OPEN_FILES")? {
+            self.storage.rocksdb.max_open_files = value;
+        }
+        if let Some(value) = parse_env(env, "CATALOG_ROCKSDB_WRITE_BUFFER_SIZE")? {
+            self.storage.rocksdb.write_buffer_size = value;
+        }
+        if let Some(value) = parse_env(env, "CATALOG_ROCKSDB_BLOCK_CACHE_SIZE")? {
+            self.storage.rocksdb.block_cache_size = value;
+        }
+        if let Some(value) = parse_env(env, "CATALOG_ROCKSDB_PARALLELISM")? {
+            self.storage.rocksdb.parallelism = Some(value);
+        }
+        if let Some(codec) = parse_env(env, "CATALOG_VALUE_CODEC")? {
+            self.storage.encoding.codec = codec;
+        }
+        if let Some(compression) = parse_env(env, "CATALOG_VALUE_COMPRESSION")? {
+            self.storage.encoding.compression = compression;
+        }
+        if let Some(level) = env("CATALOG_LOG_LEVEL") {
+            self.log.level = level;
+        }
+        if let Some(format) = parse_env(env, "CATALOG_LOG_FORMAT")? {
+            self.log.format = format;
+        }
+        if let Some(endpoint) = env("CATALOG_LOG_OTLP_ENDPOINT") {
+            self.log.otlp_endpoint = Some(endpoint);
+        }
+        if let Some(name) = env("CATALOG_LOG_SERVICE_NAME") {
+            self.log.service_name = name;
+        }
+        if let Some(enabled) = parse_env(env, "CATALOG_TLS_ENABLED")? {
+            self.tls.enabled = enabled;
+        }
+        if let Some(path) = env("CATALOG_TLS_CERT") {
+            self.tls.cert_path = Some(PathBuf::from(path));
+        }
+        if let Some(path) = env("CATALOG_TLS_KEY") {
+            self.tls.key_path = Some(PathBuf::from(path));
+        }
+        if let Some(path) = env("CATALOG_TLS_CLIENT_CA") {
+            self.tls.client_ca_path = Some(PathBuf::from(path));
+        }
+        if let Some(secs) = parse_env(env, "CATALOG_TLS_RELOAD_INTERVAL_SECS")? {
+            self.tls.reload_interval_secs = Some(secs);
+        }
+        if let Some(enabled) = parse_env(env, "CATALOG_AUTH_ENABLED")? {
+            self.auth.enabled = enabled;
+        }
+        if let Some(tokens) = env("CATALOG_AUTH_TOKENS") {
+            self.auth.tokens = tokens
+                .split(',')
+                .map(str::trim)
+                .filter(|token| !token.is_empty())
+                .map(|token| match token.split_once('=') {
+                    Some((name, token)) => Ok(ApiToken {
+                        name: name.to_string(),
+                        token: token.to_string(),
+                    }),
+                    None => Err(io::Error::new(
+                        ErrorKind::InvalidInput,
+                        "CATALOG_AUTH_TOKENS must hold comma separated name=token pairs",
+                    )),
+                })
+                .collect::<Result<_, _>>()?;
+        }
+        if let Some(secret) = env("CATALOG_AUTH_JWT_SECRET") {
+            self.auth.jwt_secret = Some(secret);
+        }
+        if let Some(ttl) = parse_env(env, "CATALOG_AUTH_TOKEN_TTL_SECS")? {
+            self.auth.token_ttl_secs = ttl;
+        }
+        if let Some(rbac) = parse_env(env, "CATALOG_AUTH_RBAC")? {
+            self.auth.rbac = rbac;
+        }
+        if let Some(admins) = env("CATALOG_AUTH_ADMINS") {
+            self.auth.admins = admins
+                .split(',')
+                .map(str::trim)
+                .filter(|admin| !admin.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        if let Some(dir) = env("CATALOG_BACKUP_DIR") {
+            self.backup.dir = Some(PathBuf::from(dir));
+        }
+        if let Some(secs) = parse_env(env, "CATALOG_BACKUP_INTERVAL_SECS")? {
+            self.backup.interval_secs = Some(secs);
+        }
+        if let Some(keep) = parse_env(env, "CATALOG_BACKUP_KEEP")? {
+            self.backup.keep = keep;
+        }
+        if let Some(enabled) = parse_env(env, "CATALOG_LIMITS_RATE_LIMIT")? {
+            self.limits.rate_limit = enabled;
+        }
+        if let Some(rate) = parse_env(env, "CATALOG_LIMITS_READS_PER_SECOND")? {
+            self.limits.reads_per_second = rate;
+        }
+        if let Some(rate) = parse_env(env, "CATALOG_LIMITS_WRITES_PER_SECOND")? {
+            self.limits.writes_per_second = rate;
+        }
+        if let Some(max) = parse_env(env, "CATALOG_LIMITS_MAX_NAMESPACES")? {
+            self.limits.max_namespaces_per_principal = Some(max);
+        }
+        if let Some(max) = parse_env(env, "CATALOG_LIMITS_MAX_TABLES")? {
+            self.limits.max_tables_per_principal = Some(max);
+        }
+        if let Some(class) = env("CATALOG_NAMING_ALLOWED_CHARACTERS") {
+            self.naming.allowed_characters = Some(class);
+        }
+        if let Some(depth) = parse_env(env, "CATALOG_NAMING_MAX_DEPTH")? {
+            self.naming.max_depth = depth;
+        }
+        if let Some(length) = parse_env(env, "CATALOG_NAMING_MAX_LENGTH")? {
+            self.naming.max_length = length;
+        }
+        if let Some(case) = parse_env(env, "CATALOG_NAMING_CASE")? {
+            self.naming.case = case;
+        }
+        Ok(())
+    }
+
+    fn apply_cli(&mut self, cli: &Cli) {
+        if let Some(address) = &cli.bind_address {
+            self.server.bind_address = address.clone();
+        }
+        if let Some(port) = cli.port {
+            self.server.port = port;
+        }
+        if let Some(dir) = &cli.data_dir {
+            self.storage.data_dir = dir.clone();
+        }
+        if let Some(level) = &cli.log_level {
+            self.log.level = level.clone();
+        }
+        if cli.tls {
+            self.tls.enabled = true;
+        }
+        if let Some(path) = &cli.tls_cert {
+            self.tls.cert_path = Some(path.clone());
+        }
+        if let Some(path) = &cli.tls_key {
+            self.tls.key_path = Some(path.clone());
+        }
+        if let Some(path) = &cli.tls_client_ca {
+            self.tls.client_ca_path = Some(path.clone());
+        }
+    }
+
+    /// Checks the whole configuration and reports every problem at once.
+    pub fn validate(&self) -> Result<(), io::Error> {
+        let mut problems = Vec::new();
+
+        if self.server.bind_address.parse::<IpAddr>().is_err() {
+            problems.push(format!(
+                "server.bind_address '{}' is not an IP address",
+                self.server.bind_address
+            ));
+        }
+        if self.server.shutdown_timeout_secs == 0 {
+            problems.push("server.shutdown_timeout_secs must be positive".to_string());
+        }
+        if self.storage.data_dir.as_os_str().is_empty() {
+            problems.push("storage.data_dir must not be empty".to_string());
+        }
+        let rocksdb = &self.storage.rocksdb;
+        if rocksdb.write_buffer_size == 0 {
+            problems.push("storage.rocksdb.write_buffer_size must be positive".to_string());
+        }
+        if rocksdb.max_write_buffer_number < 1 {
+            problems.push("storage.rocksdb.max_write_buffer_number must be at least 1".to_string());
+        }
+        if rocksdb.parallelism.is_some_and(|threads| threads < 1) {
+            problems.push("storage.rocksdb.parallelism must be at least 1".to_string());
+        }
+        for cf in self.storage.encoding.column_families.keys() {
+            if !COLUMN_FAMILIES.contains(&cf.as_str()) {
+                problems.push(format!(
+                    "storage.encoding.column_families has unknown column family {}",
+                    cf
+                ));
+            }
+        }
+        if !LOG_LEVELS.contains(&self.log.level.to_lowercase().as_str()) {
+            problems.push(format!(
+                "log.level '{}' must be one of {}",
+                self.log.level,
+                LOG_LEVELS.join(", ")
+            ));
+        }
+        if let Some(endpoint) = &self.log.otlp_endpoint {
+            if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+                problems.push(format!(
+                    "log.otlp_endpoint '{}' must be an http:// or https:// URL",
+                    endpoint
+                ));
+            }
+        }
+        if self.log.service_name.is_empty() {
+            problems.push("log.service_name must not be empty".to_string());
+        }
+        if self.tls.enabled {
+            for (name, path) in [
+                ("tls.cert_path", &self.tls.cert_path),
+                ("tls.key_path", &self.tls.key_path),
+            ] {
+                match path {
+                    None => problems.push(format!("{} is required when TLS is enabled", name)),
+                    Some(path) if !path.exists() => {
+                        problems.push(format!("{} {} does not exist", name, path.display()))
+                    }
+                    Some(_) => {}
+                }
+            }
+            if let Some(path) = &self.tls.client_ca_path {
+                if !path.exists() {
+                    problems.push(format!(
+                        "tls.client_ca_path {} does not exist",
+                        path.display()
+                    ));
+                }
+            }
+            if self.tls.reload_interval_secs == Some(0) {
+                problems.push("tls.reload_interval_secs must be positive".to_string());
+            }
+        }
+        if self.auth.enabled && self.auth.tokens.is_empty() && self.auth.jwt_secret.is_none() {
+            problems.push(
+                "auth.tokens or auth.jwt_secret is required when auth is enabled".to_string(),
+            );
+        }
+        if self.auth.jwt_secret.as_ref().is_some_and(|s| s.is_empty()) {
+            problems.push("auth.jwt_secret must not be empty".to_string());
+        }
+        if !self.auth.clients.is_empty() && self.auth.jwt_secret.is_none() {
+            problems.push("auth.jwt_secret is required to serve auth.clients".to_string());
+        }
+        if self.auth.tokens.iter().any(|t| t.token.is_empty()) {
+            problems.push("auth.tokens must not hold empty tokens".to_string());
+        }
+        if self.auth.token_ttl_secs == 0 {
+            problems.push("auth.token_ttl_secs must be positive".to_string());
+        }
+        if self.auth.rbac && !self.auth.enabled {
+            problems.push("auth.rbac requires auth.enabled".to_string());
+        }
+        if self.backup.keep == 0 {
+            problems.push("backup.keep must be at least 1".to_string());
+        }
+        if self.backup.interval_secs == Some(0) {
+            problems.push("backup.interval_secs must be positive".to_string());
+        }
+        if self.backup.interval_secs.is_some() && self.backup.dir.is_none() {
+            problems.push("backup.dir is required for scheduled backups".to_string());
+        }
+        let limits = &self.limits;
+        for (name, rate) in [
+            ("limits.reads_per_second", limits.reads_per_second),
+            ("limits.writes_per_second", limits.writes_per_second),
+            (
+                "limits.address_requests_per_second",
+                limits.address_requests_per_second,
+            ),
+        ] {
+            if !(rate.is_finite() && rate > 0.0) {
+                problems.push(format!("{} must be positive", name));
+            }
+        }
+        if limits.read_burst == 0 || limits.write_burst == 0 || limits.address_burst == 0 {
+            problems.push(
+                "limits.read_burst, limits.write_burst and limits.address_burst must be at least 1"
+                    .to_string(),
+            );
+        }
+        let naming = &self.naming;
+        if let Err(e) = naming.allowed_regex() {
+            problems.push(format!(
+                "naming.allowed_characters '{}' is not a valid character class: {}",
+                naming.allowed_characters.as_deref().unwrap_or_default(),
+                e
+            ));
+        }
+        if naming.max_depth == 0 || naming.max_length == 0 {
+            problems.push("naming.max_depth and naming.max_length must be at least 1".to_string());
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid configuration: {}", problems.join("; ")),
+            ))
+        }
+    }
+
+    /// Address the server listens on; only valid after `validate`.
+    pub fn socket_addr(&self) -> SocketAddr {
+        let ip: IpAddr = self
+            .server
+            .bind_address
+            .parse()
+            .unwrap_or(IpAddr::from([0, 0, 0, 0]));
+        SocketAddr::new(ip, self.server.port)
+    }
+}
+
+fn parse_env<F, T>(env: &F, key: &str) -> Result<Option<T>, io::Error>
+where
+    F: Fn(&str) -> Option<String>,
+    T: FromStr,
+{
+    match env(key) {
+        None => Ok(None),
+        Some(value) => value.trim().parse().map(Some).map_err(|_| {
+            io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid value '{}' for environment variable {}", value, key),
+            )
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn env_of(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |key| vars.get(key).cloned()
+    }
+
+    fn config_file(contents: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_defaults_are_valid() {
+        let config = Config::default();
+
+        assert!(config.validate().is_ok());
+        assert_eq!(config.socket_addr().to_string(), "0.0.0.0:3000");
+        assert_eq!(config.storage.data_dir, PathBuf::from("rocksdb"));
+    }
+
+    #[test]
+    fn test_example_config_is_valid() {
+        let config: Config = toml::from_str(include_str!("../../catalog.example.toml")).unwrap();
+
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn test_load_layers_file_env_and_cli() {
+        let file = config_file(
+            r#"
+            [server]
+            port = 4000
+
+            [storage]
+            data_dir = "/var/lib/catalog"
+
+            [storage.rocksdb]
+            max_open_files = 512
+
+            [log]
+            level = "debug"
+            "#,
+        );
+        let cli = Cli {
+            config: Some(file.path().to_path_buf()),
+            log_level: Some("warn".to_string()),
+            ..Default::default()
+        };
+        let env = env_of(&[("PORT", "5000"), ("CATALOG_BIND_ADDRESS", "127.0.0.1")]);
+
+        let config = Config::load_with_env(&cli, env).unwrap();
+
+        assert_eq!(config.socket_addr().to_string(), "127.0.0.1:5000");
+        assert_eq!(config.storage.data_dir, PathBuf::from("/var/lib/catalog"));
+        assert_eq!(config.storage.rocksdb.max_open_files, 512);
+        assert_eq!(config.storage.rocksdb.max_write_buffer_number, 2);
+        assert_eq!(config.log.level, "warn");
+    }
+
+    #[test]
+    fn test_encoding_per_column_family() {
+        let file = config_file(
+            r#"
+            [storage.encoding]
+            codec = "msgpack"
+
+            [storage.encoding.column_families.TableData]
+            codec = "msgpack"
+            compression = "zstd"
+            "#,
+        );
+        let cli = Cli {
+            config: Some(file.path().to_path_buf()),
+            ..Default::default()
+        };
+        let env = env_of(&[("CATALOG_VALUE_COMPRESSION", "lz4")]);
+
+        let config = Config::load_with_env(&cli, env).unwrap();
+        let encoding = &config.storage.encoding;
+
+        assert_eq!(
+            encoding.for_cf("TableData"),
+            ValueEncoding {
+                codec: Codec::MsgPack,
+                compression: Compression::Zstd,
+            }
+        );
+        assert_eq!(
+            encoding.for_cf("NamespaceData"),
+            ValueEncoding {
+                codec: Codec::MsgPack,
+                compression: Compression::Lz4,
+            }
+        );
+
+        let mut config = Config::default();
+        config
+            .storage
+            .encoding
+            .column_families
+            .insert("Tables".to_string(), ValueEncoding::default());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_catalog_port_overrides_legacy_port() {
+        let env = env_of(&[("PORT", "5000"), ("CATALOG_PORT", "6000")]);
+
+        let config = Config::load_with_env(&Cli::default(), env).unwrap();
+
+        assert_eq!(config.server.port, 6000);
+    }
+
+    #[test]
+    fn test_invalid_env_value() {
+        let env = env_of(&[("CATALOG_PORT", "http")]);
+
+        let err = Config::load_with_env(&Cli::default(), env).unwrap_err();
+
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("CATALOG_PORT"));
+    }
+
+    #[test]
+    fn test_unknown_config_key_is_rejected() {
+        let file = config_file("[server]\nprot = 4000\n");
+
+        let err = Config::from_file(file.path()).unwrap_err();
+
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("prot"));
+    }
+
+    #[test]
+    fn test_missing_config_file() {
+        let cli = Cli {
+            config: Some(PathBuf::from("/nonexistent/catalog.toml")),
+            ..Default::default()
+        };
+
+        let err = Config::load_with_env(&cli, env_of(&[])).unwrap_err();
+
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_validate_reports_every_problem() {
+        let mut config = Config::default();
+        config.server.bind_address = "example".to_string();
+        config.server.shutdown_timeout_secs = 0;
+        config.log.level = "loud".to_string();
+        config.tls.enabled = true;
+        config.auth.enabled = true;
+
+        let err = config.validate().unwrap_err();
+        let message = err.to_string();
+
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert!(message.contains("server.bind_address"));
+        assert!(message.contains("server.shutdown_timeout_secs"));
+        assert!(message.contains("log.level"));
+        assert!(message.contains("tls.cert_path is required"));
+        assert!(message.contains("tls.key_path is required"));
+        assert!(message.contains("auth.tokens"));
+    }
+
+    #[test]
+    fn test_rbac_requires_auth() {
+        let env = env_of(&[
+            ("CATALOG_AUTH_RBAC", "true"),
+            ("CATALOG_AUTH_ADMINS", "ops, ci"),
+        ]);
+        let mut config = Config::default();
+        config.apply_env(&env).unwrap();
+
+        assert!(config.auth.rbac);
+        assert_eq!(config.auth.admins, vec!["ops", "ci"]);
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("auth.rbac requires auth.enabled"));
+    }
+
+    #[test]
+    fn test_log_format_and_otlp_endpoint_from_env() {
+        let env = env_of(&[
+            ("CATALOG_LOG_FORMAT", "JSON"),
+            ("CATALOG_LOG_OTLP_ENDPOINT", "localhost:4318"),
+        ]);
+        let mut config = Config::default();
+        config.apply_env(&env).unwrap();
+
+        assert_eq!(config.log.format, LogFormat::Json);
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("log.otlp_endpoint"));
+    }
+
+    #[test]
+    fn test_limits_from_env() {
+        let env = env_of(&[
+            ("CATALOG_LIMITS_RATE_LIMIT", "true"),
+            ("CATALOG_LIMITS_READS_PER_SECOND", "0"),
+            ("CATALOG_LIMITS_MAX_TABLES", "50"),
+        ]);
+        let mut config = Config::default();
+        config.apply_env(&env).unwrap();
+
+        assert!(config.limits.rate_limit);
+        assert_eq!(config.limits.max_tables_per_principal, Some(50));
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("limits.reads_per_second"));
+    }
+
+    #[test]
+    fn test_naming_from_env() {
+        let env = env_of(&[
+            ("CATALOG_NAMING_ALLOWED_CHARACTERS", "[a-z"),
+            ("CATALOG_NAMING_MAX_DEPTH", "0"),
+            ("CATALOG_NAMING_CASE", "Lowercase"),
+        ]);
+        let mut config = Config::default();
+        config.apply_env(&env).unwrap();
+
+        assert_eq!(config.naming.case, CasePolicy::Lowercase);
+        assert_eq!("insensitive".parse(), Ok(CasePolicy::Insensitive));
+        let message = config.validate().unwrap_err().to_string();
+        assert!(message.contains("naming.allowed_characters"));
+        assert!(message.contains("naming.max_depth"));
+
+        // A comment in verbose mode swallows the anchoring the class is
+        // matched with, so it is checked in that form.
+        config.naming = NamingConfig {
+            allowed_characters: Some("(?x)[a-z] #".to_string()),
+            ..Default::default()
+        };
+        assert!(Regex::new("(?x)[a-z] #").is_ok());
+        let message = config.validate().unwrap_err().to_string();
+        assert!(message.contains("naming.allowed_characters"));
+    }
+
+    #[test]
+    fn test_cli_parsing() {
+        let cli = Cli::try_parse_from([
+            "catalog1",
+            "--port",
+            "8080",
+            "--data-dir",
+            "/tmp/catalog",
+            "--tls",
+        ])
+        .unwrap();
+
+        assert_eq!(cli.port, Some(8080));
+        assert_eq!(cli.data_dir, Some(PathBuf::from("/tmp/catalog")));
+        assert!(cli.tls);
+    }
+}
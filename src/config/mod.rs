@@ -1 +1,2 @@
-pub mod parameters;
+#[allow(clippy::module_inception)]
+pub mod config;
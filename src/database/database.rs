@@ -1,31 +1,320 @@
-use rocksdb::{ColumnFamilyDescriptor, IteratorMode, Options, DB};
+use crate::config::config::{EncodingConfig, RocksDbConfig};
+use crate::database::format::{
+    decode_value, encode_value, FORMAT_VERSION, FORMAT_VERSION_KEY, META_CF, WRITE_PROBE_KEY,
+};
+use crate::dto::backup::BackupInfo;
+use crate::dto::health::ColumnFamilySize;
+use rocksdb::backup::{BackupEngine, BackupEngineInfo, BackupEngineOptions, RestoreOptions};
+use rocksdb::checkpoint::Checkpoint;
+use rocksdb::{
+    BlockBasedOptions, Cache, ColumnFamilyDescriptor, Direction, Env, IteratorMode, Options,
+    WriteBatch, DB,
+};
 use serde::{Deserialize, Serialize};
 use std::io::{self, ErrorKind};
 use std::path::Path;
+use tracing::instrument;
+
+/// Every column family of the catalog, in creation order.
+pub const COLUMN_FAMILIES: [&str; 11] = [
+    "NamespaceData",
+    "TableData",
+    "TableNamespaceMap",
+    "TableFiles",
+    "TableStatistics",
+    "TableSketches",
+    "TableHistograms",
+    "Grants",
+    "Ownership",
+    "SearchIndex",
+    "PropertyIndexes",
+];
+
+/// An encoded key and value as stored in RocksDB.
+pub type RawEntry = (Vec<u8>, Vec<u8>);
+
+/// An encoded key of a column family with its new value, None to delete it.
+pub type RawChange<'a> = (&'a str, Vec<u8>, Option<Vec<u8>>);
+
+/// RocksDbStats is a snapshot of RocksDB internals exported as metrics.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RocksDbStats {
+    /// Block cache hits and misses since the database was opened; zero
+    /// unless statistics are enabled.
+    pub block_cache_hits: u64,
+    pub block_cache_misses: u64,
+    pub block_cache_usage: u64,
+    pub column_families: Vec<ColumnFamilyStats>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ColumnFamilyStats {
+    pub name: String,
+    pub memtable_bytes: u64,
+    pub pending_compaction_bytes: u64,
+    pub sst_files: u64,
+}
 
 pub struct Database {
     db: DB,
+    options: Options,
+    encoding: EncodingConfig,
 }
 
 impl Database {
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, std::io::Error> {
-        let mut opts = Options::default();
+        Self::open_with_config(path, &RocksDbConfig::default(), &EncodingConfig::default())
+    }
+
+    pub fn open_with_config<P: AsRef<Path>>(
+        path: P,
+        config: &RocksDbConfig,
+        encoding: &EncodingConfig,
+    ) -> Result<Self, std::io::Error> {
+        let cf_opts = Self::tuned_options(config);
+        let mut opts = cf_opts.clone();
         opts.create_if_missing(true);
         opts.create_missing_column_families(true);
+        opts.set_max_open_files(config.max_open_files);
+        if let Some(parallelism) = config.parallelism {
+            opts.increase_parallelism(parallelism);
+        }
+        if config.statistics {
+            opts.enable_statistics();
+        }
 
-        let namespace_cf = ColumnFamilyDescriptor::new("NamespaceData", Options::default());
-        let table_cf = ColumnFamilyDescriptor::new("TableData", Options::default());
-        let table_namespace_cf =
-            ColumnFamilyDescriptor::new("TableNamespaceMap", Options::default());
-
-        let cfs_vec = vec![namespace_cf, table_cf, table_namespace_cf];
+        let cfs_vec: Vec<ColumnFamilyDescriptor> = COLUMN_FAMILIES
+            .iter()
+            .chain([META_CF].iter())
+            .map(|cf| ColumnFamilyDescriptor::new(*cf, cf_opts.clone()))
+            .collect();
 
         let db = DB::open_cf_descriptors(&opts, path, cfs_vec)
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
 
-        Ok(Self { db: db.into() })
+        let database = Self {
+            db,
+            options: opts,
+            encoding: encoding.clone(),
+        };
+        match database.format_version()? {
+            Some(version) if version > FORMAT_VERSION => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "Database uses format version {}, this build supports up to version {}",
+                        version, FORMAT_VERSION
+                    ),
+                ))
+            }
+            // Without a record the data predates versioning and is left to
+            // the migrations.
+            None if database.is_empty()? => database.set_format_version(FORMAT_VERSION)?,
+            _ => {}
+        }
+        Ok(database)
+    }
+
+    /// Returns the recorded storage format version, `None` for databases
+    /// written before the format was versioned.
+    pub fn format_version(&self) -> Result<Option<u32>, io::Error> {
+        let cf_handle = self.db.cf_handle(META_CF).ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::NotFound,
+                format!("Column family {} not found", META_CF),
+            )
+        })?;
+        match self
+            .db
+            .get_cf(cf_handle, FORMAT_VERSION_KEY)
+            .map_err(io::Error::other)?
+        {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_format_version(&self, version: u32) -> Result<(), io::Error> {
+        self.write_raw(&[(
+            META_CF,
+            FORMAT_VERSION_KEY.to_vec(),
+            serde_json::to_vec(&version)?,
+        )])
+    }
+
+    /// Writes and deletes a probe record, failing when writes are refused.
+    pub fn check_writable(&self) -> Result<(), io::Error> {
+        self.write_raw(&[(META_CF, WRITE_PROBE_KEY.to_vec(), Vec::new())])?;
+        self.delete_raw(META_CF, WRITE_PROBE_KEY)
+    }
+
+    /// RocksDB's key count and size estimates of every column family.
+    pub fn column_family_sizes(&self) -> Result<Vec<ColumnFamilySize>, io::Error> {
+        let mut sizes = Vec::new();
+        for cf in COLUMN_FAMILIES {
+            let cf_handle = self.db.cf_handle(cf).ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::NotFound,
+                    format!("Column family {} not found", cf),
+                )
+            })?;
+            let property = |name: &str| -> Result<u64, io::Error> {
+                Ok(self
+                    .db
+                    .property_int_value_cf(cf_handle, name)
+                    .map_err(io::Error::other)?
+                    .unwrap_or(0))
+            };
+            sizes.push(ColumnFamilySize {
+                name: cf.to_string(),
+                estimated_keys: property("rocksdb.estimate-num-keys")?,
+                sst_bytes: property("rocksdb.total-sst-files-size")?,
+                memtable_bytes: property("rocksdb.cur-size-all-mem-tables")?,
+            });
+        }
+        Ok(sizes)
+    }
+
+    /// Flushes the memtables of every column family and syncs the WAL, then
+    /// stops compactions and flushes running in the background.
+    pub fn close(&self) -> Result<(), io::Error> {
+        for cf in COLUMN_FAMILIES.iter().chain([&META_CF]) {
+            let cf_handle = self.db.cf_handle(cf).ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::NotFound,
+                    format!("Column family {} not found", cf),
+                )
+            })?;
+            self.db.flush_cf(cf_handle).map_err(io::Error::other)?;
+        }
+        self.db.flush_wal(true).map_err(io::Error::other)?;
+        self.db.cancel_all_background_work(true);
+        Ok(())
+    }
+
+    /// Column family options carrying the memtable and block cache tuning.
+    fn tuned_options(config: &RocksDbConfig) -> Options {
+        let mut opts = Options::default();
+        opts.set_write_buffer_size(config.write_buffer_size);
+        opts.set_max_write_buffer_number(config.max_write_buffer_number);
+        let mut block_opts = BlockBasedOptions::default();
+        block_opts.set_block_cache(&Cache::new_lru_cache(config.block_cache_size));
+        opts.set_block_based_table_factory(&block_opts);
+        opts
     }
 
+    /// Reads the block cache statistics and the memtable, compaction and SST
+    /// file figures of every column family.
+    pub fn rocksdb_stats(&self) -> Result<RocksDbStats, io::Error> {
+        let mut stats = RocksDbStats::default();
+        if let Some(statistics) = self.options.get_statistics() {
+            for line in statistics.lines() {
+                let mut fields = line.split_whitespace();
+                let counter = match (fields.next(), fields.next(), fields.next(), fields.next()) {
+                    (Some(name), Some("COUNT"), Some(":"), Some(count)) => {
+                        (name, count.parse().unwrap_or(0))
+                    }
+                    _ => continue,
+                };
+                match counter {
+                    ("rocksdb.block.cache.hit", count) => stats.block_cache_hits = count,
+                    ("rocksdb.block.cache.miss", count) => stats.block_cache_misses = count,
+                    _ => {}
+                }
+            }
+        }
+        stats.block_cache_usage = self
+            .db
+            .property_int_value("rocksdb.block-cache-usage")
+            .map_err(io::Error::other)?
+            .unwrap_or(0);
+
+        let live_files = self.db.live_files().map_err(io::Error::other)?;
+        for cf in COLUMN_FAMILIES {
+            let cf_handle = self.db.cf_handle(cf).ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::NotFound,
+                    format!("Column family {} not found", cf),
+                )
+            })?;
+            let property = |name: &str| -> Result<u64, io::Error> {
+                Ok(self
+                    .db
+                    .property_int_value_cf(cf_handle, name)
+                    .map_err(io::Error::other)?
+                    .unwrap_or(0))
+            };
+            stats.column_families.push(ColumnFamilyStats {
+                name: cf.to_string(),
+                memtable_bytes: property("rocksdb.cur-size-all-mem-tables")?,
+                pending_compaction_bytes: property("rocksdb.estimate-pending-compaction-bytes")?,
+                sst_files: live_files
+                    .iter()
+                    .filter(|file| file.column_family_name == cf)
+                    .count() as u64,
+            });
+        }
+        Ok(stats)
+    }
+
+    /// Creates a consistent, hard-linked copy of the database at `path`,
+    /// which must not exist yet.
+    pub fn create_checkpoint<P: AsRef<Path>>(&self, path: P) -> Result<(), io::Error> {
+        let checkpoint = Checkpoint::new(&self.db).map_err(io::Error::other)?;
+        checkpoint.create_checkpoint(path).map_err(io::Error::other)
+    }
+
+    /// Creates an incremental hot backup and keeps only the `keep` most recent ones.
+    pub fn create_backup<P: AsRef<Path>>(
+        &self,
+        backup_dir: P,
+        keep: usize,
+    ) -> Result<BackupInfo, io::Error> {
+        let mut engine = open_backup_engine(backup_dir)?;
+        engine
+            .create_new_backup_flush(&self.db, true)
+            .map_err(io::Error::other)?;
+        engine.purge_old_backups(keep).map_err(io::Error::other)?;
+        engine
+            .get_backup_info()
+            .into_iter()
+            .max_by_key(|info| info.backup_id)
+            .map(backup_info)
+            .ok_or_else(|| io::Error::other("Backup was not recorded"))
+    }
+
+    pub fn list_backups<P: AsRef<Path>>(backup_dir: P) -> Result<Vec<BackupInfo>, io::Error> {
+        let engine = open_backup_engine(backup_dir)?;
+        Ok(engine
+            .get_backup_info()
+            .into_iter()
+            .map(backup_info)
+            .collect())
+    }
+
+    /// Restores a backup, the latest one by default, into `db_dir`. No
+    /// database may be open on `db_dir` while restoring.
+    pub fn restore_backup<P: AsRef<Path>, Q: AsRef<Path>>(
+        backup_dir: P,
+        db_dir: Q,
+        backup_id: Option<u32>,
+    ) -> Result<(), io::Error> {
+        let mut engine = open_backup_engine(backup_dir)?;
+        let opts = RestoreOptions::default();
+        let db_dir = db_dir.as_ref();
+        match backup_id {
+            Some(id) => {
+                engine.verify_backup(id).map_err(|e| {
+                    io::Error::new(ErrorKind::NotFound, format!("Backup {}: {}", id, e))
+                })?;
+                engine.restore_from_backup(db_dir, db_dir, &opts, id)
+            }
+            None => engine.restore_from_latest_backup(db_dir, db_dir, &opts),
+        }
+        .map_err(io::Error::other)
+    }
+
+    #[instrument(level = "debug", name = "rocksdb.scan", skip_all, fields(cf = cf))]
     pub fn list_all_keys<K: Serialize + for<'de> Deserialize<'de>>(
         &self,
         cf: &str,
@@ -47,6 +336,150 @@ impl Database {
         Ok(keys)
     }
 
+    /// Returns the encoded key and value of every entry of a column family.
+    #[instrument(level = "debug", name = "rocksdb.scan", skip_all, fields(cf = cf))]
+    pub fn list_raw(&self, cf: &str) -> Result<Vec<RawEntry>, io::Error> {
+        let cf_handle = self.db.cf_handle(cf).ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::NotFound,
+                format!("Column family {} not found", cf),
+            )
+        })?;
+        let mut entries = Vec::new();
+        for item in self.db.iterator_cf(cf_handle, IteratorMode::Start) {
+            let (key, value) = item.map_err(io::Error::other)?;
+            entries.push((key.to_vec(), value.to_vec()));
+        }
+        Ok(entries)
+    }
+
+    /// Like [`list_raw`](Self::list_raw) for the entries whose key starts
+    /// with `prefix`, in key order.
+    #[instrument(level = "debug", name = "rocksdb.scan", skip_all, fields(cf = cf))]
+    pub fn list_raw_prefix(&self, cf: &str, prefix: &[u8]) -> Result<Vec<RawEntry>, io::Error> {
+        let cf_handle = self.db.cf_handle(cf).ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::NotFound,
+                format!("Column family {} not found", cf),
+            )
+        })?;
+        let mut entries = Vec::new();
+        let mode = IteratorMode::From(prefix, Direction::Forward);
+        for item in self.db.iterator_cf(cf_handle, mode) {
+            let (key, value) = item.map_err(io::Error::other)?;
+            if !key.starts_with(prefix) {
+                break;
+            }
+            entries.push((key.to_vec(), value.to_vec()));
+        }
+        Ok(entries)
+    }
+
+    pub fn is_empty(&self) -> Result<bool, io::Error> {
+        for cf in COLUMN_FAMILIES {
+            let cf_handle = self.db.cf_handle(cf).ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::NotFound,
+                    format!("Column family {} not found", cf),
+                )
+            })?;
+            if self
+                .db
+                .iterator_cf(cf_handle, IteratorMode::Start)
+                .next()
+                .is_some()
+            {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Writes already encoded entries atomically.
+    #[instrument(level = "debug", name = "rocksdb.write_batch", skip_all, fields(entries = entries.len()))]
+    pub fn write_raw(&self, entries: &[(&str, Vec<u8>, Vec<u8>)]) -> Result<(), io::Error> {
+        let mut batch = WriteBatch::default();
+        for (cf, key, value) in entries {
+            let cf_handle = self.db.cf_handle(cf).ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::NotFound,
+                    format!("Column family {} not found", cf),
+                )
+            })?;
+            batch.put_cf(cf_handle, key, value);
+        }
+        self.db.write(batch).map_err(io::Error::other)
+    }
+
+    /// Writes and deletes already encoded entries atomically.
+    #[instrument(level = "debug", name = "rocksdb.write_batch", skip_all, fields(entries = changes.len()))]
+    pub fn apply_raw(&self, changes: &[RawChange]) -> Result<(), io::Error> {
+        let mut batch = WriteBatch::default();
+        for (cf, key, value) in changes {
+            let cf_handle = self.db.cf_handle(cf).ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::NotFound,
+                    format!("Column family {} not found", cf),
+                )
+            })?;
+            match value {
+                Some(value) => batch.put_cf(cf_handle, key, value),
+                None => batch.delete_cf(cf_handle, key),
+            }
+        }
+        self.db.write(batch).map_err(io::Error::other)
+    }
+
+    #[instrument(level = "debug", name = "rocksdb.delete", skip_all, fields(cf = cf))]
+    pub fn delete_raw(&self, cf: &str, key: &[u8]) -> Result<(), io::Error> {
+        let cf_handle = self.db.cf_handle(cf).ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::NotFound,
+                format!("Column family {} not found", cf),
+            )
+        })?;
+        self.db.delete_cf(cf_handle, key).map_err(io::Error::other)
+    }
+
+    /// Encodes a value the way the column family is configured to store it.
+    pub fn encode_value<V: Serialize>(&self, cf: &str, value: &V) -> Result<Vec<u8>, io::Error> {
+        encode_value(value, self.encoding.for_cf(cf))
+    }
+
+    /// Encodes a key the way every column family stores it.
+    pub fn encode_key<K: Serialize>(&self, key: &K) -> Result<Vec<u8>, io::Error> {
+        serde_json::to_vec(key).map_err(|e| io::Error::other(e.to_string()))
+    }
+
+    /// Reads many keys of one column family in a single call, returning the
+    /// values in the order of the keys.
+    #[instrument(level = "debug", name = "rocksdb.multi_get", skip_all, fields(cf = cf, keys = keys.len()))]
+    pub fn multi_get<K: Serialize, V: for<'de> Deserialize<'de>>(
+        &self,
+        cf: &str,
+        keys: &[K],
+    ) -> Result<Vec<Option<V>>, io::Error> {
+        let cf_handle = self.db.cf_handle(cf).ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::NotFound,
+                format!("Column family {} not found", cf),
+            )
+        })?;
+        let key_bytes = keys
+            .iter()
+            .map(|key| self.encode_key(key))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.db
+            .multi_get_cf(key_bytes.iter().map(|key| (cf_handle, key)))
+            .into_iter()
+            .map(|value| match value.map_err(io::Error::other)? {
+                Some(db_vec) => decode_value(&db_vec).map(Some),
+                None => Ok(None),
+            })
+            .collect()
+    }
+
+    #[instrument(level = "debug", name = "rocksdb.put", skip_all, fields(cf = cf))]
     pub fn insert<K: Serialize, V: Serialize>(
         &self,
         cf: &str,
@@ -59,8 +492,7 @@ impl Database {
                 format!("Column family {} not found", cf),
             )
         })?;
-        let value = serde_json::to_vec(value)
-            .map_err(|e| io::Error::new(ErrorKind::Other, e.to_string()))?;
+        let value = self.encode_value(cf, value)?;
         let key_bytes =
             serde_json::to_vec(key).map_err(|e| io::Error::new(ErrorKind::Other, e.to_string()))?;
         self.db
@@ -69,6 +501,7 @@ impl Database {
         Ok(())
     }
 
+    #[instrument(level = "debug", name = "rocksdb.get", skip_all, fields(cf = cf))]
     pub fn get<K: for<'de> Deserialize<'de> + Serialize, V: for<'de> Deserialize<'de>>(
         &self,
         cf: &str,
@@ -88,13 +521,14 @@ impl Database {
             .map_err(|e| io::Error::new(ErrorKind::Other, e))?;
         match value {
             Some(db_vec) => {
-                let v: V = serde_json::from_slice(&db_vec)?;
+                let v: V = decode_value(&db_vec)?;
                 Ok(Some(v))
             }
             None => Ok(None),
         }
     }
 
+    #[instrument(level = "debug", name = "rocksdb.delete", skip_all, fields(cf = cf))]
     pub fn delete<K: for<'de> Deserialize<'de> + Serialize>(
         &self,
         cf: &str,
@@ -114,6 +548,7 @@ impl Database {
         Ok(())
     }
 
+    #[instrument(level = "debug", name = "rocksdb.put", skip_all, fields(cf = cf))]
     pub fn update<K: Serialize, V: Serialize>(
         &self,
         cf: &str,
@@ -126,8 +561,7 @@ impl Database {
                 format!("Column family {} not found", cf),
             )
         })?;
-        let value = serde_json::to_vec(value)
-            .map_err(|e| io::Error::new(ErrorKind::Other, e.to_string()))?;
+        let value = self.encode_value(cf, value)?;
         let key_bytes =
             serde_json::to_vec(key).map_err(|e| io::Error::new(ErrorKind::Other, e.to_string()))?;
         self.db
@@ -137,9 +571,25 @@ impl Database {
     }
 }
 
+fn open_backup_engine<P: AsRef<Path>>(backup_dir: P) -> Result<BackupEngine, io::Error> {
+    let opts = BackupEngineOptions::new(backup_dir).map_err(io::Error::other)?;
+    let env = Env::new().map_err(io::Error::other)?;
+    BackupEngine::open(&opts, &env).map_err(io::Error::other)
+}
+
+fn backup_info(info: BackupEngineInfo) -> BackupInfo {
+    BackupInfo {
+        backup_id: info.backup_id,
+        timestamp: info.timestamp,
+        size_in_bytes: info.size,
+        num_files: info.num_files,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::config::{Codec, Compression, ValueEncoding};
     use tempfile::tempdir;
 
     #[test]
@@ -171,6 +621,66 @@ mod tests {
         assert_eq!(retrieved_value, None);
     }
 
+    #[test]
+    fn test_batch_operations() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let key = "key1".to_string();
+        db.insert("NamespaceData", &key, &"value1").unwrap();
+
+        // Test multi_get
+        let values: Vec<Option<String>> = db
+            .multi_get("NamespaceData", &["missing".to_string(), key.clone()])
+            .unwrap();
+        assert_eq!(values, vec![None, Some("value1".to_string())]);
+
+        // Test apply_raw
+        let other = "other".to_string();
+        db.apply_raw(&[
+            ("NamespaceData", db.encode_key(&key).unwrap(), None),
+            (
+                "NamespaceData",
+                db.encode_key(&other).unwrap(),
+                Some(db.encode_value("NamespaceData", &"value2").unwrap()),
+            ),
+        ])
+        .unwrap();
+        let values: Vec<Option<String>> = db
+            .multi_get("NamespaceData", &[key.clone(), other])
+            .unwrap();
+        assert_eq!(values, vec![None, Some("value2".to_string())]);
+
+        // Test list_raw_prefix
+        db.insert("NamespaceData", &"otter".to_string(), &"value3")
+            .unwrap();
+        let keys: Vec<Vec<u8>> = db
+            .list_raw_prefix("NamespaceData", b"\"ot")
+            .unwrap()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        assert_eq!(keys, vec![b"\"other\"".to_vec(), b"\"otter\"".to_vec()]);
+        assert!(db
+            .list_raw_prefix("NamespaceData", b"\"z")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_close_keeps_data() {
+        let dir = tempdir().unwrap();
+        {
+            let db = Database::open(dir.path()).unwrap();
+            db.insert("NamespaceData", &"key1".to_string(), &"value1")
+                .unwrap();
+            db.close().unwrap();
+        }
+
+        let db = Database::open(dir.path()).unwrap();
+        let value: Option<String> = db.get("NamespaceData", &"key1".to_string()).unwrap();
+        assert_eq!(value, Some("value1".to_string()));
+    }
+
     #[test]
     fn test_database_operations_negative_paths() {
         let dir = tempdir().unwrap();
@@ -209,4 +719,64 @@ mod tests {
         let result = db.delete(non_existing_cf, &key);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_value_encoding_per_column_family() {
+        let dir = tempdir().unwrap();
+        let mut encoding = EncodingConfig::default();
+        encoding.column_families.insert(
+            "TableData".to_string(),
+            ValueEncoding {
+                codec: Codec::MsgPack,
+                compression: Compression::Lz4,
+            },
+        );
+        let key = "key1".to_string();
+        {
+            let db = Database::open_with_config(dir.path(), &RocksDbConfig::default(), &encoding)
+                .unwrap();
+            db.insert("TableData", &key, &"value1").unwrap();
+            db.insert("NamespaceData", &key, &"value2").unwrap();
+            let raw = db.list_raw("NamespaceData").unwrap();
+            assert_eq!(raw[0].1, br#"{"version":2,"data":"value2"}"#.to_vec());
+        }
+
+        // Values stay readable once the configured encoding changes.
+        let db = Database::open(dir.path()).unwrap();
+        let value: Option<String> = db.get("TableData", &key).unwrap();
+        assert_eq!(value, Some("value1".to_string()));
+    }
+
+    #[test]
+    fn test_backup_and_restore() {
+        let dir = tempdir().unwrap();
+        let backup_dir = dir.path().join("backups");
+        let key = "key1".to_string();
+        {
+            let db = Database::open(dir.path().join("db")).unwrap();
+            db.insert("NamespaceData", &key, &"value1").unwrap();
+            db.create_backup(&backup_dir, 2).unwrap();
+            db.insert("NamespaceData", &key, &"value2").unwrap();
+            db.create_backup(&backup_dir, 2).unwrap();
+            db.create_backup(&backup_dir, 2).unwrap();
+            db.create_checkpoint(dir.path().join("checkpoint")).unwrap();
+            assert!(db.create_checkpoint(dir.path().join("checkpoint")).is_err());
+        }
+
+        let backups = Database::list_backups(&backup_dir).unwrap();
+        assert_eq!(backups.len(), 2);
+
+        let restored = dir.path().join("restored");
+        Database::restore_backup(&backup_dir, &restored, Some(backups[0].backup_id)).unwrap();
+        let db = Database::open(&restored).unwrap();
+        let value: Option<String> = db.get("NamespaceData", &key).unwrap();
+        assert_eq!(value, Some("value2".to_string()));
+
+        let err = Database::restore_backup(&backup_dir, dir.path().join("x"), Some(1)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+
+        let checkpoint = Database::open(dir.path().join("checkpoint")).unwrap();
+        let value: Option<String> = checkpoint.get("NamespaceData", &key).unwrap();
+        assert_eq!(value, Some("value2".to_string()));
+    }
 }
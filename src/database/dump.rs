@@ -0,0 +1,454 @@
+use crate::database::database::{Database, COLUMN_FAMILIES};
+use crate::database::format::{self, decode_value, FORMAT_VERSION, META_CF};
+use crate::database::migration;
+use crate::database::search_index;
+use crate::dto::namespace_data::{NamespaceData, NamespaceIdent};
+use crate::dto::table_data::{Table, TableIdent};
+use crate::repository::table::TABLE_DATA_CFS;
+use crate::repository::version;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{self, BufRead, ErrorKind, Write};
+
+pub const DUMP_FORMAT: &str = "catalog1-dump";
+/// Version written by this build, which is the storage format version of the
+/// values a dump holds; older dumps are upgraded when read.
+pub const DUMP_VERSION: u32 = FORMAT_VERSION;
+
+/// DumpHeader is the first line of a dump.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DumpHeader {
+    pub format: String,
+    pub version: u32,
+    pub column_families: Vec<String>,
+}
+
+/// Every following line holds one entry; keys and values are kept exactly
+/// as encoded in the database.
+#[derive(Serialize, Deserialize, Debug)]
+struct DumpEntry {
+    cf: String,
+    key: Box<RawValue>,
+    value: Box<RawValue>,
+}
+
+/// DumpSummary counts the entries of each column family in a dump.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct DumpSummary {
+    pub version: u32,
+    pub entries: BTreeMap<String, usize>,
+}
+
+/// Dump is a parsed NDJSON dump of the whole catalog.
+#[derive(Debug)]
+pub struct Dump {
+    pub header: DumpHeader,
+    entries: Vec<(String, Vec<u8>, Vec<u8>)>,
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message)
+}
+
+/// Writes every column family of the database as NDJSON, followed by the
+/// version records of dropped objects.
+pub fn write_dump<W: Write>(db: &Database, mut writer: W) -> Result<DumpSummary, io::Error> {
+    migration::ensure_current(db)?;
+    let header = DumpHeader {
+        format: DUMP_FORMAT.to_string(),
+        version: DUMP_VERSION,
+        column_families: COLUMN_FAMILIES
+            .iter()
+            .chain([&META_CF])
+            .map(|cf| cf.to_string())
+            .collect(),
+    };
+    serde_json::to_writer(&mut writer, &header)?;
+    writer.write_all(b"\n")?;
+
+    let mut summary = DumpSummary {
+        version: DUMP_VERSION,
+        entries: BTreeMap::new(),
+    };
+    for cf in COLUMN_FAMILIES {
+        let entries = db.list_raw(cf)?;
+        for (key, value) in &entries {
+            let entry = DumpEntry {
+                cf: cf.to_string(),
+                key: raw_value(key)?,
+                value: raw_value(&format::to_json(value)?)?,
+            };
+            serde_json::to_writer(&mut writer, &entry)?;
+            writer.write_all(b"\n")?;
+        }
+        summary.entries.insert(cf.to_string(), entries.len());
+    }
+    let records = version::version_records(db)?;
+    for (key, value) in &records {
+        let entry = DumpEntry {
+            cf: META_CF.to_string(),
+            key: raw_value(key)?,
+            value: raw_value(&format::to_json(value)?)?,
+        };
+        serde_json::to_writer(&mut writer, &entry)?;
+        writer.write_all(b"\n")?;
+    }
+    summary.entries.insert(META_CF.to_string(), records.len());
+    writer.flush()?;
+    Ok(summary)
+}
+
+fn raw_value(bytes: &[u8]) -> Result<Box<RawValue>, io::Error> {
+    let text = String::from_utf8(bytes.to_vec()).map_err(|e| invalid_data(e.to_string()))?;
+    RawValue::from_string(text).map_err(|e| invalid_data(e.to_string()))
+}
+
+/// Parses a dump and checks it with [`Dump::validate`].
+pub fn read_dump<R: BufRead>(reader: R) -> Result<Dump, io::Error> {
+    let mut lines = reader.lines().enumerate();
+    let header: DumpHeader = match lines.next() {
+        Some((_, line)) => serde_json::from_str(&line?)
+            .map_err(|e| invalid_data(format!("Invalid dump header: {}", e)))?,
+        None => return Err(invalid_data("Dump is empty".to_string())),
+    };
+    if header.format != DUMP_FORMAT {
+        return Err(invalid_data(format!(
+            "Unknown dump format {}",
+            header.format
+        )));
+    }
+    if header.version == 0 || header.version > DUMP_VERSION {
+        return Err(invalid_data(format!(
+            "Unsupported dump version {}, this build reads up to version {}",
+            header.version, DUMP_VERSION
+        )));
+    }
+
+    let mut entries: Vec<(String, Vec<u8>, Vec<u8>)> = Vec::new();
+    for (index, line) in lines {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: DumpEntry = serde_json::from_str(&line)
+            .map_err(|e| invalid_data(format!("Invalid dump line {}: {}", index + 1, e)))?;
+        entries.push((
+            entry.cf,
+            entry.key.get().as_bytes().to_vec(),
+            entry.value.get().as_bytes().to_vec(),
+        ));
+    }
+
+    migration::upgrade_entries(header.version, &mut entries)?;
+
+    let dump = Dump { header, entries };
+    dump.validate()?;
+    Ok(dump)
+}
+
+fn decode<T: DeserializeOwned>(cf: &str, bytes: &[u8]) -> Result<T, String> {
+    serde_json::from_slice(bytes)
+        .map_err(|e| format!("{} entry {}: {}", cf, String::from_utf8_lossy(bytes), e))
+}
+
+fn decode_stored<T: DeserializeOwned>(cf: &str, bytes: &[u8]) -> Result<T, String> {
+    decode_value(bytes)
+        .map_err(|e| format!("{} entry {}: {}", cf, String::from_utf8_lossy(bytes), e))
+}
+
+impl Dump {
+    pub fn summary(&self) -> DumpSummary {
+        let mut entries: BTreeMap<String, usize> = COLUMN_FAMILIES
+            .iter()
+            .chain([&META_CF])
+            .map(|cf| (cf.to_string(), 0))
+            .collect();
+        for (cf, _, _) in &self.entries {
+            *entries.entry(cf.clone()).or_default() += 1;
+        }
+        DumpSummary {
+            version: self.header.version,
+            entries,
+        }
+    }
+
+    /// Checks that every entry decodes and that namespaces, tables and the
+    /// per-table column families reference each other consistently.
+    pub fn validate(&self) -> Result<(), io::Error> {
+        let mut problems = Vec::new();
+        let mut seen = HashSet::new();
+        let mut namespaces = HashSet::new();
+        let mut tables = HashSet::new();
+        let mut namespace_maps: HashMap<NamespaceIdent, Vec<TableIdent>> = HashMap::new();
+        let mut table_data_keys = Vec::new();
+
+        for (cf, key, value) in &self.entries {
+            if !COLUMN_FAMILIES.contains(&cf.as_str()) && cf != META_CF {
+                problems.push(format!("Unknown column family {}", cf));
+                continue;
+            }
+            if !seen.insert((cf.as_str(), key.as_slice())) {
+                problems.push(format!(
+                    "Duplicate {} key {}",
+                    cf,
+                    String::from_utf8_lossy(key)
+                ));
+                continue;
+            }
+            let checked = match cf.as_str() {
+                "NamespaceData" => decode::<NamespaceIdent>(cf, key).and_then(|id| {
+                    let data: NamespaceData = decode_stored(cf, value)?;
+                    if data.name != id {
+                        return Err(format!(
+                            "Namespace {:?} is stored under {:?}",
+                            data.name, id
+                        ));
+                    }
+                    namespaces.insert(id);
+                    Ok(())
+                }),
+                "TableData" => decode::<TableIdent>(cf, key).and_then(|id| {
+                    let table: Table = decode_stored(cf, value)?;
+                    if table.id != id {
+                        return Err(format!("Table {:?} is stored under {:?}", table.id, id));
+                    }
+                    tables.insert(id);
+                    Ok(())
+                }),
+                "TableNamespaceMap" => decode::<NamespaceIdent>(cf, key).and_then(|id| {
+                    namespace_maps.insert(id, decode_stored(cf, value)?);
+                    Ok(())
+                }),
+                META_CF if version::is_version_record(key) => {
+                    decode_stored::<u64>(cf, value).map(|_| ())
+                }
+                META_CF => Err(format!(
+                    "{} entry {} is not a version record",
+                    cf,
+                    String::from_utf8_lossy(key)
+                )),
+                _ if TABLE_DATA_CFS.contains(&cf.as_str()) => {
+                    decode::<TableIdent>(cf, key).map(|id| table_data_keys.push((cf.as_str(), id)))
+                }
+                _ => Ok(()),
+            };
+            if let Err(problem) = checked {
+                problems.push(problem);
+            }
+        }
+
+        for table in &tables {
+            if !namespaces.contains(&table.namespace) {
+                problems.push(format!(
+                    "Table {} belongs to missing namespace {:?}",
+                    table.name, table.namespace.0
+                ));
+            }
+            if !namespace_maps
+                .get(&table.namespace)
+                .is_some_and(|listed| listed.contains(table))
+            {
+                problems.push(format!(
+                    "Table {} is not listed in namespace {:?}",
+                    table.name, table.namespace.0
+                ));
+            }
+        }
+        for (namespace, listed) in &namespace_maps {
+            if !namespaces.contains(namespace) {
+                problems.push(format!("Table list of missing namespace {:?}", namespace.0));
+            }
+            for table in listed {
+                if table.namespace != *namespace || !tables.contains(table) {
+                    problems.push(format!(
+                        "Namespace {:?} lists missing table {}",
+                        namespace.0, table.name
+                    ));
+                }
+            }
+        }
+        for (cf, table) in &table_data_keys {
+            if !tables.contains(table) {
+                problems.push(format!("{} entry of missing table {}", cf, table.name));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(invalid_data(format!(
+                "Inconsistent dump: {}",
+                problems.join("; ")
+            )))
+        }
+    }
+}
+
+/// Restores a validated dump into a database that holds no data yet,
+/// version records included.
+pub fn restore(db: &Database, dump: &Dump) -> Result<DumpSummary, io::Error> {
+    if !db.is_empty()? {
+        return Err(io::Error::new(
+            ErrorKind::AlreadyExists,
+            "Cannot restore a dump into a database that already holds data",
+        ));
+    }
+    let entries: Vec<(&str, Vec<u8>, Vec<u8>)> = dump
+        .entries
+        .iter()
+        .map(|(cf, key, value)| (cf.as_str(), key.clone(), value.clone()))
+        .collect();
+    db.write_raw(&entries)?;
+    // Dumps taken before the search index existed carry none.
+    search_index::rebuild(db)?;
+    Ok(dump.summary())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dto::constraint::TableConstraints;
+    use crate::dto::table_data::TableMetadata;
+    use crate::repository::namespace::NamespaceRepository;
+    use serde_json::{json, Map};
+    use std::sync::{Arc, Mutex};
+    use tempfile::{tempdir, TempDir};
+
+    /// A database holding one namespace and table, with its directory.
+    fn seeded_database() -> (Database, TempDir) {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let namespace = NamespaceIdent(vec!["sales".to_string()]);
+        let table = TableIdent::new(namespace.clone(), "orders".to_string());
+        db.insert(
+            "NamespaceData",
+            &namespace,
+            &NamespaceData {
+                name: namespace.clone(),
+                properties: json!({"owner": "etl"}),
+                version: 1,
+            },
+        )
+        .unwrap();
+        db.insert(
+            "TableData",
+            &table,
+            &Table {
+                id: table.clone(),
+                metadata: TableMetadata {
+                    table_uuid: "uuid".to_string(),
+                    columns: None,
+                    constraints: TableConstraints::default(),
+                    properties: None,
+                },
+                version: 1,
+            },
+        )
+        .unwrap();
+        db.insert("TableNamespaceMap", &namespace, &vec![table.clone()])
+            .unwrap();
+        db.insert("TableStatistics", &table, &json!({"snapshots": []}))
+            .unwrap();
+        (db, dir)
+    }
+
+    #[test]
+    fn test_dump_round_trip() {
+        let (source, _source_dir) = seeded_database();
+        let mut buffer = Vec::new();
+        let summary = write_dump(&source, &mut buffer).unwrap();
+        assert_eq!(summary.entries["TableData"], 1);
+
+        let dump = read_dump(buffer.as_slice()).unwrap();
+        assert_eq!(dump.summary(), summary);
+
+        let target_dir = tempdir().unwrap();
+        let target = Database::open(target_dir.path()).unwrap();
+        restore(&target, &dump).unwrap();
+        let table = TableIdent::new(
+            NamespaceIdent(vec!["sales".to_string()]),
+            "orders".to_string(),
+        );
+        let restored: Option<Table> = target.get("TableData", &table).unwrap();
+        assert_eq!(restored.unwrap().metadata.table_uuid, "uuid");
+
+        let err = restore(&target, &dump).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+    }
+
+    #[test]
+    fn test_restore_keeps_counting_versions() {
+        let source_dir = tempdir().unwrap();
+        let source = Arc::new(Mutex::new(Database::open(source_dir.path()).unwrap()));
+        let namespaces = NamespaceRepository::new(source.clone());
+        let sales = NamespaceIdent(vec!["sales".to_string()]);
+        namespaces
+            .create_namespace(sales.clone(), None, None)
+            .unwrap();
+        namespaces
+            .set_namespace_properties(sales.clone(), vec![], Map::new(), None)
+            .unwrap();
+        namespaces.delete_namespace(&sales, None).unwrap();
+        let mut buffer = Vec::new();
+        write_dump(&source.lock().unwrap(), &mut buffer).unwrap();
+        let dump = read_dump(buffer.as_slice()).unwrap();
+        assert_eq!(dump.summary().entries[META_CF], 1);
+
+        let target_dir = tempdir().unwrap();
+        let target = Arc::new(Mutex::new(Database::open(target_dir.path()).unwrap()));
+        restore(&target.lock().unwrap(), &dump).unwrap();
+        let namespaces = NamespaceRepository::new(target);
+        namespaces
+            .create_namespace(sales.clone(), None, None)
+            .unwrap();
+        let recreated = namespaces.load_namespace(&sales).unwrap().unwrap();
+        assert_eq!(recreated.version, 3);
+    }
+
+    #[test]
+    fn test_read_dump_rejects_inconsistent_dump() {
+        let (source, _source_dir) = seeded_database();
+        source
+            .delete("NamespaceData", &NamespaceIdent(vec!["sales".to_string()]))
+            .unwrap();
+        let mut buffer = Vec::new();
+        write_dump(&source, &mut buffer).unwrap();
+
+        let err = read_dump(buffer.as_slice()).unwrap_err();
+
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err.to_string().contains("missing namespace"));
+    }
+
+    #[test]
+    fn test_read_dump_upgrades_version_1() {
+        let fixture = include_str!("../../fixtures/catalog-format-v1.ndjson");
+
+        let dump = read_dump(fixture.as_bytes()).unwrap();
+        assert_eq!(dump.header.version, 1);
+        assert_eq!(dump.summary().entries["TableData"], 2);
+
+        let dir = tempdir().unwrap();
+        let target = Database::open(dir.path()).unwrap();
+        restore(&target, &dump).unwrap();
+        let namespace = NamespaceIdent(vec!["sales".to_string()]);
+        let data: Option<NamespaceData> = target.get("NamespaceData", &namespace).unwrap();
+        assert_eq!(data.unwrap().properties["owner"], "etl");
+    }
+
+    #[test]
+    fn test_read_dump_rejects_newer_version() {
+        let dump = format!(
+            "{{\"format\":\"{}\",\"version\":{},\"column_families\":[]}}\n",
+            DUMP_FORMAT,
+            DUMP_VERSION + 1
+        );
+
+        let err = read_dump(dump.as_bytes()).unwrap_err();
+
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err.to_string().contains("Unsupported dump version"));
+    }
+}
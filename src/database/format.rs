@@ -0,0 +1,203 @@
+use crate::config::config::{Codec, Compression, ValueEncoding};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::io::{self, ErrorKind};
+
+/// Storage format written by this build.
+///
+/// Version 1 stored bare JSON values and had no format record, version 2
+/// wraps every value in an [`Envelope`].
+pub const FORMAT_VERSION: u32 = 2;
+
+/// Column family holding catalog metadata such as the format version. Dumps
+/// carry only its version records, and the emptiness check skips it.
+pub const META_CF: &str = "CatalogMeta";
+
+/// Key of the format version record in [`META_CF`].
+pub const FORMAT_VERSION_KEY: &[u8] = b"\"format_version\"";
+
+/// Key in [`META_CF`] written and deleted again by readiness checks.
+pub const WRITE_PROBE_KEY: &[u8] = b"\"write_probe\"";
+
+/// First byte of values in any encoding but plain JSON, followed by a codec
+/// byte and a compression byte. JSON text never starts with it, so plain
+/// JSON values are read as before.
+const ENCODED_MARKER: u8 = 0;
+
+/// Envelope wraps a stored value with the format version it was written in.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Envelope<V> {
+    pub version: u32,
+    pub data: V,
+}
+
+#[derive(Serialize)]
+struct EnvelopeRef<'a, V> {
+    version: u32,
+    data: &'a V,
+}
+
+fn invalid_data<E: ToString>(e: E) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, e.to_string())
+}
+
+fn codec_id(codec: Codec) -> u8 {
+    match codec {
+        Codec::Json => 0,
+        Codec::MsgPack => 1,
+    }
+}
+
+fn compression_id(compression: Compression) -> u8 {
+    match compression {
+        Compression::None => 0,
+        Compression::Lz4 => 1,
+        Compression::Zstd => 2,
+    }
+}
+
+/// Encodes a value in an envelope of the current format version.
+pub fn encode_value<V: Serialize>(
+    value: &V,
+    encoding: ValueEncoding,
+) -> Result<Vec<u8>, io::Error> {
+    let envelope = EnvelopeRef {
+        version: FORMAT_VERSION,
+        data: value,
+    };
+    let payload = match encoding.codec {
+        Codec::Json => serde_json::to_vec(&envelope).map_err(io::Error::other)?,
+        // Field names are kept so that added fields decode like with JSON.
+        Codec::MsgPack => rmp_serde::to_vec_named(&envelope).map_err(io::Error::other)?,
+    };
+    if encoding == ValueEncoding::default() {
+        return Ok(payload);
+    }
+
+    let mut bytes = vec![
+        ENCODED_MARKER,
+        codec_id(encoding.codec),
+        compression_id(encoding.compression),
+    ];
+    match encoding.compression {
+        Compression::None => bytes.extend_from_slice(&payload),
+        Compression::Lz4 => bytes.extend(lz4_flex::compress_prepend_size(&payload)),
+        Compression::Zstd => bytes.extend(zstd::encode_all(payload.as_slice(), 0)?),
+    }
+    Ok(bytes)
+}
+
+fn decode_envelope<V: DeserializeOwned>(bytes: &[u8]) -> Result<Envelope<V>, io::Error> {
+    Ok(match bytes {
+        [ENCODED_MARKER, codec, compression, payload @ ..] => {
+            let payload = match *compression {
+                0 => payload.to_vec(),
+                1 => lz4_flex::decompress_size_prepended(payload).map_err(invalid_data)?,
+                2 => zstd::decode_all(payload)?,
+                other => return Err(invalid_data(format!("Unknown compression {}", other))),
+            };
+            match *codec {
+                0 => serde_json::from_slice(&payload).map_err(invalid_data)?,
+                1 => rmp_serde::from_slice(&payload).map_err(invalid_data)?,
+                other => return Err(invalid_data(format!("Unknown codec {}", other))),
+            }
+        }
+        _ => serde_json::from_slice(bytes).map_err(invalid_data)?,
+    })
+}
+
+/// Decodes an enveloped value in any encoding, refusing values written by a
+/// newer build.
+pub fn decode_value<V: DeserializeOwned>(bytes: &[u8]) -> Result<V, io::Error> {
+    let envelope: Envelope<V> = decode_envelope(bytes)?;
+    if envelope.version > FORMAT_VERSION {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Value was written in format version {}, this build reads up to version {}",
+                envelope.version, FORMAT_VERSION
+            ),
+        ));
+    }
+    Ok(envelope.data)
+}
+
+/// Returns a stored value as plain JSON text, as dumps carry it. Plain JSON
+/// values are returned unchanged.
+pub fn to_json(bytes: &[u8]) -> Result<Vec<u8>, io::Error> {
+    if bytes.first() != Some(&ENCODED_MARKER) {
+        return Ok(bytes.to_vec());
+    }
+    let envelope: Envelope<serde_json::Value> = decode_envelope(bytes)?;
+    serde_json::to_vec(&envelope).map_err(invalid_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn encodings() -> Vec<ValueEncoding> {
+        let mut encodings = Vec::new();
+        for codec in [Codec::Json, Codec::MsgPack] {
+            for compression in [Compression::None, Compression::Lz4, Compression::Zstd] {
+                encodings.push(ValueEncoding { codec, compression });
+            }
+        }
+        encodings
+    }
+
+    #[test]
+    fn test_envelope_round_trip() {
+        let bytes = encode_value(&json!({"owner": "etl"}), ValueEncoding::default()).unwrap();
+        assert_eq!(
+            String::from_utf8(bytes.clone()).unwrap(),
+            r#"{"version":2,"data":{"owner":"etl"}}"#
+        );
+        let value: serde_json::Value = decode_value(&bytes).unwrap();
+        assert_eq!(value["owner"], "etl");
+    }
+
+    #[test]
+    fn test_every_encoding_round_trips() {
+        let value = json!({"name": ["sales"], "properties": {"rows": 12, "tags": ["a", "b"]}});
+        for encoding in encodings() {
+            let bytes = encode_value(&value, encoding).unwrap();
+            let decoded: serde_json::Value = decode_value(&bytes).unwrap();
+            assert_eq!(decoded, value, "{:?}", encoding);
+
+            let json: Envelope<serde_json::Value> =
+                serde_json::from_slice(&to_json(&bytes).unwrap()).unwrap();
+            assert_eq!(json.data, value, "{:?}", encoding);
+        }
+    }
+
+    #[test]
+    fn test_compression_shrinks_large_values() {
+        let columns: Vec<String> = (0..500).map(|i| format!("column_{}", i)).collect();
+        let json = encode_value(&columns, ValueEncoding::default()).unwrap();
+        let compressed = encode_value(
+            &columns,
+            ValueEncoding {
+                codec: Codec::MsgPack,
+                compression: Compression::Zstd,
+            },
+        )
+        .unwrap();
+
+        assert!(compressed.len() * 4 < json.len());
+    }
+
+    #[test]
+    fn test_decode_rejects_newer_and_bare_values() {
+        let newer = format!(r#"{{"version":{},"data":1}}"#, FORMAT_VERSION + 1);
+        let err = decode_value::<u32>(newer.as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let err = decode_value::<u32>(b"1").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let err = decode_value::<u32>(&[ENCODED_MARKER, 7, 0]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
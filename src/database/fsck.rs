@@ -0,0 +1,507 @@
+use crate::database::database::{Database, RawChange, RawEntry};
+use crate::database::format::decode_value;
+use crate::database::migration;
+use crate::database::search_index::{self, Posting};
+use crate::dto::data_file::FileRegistry;
+use crate::dto::fsck::{FsckIssue, FsckReport};
+use crate::dto::histogram::ColumnHistogram;
+use crate::dto::namespace_data::{NamespaceData, NamespaceIdent};
+use crate::dto::search::CatalogObject;
+use crate::dto::statistics::{ColumnSketch, TableStatistics};
+use crate::dto::table_data::{Table, TableIdent};
+use crate::repository::quota::{self, Owned, OwnershipKey, Usage};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::io;
+
+/// A change that repairs one issue, applied on encoded keys so that entries
+/// which do not decode can still be removed.
+enum Fix {
+    Delete(&'static str, Vec<u8>),
+    Put(&'static str, Vec<u8>, Vec<u8>),
+}
+
+#[derive(Default)]
+struct Scan {
+    issues: Vec<FsckIssue>,
+    fixes: Vec<Fix>,
+}
+
+fn encode<T: Serialize>(value: &T) -> io::Result<Vec<u8>> {
+    serde_json::to_vec(value).map_err(io::Error::other)
+}
+
+impl Scan {
+    /// Decodes every entry of a column family. Entries that do not decode,
+    /// or that repeat an ident under a differently encoded key, are reported
+    /// and scheduled for removal.
+    fn decode<K, V>(&mut self, cf: &'static str, entries: Vec<RawEntry>) -> io::Result<Vec<(K, V)>>
+    where
+        K: DeserializeOwned + Serialize,
+        V: DeserializeOwned,
+    {
+        let keys: HashSet<Vec<u8>> = entries.iter().map(|(key, _)| key.clone()).collect();
+        let mut decoded = Vec::new();
+        for (key, value) in entries {
+            let result = serde_json::from_slice::<K>(&key)
+                .map_err(io::Error::from)
+                .and_then(|k| decode_value::<V>(&value).map(|v| (k, v)));
+            match result {
+                Ok((k, v)) => {
+                    let canonical = encode(&k)?;
+                    if canonical != key && keys.contains(&canonical) {
+                        self.issues.push(FsckIssue::DuplicateIdent {
+                            cf: cf.to_string(),
+                            key: String::from_utf8_lossy(&key).into_owned(),
+                        });
+                        self.fixes.push(Fix::Delete(cf, key));
+                    } else {
+                        decoded.push((k, v));
+                    }
+                }
+                Err(e) => {
+                    self.issues.push(FsckIssue::UndecodableEntry {
+                        cf: cf.to_string(),
+                        key: String::from_utf8_lossy(&key).into_owned(),
+                        error: e.to_string(),
+                    });
+                    self.fixes.push(Fix::Delete(cf, key));
+                }
+            }
+        }
+        Ok(decoded)
+    }
+
+    /// Checks per-table data of one column family against the live tables.
+    fn check_table_data<V: DeserializeOwned>(
+        &mut self,
+        db: &Database,
+        cf: &'static str,
+        tables: &HashSet<TableIdent>,
+    ) -> io::Result<()> {
+        for (table, _) in self.decode::<TableIdent, V>(cf, db.list_raw(cf)?)? {
+            if !tables.contains(&table) {
+                self.fixes.push(Fix::Delete(cf, encode(&table)?));
+                self.issues.push(FsckIssue::OrphanedTableData {
+                    cf: cf.to_string(),
+                    table,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+fn scan(db: &Database) -> io::Result<Scan> {
+    let mut scan = Scan::default();
+
+    let mut namespaces = HashSet::new();
+    let mut namespace_data = Vec::new();
+    for (key, mut data) in scan
+        .decode::<NamespaceIdent, NamespaceData>("NamespaceData", db.list_raw("NamespaceData")?)?
+    {
+        if data.name != key {
+            scan.issues.push(FsckIssue::IdentMismatch {
+                cf: "NamespaceData".to_string(),
+                key: key.0.join("."),
+                stored: data.name.0.join("."),
+            });
+            data.name = key.clone();
+            scan.fixes.push(Fix::Put(
+                "NamespaceData",
+                encode(&key)?,
+                db.encode_value("NamespaceData", &data)?,
+            ));
+        }
+        namespaces.insert(key);
+        namespace_data.push(data);
+    }
+
+    // Tables whose namespace is gone, which a namespace drop refuses to
+    // leave behind, cannot be reached and are dropped.
+    let mut tables = HashSet::new();
+    let mut table_data = Vec::new();
+    for (key, mut table) in
+        scan.decode::<TableIdent, Table>("TableData", db.list_raw("TableData")?)?
+    {
+        if !namespaces.contains(&key.namespace) {
+            scan.fixes.push(Fix::Delete("TableData", encode(&key)?));
+            scan.issues.push(FsckIssue::OrphanedTable { table: key });
+            continue;
+        }
+        if table.id != key {
+            scan.issues.push(FsckIssue::IdentMismatch {
+                cf: "TableData".to_string(),
+                key: format!("{}.{}", key.namespace.0.join("."), key.name),
+                stored: format!("{}.{}", table.id.namespace.0.join("."), table.id.name),
+            });
+            table.id = key.clone();
+            scan.fixes.push(Fix::Put(
+                "TableData",
+                encode(&key)?,
+                db.encode_value("TableData", &table)?,
+            ));
+        }
+        tables.insert(key);
+        table_data.push(table);
+    }
+
+    let mut index: BTreeMap<NamespaceIdent, Vec<TableIdent>> = BTreeMap::new();
+    for (namespace, listed) in scan.decode::<NamespaceIdent, Vec<TableIdent>>(
+        "TableNamespaceMap",
+        db.list_raw("TableNamespaceMap")?,
+    )? {
+        if !namespaces.contains(&namespace) {
+            scan.fixes
+                .push(Fix::Delete("TableNamespaceMap", encode(&namespace)?));
+            scan.issues.push(FsckIssue::OrphanedIndex { namespace });
+            continue;
+        }
+        let mut kept: Vec<TableIdent> = Vec::new();
+        for table in listed.iter() {
+            if table.namespace != namespace || !tables.contains(table) {
+                scan.issues.push(FsckIssue::DanglingIndexEntry {
+                    namespace: namespace.clone(),
+                    table: table.clone(),
+                });
+            } else if kept.contains(table) {
+                scan.issues.push(FsckIssue::DuplicateIndexEntry {
+                    namespace: namespace.clone(),
+                    table: table.clone(),
+                });
+            } else {
+                kept.push(table.clone());
+            }
+        }
+        if kept != listed {
+            scan.fixes.push(Fix::Put(
+                "TableNamespaceMap",
+                encode(&namespace)?,
+                db.encode_value("TableNamespaceMap", &kept)?,
+            ));
+        }
+        index.insert(namespace, kept);
+    }
+
+    let mut unindexed: BTreeMap<NamespaceIdent, Vec<TableIdent>> = BTreeMap::new();
+    let mut sorted: Vec<&TableIdent> = tables.iter().collect();
+    sorted.sort_by(|a, b| a.name.cmp(&b.name));
+    for table in sorted {
+        let listed = index.get(&table.namespace);
+        if !listed.is_some_and(|listed| listed.contains(table)) {
+            scan.issues.push(FsckIssue::UnindexedTable {
+                table: table.clone(),
+            });
+            unindexed
+                .entry(table.namespace.clone())
+                .or_insert_with(|| listed.cloned().unwrap_or_default())
+                .push(table.clone());
+        }
+    }
+    for (namespace, listed) in unindexed {
+        scan.fixes.push(Fix::Put(
+            "TableNamespaceMap",
+            encode(&namespace)?,
+            db.encode_value("TableNamespaceMap", &listed)?,
+        ));
+    }
+
+    scan.check_table_data::<FileRegistry>(db, "TableFiles", &tables)?;
+    scan.check_table_data::<TableStatistics>(db, "TableStatistics", &tables)?;
+    scan.check_table_data::<BTreeMap<String, ColumnSketch>>(db, "TableSketches", &tables)?;
+    scan.check_table_data::<BTreeMap<String, ColumnHistogram>>(db, "TableHistograms", &tables)?;
+
+    // Owners of objects that are gone are forgotten and usage is counted
+    // again from the owners that remain.
+    let mut owners = Vec::new();
+    let mut stored_usage = BTreeMap::new();
+    for (key, value) in
+        scan.decode::<OwnershipKey, Value>("Ownership", db.list_raw("Ownership")?)?
+    {
+        let undecodable = match &key {
+            OwnershipKey::Owner(object) => match serde_json::from_value::<String>(value) {
+                Ok(principal) => {
+                    owners.push((object.clone(), principal));
+                    None
+                }
+                Err(e) => Some(e),
+            },
+            OwnershipKey::Usage(principal) => match serde_json::from_value::<Usage>(value) {
+                Ok(usage) => {
+                    stored_usage.insert(principal.clone(), usage);
+                    None
+                }
+                Err(e) => Some(e),
+            },
+        };
+        if let Some(e) = undecodable {
+            let key = encode(&key)?;
+            scan.issues.push(FsckIssue::UndecodableEntry {
+                cf: "Ownership".to_string(),
+                key: String::from_utf8_lossy(&key).into_owned(),
+                error: e.to_string(),
+            });
+            scan.fixes.push(Fix::Delete("Ownership", key));
+        }
+    }
+    let mut live_owners = Vec::new();
+    for (object, principal) in owners {
+        let live = match &object {
+            Owned::Namespace(name) => namespaces.contains(name),
+            Owned::Table(table) => tables.contains(table),
+        };
+        if live {
+            live_owners.push((object, principal));
+            continue;
+        }
+        scan.fixes.push(Fix::Delete(
+            "Ownership",
+            encode(&OwnershipKey::Owner(object.clone()))?,
+        ));
+        scan.issues.push(FsckIssue::StaleOwnership {
+            object: match object {
+                Owned::Namespace(name) => CatalogObject::Namespace(name),
+                Owned::Table(table) => CatalogObject::Table(table),
+            },
+        });
+    }
+    let counted = quota::count_usage(
+        live_owners
+            .iter()
+            .map(|(object, principal)| (object, principal)),
+    );
+    let principals: BTreeSet<&String> = stored_usage.keys().chain(counted.keys()).collect();
+    for principal in principals {
+        let usage = counted.get(principal);
+        if stored_usage.get(principal) != usage {
+            let key = encode(&OwnershipKey::Usage(principal.clone()))?;
+            scan.fixes.push(match usage {
+                Some(usage) => Fix::Put("Ownership", key, db.encode_value("Ownership", usage)?),
+                None => Fix::Delete("Ownership", key),
+            });
+            scan.issues.push(FsckIssue::UsageMismatch {
+                principal: principal.clone(),
+            });
+        }
+    }
+
+    // The search index is derived from the repaired namespaces and tables.
+    let declared = search_index::declared_keys(db)?;
+    let expected = search_index::build(&namespace_data, &table_data, &declared);
+    let mut indexed = HashSet::new();
+    for (posting, ()) in scan.decode::<Posting, ()>("SearchIndex", db.list_raw("SearchIndex")?)? {
+        if !expected.contains(&posting) {
+            scan.fixes
+                .push(Fix::Delete("SearchIndex", encode(&posting)?));
+            scan.issues.push(FsckIssue::StaleSearchEntry {
+                term: posting.0.clone(),
+            });
+        }
+        indexed.insert(posting);
+    }
+    for posting in &expected {
+        if !indexed.contains(posting) {
+            scan.fixes.push(Fix::Put(
+                "SearchIndex",
+                encode(posting)?,
+                db.encode_value("SearchIndex", &())?,
+            ));
+            scan.issues.push(FsckIssue::StaleSearchEntry {
+                term: posting.0.clone(),
+            });
+        }
+    }
+
+    Ok(scan)
+}
+
+/// Checks the invariants tying namespaces, tables, the namespace index, the
+/// search index, ownership and per-table data together, and repairs what it finds when
+/// asked to. A repair also indexes data written before the search index
+/// existed.
+///
+/// Running it twice with `repair` leaves no issues on the second run.
+pub fn fsck(db: &Database, repair: bool) -> Result<FsckReport, io::Error> {
+    // Older formats would read as undecodable and be deleted by a repair.
+    migration::ensure_current(db)?;
+    let scan = scan(db)?;
+    if repair {
+        // One batch, deletes first so that a rewrite of the same key survives.
+        let deletes = scan.fixes.iter().filter_map(|fix| match fix {
+            Fix::Delete(cf, key) => Some((*cf, key.clone(), None)),
+            Fix::Put(..) => None,
+        });
+        let puts = scan.fixes.iter().filter_map(|fix| match fix {
+            Fix::Put(cf, key, value) => Some((*cf, key.clone(), Some(value.clone()))),
+            Fix::Delete(..) => None,
+        });
+        let changes: Vec<RawChange> = deletes.chain(puts).collect();
+        db.apply_raw(&changes)?;
+    }
+    Ok(FsckReport {
+        issues: scan.issues,
+        repaired: repair,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dto::constraint::TableConstraints;
+    use crate::dto::search::{PropertyIndex, SearchTerm};
+    use crate::dto::table_data::TableMetadata;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    fn namespace(name: &str) -> NamespaceIdent {
+        NamespaceIdent(vec![name.to_string()])
+    }
+
+    fn insert_table(db: &Database, table: &TableIdent) {
+        db.insert(
+            "TableData",
+            table,
+            &Table {
+                id: table.clone(),
+                metadata: TableMetadata {
+                    table_uuid: "uuid".to_string(),
+                    columns: None,
+                    constraints: TableConstraints::default(),
+                    properties: None,
+                },
+                version: 1,
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_fsck_detects_and_repairs() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let index = PropertyIndex {
+            key: "owner".to_string(),
+        };
+        db.insert("PropertyIndexes", &index.key, &index).unwrap();
+        let sales = namespace("sales");
+        db.insert(
+            "NamespaceData",
+            &sales,
+            &NamespaceData {
+                name: namespace("other"),
+                properties: json!({"owner": "teamA"}),
+                version: 1,
+            },
+        )
+        .unwrap();
+        let orders = TableIdent::new(sales.clone(), "orders".to_string());
+        let missing = TableIdent::new(sales.clone(), "missing".to_string());
+        let unlisted = TableIdent::new(sales.clone(), "unlisted".to_string());
+        let orphan = TableIdent::new(namespace("dropped"), "orphan".to_string());
+        insert_table(&db, &orders);
+        insert_table(&db, &unlisted);
+        insert_table(&db, &orphan);
+        db.insert(
+            "TableNamespaceMap",
+            &sales,
+            &vec![orders.clone(), missing.clone(), orders.clone()],
+        )
+        .unwrap();
+        db.insert(
+            "TableNamespaceMap",
+            &namespace("dropped"),
+            &vec![orphan.clone()],
+        )
+        .unwrap();
+        db.insert("TableStatistics", &missing, &TableStatistics::default())
+            .unwrap();
+        db.write_raw(&[("TableHistograms", b"not json".to_vec(), b"{}".to_vec())])
+            .unwrap();
+        db.insert(
+            "SearchIndex",
+            &(
+                SearchTerm::Column("gone".to_string()),
+                CatalogObject::Table(missing.clone()),
+            ),
+            &(),
+        )
+        .unwrap();
+        let alice = "alice".to_string();
+        for object in [Owned::Table(orders.clone()), Owned::Table(orphan.clone())] {
+            db.insert("Ownership", &OwnershipKey::Owner(object), &alice)
+                .unwrap();
+        }
+        let stale = Usage {
+            namespaces: 1,
+            tables: 2,
+        };
+        db.insert("Ownership", &OwnershipKey::Usage(alice.clone()), &stale)
+            .unwrap();
+
+        let report = fsck(&db, false).unwrap();
+        let kinds: Vec<String> = report
+            .issues
+            .iter()
+            .map(|issue| serde_json::to_value(issue).unwrap()["kind"].to_string())
+            .collect();
+        for kind in [
+            "ident-mismatch",
+            "orphaned-table",
+            "dangling-index-entry",
+            "duplicate-index-entry",
+            "orphaned-index",
+            "unindexed-table",
+            "orphaned-table-data",
+            "undecodable-entry",
+            "stale-search-entry",
+            "stale-ownership",
+            "usage-mismatch",
+        ] {
+            assert!(kinds.contains(&format!("\"{}\"", kind)), "missing {}", kind);
+        }
+        assert_eq!(fsck(&db, false).unwrap().issues.len(), report.issues.len());
+
+        let repaired = fsck(&db, true).unwrap();
+        assert!(repaired.repaired);
+        assert_eq!(fsck(&db, false).unwrap().issues, vec![]);
+
+        // Data written without the index gets indexed, stale terms go away:
+        // the owner of the namespace and the names of it and its two tables.
+        assert_eq!(db.list_raw("SearchIndex").unwrap().len(), 4);
+        let owner = SearchTerm::Property {
+            key: "owner".to_string(),
+            value: "teamA".to_string(),
+        };
+        assert_eq!(
+            search_index::lookup(&db, &owner).unwrap(),
+            vec![CatalogObject::Namespace(sales.clone())]
+        );
+        let listed: Vec<TableIdent> = db.get("TableNamespaceMap", &sales).unwrap().unwrap();
+        assert_eq!(listed, vec![orders, unlisted]);
+        let data: NamespaceData = db.get("NamespaceData", &sales).unwrap().unwrap();
+        assert_eq!(data.name, sales);
+        assert!(db
+            .get::<TableIdent, Table>("TableData", &orphan)
+            .unwrap()
+            .is_none());
+
+        // The orphan goes back to its owner's quota.
+        let owner: Option<String> = db
+            .get("Ownership", &OwnershipKey::Owner(Owned::Table(orphan)))
+            .unwrap();
+        assert_eq!(owner, None);
+        let usage: Usage = db
+            .get("Ownership", &OwnershipKey::Usage(alice))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            usage,
+            Usage {
+                namespaces: 0,
+                tables: 1
+            }
+        );
+    }
+}
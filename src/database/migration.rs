@@ -0,0 +1,239 @@
+use crate::config::config::BackupConfig;
+use crate::database::database::{Database, COLUMN_FAMILIES};
+use crate::database::format::{Envelope, FORMAT_VERSION, FORMAT_VERSION_KEY, META_CF};
+use crate::database::search_index;
+use crate::dto::migration::{MigrationReport, MigrationStep};
+use serde_json::value::RawValue;
+use std::io::{self, ErrorKind};
+
+/// Format version of databases that carry no format record.
+pub const LEGACY_FORMAT_VERSION: u32 = 1;
+
+/// Migration upgrades every stored value from format version `to - 1` to `to`.
+pub struct Migration {
+    pub to: u32,
+    pub description: &'static str,
+    upgrade: fn(cf: &str, value: &[u8]) -> Result<Vec<u8>, String>,
+}
+
+/// Every migration in version order; the last one produces [`FORMAT_VERSION`].
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    to: 2,
+    description: "Wrap values in versioned envelopes",
+    upgrade: wrap_in_envelope,
+}];
+
+fn wrap_in_envelope(_cf: &str, value: &[u8]) -> Result<Vec<u8>, String> {
+    let data: &RawValue = serde_json::from_slice(value).map_err(|e| e.to_string())?;
+    serde_json::to_vec(&Envelope { version: 2, data }).map_err(|e| e.to_string())
+}
+
+fn pending(from: u32) -> impl Iterator<Item = &'static Migration> {
+    MIGRATIONS
+        .iter()
+        .filter(move |migration| migration.to > from)
+}
+
+fn upgrade(migration: &Migration, cf: &str, key: &[u8], value: &mut Vec<u8>) -> io::Result<()> {
+    *value = (migration.upgrade)(cf, value).map_err(|e| {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Migration to format version {} failed on {} entry {}: {}",
+                migration.to,
+                cf,
+                String::from_utf8_lossy(key),
+                e
+            ),
+        )
+    })?;
+    Ok(())
+}
+
+/// Upgrades entries written in format version `from` to the current one.
+pub fn upgrade_entries(from: u32, entries: &mut [(String, Vec<u8>, Vec<u8>)]) -> io::Result<()> {
+    for migration in pending(from) {
+        for (cf, key, value) in entries.iter_mut() {
+            upgrade(migration, cf, key, value)?;
+        }
+    }
+    Ok(())
+}
+
+/// Fails unless the database is in the format this build writes, so that
+/// tools decoding values do not misread older data.
+pub fn ensure_current(db: &Database) -> io::Result<()> {
+    let version = db.format_version()?.unwrap_or(LEGACY_FORMAT_VERSION);
+    if version != FORMAT_VERSION {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Database is in format version {}, migrate it to version {} first",
+                version, FORMAT_VERSION
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Upgrades the database to [`FORMAT_VERSION`], one migration at a time.
+///
+/// Each migration is written in a single batch together with its version
+/// record, so an interrupted upgrade resumes from the last completed step.
+/// When backups are enabled a backup is taken first to roll back to. The
+/// search index is rebuilt once the data is current. A dry run reports the
+/// migrations and the entries they would rewrite.
+pub fn migrate(db: &Database, dry_run: bool, backup: &BackupConfig) -> io::Result<MigrationReport> {
+    let from = db.format_version()?.unwrap_or(LEGACY_FORMAT_VERSION);
+    let mut report = MigrationReport {
+        from_version: from,
+        to_version: FORMAT_VERSION,
+        dry_run,
+        steps: Vec::new(),
+        backup: None,
+    };
+    if from >= FORMAT_VERSION {
+        return Ok(report);
+    }
+    if !dry_run {
+        if let Some(dir) = &backup.dir {
+            report.backup = Some(db.create_backup(dir, backup.keep)?);
+        }
+    }
+
+    let mut entries = Vec::new();
+    for cf in COLUMN_FAMILIES {
+        for (key, value) in db.list_raw(cf)? {
+            entries.push((cf, key, value));
+        }
+    }
+    for migration in pending(from) {
+        for (cf, key, value) in entries.iter_mut() {
+            upgrade(migration, cf, key, value).map_err(|e| match &report.backup {
+                Some(info) => io::Error::new(
+                    e.kind(),
+                    format!("{}; restore backup {} to roll back", e, info.backup_id),
+                ),
+                None => e,
+            })?;
+        }
+        if !dry_run {
+            let mut batch: Vec<(&str, Vec<u8>, Vec<u8>)> = entries
+                .iter()
+                .map(|(cf, key, value)| (*cf, key.clone(), value.clone()))
+                .collect();
+            batch.push((
+                META_CF,
+                FORMAT_VERSION_KEY.to_vec(),
+                serde_json::to_vec(&migration.to)?,
+            ));
+            db.write_raw(&batch)?;
+        }
+        report.steps.push(MigrationStep {
+            version: migration.to,
+            description: migration.description.to_string(),
+            entries: entries.len(),
+        });
+    }
+    if !dry_run {
+        search_index::rebuild(db)?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::fsck::fsck;
+    use crate::dto::namespace_data::{NamespaceData, NamespaceIdent};
+    use crate::dto::table_data::{Table, TableIdent};
+    use serde::Deserialize;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    #[derive(Deserialize)]
+    struct FixtureEntry {
+        cf: String,
+        key: Box<RawValue>,
+        value: Box<RawValue>,
+    }
+
+    /// Builds a database as written by format version 1 from a fixture.
+    fn legacy_database(path: &Path) -> Database {
+        let db = Database::open(path).unwrap();
+        db.delete_raw(META_CF, FORMAT_VERSION_KEY).unwrap();
+        let entries: Vec<FixtureEntry> = include_str!("../../fixtures/catalog-format-v1.ndjson")
+            .lines()
+            .skip(1)
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let raw: Vec<(&str, Vec<u8>, Vec<u8>)> = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.cf.as_str(),
+                    entry.key.get().as_bytes().to_vec(),
+                    entry.value.get().as_bytes().to_vec(),
+                )
+            })
+            .collect();
+        db.write_raw(&raw).unwrap();
+        db
+    }
+
+    #[test]
+    fn test_new_database_is_current() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        assert_eq!(db.format_version().unwrap(), Some(FORMAT_VERSION));
+
+        let report = migrate(&db, false, &BackupConfig::default()).unwrap();
+        assert!(report.steps.is_empty());
+    }
+
+    #[test]
+    fn test_migrate_format_version_1() {
+        let dir = tempdir().unwrap();
+        let db = legacy_database(&dir.path().join("db"));
+        let sales = NamespaceIdent(vec!["sales".to_string()]);
+        assert!(db.get::<_, NamespaceData>("NamespaceData", &sales).is_err());
+        assert!(ensure_current(&db).is_err());
+
+        let dry_run = migrate(&db, true, &BackupConfig::default()).unwrap();
+        assert_eq!(dry_run.from_version, LEGACY_FORMAT_VERSION);
+        assert_eq!(dry_run.steps.len(), 1);
+        assert_eq!(dry_run.steps[0].entries, 7);
+        assert_eq!(db.format_version().unwrap(), None);
+
+        let backup = BackupConfig {
+            dir: Some(dir.path().join("backups")),
+            ..Default::default()
+        };
+        let report = migrate(&db, false, &backup).unwrap();
+        assert!(report.backup.is_some());
+        assert_eq!(db.format_version().unwrap(), Some(FORMAT_VERSION));
+        let data: NamespaceData = db.get("NamespaceData", &sales).unwrap().unwrap();
+        assert_eq!(data.properties["owner"], "etl");
+        let customers = TableIdent::new(
+            NamespaceIdent(vec!["sales".to_string(), "eu".to_string()]),
+            "customers".to_string(),
+        );
+        let table: Table = db.get("TableData", &customers).unwrap().unwrap();
+        assert!(table.metadata.constraints.primary_key.is_none());
+        assert!(fsck(&db, false).unwrap().issues.is_empty());
+
+        let again = migrate(&db, false, &backup).unwrap();
+        assert!(again.steps.is_empty());
+    }
+
+    #[test]
+    fn test_open_rejects_newer_format() {
+        let dir = tempdir().unwrap();
+        {
+            let db = Database::open(dir.path()).unwrap();
+            db.set_format_version(FORMAT_VERSION + 1).unwrap();
+        }
+        let err = Database::open(dir.path()).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
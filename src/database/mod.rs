@@ -1 +1,6 @@
 pub mod database;
+pub mod dump;
+pub mod format;
+pub mod fsck;
+pub mod migration;
+pub mod search_index;
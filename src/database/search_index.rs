@@ -0,0 +1,391 @@
+use crate::database::database::{Database, RawChange};
+use crate::dto::namespace_data::{NamespaceData, NamespaceIdent};
+use crate::dto::search::{CatalogObject, SearchTerm};
+use crate::dto::table_data::{Table, TableIdent};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::io::{self, ErrorKind};
+
+/// Key of a search index entry: a term and an object listed under it. The
+/// entries of a term share its encoding as key prefix and carry no value,
+/// so that listing or unlisting an object writes one small entry.
+pub type Posting = (SearchTerm, CatalogObject);
+
+/// Property keys with a declared index.
+pub fn declared_keys(db: &Database) -> io::Result<HashSet<String>> {
+    Ok(db
+        .list_all_keys::<String>("PropertyIndexes")?
+        .into_iter()
+        .collect())
+}
+
+/// Renders a property value the way it is indexed; only scalars are.
+pub fn property_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(flag) => Some(flag.to_string()),
+        _ => None,
+    }
+}
+
+/// Terms a namespace is listed under.
+pub fn namespace_terms(data: &NamespaceData, declared: &HashSet<String>) -> Vec<SearchTerm> {
+    let properties = data
+        .properties
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(key, _)| declared.contains(*key))
+        .filter_map(|(key, value)| {
+            property_text(value).map(|value| SearchTerm::Property {
+                key: key.clone(),
+                value,
+            })
+        });
+    std::iter::once(SearchTerm::Name(data.name.to_string()))
+        .chain(properties)
+        .collect()
+}
+
+/// Terms a table is listed under.
+pub fn table_terms(table: &Table, declared: &HashSet<String>) -> Vec<SearchTerm> {
+    let columns = table
+        .metadata
+        .columns
+        .iter()
+        .flatten()
+        .map(|column| SearchTerm::Column(column.name.clone()));
+    let properties = table
+        .metadata
+        .properties
+        .iter()
+        .flatten()
+        .filter(|(key, _)| declared.contains(*key))
+        .map(|(key, value)| SearchTerm::Property {
+            key: key.clone(),
+            value: value.clone(),
+        });
+    let references = table
+        .metadata
+        .constraints
+        .foreign_keys
+        .iter()
+        .map(|fk| SearchTerm::References(fk.referenced_table.clone()));
+    let mut terms: Vec<SearchTerm> = std::iter::once(SearchTerm::Name(table.id.to_string()))
+        .chain(columns)
+        .chain(properties)
+        .chain(references)
+        .collect();
+    terms.sort();
+    terms.dedup();
+    terms
+}
+
+/// IndexUpdate collects the index changes that go with writes to namespaces
+/// and tables, so that both land in the same batch.
+pub struct IndexUpdate<'a> {
+    db: &'a Database,
+    declared: HashSet<String>,
+    /// Entries to write, true to list the object and false to unlist it.
+    postings: BTreeMap<Posting, bool>,
+}
+
+impl<'a> IndexUpdate<'a> {
+    pub fn new(db: &'a Database) -> io::Result<Self> {
+        Ok(Self {
+            db,
+            declared: declared_keys(db)?,
+            postings: BTreeMap::new(),
+        })
+    }
+
+    /// Records a namespace changing from `old` to `new`, where None stands
+    /// for a namespace that does not exist.
+    pub fn namespace(
+        &mut self,
+        old: Option<&NamespaceData>,
+        new: Option<&NamespaceData>,
+    ) -> io::Result<()> {
+        let Some(data) = new.or(old) else {
+            return Ok(());
+        };
+        let object = CatalogObject::Namespace(data.name.clone());
+        let old = old.map_or_else(Vec::new, |data| namespace_terms(data, &self.declared));
+        let new = new.map_or_else(Vec::new, |data| namespace_terms(data, &self.declared));
+        self.relist(object, &old, &new);
+        Ok(())
+    }
+
+    /// Records a table changing from `old` to `new`, where None stands for a
+    /// table that does not exist.
+    pub fn table(&mut self, old: Option<&Table>, new: Option<&Table>) -> io::Result<()> {
+        let Some(table) = new.or(old) else {
+            return Ok(());
+        };
+        let object = CatalogObject::Table(table.id.clone());
+        let old = old.map_or_else(Vec::new, |table| table_terms(table, &self.declared));
+        let new = new.map_or_else(Vec::new, |table| table_terms(table, &self.declared));
+        self.relist(object, &old, &new);
+        Ok(())
+    }
+
+    fn relist(&mut self, object: CatalogObject, old: &[SearchTerm], new: &[SearchTerm]) {
+        for term in old.iter().filter(|term| !new.contains(term)) {
+            self.postings.insert((term.clone(), object.clone()), false);
+        }
+        for term in new.iter().filter(|term| !old.contains(term)) {
+            self.postings.insert((term.clone(), object.clone()), true);
+        }
+    }
+
+    /// Writes `changes` together with the index changes in one batch.
+    pub fn commit(self, mut changes: Vec<RawChange>) -> io::Result<()> {
+        for (posting, listed) in &self.postings {
+            changes.push(if *listed {
+                put(self.db, posting)?
+            } else {
+                ("SearchIndex", self.db.encode_key(posting)?, None)
+            });
+        }
+        self.db.apply_raw(&changes)
+    }
+}
+
+/// Entries of the terms whose encoding starts with `term_prefix`.
+fn postings(db: &Database, term_prefix: &[u8]) -> io::Result<Vec<Posting>> {
+    // A posting encodes as `[<term>,<object>]`.
+    let mut prefix = b"[".to_vec();
+    prefix.extend_from_slice(term_prefix);
+    db.list_raw_prefix("SearchIndex", &prefix)?
+        .into_iter()
+        .map(|(key, _)| {
+            serde_json::from_slice(&key).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+        })
+        .collect()
+}
+
+/// Encoding of `term` up to its last string, left open, which escaping
+/// keeps a prefix of the encoding of every term extending that string.
+fn open_prefix(db: &Database, term: &SearchTerm) -> io::Result<Vec<u8>> {
+    let mut prefix = db.encode_key(term)?;
+    let open = prefix.iter().rposition(|byte| *byte == b'"').unwrap_or(0);
+    prefix.truncate(open);
+    Ok(prefix)
+}
+
+/// Objects listed under a term.
+pub fn lookup(db: &Database, term: &SearchTerm) -> io::Result<Vec<CatalogObject>> {
+    let mut prefix = db.encode_key(term)?;
+    prefix.push(b',');
+    Ok(postings(db, &prefix)?
+        .into_iter()
+        .map(|(_, object)| object)
+        .collect())
+}
+
+/// Namespaces and tables whose dotted name starts with `prefix`.
+pub fn named_with_prefix(db: &Database, prefix: &str) -> io::Result<Vec<CatalogObject>> {
+    let term = open_prefix(db, &SearchTerm::Name(prefix.to_string()))?;
+    Ok(postings(db, &term)?
+        .into_iter()
+        .map(|(_, object)| object)
+        .collect())
+}
+
+/// Tables other than `table` whose foreign keys reference it.
+pub fn referencing(db: &Database, table: &TableIdent) -> io::Result<Vec<TableIdent>> {
+    Ok(lookup(db, &SearchTerm::References(table.clone()))?
+        .into_iter()
+        .filter_map(|object| match object {
+            CatalogObject::Table(id) if id != *table => Some(id),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Entries of every value of the property `key`.
+pub fn property_postings(db: &Database, key: &str) -> io::Result<Vec<Posting>> {
+    let term = SearchTerm::Property {
+        key: key.to_string(),
+        value: String::new(),
+    };
+    postings(db, &open_prefix(db, &term)?)
+}
+
+/// Objects whose property `key` is `value`, found through the index when
+/// the key has one and by scanning every namespace and table otherwise.
+pub fn with_property(db: &Database, key: &str, value: &str) -> io::Result<Vec<CatalogObject>> {
+    if declared_keys(db)?.contains(key) {
+        let term = SearchTerm::Property {
+            key: key.to_string(),
+            value: value.to_string(),
+        };
+        return lookup(db, &term);
+    }
+    let (namespaces, tables) = load_all(db)?;
+    let namespaces = namespaces
+        .into_iter()
+        .filter(|data| data.properties.get(key).and_then(property_text).as_deref() == Some(value))
+        .map(|data| CatalogObject::Namespace(data.name));
+    let tables = tables
+        .into_iter()
+        .filter(|table| {
+            table
+                .metadata
+                .properties
+                .as_ref()
+                .and_then(|properties| properties.get(key))
+                .is_some_and(|property| property == value)
+        })
+        .map(|table| CatalogObject::Table(table.id));
+    Ok(namespaces.chain(tables).collect())
+}
+
+/// Every stored namespace and table.
+pub fn load_all(db: &Database) -> io::Result<(Vec<NamespaceData>, Vec<Table>)> {
+    let names: Vec<NamespaceIdent> = db.list_all_keys("NamespaceData")?;
+    let namespaces: Vec<Option<NamespaceData>> = db.multi_get("NamespaceData", &names)?;
+    let ids: Vec<TableIdent> = db.list_all_keys("TableData")?;
+    let tables: Vec<Option<Table>> = db.multi_get("TableData", &ids)?;
+    Ok((
+        namespaces.into_iter().flatten().collect(),
+        tables.into_iter().flatten().collect(),
+    ))
+}
+
+/// The index entries there should be for the given namespaces and tables.
+pub fn build<'a>(
+    namespaces: impl IntoIterator<Item = &'a NamespaceData>,
+    tables: impl IntoIterator<Item = &'a Table>,
+    declared: &HashSet<String>,
+) -> BTreeSet<Posting> {
+    let mut index = BTreeSet::new();
+    for data in namespaces {
+        for term in namespace_terms(data, declared) {
+            index.insert((term, CatalogObject::Namespace(data.name.clone())));
+        }
+    }
+    for table in tables {
+        for term in table_terms(table, declared) {
+            index.insert((term, CatalogObject::Table(table.id.clone())));
+        }
+    }
+    index
+}
+
+/// Change listing an object under a term.
+pub fn put(db: &Database, posting: &Posting) -> io::Result<RawChange<'static>> {
+    Ok((
+        "SearchIndex",
+        db.encode_key(posting)?,
+        Some(db.encode_value("SearchIndex", &())?),
+    ))
+}
+
+/// Replaces the whole index with one built from the stored namespaces and
+/// tables, for data written without it. Returns the number of entries.
+pub fn rebuild(db: &Database) -> io::Result<usize> {
+    let (namespaces, tables) = load_all(db)?;
+    let index = build(&namespaces, &tables, &declared_keys(db)?);
+
+    let mut changes = Vec::new();
+    for (key, _) in db.list_raw("SearchIndex")? {
+        changes.push(("SearchIndex", key, None));
+    }
+    for posting in &index {
+        changes.push(put(db, posting)?);
+    }
+    db.apply_raw(&changes)?;
+    Ok(index.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dto::constraint::TableConstraints;
+    use crate::dto::search::PropertyIndex;
+    use crate::dto::table_data::TableMetadata;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_index_update() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let declaration = PropertyIndex {
+            key: "owner".to_string(),
+        };
+        db.insert("PropertyIndexes", &declaration.key, &declaration)
+            .unwrap();
+        let data = NamespaceData {
+            name: NamespaceIdent(vec!["sales".to_string()]),
+            properties: json!({"owner": "teamA", "replicas": 3}),
+            version: 1,
+        };
+        let table = Table {
+            id: TableIdent::new(data.name.clone(), "orders".to_string()),
+            metadata: TableMetadata {
+                table_uuid: "uuid".to_string(),
+                columns: None,
+                constraints: TableConstraints::default(),
+                properties: Some([("owner".to_string(), "teamA".to_string())].into()),
+            },
+            version: 1,
+        };
+        let owner = SearchTerm::Property {
+            key: "owner".to_string(),
+            value: "teamA".to_string(),
+        };
+        let declared = declared_keys(&db).unwrap();
+        // Only declared keys are indexed, names always are.
+        assert_eq!(
+            namespace_terms(&data, &declared),
+            vec![SearchTerm::Name("sales".to_string()), owner.clone()]
+        );
+
+        let namespace = CatalogObject::Namespace(data.name.clone());
+        let orders = CatalogObject::Table(table.id.clone());
+        let mut index = IndexUpdate::new(&db).unwrap();
+        index.namespace(None, Some(&data)).unwrap();
+        index.table(None, Some(&table)).unwrap();
+        index.commit(vec![]).unwrap();
+        assert_eq!(
+            lookup(&db, &owner).unwrap(),
+            vec![namespace.clone(), orders.clone()]
+        );
+        let built = build([&data], [&table], &declared);
+        assert_eq!(built.len(), 4);
+        assert!(built.contains(&(owner.clone(), orders.clone())));
+        assert_eq!(property_postings(&db, "owner").unwrap().len(), 2);
+        assert_eq!(
+            named_with_prefix(&db, "sales").unwrap(),
+            vec![namespace.clone(), orders.clone()]
+        );
+        assert_eq!(
+            named_with_prefix(&db, "sales.o").unwrap(),
+            vec![orders.clone()]
+        );
+        assert_eq!(named_with_prefix(&db, "sales.x").unwrap(), vec![]);
+
+        // Without the index the property is found by scanning.
+        db.insert("NamespaceData", &data.name, &data).unwrap();
+        assert_eq!(
+            with_property(&db, "replicas", "3").unwrap(),
+            vec![namespace.clone()]
+        );
+
+        db.delete("SearchIndex", &(owner.clone(), namespace.clone()))
+            .unwrap();
+        assert_eq!(rebuild(&db).unwrap(), 2);
+        assert_eq!(
+            with_property(&db, "owner", "teamA").unwrap(),
+            vec![namespace.clone()]
+        );
+
+        let mut index = IndexUpdate::new(&db).unwrap();
+        index.namespace(Some(&data), None).unwrap();
+        index.commit(vec![]).unwrap();
+        assert!(db.list_raw("SearchIndex").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,129 @@
+use crate::dto::namespace_data::NamespaceIdent;
+use crate::dto::table_data::TableIdent;
+use serde::{Deserialize, Serialize};
+
+/// Privilege is an action a grant allows.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Privilege {
+    ReadMetadata,
+    CreateNamespace,
+    CreateTable,
+    Drop,
+    /// Changes to properties, constraints, files and statistics.
+    Commit,
+    ManageGrants,
+    /// Whole-catalog maintenance: dumps, backups and consistency checks.
+    ManageCatalog,
+}
+
+/// GrantScope is what a grant applies to; a namespace scope covers every
+/// nested namespace and table.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum GrantScope {
+    Catalog,
+    Namespace { namespace: NamespaceIdent },
+    Table { table: TableIdent },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grant {
+    pub privilege: Privilege,
+    pub scope: GrantScope,
+}
+
+/// Role is a named set of grants assigned to principals.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Role {
+    pub name: String,
+    #[serde(default)]
+    pub grants: Vec<Grant>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CreateRoleRequest {
+    pub name: String,
+}
+
+/// Resource is the target of a request checked against grants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource<'a> {
+    Catalog,
+    Namespace(&'a NamespaceIdent),
+    Table(&'a TableIdent),
+}
+
+impl GrantScope {
+    /// The resource the scope is rooted at.
+    pub fn resource(&self) -> Resource<'_> {
+        match self {
+            GrantScope::Catalog => Resource::Catalog,
+            GrantScope::Namespace { namespace } => Resource::Namespace(namespace),
+            GrantScope::Table { table } => Resource::Table(table),
+        }
+    }
+
+    /// Whether a grant on this scope covers the resource.
+    pub fn covers(&self, resource: &Resource) -> bool {
+        match (self, resource) {
+            (GrantScope::Catalog, _) => true,
+            (GrantScope::Namespace { namespace }, Resource::Namespace(target)) => {
+                target.0.starts_with(&namespace.0)
+            }
+            (GrantScope::Namespace { namespace }, Resource::Table(table)) => {
+                table.namespace.0.starts_with(&namespace.0)
+            }
+            (GrantScope::Table { table }, Resource::Table(target)) => table == *target,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn namespace(parts: &[&str]) -> NamespaceIdent {
+        NamespaceIdent(parts.iter().map(|p| p.to_string()).collect())
+    }
+
+    #[test]
+    fn test_grant_serialization() {
+        let grant = Grant {
+            privilege: Privilege::CreateTable,
+            scope: GrantScope::Namespace {
+                namespace: namespace(&["sales"]),
+            },
+        };
+
+        assert_eq!(
+            serde_json::to_value(&grant).unwrap(),
+            json!({
+                "privilege": "CREATE_TABLE",
+                "scope": {"type": "namespace", "namespace": ["sales"]}
+            })
+        );
+    }
+
+    #[test]
+    fn test_scope_covers_subtree() {
+        let sales = GrantScope::Namespace {
+            namespace: namespace(&["sales"]),
+        };
+        let eu = namespace(&["sales", "eu"]);
+        let orders = TableIdent::new(eu.clone(), "orders".to_string());
+
+        assert!(sales.covers(&Resource::Namespace(&eu)));
+        assert!(sales.covers(&Resource::Table(&orders)));
+        assert!(!sales.covers(&Resource::Namespace(&namespace(&["salesforce"]))));
+        assert!(!sales.covers(&Resource::Catalog));
+        assert!(GrantScope::Catalog.covers(&Resource::Table(&orders)));
+        let table = GrantScope::Table {
+            table: orders.clone(),
+        };
+        assert!(table.covers(&Resource::Table(&orders)));
+        assert!(!table.covers(&Resource::Namespace(&eu)));
+    }
+}
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+/// BackupInfo describes one backup held by the backup engine.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BackupInfo {
+    pub backup_id: u32,
+    /// Seconds since the Unix epoch.
+    pub timestamp: i64,
+    pub size_in_bytes: u64,
+    pub num_files: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CheckpointResponse {
+    pub path: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backup_info_serialization() {
+        let info = BackupInfo {
+            backup_id: 3,
+            timestamp: 1700000000,
+            size_in_bytes: 4096,
+            num_files: 5,
+        };
+
+        let serialized = serde_json::to_string(&info).unwrap();
+
+        assert_eq!(
+            serialized,
+            r#"{"backup_id":3,"timestamp":1700000000,"size_in_bytes":4096,"num_files":5}"#
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use crate::dto::namespace_data::{NamespaceData, NamespaceIdent};
+use crate::dto::table_data::{Table, TableCreation, TableIdent};
+use serde::{Deserialize, Serialize};
+
+/// Most items a single batch request may hold.
+pub const MAX_BATCH_SIZE: usize = 1000;
+
+/// BatchCreateNamespacesRequest creates many namespaces in one call. When
+/// `atomic` is set either all of them are created or none.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatchCreateNamespacesRequest {
+    pub namespaces: Vec<NamespaceData>,
+    #[serde(default)]
+    pub atomic: bool,
+}
+
+/// BatchTableCreation is a table to create together with its namespace.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BatchTableCreation {
+    pub namespace: NamespaceIdent,
+    #[serde(flatten)]
+    pub table: TableCreation,
+}
+
+impl BatchTableCreation {
+    pub fn ident(&self) -> TableIdent {
+        TableIdent::new(self.namespace.clone(), self.table.name.clone())
+    }
+}
+
+/// BatchCreateTablesRequest creates many tables, possibly in different
+/// namespaces, in one call. When `atomic` is set either all of them are
+/// created or none.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatchCreateTablesRequest {
+    pub tables: Vec<BatchTableCreation>,
+    #[serde(default)]
+    pub atomic: bool,
+}
+
+/// BatchItemResult is the outcome of one item of a batch, given as the
+/// status code a single request for it would have returned.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BatchItemResult<I> {
+    pub id: I,
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// BatchCreateResponse lists the outcome of every item, in request order.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BatchCreateResponse<I> {
+    pub results: Vec<BatchItemResult<I>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatchLoadTablesRequest {
+    pub tables: Vec<TableIdent>,
+}
+
+/// BatchLoadTablesResponse holds the tables in request order, with null
+/// for those that do not exist.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatchLoadTablesResponse {
+    pub tables: Vec<Option<Table>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_table_creation_deserialization() {
+        let data = r#"
+        {
+            "tables": [
+                {"namespace": ["sales"], "name": "orders"},
+                {"namespace": ["sales", "eu"], "name": "items", "columns": []}
+            ]
+        }
+        "#;
+
+        let request: BatchCreateTablesRequest = serde_json::from_str(data).unwrap();
+        assert!(!request.atomic);
+        assert_eq!(request.tables[0].table.name, "orders");
+        assert_eq!(request.tables[0].table.columns, None);
+        assert_eq!(request.tables[1].ident().to_string(), "sales.eu.items");
+    }
+
+    #[test]
+    fn test_batch_item_result_serialization() {
+        let result = BatchItemResult {
+            id: NamespaceIdent(vec!["sales".to_string()]),
+            status: 201,
+            error: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&result).unwrap(),
+            r#"{"id":["sales"],"status":201}"#
+        );
+    }
+}
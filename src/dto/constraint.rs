@@ -0,0 +1,119 @@
+use crate::dto::table_data::TableIdent;
+use serde::{Deserialize, Serialize};
+
+/// TableConstraints holds the key and dependency constraints declared on a table.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct TableConstraints {
+    #[serde(default)]
+    pub primary_key: Option<PrimaryKey>,
+    #[serde(default)]
+    pub unique_keys: Vec<UniqueKey>,
+    #[serde(default)]
+    pub foreign_keys: Vec<ForeignKey>,
+    #[serde(default)]
+    pub functional_dependencies: Vec<FunctionalDependency>,
+}
+
+impl TableConstraints {
+    /// Whether the columns, in order, form the primary key or a unique key.
+    pub fn is_key(&self, columns: &[String]) -> bool {
+        self.primary_key
+            .as_ref()
+            .is_some_and(|pk| pk.columns == columns)
+            || self.unique_keys.iter().any(|uk| uk.columns == columns)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PrimaryKey {
+    pub columns: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct UniqueKey {
+    pub name: String,
+    pub columns: Vec<String>,
+}
+
+/// ForeignKey references the primary key or a unique key of another table.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ForeignKey {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub referenced_table: TableIdent,
+    pub referenced_columns: Vec<String>,
+}
+
+/// FunctionalDependency states that the `determinant` columns determine the
+/// `dependent` ones.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FunctionalDependency {
+    pub determinant: Vec<String>,
+    pub dependent: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TableConstraintsEntry {
+    pub table: TableIdent,
+    pub constraints: TableConstraints,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dto::namespace_data::NamespaceIdent;
+
+    #[test]
+    fn test_table_constraints_deserialization() {
+        let data = r#"
+            {
+                "primary_key": {"columns": ["id"]},
+                "foreign_keys": [
+                    {
+                        "name": "fk_customer",
+                        "columns": ["customer_id"],
+                        "referenced_table": {"namespace": ["sales"], "name": "customers"},
+                        "referenced_columns": ["id"]
+                    }
+                ]
+            }
+            "#;
+
+        let constraints: TableConstraints = serde_json::from_str(data).unwrap();
+
+        assert_eq!(
+            constraints.primary_key,
+            Some(PrimaryKey {
+                columns: vec!["id".to_string()]
+            })
+        );
+        assert!(constraints.unique_keys.is_empty());
+        assert_eq!(
+            constraints.foreign_keys[0].referenced_table,
+            TableIdent::new(
+                NamespaceIdent(vec!["sales".to_string()]),
+                "customers".to_string()
+            )
+        );
+        assert!(constraints.functional_dependencies.is_empty());
+    }
+
+    #[test]
+    fn test_is_key() {
+        let constraints = TableConstraints {
+            primary_key: Some(PrimaryKey {
+                columns: vec!["id".to_string()],
+            }),
+            unique_keys: vec![UniqueKey {
+                name: "uk_email".to_string(),
+                columns: vec!["email".to_string()],
+            }],
+            foreign_keys: vec![],
+            functional_dependencies: vec![],
+        };
+
+        assert!(constraints.is_key(&["id".to_string()]));
+        assert!(constraints.is_key(&["email".to_string()]));
+        assert!(!constraints.is_key(&["name".to_string()]));
+    }
+}
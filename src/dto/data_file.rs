@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// DataFile describes a single data file of a table together with the
+/// statistics used to prune it during scan planning.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DataFile {
+    pub file_path: String,
+    #[serde(default)]
+    pub file_format: Option<String>,
+    /// Partition values of the file, keyed by partition column name.
+    #[serde(default)]
+    pub partition: BTreeMap<String, Value>,
+    /// Transform of each partition value, such as `bucket[16]` or `day`.
+    /// Values without one are identity values, the column's own value.
+    #[serde(default)]
+    pub partition_transforms: BTreeMap<String, String>,
+    pub record_count: u64,
+    #[serde(default)]
+    pub file_size_in_bytes: u64,
+    #[serde(default)]
+    pub null_value_counts: BTreeMap<String, u64>,
+    #[serde(default)]
+    pub nan_value_counts: BTreeMap<String, u64>,
+    /// Inclusive lower bound of each column's values in the file.
+    #[serde(default)]
+    pub lower_bounds: BTreeMap<String, Value>,
+    /// Inclusive upper bound of each column's values in the file.
+    #[serde(default)]
+    pub upper_bounds: BTreeMap<String, Value>,
+}
+
+/// RegisteredDataFile is a data file as kept in the file registry, with the
+/// snapshots that added and removed it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RegisteredDataFile {
+    pub added_snapshot_id: i64,
+    pub removed_snapshot_id: Option<i64>,
+    pub data_file: DataFile,
+}
+
+impl RegisteredDataFile {
+    /// Whether the file is part of the table as of the given snapshot.
+    pub fn is_live_in(&self, snapshot_id: i64) -> bool {
+        self.added_snapshot_id <= snapshot_id
+            && self
+                .removed_snapshot_id
+                .is_none_or(|removed| removed > snapshot_id)
+    }
+}
+
+/// FileRegistry holds every data file ever committed to a table.
+///
+/// Snapshot ids are assigned in increasing order, so a file belongs to a
+/// snapshot when it was added at or before it and not removed by then.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct FileRegistry {
+    pub current_snapshot_id: Option<i64>,
+    /// Named references (branches and tags) to snapshots.
+    pub refs: BTreeMap<String, i64>,
+    pub snapshot_ids: Vec<i64>,
+    pub files: Vec<RegisteredDataFile>,
+}
+
+impl FileRegistry {
+    pub fn files_in(&self, snapshot_id: i64) -> Vec<DataFile> {
+        self.files
+            .iter()
+            .filter(|file| file.is_live_in(snapshot_id))
+            .map(|file| file.data_file.clone())
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RegisterDataFilesRequest {
+    #[serde(default)]
+    pub added_files: Vec<DataFile>,
+    /// Paths of previously registered files that are no longer part of the table.
+    #[serde(default)]
+    pub removed_file_paths: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RegisterDataFilesResponse {
+    pub snapshot_id: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn data_file(path: &str) -> DataFile {
+        DataFile {
+            file_path: path.to_string(),
+            file_format: None,
+            partition: BTreeMap::new(),
+            partition_transforms: BTreeMap::new(),
+            record_count: 10,
+            file_size_in_bytes: 100,
+            null_value_counts: BTreeMap::new(),
+            nan_value_counts: BTreeMap::new(),
+            lower_bounds: BTreeMap::new(),
+            upper_bounds: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_data_file_deserialization() {
+        let data = r#"
+            {
+                "file_path": "s3://bucket/data/0.parquet",
+                "partition": {"region": "us"},
+                "record_count": 42,
+                "lower_bounds": {"id": 1},
+                "upper_bounds": {"id": 99}
+            }
+            "#;
+
+        let data_file: DataFile = serde_json::from_str(data).unwrap();
+
+        assert_eq!(data_file.file_path, "s3://bucket/data/0.parquet");
+        assert_eq!(data_file.partition["region"], json!("us"));
+        assert_eq!(data_file.record_count, 42);
+        assert_eq!(data_file.file_size_in_bytes, 0);
+        assert_eq!(data_file.lower_bounds["id"], json!(1));
+        assert_eq!(data_file.upper_bounds["id"], json!(99));
+        assert!(data_file.null_value_counts.is_empty());
+    }
+
+    #[test]
+    fn test_file_registry_files_in() {
+        let registry = FileRegistry {
+            current_snapshot_id: Some(2),
+            refs: BTreeMap::from([("main".to_string(), 2)]),
+            snapshot_ids: vec![1, 2],
+            files: vec![
+                RegisteredDataFile {
+                    added_snapshot_id: 1,
+                    removed_snapshot_id: Some(2),
+                    data_file: data_file("a"),
+                },
+                RegisteredDataFile {
+                    added_snapshot_id: 2,
+                    removed_snapshot_id: None,
+                    data_file: data_file("b"),
+                },
+            ],
+        };
+
+        assert_eq!(registry.files_in(1), vec![data_file("a")]);
+        assert_eq!(registry.files_in(2), vec![data_file("b")]);
+    }
+}
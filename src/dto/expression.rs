@@ -0,0 +1,234 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Expression represents a row filter in the Iceberg REST expression JSON format.
+///
+/// Boolean connectives carry child expressions, predicates carry the column
+/// name they apply to in `term` and the literal(s) they compare against.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Expression {
+    True,
+    False,
+    And {
+        left: Box<Expression>,
+        right: Box<Expression>,
+    },
+    Or {
+        left: Box<Expression>,
+        right: Box<Expression>,
+    },
+    Not {
+        child: Box<Expression>,
+    },
+    Lt {
+        term: String,
+        value: Value,
+    },
+    LtEq {
+        term: String,
+        value: Value,
+    },
+    Gt {
+        term: String,
+        value: Value,
+    },
+    GtEq {
+        term: String,
+        value: Value,
+    },
+    Eq {
+        term: String,
+        value: Value,
+    },
+    NotEq {
+        term: String,
+        value: Value,
+    },
+    StartsWith {
+        term: String,
+        value: String,
+    },
+    NotStartsWith {
+        term: String,
+        value: String,
+    },
+    In {
+        term: String,
+        values: Vec<Value>,
+    },
+    NotIn {
+        term: String,
+        values: Vec<Value>,
+    },
+    IsNull {
+        term: String,
+    },
+    NotNull {
+        term: String,
+    },
+    IsNan {
+        term: String,
+    },
+    NotNan {
+        term: String,
+    },
+}
+
+impl Expression {
+    /// Returns the logical negation of the expression, pushing `not` down to
+    /// the predicates so that no `Not` node remains in the result.
+    pub fn negate(&self) -> Expression {
+        match self {
+            Expression::True => Expression::False,
+            Expression::False => Expression::True,
+            Expression::And { left, right } => Expression::Or {
+                left: Box::new(left.negate()),
+                right: Box::new(right.negate()),
+            },
+            Expression::Or { left, right } => Expression::And {
+                left: Box::new(left.negate()),
+                right: Box::new(right.negate()),
+            },
+            Expression::Not { child } => child.rewrite_not(),
+            Expression::Lt { term, value } => Expression::GtEq {
+                term: term.clone(),
+                value: value.clone(),
+            },
+            Expression::LtEq { term, value } => Expression::Gt {
+                term: term.clone(),
+                value: value.clone(),
+            },
+            Expression::Gt { term, value } => Expression::LtEq {
+                term: term.clone(),
+                value: value.clone(),
+            },
+            Expression::GtEq { term, value } => Expression::Lt {
+                term: term.clone(),
+                value: value.clone(),
+            },
+            Expression::Eq { term, value } => Expression::NotEq {
+                term: term.clone(),
+                value: value.clone(),
+            },
+            Expression::NotEq { term, value } => Expression::Eq {
+                term: term.clone(),
+                value: value.clone(),
+            },
+            Expression::StartsWith { term, value } => Expression::NotStartsWith {
+                term: term.clone(),
+                value: value.clone(),
+            },
+            Expression::NotStartsWith { term, value } => Expression::StartsWith {
+                term: term.clone(),
+                value: value.clone(),
+            },
+            Expression::In { term, values } => Expression::NotIn {
+                term: term.clone(),
+                values: values.clone(),
+            },
+            Expression::NotIn { term, values } => Expression::In {
+                term: term.clone(),
+                values: values.clone(),
+            },
+            Expression::IsNull { term } => Expression::NotNull { term: term.clone() },
+            Expression::NotNull { term } => Expression::IsNull { term: term.clone() },
+            Expression::IsNan { term } => Expression::NotNan { term: term.clone() },
+            Expression::NotNan { term } => Expression::IsNan { term: term.clone() },
+        }
+    }
+
+    /// Returns an equivalent expression without any `Not` nodes.
+    pub fn rewrite_not(&self) -> Expression {
+        match self {
+            Expression::Not { child } => child.negate(),
+            Expression::And { left, right } => Expression::And {
+                left: Box::new(left.rewrite_not()),
+                right: Box::new(right.rewrite_not()),
+            },
+            Expression::Or { left, right } => Expression::Or {
+                left: Box::new(left.rewrite_not()),
+                right: Box::new(right.rewrite_not()),
+            },
+            other => other.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_expression_deserialization() {
+        let data = r#"
+            {
+                "type": "and",
+                "left": {"type": "lt-eq", "term": "id", "value": 10},
+                "right": {
+                    "type": "not",
+                    "child": {"type": "in", "term": "region", "values": ["us", "eu"]}
+                }
+            }
+            "#;
+
+        let expression: Expression = serde_json::from_str(data).unwrap();
+
+        assert_eq!(
+            expression,
+            Expression::And {
+                left: Box::new(Expression::LtEq {
+                    term: "id".to_string(),
+                    value: json!(10),
+                }),
+                right: Box::new(Expression::Not {
+                    child: Box::new(Expression::In {
+                        term: "region".to_string(),
+                        values: vec![json!("us"), json!("eu")],
+                    }),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn test_expression_serialization() {
+        let expression = Expression::IsNull {
+            term: "name".to_string(),
+        };
+        let serialized = serde_json::to_string(&expression).unwrap();
+
+        assert_eq!(serialized, r#"{"type":"is-null","term":"name"}"#);
+    }
+
+    #[test]
+    fn test_rewrite_not() {
+        let expression = Expression::Not {
+            child: Box::new(Expression::Or {
+                left: Box::new(Expression::Lt {
+                    term: "id".to_string(),
+                    value: json!(5),
+                }),
+                right: Box::new(Expression::StartsWith {
+                    term: "name".to_string(),
+                    value: "a".to_string(),
+                }),
+            }),
+        };
+
+        assert_eq!(
+            expression.rewrite_not(),
+            Expression::And {
+                left: Box::new(Expression::GtEq {
+                    term: "id".to_string(),
+                    value: json!(5),
+                }),
+                right: Box::new(Expression::NotStartsWith {
+                    term: "name".to_string(),
+                    value: "a".to_string(),
+                }),
+            }
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use crate::dto::namespace_data::NamespaceIdent;
+use crate::dto::search::{CatalogObject, SearchTerm};
+use crate::dto::table_data::TableIdent;
+use serde::{Deserialize, Serialize};
+
+/// FsckIssue is one broken catalog invariant found by the consistency checker.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum FsckIssue {
+    /// A key or value that does not decode to the type its column family holds.
+    UndecodableEntry {
+        cf: String,
+        key: String,
+        error: String,
+    },
+    /// A namespace or table stored under the key of another ident.
+    IdentMismatch {
+        cf: String,
+        key: String,
+        stored: String,
+    },
+    /// Two keys of a column family decoding to the same ident.
+    DuplicateIdent { cf: String, key: String },
+    /// A `TableNamespaceMap` entry listing a table that does not exist there.
+    DanglingIndexEntry {
+        namespace: NamespaceIdent,
+        table: TableIdent,
+    },
+    /// A table listed twice in its namespace.
+    DuplicateIndexEntry {
+        namespace: NamespaceIdent,
+        table: TableIdent,
+    },
+    /// A table list kept for a namespace that does not exist.
+    OrphanedIndex { namespace: NamespaceIdent },
+    /// A table whose namespace does not exist.
+    OrphanedTable { table: TableIdent },
+    /// A table missing from the table list of its namespace.
+    UnindexedTable { table: TableIdent },
+    /// Per-table data of a table that does not exist.
+    OrphanedTableData { cf: String, table: TableIdent },
+    /// A search index entry that does not list exactly the objects carrying
+    /// its term, or a term missing from the index.
+    StaleSearchEntry { term: SearchTerm },
+    /// An owner recorded for a namespace or table that does not exist.
+    StaleOwnership { object: CatalogObject },
+    /// Usage of a principal that does not match the objects it owns.
+    UsageMismatch { principal: String },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FsckReport {
+    pub issues: Vec<FsckIssue>,
+    /// Whether the issues were repaired.
+    pub repaired: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fsck_issue_serialization() {
+        let issue = FsckIssue::OrphanedIndex {
+            namespace: NamespaceIdent(vec!["sales".to_string()]),
+        };
+
+        let serialized = serde_json::to_string(&issue).unwrap();
+
+        assert_eq!(
+            serialized,
+            r#"{"kind":"orphaned-index","namespace":["sales"]}"#
+        );
+    }
+}
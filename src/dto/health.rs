@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct HealthResponse {
+    pub status: String,
+}
+
+/// ReadinessCheck is the outcome of one condition the catalog needs to serve.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ReadinessCheck {
+    pub name: String,
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ReadinessReport {
+    pub ready: bool,
+    pub checks: Vec<ReadinessCheck>,
+}
+
+/// ColumnFamilySize holds RocksDB's estimates for one column family.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ColumnFamilySize {
+    pub name: String,
+    pub estimated_keys: u64,
+    pub sst_bytes: u64,
+    pub memtable_bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ServerInfo {
+    pub version: String,
+    /// Format version of the stored data; `None` before it is versioned.
+    pub format_version: Option<u32>,
+    /// Format version this build writes.
+    pub latest_format_version: u32,
+    pub uptime_secs: u64,
+    pub column_families: Vec<ColumnFamilySize>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_readiness_check_omits_empty_detail() {
+        let check = ReadinessCheck {
+            name: "writable".to_string(),
+            ok: true,
+            detail: None,
+        };
+
+        let serialized = serde_json::to_string(&check).unwrap();
+
+        assert_eq!(serialized, r#"{"name":"writable","ok":true}"#);
+    }
+}
@@ -0,0 +1,99 @@
+use crate::dto::expression::Expression;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Type of the values of a histogram; dates are `YYYY-MM-DD` strings.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HistogramType {
+    Int,
+    Float,
+    String,
+    Date,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MostCommonValue {
+    pub value: Value,
+    /// Fraction of all rows holding the value.
+    pub frequency: f64,
+}
+
+/// ColumnHistogram is the value distribution of a column reported by the
+/// execution engine.
+///
+/// `bounds` are the boundaries of an equi-depth histogram in ascending
+/// order: each of the `bounds.len() - 1` buckets holds the same share of the
+/// rows that are neither null nor one of the most common values.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ColumnHistogram {
+    #[serde(rename = "type")]
+    pub histogram_type: HistogramType,
+    #[serde(default)]
+    pub bounds: Vec<Value>,
+    #[serde(default)]
+    pub most_common_values: Vec<MostCommonValue>,
+    #[serde(default)]
+    pub null_fraction: f64,
+    #[serde(default)]
+    pub ndv: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SetHistogramsRequest {
+    pub columns: BTreeMap<String, ColumnHistogram>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EstimateSelectivityRequest {
+    pub filter: Expression,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EstimateSelectivityResponse {
+    pub selectivity: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_column_histogram_deserialization() {
+        let data = r#"
+            {
+                "type": "date",
+                "bounds": ["2024-01-01", "2024-02-01", "2024-03-01"],
+                "most_common_values": [{"value": "2024-01-15", "frequency": 0.1}],
+                "null_fraction": 0.05
+            }
+            "#;
+
+        let histogram: ColumnHistogram = serde_json::from_str(data).unwrap();
+
+        assert_eq!(histogram.histogram_type, HistogramType::Date);
+        assert_eq!(histogram.bounds.len(), 3);
+        assert_eq!(histogram.most_common_values[0].value, json!("2024-01-15"));
+        assert_eq!(histogram.null_fraction, 0.05);
+        assert_eq!(histogram.ndv, None);
+    }
+
+    #[test]
+    fn test_column_histogram_serialization() {
+        let histogram = ColumnHistogram {
+            histogram_type: HistogramType::Int,
+            bounds: vec![json!(1), json!(10)],
+            most_common_values: vec![],
+            null_fraction: 0.0,
+            ndv: Some(10),
+        };
+        let serialized = serde_json::to_string(&histogram).unwrap();
+
+        assert_eq!(
+            serialized,
+            r#"{"type":"int","bounds":[1,10],"most_common_values":[],"null_fraction":0.0,"ndv":10}"#
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use crate::dto::backup::BackupInfo;
+use serde::{Deserialize, Serialize};
+
+/// MigrationStep reports one migration and how many entries it rewrote.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStep {
+    pub version: u32,
+    pub description: String,
+    pub entries: usize,
+}
+
+/// MigrationReport describes an upgrade of the storage format, or the
+/// upgrade that would run for a dry run.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    pub dry_run: bool,
+    pub steps: Vec<MigrationStep>,
+    /// Backup taken before migrating, restore it to roll back.
+    pub backup: Option<BackupInfo>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_migration_report_serialization() {
+        let report = MigrationReport {
+            from_version: 1,
+            to_version: 2,
+            dry_run: true,
+            steps: vec![MigrationStep {
+                version: 2,
+                description: "Wrap values".to_string(),
+                entries: 3,
+            }],
+            backup: None,
+        };
+
+        assert_eq!(
+            serde_json::to_value(&report).unwrap(),
+            json!({
+                "from_version": 1,
+                "to_version": 2,
+                "dry_run": true,
+                "steps": [{"version": 2, "description": "Wrap values", "entries": 3}],
+                "backup": null
+            })
+        );
+    }
+}
@@ -1,5 +1,19 @@
+pub mod acl;
+pub mod backup;
+pub mod batch;
 pub mod column_data;
+pub mod constraint;
+pub mod data_file;
+pub mod expression;
+pub mod fsck;
+pub mod health;
+pub mod histogram;
+pub mod migration;
 pub mod namespace_data;
+pub mod oauth;
 pub mod rename_request;
+pub mod scan_plan;
+pub mod search;
 pub mod set_namespace_properties_req;
+pub mod statistics;
 pub mod table_data;
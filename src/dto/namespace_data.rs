@@ -1,10 +1,14 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::fmt;
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct NamespaceData {
     pub name: NamespaceIdent,
     pub properties: Value,
+    /// Bumped on every change and exposed as the namespace's ETag.
+    #[serde(default)]
+    pub version: u64,
 }
 
 impl NamespaceData {
@@ -31,6 +35,13 @@ impl NamespaceIdent {
     }
 }
 
+/// Formats the namespace with its levels joined by dots, as in logs.
+impl fmt::Display for NamespaceIdent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.join("."))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -42,6 +53,10 @@ mod tests {
         let namespace_ident = NamespaceIdent::new(id.clone());
 
         assert_eq!(namespace_ident.0, id);
+        assert_eq!(
+            NamespaceIdent(vec!["a".to_string(), "b".to_string()]).to_string(),
+            "a.b"
+        );
     }
 
     #[test]
@@ -53,6 +68,7 @@ mod tests {
         let namespace_data = NamespaceData {
             name: namespace_ident.clone(),
             properties: properties.clone(),
+            version: 1,
         };
 
         assert_eq!(*namespace_data.get_name(), namespace_ident);
@@ -82,17 +98,22 @@ mod tests {
         let namespace_data = NamespaceData {
             name: namespace_ident.clone(),
             properties: properties.clone(),
+            version: 1,
         };
 
         // Serialize
         let serialized = serde_json::to_string(&namespace_data).unwrap();
         assert_eq!(
             serialized,
-            r#"{"name":["test"],"properties":{"key":"value"}}"#
+            r#"{"name":["test"],"properties":{"key":"value"},"version":1}"#
         );
 
         // Deserialize
         let deserialized: NamespaceData = serde_json::from_str(&serialized).unwrap();
         assert_eq!(deserialized, namespace_data);
+        // Namespaces stored before versioning read as version 0.
+        let legacy: NamespaceData =
+            serde_json::from_str(r#"{"name":["test"],"properties":{}}"#).unwrap();
+        assert_eq!(legacy.version, 0);
     }
 }
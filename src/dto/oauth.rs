@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+/// TokenRequest is the form posted to `/v1/oauth/tokens`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TokenRequest {
+    pub grant_type: String,
+    pub client_id: String,
+    pub client_secret: String,
+    #[serde(default)]
+    pub scope: Option<String>,
+}
+
+/// TokenResponse follows the Iceberg REST OAuth token response.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TokenResponse {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: u64,
+    pub issued_token_type: String,
+}
+
+impl TokenResponse {
+    pub fn bearer(access_token: String, expires_in: u64) -> Self {
+        Self {
+            access_token,
+            token_type: "bearer".to_string(),
+            expires_in,
+            issued_token_type: "urn:ietf:params:oauth:token-type:access_token".to_string(),
+        }
+    }
+}
+
+/// OAuthError is the error body of the token endpoint, as RFC 6749 defines it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OAuthError {
+    pub error: String,
+    pub error_description: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_request_from_form() {
+        let request: TokenRequest = serde_json::from_value(serde_json::json!({
+            "grant_type": "client_credentials",
+            "client_id": "spark",
+            "client_secret": "secret"
+        }))
+        .unwrap();
+
+        assert_eq!(request.client_id, "spark");
+        assert_eq!(request.scope, None);
+    }
+
+    #[test]
+    fn test_token_response_serialization() {
+        let response = TokenResponse::bearer("abc".to_string(), 60);
+        let json = serde_json::to_value(&response).unwrap();
+
+        assert_eq!(json["token_type"], "bearer");
+        assert_eq!(json["expires_in"], 60);
+    }
+}
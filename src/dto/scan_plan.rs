@@ -0,0 +1,52 @@
+use crate::dto::data_file::DataFile;
+use crate::dto::expression::Expression;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PlanTableScanRequest {
+    /// Row filter; files that cannot contain a matching row are pruned.
+    #[serde(default)]
+    pub filter: Option<Expression>,
+    #[serde(default)]
+    pub snapshot_id: Option<i64>,
+    /// Branch or tag to plan against, `main` if neither this nor
+    /// `snapshot_id` is given.
+    #[serde(default, rename = "ref")]
+    pub reference: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PlanTableScanResponse {
+    pub snapshot_id: Option<i64>,
+    pub data_files: Vec<DataFile>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_table_scan_request_deserialization() {
+        let data = r#"
+            {
+                "filter": {"type": "eq", "term": "region", "value": "us"},
+                "ref": "main"
+            }
+            "#;
+
+        let request: PlanTableScanRequest = serde_json::from_str(data).unwrap();
+
+        assert!(request.filter.is_some());
+        assert_eq!(request.snapshot_id, None);
+        assert_eq!(request.reference, Some("main".to_string()));
+    }
+
+    #[test]
+    fn test_plan_table_scan_request_defaults() {
+        let request: PlanTableScanRequest = serde_json::from_str("{}").unwrap();
+
+        assert!(request.filter.is_none());
+        assert_eq!(request.snapshot_id, None);
+        assert_eq!(request.reference, None);
+    }
+}
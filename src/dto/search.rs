@@ -0,0 +1,95 @@
+use crate::dto::namespace_data::NamespaceIdent;
+use crate::dto::table_data::TableIdent;
+use serde::{Deserialize, Serialize};
+
+/// SearchTerm is a key of the search index.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchTerm {
+    /// A property with a declared index and a scalar value, rendered as
+    /// text, of a namespace or table.
+    Property { key: String, value: String },
+    /// A column name of a table.
+    Column(String),
+    /// The dotted name of a namespace or table.
+    Name(String),
+    /// A table the foreign keys of a table reference.
+    References(TableIdent),
+}
+
+/// CatalogObject is a namespace or table listed under a search term.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum CatalogObject {
+    Namespace(NamespaceIdent),
+    Table(TableIdent),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchKind {
+    Namespace,
+    Table,
+}
+
+/// SearchQuery holds the filters of a search; an object must pass all of
+/// them. Names are matched in their dotted form, e.g. `sales.eu.orders`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SearchQuery {
+    /// Glob on the name, where `*` matches any run of characters and `?`
+    /// a single one. Only the names starting with the text before the first
+    /// wildcard are looked at.
+    pub name: Option<String>,
+    /// Regular expression searched for in the name, which is checked
+    /// against every name.
+    pub regex: Option<String>,
+    /// `key=value` property filter, answered from the index when the key has
+    /// one. Tables match on their own properties or through their namespace.
+    pub property: Option<String>,
+    /// Column a table must have; namespaces never match it.
+    pub column: Option<String>,
+    /// Restricts the search to namespaces or to tables.
+    #[serde(rename = "type")]
+    pub kind: Option<SearchKind>,
+}
+
+/// PropertyIndex declares a secondary index on a property key of namespaces
+/// and tables.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PropertyIndex {
+    pub key: String,
+}
+
+/// SearchResponse lists the matches, sorted by name.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SearchResponse {
+    pub namespaces: Vec<NamespaceIdent>,
+    pub tables: Vec<TableIdent>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_serialization() {
+        let term = SearchTerm::Property {
+            key: "owner".to_string(),
+            value: "teamA".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_string(&term).unwrap(),
+            r#"{"property":{"key":"owner","value":"teamA"}}"#
+        );
+        let object = CatalogObject::Namespace(NamespaceIdent(vec!["sales".to_string()]));
+        assert_eq!(
+            serde_json::to_string(&object).unwrap(),
+            r#"{"namespace":["sales"]}"#
+        );
+
+        let query: SearchQuery =
+            serde_json::from_str(r#"{"name": "sales.*", "type": "table"}"#).unwrap();
+        assert_eq!(query.kind, Some(SearchKind::Table));
+        assert_eq!(query.property, None);
+    }
+}
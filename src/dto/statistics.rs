@@ -0,0 +1,206 @@
+use crate::dto::column_data::ColumnData;
+use crate::dto::table_data::TableIdent;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// StatisticsFile points to a Puffin file holding statistics blobs for a snapshot.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StatisticsFile {
+    pub snapshot_id: i64,
+    pub statistics_path: String,
+    pub file_size_in_bytes: u64,
+    pub file_footer_size_in_bytes: u64,
+    #[serde(default)]
+    pub blob_metadata: Vec<BlobMetadata>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BlobMetadata {
+    /// Blob type, e.g. `apache-datasketches-theta-v1`.
+    #[serde(rename = "type")]
+    pub blob_type: String,
+    pub snapshot_id: i64,
+    pub sequence_number: i64,
+    /// Columns the blob was computed over.
+    pub columns: Vec<String>,
+    /// Blob properties; an `ndv` property carries the distinct count estimate.
+    #[serde(default)]
+    pub properties: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PartitionStatisticsFile {
+    pub snapshot_id: i64,
+    pub statistics_path: String,
+    pub file_size_in_bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ColumnStatistics {
+    #[serde(default)]
+    pub ndv: Option<u64>,
+    #[serde(default)]
+    pub null_count: Option<u64>,
+    #[serde(default)]
+    pub value_range: Option<(i32, i32)>,
+}
+
+/// SnapshotStatistics are the summary statistics an engine reports for a snapshot.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SnapshotStatistics {
+    pub snapshot_id: i64,
+    pub row_count: u64,
+    pub total_size_in_bytes: u64,
+    #[serde(default)]
+    pub columns: BTreeMap<String, ColumnStatistics>,
+}
+
+/// TableStatistics is everything stored about the statistics of one table.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct TableStatistics {
+    pub snapshots: Vec<SnapshotStatistics>,
+    pub statistics_files: Vec<StatisticsFile>,
+    pub partition_statistics_files: Vec<PartitionStatisticsFile>,
+}
+
+/// TableStatisticsUpdate mirrors the statistics related Iceberg table updates.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "action", rename_all = "kebab-case")]
+#[allow(clippy::enum_variant_names)]
+pub enum TableStatisticsUpdate {
+    SetSnapshotStatistics {
+        statistics: SnapshotStatistics,
+    },
+    SetStatistics {
+        statistics: StatisticsFile,
+    },
+    RemoveStatistics {
+        snapshot_id: i64,
+    },
+    SetPartitionStatistics {
+        partition_statistics: PartitionStatisticsFile,
+    },
+    RemovePartitionStatistics {
+        snapshot_id: i64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateTableStatisticsRequest {
+    pub updates: Vec<TableStatisticsUpdate>,
+}
+
+/// ColumnSketch is an opaque distinct-count sketch (theta, HLL, ...) of a column.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ColumnSketch {
+    pub sketch_type: String,
+    #[serde(default)]
+    pub snapshot_id: Option<i64>,
+    pub data: Vec<u8>,
+}
+
+/// HllSketch is a dense HyperLogLog sketch as reported by a writer, one
+/// register per byte.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HllSketch {
+    pub precision: u8,
+    pub registers: Vec<u8>,
+}
+
+/// MergeHllSketchesRequest carries the HLL sketches of one commit, keyed by column.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MergeHllSketchesRequest {
+    #[serde(default)]
+    pub snapshot_id: Option<i64>,
+    pub columns: BTreeMap<String, HllSketch>,
+}
+
+/// MergeHllSketchesResponse holds the merged NDV estimate of each column.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MergeHllSketchesResponse {
+    pub columns: BTreeMap<String, u64>,
+}
+
+/// TableStatisticsSummary is the merged view of a table's statistics handed
+/// to the optimizer.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TableStatisticsSummary {
+    pub table: TableIdent,
+    pub snapshot_id: Option<i64>,
+    pub row_count: Option<u64>,
+    pub total_size_in_bytes: Option<u64>,
+    pub columns: Vec<ColumnData>,
+    pub statistics_files: Vec<StatisticsFile>,
+    pub partition_statistics_files: Vec<PartitionStatisticsFile>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_statistics_update_deserialization() {
+        let data = r#"
+            {
+                "updates": [
+                    {
+                        "action": "set-statistics",
+                        "statistics": {
+                            "snapshot_id": 1,
+                            "statistics_path": "s3://bucket/stats/1.puffin",
+                            "file_size_in_bytes": 1024,
+                            "file_footer_size_in_bytes": 64,
+                            "blob_metadata": [
+                                {
+                                    "type": "apache-datasketches-theta-v1",
+                                    "snapshot_id": 1,
+                                    "sequence_number": 1,
+                                    "columns": ["id"],
+                                    "properties": {"ndv": "100"}
+                                }
+                            ]
+                        }
+                    },
+                    {"action": "remove-partition-statistics", "snapshot_id": 1}
+                ]
+            }
+            "#;
+
+        let request: UpdateTableStatisticsRequest = serde_json::from_str(data).unwrap();
+
+        assert_eq!(request.updates.len(), 2);
+        match &request.updates[0] {
+            TableStatisticsUpdate::SetStatistics { statistics } => {
+                assert_eq!(statistics.snapshot_id, 1);
+                assert_eq!(statistics.blob_metadata[0].columns, vec!["id".to_string()]);
+                assert_eq!(statistics.blob_metadata[0].properties["ndv"], "100");
+            }
+            other => panic!("unexpected update {:?}", other),
+        }
+        assert_eq!(
+            request.updates[1],
+            TableStatisticsUpdate::RemovePartitionStatistics { snapshot_id: 1 }
+        );
+    }
+
+    #[test]
+    fn test_merge_hll_sketches_request_deserialization() {
+        let data = r#"{"columns": {"id": {"precision": 4, "registers": [0, 1, 2, 3]}}}"#;
+
+        let request: MergeHllSketchesRequest = serde_json::from_str(data).unwrap();
+
+        assert_eq!(request.snapshot_id, None);
+        assert_eq!(request.columns["id"].precision, 4);
+        assert_eq!(request.columns["id"].registers, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_snapshot_statistics_defaults() {
+        let data = r#"{"snapshot_id": 3, "row_count": 10, "total_size_in_bytes": 500}"#;
+
+        let statistics: SnapshotStatistics = serde_json::from_str(data).unwrap();
+
+        assert_eq!(statistics.snapshot_id, 3);
+        assert!(statistics.columns.is_empty());
+    }
+}
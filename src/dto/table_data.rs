@@ -1,17 +1,22 @@
-// use crate::dto::column_data::ColumnData;
+use crate::dto::column_data::ColumnData;
+use crate::dto::constraint::TableConstraints;
 use crate::dto::namespace_data::NamespaceIdent;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
 use typed_builder::TypedBuilder;
-// use std::collections::HashMap;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Table {
     pub id: TableIdent,
     pub metadata: TableMetadata,
+    /// Bumped on every change and exposed as the table's ETag.
+    #[serde(default)]
+    pub version: u64,
 }
 
 /// TableIdent represents the identifier of a table in the catalog.
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct TableIdent {
     /// Namespace of the table.
     pub namespace: NamespaceIdent,
@@ -26,21 +31,45 @@ impl TableIdent {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, TypedBuilder, Clone, PartialEq, Eq, Hash)]
+impl fmt::Display for TableIdent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.namespace, self.name)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, TypedBuilder, Clone, PartialEq)]
 pub struct TableCreation {
     /// The name of the table.
     pub name: String,
     // pub file_urls: Option<Vec<String>>,
-    // pub columns: Option<Vec<ColumnData>>,
-    // pub properties: Option<HashMap<String, String>>,
+    /// The columns of the table, if its schema is known.
+    #[serde(default)]
+    #[builder(default)]
+    pub columns: Option<Vec<ColumnData>>,
+    #[serde(default)]
+    #[builder(default)]
+    pub properties: Option<HashMap<String, String>>,
 }
 
 #[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
 pub struct TableMetadata {
     pub table_uuid: String,
     //   pub file_urls: Option<Vec<String>>,
-    //   pub columns: Option<Vec<ColumnData>>,
-    //   pub properties: Option<HashMap<String, String>>,
+    #[serde(default)]
+    pub columns: Option<Vec<ColumnData>>,
+    #[serde(default)]
+    pub constraints: TableConstraints,
+    #[serde(default)]
+    pub properties: Option<HashMap<String, String>>,
+}
+
+/// SetTablePropertiesRequest removes and then sets properties of a table.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SetTablePropertiesRequest {
+    #[serde(default)]
+    pub removals: Vec<String>,
+    #[serde(default)]
+    pub updates: HashMap<String, String>,
 }
 
 #[cfg(test)]
@@ -64,6 +93,31 @@ mod tests {
         let table_creation = TableCreation::builder().name(name.clone()).build();
 
         assert_eq!(table_creation.name, name);
+        assert_eq!(table_creation.columns, None);
+    }
+
+    #[test]
+    fn test_table_creation_with_columns_deserialization() {
+        let data = r#"
+        {
+            "name": "table",
+            "columns": [
+                {
+                    "name": "id",
+                    "aggregates": {},
+                    "value_range": [0, 100],
+                    "is_strong_key": true,
+                    "is_weak_key": false,
+                    "primary_key_col_name": ""
+                }
+            ]
+        }
+        "#;
+
+        let table_creation: TableCreation = serde_json::from_str(data).unwrap();
+
+        assert_eq!(table_creation.name, "table");
+        assert_eq!(table_creation.columns.unwrap()[0].name, "id");
     }
 
     #[test]
@@ -71,6 +125,9 @@ mod tests {
         let table_uuid = "uuid".to_string();
         let table_metadata = TableMetadata {
             table_uuid: table_uuid.clone(),
+            columns: None,
+            constraints: TableConstraints::default(),
+            properties: None,
         };
 
         assert_eq!(table_metadata.table_uuid, table_uuid);
@@ -84,10 +141,14 @@ mod tests {
         );
         let metadata = TableMetadata {
             table_uuid: "uuid".to_string(),
+            columns: None,
+            constraints: TableConstraints::default(),
+            properties: None,
         };
         let table = Table {
             id: id.clone(),
             metadata: metadata.clone(),
+            version: 1,
         };
 
         assert_eq!(table.id, id);
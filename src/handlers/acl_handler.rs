@@ -0,0 +1,226 @@
+use crate::auth::access::Caller;
+use crate::dto::acl::{CreateRoleRequest, Grant, Privilege, Resource, Role};
+use crate::repository::acl::AclRepository;
+use axum::{
+    extract::{Extension, Json, Path, State},
+    http::StatusCode,
+};
+use std::io::{self, ErrorKind};
+use std::sync::Arc;
+
+fn error_response(e: io::Error) -> (StatusCode, String) {
+    match e.kind() {
+        ErrorKind::NotFound => (StatusCode::NOT_FOUND, format!("Error: {}", e)),
+        ErrorKind::AlreadyExists => (StatusCode::CONFLICT, format!("Error: {}", e)),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e)),
+    }
+}
+
+pub async fn list_roles(
+    State(repo): State<Arc<AclRepository>>,
+    Extension(caller): Extension<Caller>,
+) -> Result<Json<Vec<Role>>, (StatusCode, String)> {
+    caller.require(Privilege::ManageGrants, Resource::Catalog)?;
+    repo.list_roles().map(Json).map_err(error_response)
+}
+
+pub async fn create_role(
+    State(repo): State<Arc<AclRepository>>,
+    Extension(caller): Extension<Caller>,
+    request: Json<CreateRoleRequest>,
+) -> Result<(StatusCode, Json<Role>), (StatusCode, String)> {
+    caller.require(Privilege::ManageGrants, Resource::Catalog)?;
+    if request.name.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Error: Role name must not be empty".to_string(),
+        ));
+    }
+    repo.create_role(&request.name)
+        .map(|role| (StatusCode::CREATED, Json(role)))
+        .map_err(error_response)
+}
+
+pub async fn load_role(
+    State(repo): State<Arc<AclRepository>>,
+    Extension(caller): Extension<Caller>,
+    Path(role): Path<String>,
+) -> Result<Json<Role>, (StatusCode, String)> {
+    caller.require(Privilege::ManageGrants, Resource::Catalog)?;
+    match repo.load_role(&role) {
+        Ok(Some(role)) => Ok(Json(role)),
+        Ok(None) => Err((StatusCode::NOT_FOUND, format!("Role {} not found", role))),
+        Err(e) => Err(error_response(e)),
+    }
+}
+
+pub async fn drop_role(
+    State(repo): State<Arc<AclRepository>>,
+    Extension(caller): Extension<Caller>,
+    Path(role): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    caller.require(Privilege::ManageGrants, Resource::Catalog)?;
+    repo.drop_role(&role)
+        .map(|_| StatusCode::NO_CONTENT)
+        .map_err(error_response)
+}
+
+/// Adds a grant to a role; the caller must manage grants on its scope.
+pub async fn grant_privilege(
+    State(repo): State<Arc<AclRepository>>,
+    Extension(caller): Extension<Caller>,
+    Path(role): Path<String>,
+    grant: Json<Grant>,
+) -> Result<Json<Role>, (StatusCode, String)> {
+    caller.require(Privilege::ManageGrants, grant.scope.resource())?;
+    repo.grant(&role, grant.0).map(Json).map_err(error_response)
+}
+
+pub async fn revoke_privilege(
+    State(repo): State<Arc<AclRepository>>,
+    Extension(caller): Extension<Caller>,
+    Path(role): Path<String>,
+    grant: Json<Grant>,
+) -> Result<Json<Role>, (StatusCode, String)> {
+    caller.require(Privilege::ManageGrants, grant.scope.resource())?;
+    repo.revoke(&role, &grant).map(Json).map_err(error_response)
+}
+
+pub async fn list_principal_roles(
+    State(repo): State<Arc<AclRepository>>,
+    Extension(caller): Extension<Caller>,
+    Path(principal): Path<String>,
+) -> Result<Json<Vec<String>>, (StatusCode, String)> {
+    // Callers may always see their own roles.
+    if principal != caller.principal.name {
+        caller.require(Privilege::ManageGrants, Resource::Catalog)?;
+    }
+    repo.principal_roles(&principal)
+        .map(Json)
+        .map_err(error_response)
+}
+
+pub async fn assign_role(
+    State(repo): State<Arc<AclRepository>>,
+    Extension(caller): Extension<Caller>,
+    Path((principal, role)): Path<(String, String)>,
+) -> Result<Json<Vec<String>>, (StatusCode, String)> {
+    caller.require(Privilege::ManageGrants, Resource::Catalog)?;
+    repo.assign_role(&principal, &role)
+        .map(Json)
+        .map_err(error_response)
+}
+
+pub async fn unassign_role(
+    State(repo): State<Arc<AclRepository>>,
+    Extension(caller): Extension<Caller>,
+    Path((principal, role)): Path<(String, String)>,
+) -> Result<Json<Vec<String>>, (StatusCode, String)> {
+    caller.require(Privilege::ManageGrants, Resource::Catalog)?;
+    repo.unassign_role(&principal, &role)
+        .map(Json)
+        .map_err(error_response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::authenticator::{AuthMethod, Principal};
+    use crate::database::database::Database;
+    use crate::dto::acl::GrantScope;
+    use crate::dto::namespace_data::NamespaceIdent;
+    use std::sync::Mutex;
+    use tempfile::tempdir;
+
+    fn caller() -> Caller {
+        Caller::unrestricted(Principal::anonymous())
+    }
+
+    #[tokio::test]
+    async fn test_acl_endpoints() {
+        let dir = tempdir().unwrap();
+        let repo = Arc::new(AclRepository::new(Arc::new(Mutex::new(
+            Database::open(dir.path()).unwrap(),
+        ))));
+        let sales = NamespaceIdent(vec!["sales".to_string()]);
+        let grant = Grant {
+            privilege: Privilege::Commit,
+            scope: GrantScope::Namespace {
+                namespace: sales.clone(),
+            },
+        };
+
+        let (status, _) = create_role(
+            State(repo.clone()),
+            Extension(caller()),
+            Json(CreateRoleRequest {
+                name: "etl".to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+
+        let role = grant_privilege(
+            State(repo.clone()),
+            Extension(caller()),
+            Path("etl".to_string()),
+            Json(grant.clone()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(role.grants, vec![grant.clone()]);
+
+        let roles = assign_role(
+            State(repo.clone()),
+            Extension(caller()),
+            Path(("alice".to_string(), "etl".to_string())),
+        )
+        .await
+        .unwrap();
+        assert_eq!(roles.0, vec!["etl"]);
+
+        // Alice may see her roles but not manage grants.
+        let alice = Caller::with_grants(
+            Principal {
+                name: "alice".to_string(),
+                method: AuthMethod::ApiToken,
+            },
+            repo.grants_of("alice").unwrap(),
+        );
+        assert!(list_principal_roles(
+            State(repo.clone()),
+            Extension(alice.clone()),
+            Path("alice".to_string())
+        )
+        .await
+        .is_ok());
+        let err = revoke_privilege(
+            State(repo.clone()),
+            Extension(alice),
+            Path("etl".to_string()),
+            Json(grant.clone()),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+
+        assert_eq!(
+            drop_role(
+                State(repo.clone()),
+                Extension(caller()),
+                Path("etl".to_string())
+            )
+            .await
+            .unwrap(),
+            StatusCode::NO_CONTENT
+        );
+        assert_eq!(
+            load_role(State(repo), Extension(caller()), Path("etl".to_string()))
+                .await
+                .unwrap_err()
+                .0,
+            StatusCode::NOT_FOUND
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use crate::auth::access::Caller;
+use crate::database::dump::DumpSummary;
+use crate::dto::acl::{Privilege, Resource};
+use crate::dto::backup::{BackupInfo, CheckpointResponse};
+use crate::dto::fsck::FsckReport;
+use crate::repository::admin::AdminRepository;
+use axum::{
+    body::Bytes,
+    extract::{Extension, Json, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use std::io::ErrorKind;
+use std::sync::Arc;
+
+pub async fn export_catalog(
+    State(repo): State<Arc<AdminRepository>>,
+    Extension(caller): Extension<Caller>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    caller.require(Privilege::ManageCatalog, Resource::Catalog)?;
+    match repo.export_dump() {
+        Ok(dump) => Ok(([(header::CONTENT_TYPE, "application/x-ndjson")], dump)),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e))),
+    }
+}
+
+pub async fn import_catalog(
+    State(repo): State<Arc<AdminRepository>>,
+    Extension(caller): Extension<Caller>,
+    body: Bytes,
+) -> Result<Json<DumpSummary>, (StatusCode, String)> {
+    caller.require(Privilege::ManageCatalog, Resource::Catalog)?;
+    match repo.import_dump(&body) {
+        Ok(summary) => Ok(Json(summary)),
+        Err(e) => match e.kind() {
+            ErrorKind::InvalidData => Err((StatusCode::BAD_REQUEST, format!("Error: {}", e))),
+            ErrorKind::AlreadyExists => Err((StatusCode::CONFLICT, format!("Error: {}", e))),
+            _ => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e))),
+        },
+    }
+}
+
+pub async fn check_catalog(
+    State(repo): State<Arc<AdminRepository>>,
+    Extension(caller): Extension<Caller>,
+) -> Result<Json<FsckReport>, (StatusCode, String)> {
+    caller.require(Privilege::ManageCatalog, Resource::Catalog)?;
+    repo.fsck(false)
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e)))
+}
+
+pub async fn repair_catalog(
+    State(repo): State<Arc<AdminRepository>>,
+    Extension(caller): Extension<Caller>,
+) -> Result<Json<FsckReport>, (StatusCode, String)> {
+    caller.require(Privilege::ManageCatalog, Resource::Catalog)?;
+    repo.fsck(true)
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e)))
+}
+
+pub async fn list_backups(
+    State(repo): State<Arc<AdminRepository>>,
+    Extension(caller): Extension<Caller>,
+) -> Result<Json<Vec<BackupInfo>>, (StatusCode, String)> {
+    caller.require(Privilege::ManageCatalog, Resource::Catalog)?;
+    match repo.list_backups() {
+        Ok(backups) => Ok(Json(backups)),
+        Err(e) => match e.kind() {
+            ErrorKind::InvalidInput => Err((StatusCode::BAD_REQUEST, format!("Error: {}", e))),
+            _ => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e))),
+        },
+    }
+}
+
+pub async fn create_backup(
+    State(repo): State<Arc<AdminRepository>>,
+    Extension(caller): Extension<Caller>,
+) -> Result<(StatusCode, Json<BackupInfo>), (StatusCode, String)> {
+    caller.require(Privilege::ManageCatalog, Resource::Catalog)?;
+    match tokio::task::spawn_blocking(move || repo.create_backup()).await {
+        Ok(Ok(backup)) => Ok((StatusCode::CREATED, Json(backup))),
+        Ok(Err(e)) => match e.kind() {
+            ErrorKind::InvalidInput => Err((StatusCode::BAD_REQUEST, format!("Error: {}", e))),
+            _ => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e))),
+        },
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e))),
+    }
+}
+
+pub async fn create_checkpoint(
+    State(repo): State<Arc<AdminRepository>>,
+    Extension(caller): Extension<Caller>,
+) -> Result<(StatusCode, Json<CheckpointResponse>), (StatusCode, String)> {
+    caller.require(Privilege::ManageCatalog, Resource::Catalog)?;
+    match tokio::task::spawn_blocking(move || repo.create_checkpoint()).await {
+        Ok(Ok(checkpoint)) => Ok((StatusCode::CREATED, Json(checkpoint))),
+        Ok(Err(e)) => match e.kind() {
+            ErrorKind::InvalidInput => Err((StatusCode::BAD_REQUEST, format!("Error: {}", e))),
+            _ => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e))),
+        },
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::authenticator::Principal;
+    use crate::config::config::BackupConfig;
+    use crate::database::database::Database;
+    use std::sync::Mutex;
+    use tempfile::tempdir;
+
+    fn caller() -> Caller {
+        Caller::unrestricted(Principal::anonymous())
+    }
+
+    #[tokio::test]
+    async fn test_admin_endpoints() {
+        let dir = tempdir().unwrap();
+        let repo = Arc::new(AdminRepository::new(
+            Arc::new(Mutex::new(Database::open(dir.path()).unwrap())),
+            BackupConfig::default(),
+        ));
+
+        let dump = repo.export_dump().unwrap();
+        let response = export_catalog(State(repo.clone()), Extension(caller()))
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // An empty catalog can be restored into an empty catalog.
+        let summary = import_catalog(State(repo.clone()), Extension(caller()), Bytes::from(dump))
+            .await
+            .unwrap();
+        assert_eq!(summary.entries["TableData"], 0);
+
+        let err = import_catalog(State(repo), Extension(caller()), Bytes::from_static(b"{}"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_fsck_endpoints() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        db.write_raw(&[("TableData", b"[".to_vec(), b"{}".to_vec())])
+            .unwrap();
+        let repo = Arc::new(AdminRepository::new(
+            Arc::new(Mutex::new(db)),
+            BackupConfig::default(),
+        ));
+
+        let report = check_catalog(State(repo.clone()), Extension(caller()))
+            .await
+            .unwrap();
+        assert_eq!(report.issues.len(), 1);
+        assert!(!report.repaired);
+        let report = repair_catalog(State(repo.clone()), Extension(caller()))
+            .await
+            .unwrap();
+        assert!(report.repaired);
+        let report = check_catalog(State(repo), Extension(caller()))
+            .await
+            .unwrap();
+        assert!(report.issues.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_backup_endpoints() {
+        let dir = tempdir().unwrap();
+        let repo = Arc::new(AdminRepository::new(
+            Arc::new(Mutex::new(Database::open(dir.path().join("db")).unwrap())),
+            BackupConfig {
+                dir: Some(dir.path().join("backups")),
+                ..Default::default()
+            },
+        ));
+
+        let (status, backup) = create_backup(State(repo.clone()), Extension(caller()))
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        let backups = list_backups(State(repo.clone()), Extension(caller()))
+            .await
+            .unwrap();
+        assert_eq!(backups.0, vec![backup.0]);
+        let (status, _) = create_checkpoint(State(repo), Extension(caller()))
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+    }
+}
@@ -0,0 +1,77 @@
+use crate::dto::batch::{BatchCreateResponse, BatchItemResult, MAX_BATCH_SIZE};
+use axum::http::StatusCode;
+use std::io::{self, ErrorKind};
+
+/// Rejects empty batches and batches over [`MAX_BATCH_SIZE`].
+pub fn check_batch_size(len: usize) -> Result<(), (StatusCode, String)> {
+    if len == 0 || len > MAX_BATCH_SIZE {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Error: A batch must hold between 1 and {} items, got {}",
+                MAX_BATCH_SIZE, len
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Status a failed batch item, or a failed atomic batch, is reported with.
+pub fn error_status(e: &io::Error) -> StatusCode {
+    match e.kind() {
+        ErrorKind::NotFound => StatusCode::NOT_FOUND,
+        ErrorKind::AlreadyExists => StatusCode::CONFLICT,
+        ErrorKind::InvalidInput => StatusCode::BAD_REQUEST,
+        ErrorKind::PermissionDenied => StatusCode::FORBIDDEN,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Pairs every item with its outcome; created items get `created`.
+pub fn create_response<I>(
+    ids: impl IntoIterator<Item = I>,
+    results: Vec<io::Result<()>>,
+    created: StatusCode,
+) -> BatchCreateResponse<I> {
+    let results = ids
+        .into_iter()
+        .zip(results)
+        .map(|(id, result)| match result {
+            Ok(()) => BatchItemResult {
+                id,
+                status: created.as_u16(),
+                error: None,
+            },
+            Err(e) => BatchItemResult {
+                id,
+                status: error_status(&e).as_u16(),
+                error: Some(e.to_string()),
+            },
+        })
+        .collect();
+    BatchCreateResponse { results }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_helpers() {
+        assert!(check_batch_size(0).is_err());
+        assert!(check_batch_size(MAX_BATCH_SIZE).is_ok());
+        assert!(check_batch_size(MAX_BATCH_SIZE + 1).is_err());
+
+        let response = create_response(
+            ["a", "b"],
+            vec![
+                Ok(()),
+                Err(io::Error::new(ErrorKind::AlreadyExists, "exists")),
+            ],
+            StatusCode::CREATED,
+        );
+        assert_eq!(response.results[0].status, 201);
+        assert_eq!(response.results[1].status, 409);
+        assert_eq!(response.results[1].error.as_deref(), Some("exists"));
+    }
+}
@@ -0,0 +1,124 @@
+use axum::{
+    http::{header, HeaderMap, HeaderName, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+/// Formats a version as a strong entity tag.
+pub fn etag(version: u64) -> String {
+    format!("\"{}\"", version)
+}
+
+/// Header setting the ETag of a response.
+pub fn etag_header(version: u64) -> [(HeaderName, String); 1] {
+    [(header::ETAG, etag(version))]
+}
+
+fn parse_etag(tag: &str) -> Option<u64> {
+    tag.strip_prefix('"')?.strip_suffix('"')?.parse().ok()
+}
+
+fn tags(headers: &HeaderMap, name: HeaderName) -> impl Iterator<Item = &str> {
+    headers
+        .get_all(name)
+        .into_iter()
+        .flat_map(|value| value.to_str().unwrap_or_default().split(','))
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+}
+
+/// Returns the versions `If-Match` accepts, or None when any version will
+/// do because the header is absent or `*`. Weak and unknown tags match no
+/// version, so a request carrying only those always fails.
+pub fn if_match(headers: &HeaderMap) -> Option<Vec<u64>> {
+    if !headers.contains_key(header::IF_MATCH) {
+        return None;
+    }
+    let mut versions = Vec::new();
+    for tag in tags(headers, header::IF_MATCH) {
+        if tag == "*" {
+            return None;
+        }
+        versions.extend(parse_etag(tag));
+    }
+    Some(versions)
+}
+
+/// Whether `If-None-Match` names `version`, in which case the client's
+/// copy is current.
+pub fn is_not_modified(headers: &HeaderMap, version: u64) -> bool {
+    tags(headers, header::IF_NONE_MATCH)
+        .any(|tag| tag == "*" || parse_etag(tag.strip_prefix("W/").unwrap_or(tag)) == Some(version))
+}
+
+/// Versioned answers a load with the resource and its ETag, or with 304 Not
+/// Modified when the client sent the current ETag in `If-None-Match`.
+#[derive(Debug)]
+pub struct Versioned<T> {
+    pub version: u64,
+    pub body: Option<T>,
+}
+
+impl<T> Versioned<T> {
+    pub fn new(headers: &HeaderMap, version: u64, body: T) -> Self {
+        Self {
+            version,
+            body: (!is_not_modified(headers, version)).then_some(body),
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for Versioned<T> {
+    fn into_response(self) -> Response {
+        match self.body {
+            Some(body) => (etag_header(self.version), Json(body)).into_response(),
+            None => (StatusCode::NOT_MODIFIED, etag_header(self.version)).into_response(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(name: HeaderName, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_if_match() {
+        assert_eq!(if_match(&HeaderMap::new()), None);
+        assert_eq!(if_match(&headers(header::IF_MATCH, "*")), None);
+        assert_eq!(
+            if_match(&headers(header::IF_MATCH, r#""3", "4""#)),
+            Some(vec![3, 4])
+        );
+        assert_eq!(
+            if_match(&headers(header::IF_MATCH, r#"W/"3", "abc""#)),
+            Some(vec![])
+        );
+    }
+
+    #[test]
+    fn test_if_none_match() {
+        assert!(!is_not_modified(&HeaderMap::new(), 3));
+        assert!(is_not_modified(
+            &headers(header::IF_NONE_MATCH, r#""2", W/"3""#),
+            3
+        ));
+        assert!(!is_not_modified(
+            &headers(header::IF_NONE_MATCH, r#""2""#),
+            3
+        ));
+        assert!(is_not_modified(&headers(header::IF_NONE_MATCH, "*"), 3));
+
+        let response =
+            Versioned::new(&headers(header::IF_NONE_MATCH, r#""3""#), 3, "x").into_response();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], r#""3""#);
+    }
+}
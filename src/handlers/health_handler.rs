@@ -0,0 +1,62 @@
+use crate::dto::health::{HealthResponse, ReadinessReport, ServerInfo};
+use crate::repository::health::HealthRepository;
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+};
+use std::sync::Arc;
+
+/// Liveness: answering at all means the process is up.
+pub async fn health() -> Json<HealthResponse> {
+    Json(HealthResponse {
+        status: "ok".to_string(),
+    })
+}
+
+/// Readiness: 503 with the failed checks while the catalog cannot serve.
+pub async fn ready(
+    State(repo): State<Arc<HealthRepository>>,
+) -> (StatusCode, Json<ReadinessReport>) {
+    let report = repo.readiness();
+    let status = if report.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(report))
+}
+
+pub async fn info(
+    State(repo): State<Arc<HealthRepository>>,
+) -> Result<Json<ServerInfo>, (StatusCode, String)> {
+    repo.info()
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::database::Database;
+    use std::sync::Mutex;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_health_endpoints() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let db = Arc::new(Mutex::new(db));
+        let repo = Arc::new(HealthRepository::new(db.clone()));
+
+        assert_eq!(health().await.status, "ok");
+        let (status, report) = ready(State(repo.clone())).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(report.ready);
+        let info = info(State(repo.clone())).await.unwrap();
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+
+        db.lock().unwrap().set_format_version(1).unwrap();
+        let (status, _) = ready(State(repo)).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
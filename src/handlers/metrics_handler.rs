@@ -0,0 +1,33 @@
+use crate::repository::metrics::MetricsRepository;
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+};
+use std::sync::Arc;
+
+/// Prometheus text exposition of the request, lock and RocksDB metrics.
+pub async fn metrics(
+    State(repo): State<Arc<MetricsRepository>>,
+) -> Result<([(header::HeaderName, &'static str); 1], String), (StatusCode, String)> {
+    repo.render()
+        .map(|text| ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], text))
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::database::Database;
+    use std::sync::Mutex;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_metrics() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let repo = Arc::new(MetricsRepository::new(Arc::new(Mutex::new(db))));
+        let (headers, text) = metrics(State(repo)).await.unwrap();
+        assert_eq!(headers[0].1, "text/plain; version=0.0.4");
+        assert!(text.contains("catalog_rocksdb_block_cache_usage_bytes"));
+    }
+}
@@ -1,2 +1,12 @@
+pub mod acl_handler;
+pub mod admin_handler;
+pub mod batch;
+pub mod conditional;
+pub mod health_handler;
+pub mod metrics_handler;
 pub mod namespace_handler;
+pub mod naming;
+pub mod oauth_handler;
+pub mod search_handler;
+pub mod statistics_handler;
 pub mod table_handler;
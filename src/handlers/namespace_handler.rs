@@ -1,13 +1,23 @@
+use crate::auth::access::Caller;
+use crate::dto::acl::{Privilege, Resource};
+use crate::dto::batch::{BatchCreateNamespacesRequest, BatchCreateResponse};
 use crate::dto::namespace_data::{NamespaceData, NamespaceIdent};
 use crate::dto::set_namespace_properties_req::SetNamespacePropertiesRequest;
+use crate::handlers::batch::{check_batch_size, create_response, error_status};
+use crate::handlers::conditional::{etag_header, if_match, Versioned};
+use crate::handlers::naming::{invalid_name, NamingRules};
 use crate::repository::namespace::NamespaceRepository;
+use crate::repository::quota::QuotaRepository;
+use crate::repository::version::is_version_mismatch;
 
 use axum::{
-    extract::{Json, Path, State},
-    http::StatusCode,
+    extract::{Extension, Json, Path, State},
+    http::{HeaderMap, HeaderName, StatusCode},
 };
 use serde_json::{json, Value};
+use std::io::ErrorKind;
 use std::sync::Arc;
+use tracing::info;
 
 /*
     TODO:
@@ -15,9 +25,16 @@ use std::sync::Arc;
 */
 pub async fn list_namespaces(
     State(repo): State<Arc<NamespaceRepository>>,
+    Extension(caller): Extension<Caller>,
 ) -> Result<Json<Value>, (StatusCode, String)> {
     match repo.list_all_namespaces() {
         Ok(namespaces) => {
+            let namespaces: Vec<NamespaceIdent> = namespaces
+                .into_iter()
+                .filter(|namespace| {
+                    caller.allows(Privilege::ReadMetadata, Resource::Namespace(namespace))
+                })
+                .collect();
             let json_object = json!({
                 "namespaces": namespaces
             });
@@ -32,31 +49,83 @@ pub async fn list_namespaces(
 
 pub async fn create_namespace(
     State(repo): State<Arc<NamespaceRepository>>,
+    Extension(caller): Extension<Caller>,
+    Extension(quotas): Extension<Arc<QuotaRepository>>,
+    Extension(naming): Extension<Arc<NamingRules>>,
     new_namespace: Json<NamespaceData>,
 ) -> Result<Json<NamespaceData>, (StatusCode, String)> {
-    if repo.namespace_exists(new_namespace.get_name()).unwrap() {
-        return Err((StatusCode::CONFLICT, format!("namespace already exists")));
+    naming
+        .check_new_namespace(new_namespace.get_name())
+        .map_err(invalid_name)?;
+    let name = &new_namespace.get_name().0;
+    let parent = NamespaceIdent::new(name[..name.len().saturating_sub(1)].to_vec());
+    if parent.0.is_empty() {
+        caller.require(Privilege::CreateNamespace, Resource::Catalog)?;
+    } else {
+        caller.require(Privilege::CreateNamespace, Resource::Namespace(&parent))?;
     }
+    let owner = quotas.owner(&caller.principal.name);
     repo.create_namespace(
         new_namespace.get_name().clone(),
         Some(new_namespace.get_properties().clone()),
+        Some(&owner),
     )
     .map(|_| new_namespace)
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e)))
+    .map_err(|e| match e.kind() {
+        ErrorKind::AlreadyExists => (StatusCode::CONFLICT, format!("Error: {}", e)),
+        ErrorKind::PermissionDenied => (StatusCode::FORBIDDEN, format!("Error: {}", e)),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e)),
+    })
+}
+
+/// Creates many namespaces in one call, see [`BatchCreateNamespacesRequest`].
+pub async fn create_namespaces(
+    State(repo): State<Arc<NamespaceRepository>>,
+    Extension(caller): Extension<Caller>,
+    Extension(quotas): Extension<Arc<QuotaRepository>>,
+    Extension(naming): Extension<Arc<NamingRules>>,
+    Json(request): Json<BatchCreateNamespacesRequest>,
+) -> Result<Json<BatchCreateResponse<NamespaceIdent>>, (StatusCode, String)> {
+    check_batch_size(request.namespaces.len())?;
+    for namespace in &request.namespaces {
+        naming
+            .check_new_namespace(namespace.get_name())
+            .map_err(invalid_name)?;
+        let name = &namespace.get_name().0;
+        let parent = NamespaceIdent::new(name[..name.len().saturating_sub(1)].to_vec());
+        if parent.0.is_empty() {
+            caller.require(Privilege::CreateNamespace, Resource::Catalog)?;
+        } else {
+            caller.require(Privilege::CreateNamespace, Resource::Namespace(&parent))?;
+        }
+    }
+    let owner = quotas.owner(&caller.principal.name);
+    repo.create_namespaces(&request.namespaces, request.atomic, Some(&owner))
+        .map(|results| {
+            let names = request
+                .namespaces
+                .into_iter()
+                .map(|namespace| namespace.name);
+            Json(create_response(names, results, StatusCode::OK))
+        })
+        .map_err(|e| (error_status(&e), format!("Error: {}", e)))
 }
 
 pub async fn load_namespace_metadata(
     State(repo): State<Arc<NamespaceRepository>>,
+    Extension(caller): Extension<Caller>,
     Path(namespace): Path<String>,
-) -> Result<Json<NamespaceData>, (StatusCode, String)> {
+    headers: HeaderMap,
+) -> Result<Versioned<NamespaceData>, (StatusCode, String)> {
     let id = NamespaceIdent::new(
         namespace
             .split('\u{1F}')
             .map(|part| part.to_string())
             .collect(),
     );
+    caller.require(Privilege::ReadMetadata, Resource::Namespace(&id))?;
     match repo.load_namespace(&id) {
-        Ok(Some(metadata)) => Ok(Json(metadata)),
+        Ok(Some(metadata)) => Ok(Versioned::new(&headers, metadata.version, metadata)),
         Ok(None) => Err((
             StatusCode::NOT_FOUND,
             format!("Namespace {} not found", namespace),
@@ -67,6 +136,7 @@ pub async fn load_namespace_metadata(
 
 pub async fn namespace_exists(
     State(repo): State<Arc<NamespaceRepository>>,
+    Extension(caller): Extension<Caller>,
     Path(namespace): Path<String>,
 ) -> Result<StatusCode, (StatusCode, String)> {
     let id = NamespaceIdent::new(
@@ -75,6 +145,7 @@ pub async fn namespace_exists(
             .map(|part| part.to_string())
             .collect(),
     );
+    caller.require(Privilege::ReadMetadata, Resource::Namespace(&id))?;
     repo.namespace_exists(&id)
         .map(|exists| {
             if exists {
@@ -89,7 +160,9 @@ pub async fn namespace_exists(
 
 pub async fn drop_namespace(
     State(repo): State<Arc<NamespaceRepository>>,
+    Extension(caller): Extension<Caller>,
     Path(namespace): Path<String>,
+    headers: HeaderMap,
 ) -> Result<StatusCode, (StatusCode, String)> {
     let id = NamespaceIdent::new(
         namespace
@@ -97,28 +170,47 @@ pub async fn drop_namespace(
             .map(|part| part.to_string())
             .collect(),
     );
-    if !repo.namespace_exists(&id).unwrap() {
+    caller.require(Privilege::Drop, Resource::Namespace(&id))?;
+    if !repo
+        .namespace_exists(&id)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e)))?
+    {
         return Err((StatusCode::NOT_FOUND, format!("namespace does not exist")));
     }
 
-    repo.delete_namespace(&id)
-        .map(|_| StatusCode::NO_CONTENT)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e)))
+    repo.delete_namespace(&id, if_match(&headers).as_deref())
+        .map(|_| {
+            info!(target: "audit", principal = %caller.principal, namespace = %id, "dropped namespace");
+            StatusCode::NO_CONTENT
+        })
+        .map_err(|e| match e.kind() {
+            ErrorKind::ResourceBusy => (StatusCode::CONFLICT, format!("Error: {}", e)),
+            ErrorKind::Other if is_version_mismatch(&e) => {
+                (StatusCode::PRECONDITION_FAILED, format!("Error: {}", e))
+            }
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e)),
+        })
 }
 
 pub async fn set_namespace_properties(
     State(repo): State<Arc<NamespaceRepository>>,
+    Extension(caller): Extension<Caller>,
     Path(namespace): Path<String>,
+    headers: HeaderMap,
     request_body: Json<SetNamespacePropertiesRequest>,
-) -> Result<StatusCode, (StatusCode, String)> {
+) -> Result<(StatusCode, [(HeaderName, String); 1]), (StatusCode, String)> {
     let id = NamespaceIdent::new(
         namespace
             .split('\u{1F}')
             .map(|part| part.to_string())
             .collect(),
     );
+    caller.require(Privilege::Commit, Resource::Namespace(&id))?;
 
-    if !repo.namespace_exists(&id).unwrap() {
+    if !repo
+        .namespace_exists(&id)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e)))?
+    {
         return Err((StatusCode::NOT_FOUND, format!("namespace does not exist")));
     }
 
@@ -128,96 +220,223 @@ pub async fn set_namespace_properties(
         id,
         request_body.removals.clone(),
         request_body.updates.clone(),
+        if_match(&headers).as_deref(),
     )
-    .map(|_| StatusCode::OK)
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e)))
+    .map(|version| (StatusCode::OK, etag_header(version)))
+    .map_err(|e| match e.kind() {
+        ErrorKind::Other if is_version_mismatch(&e) => {
+            (StatusCode::PRECONDITION_FAILED, format!("Error: {}", e))
+        }
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e)),
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::auth::authenticator::Principal;
     use crate::database::database::Database;
     use crate::dto::set_namespace_properties_req::SetNamespacePropertiesRequest;
-    use axum::http::StatusCode;
+    use crate::dto::table_data::TableCreation;
+    use crate::repository::table::TableRepository;
+    use axum::http::{header, HeaderValue, StatusCode};
     use std::sync::{Arc, Mutex};
     use tempfile::tempdir;
+
+    fn caller() -> Caller {
+        Caller::unrestricted(Principal::anonymous())
+    }
+
+    fn naming() -> Arc<NamingRules> {
+        Arc::new(NamingRules::new(&Default::default()).unwrap())
+    }
+
+    fn if_match_header(etag: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_MATCH, HeaderValue::from_static(etag));
+        headers
+    }
+
     #[tokio::test]
     async fn test_namespace_endpoints() {
-        let repo = Arc::new(NamespaceRepository::new(Arc::new(Mutex::new(
-            Database::open(tempdir().unwrap().path()).unwrap(),
-        ))));
+        let dir = tempdir().unwrap();
+        let db = Arc::new(Mutex::new(Database::open(dir.path()).unwrap()));
+        let repo = Arc::new(NamespaceRepository::new(db.clone()));
+        let quotas = Arc::new(QuotaRepository::new(db.clone(), Default::default()));
         // Test create_namespace
         let new_namespace = Json(NamespaceData {
             name: NamespaceIdent(vec!["test".to_string()]),
             properties: json!({"property1": "value1"}),
+            version: 0,
         });
         assert_eq!(
-            create_namespace(State(repo.clone()), new_namespace.clone())
-                .await
-                .unwrap()
-                .name,
+            create_namespace(
+                State(repo.clone()),
+                Extension(caller()),
+                Extension(quotas.clone()),
+                Extension(naming()),
+                new_namespace.clone()
+            )
+            .await
+            .unwrap()
+            .name,
             new_namespace.name
         );
 
+        // Test create_namespace without a name
+        let unnamed = Json(NamespaceData {
+            name: NamespaceIdent(vec![]),
+            properties: json!({}),
+            version: 0,
+        });
+        assert_eq!(
+            create_namespace(
+                State(repo.clone()),
+                Extension(caller()),
+                Extension(quotas.clone()),
+                Extension(naming()),
+                unnamed
+            )
+            .await
+            .unwrap_err()
+            .0,
+            StatusCode::BAD_REQUEST
+        );
+
         // Test namespace_exists
         assert_eq!(
-            namespace_exists(State(repo.clone()), Path("test".to_string()))
-                .await
-                .unwrap(),
+            namespace_exists(
+                State(repo.clone()),
+                Extension(caller()),
+                Path("test".to_string())
+            )
+            .await
+            .unwrap(),
             StatusCode::NO_CONTENT
         );
 
         // Test load_namespace_metadata
+        let loaded = load_namespace_metadata(
+            State(repo.clone()),
+            Extension(caller()),
+            Path("test".to_string()),
+            HeaderMap::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(loaded.version, 1);
+        assert_eq!(loaded.body.unwrap().name, new_namespace.name);
+
+        // Test set_namespace_properties
+        let set_namespace_properties_request = || {
+            Json(SetNamespacePropertiesRequest {
+                removals: vec!["property1".to_string()],
+                updates: serde_json::from_value(json!({"property2": "value2"})).unwrap(),
+            })
+        };
+        let (status, etag) = set_namespace_properties(
+            State(repo.clone()),
+            Extension(caller()),
+            Path("test".to_string()),
+            if_match_header("\"1\""),
+            set_namespace_properties_request(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(etag[0].1, "\"2\"");
+
+        // A second writer still holding version 1 loses
+        let err = set_namespace_properties(
+            State(repo.clone()),
+            Extension(caller()),
+            Path("test".to_string()),
+            if_match_header("\"1\""),
+            set_namespace_properties_request(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::PRECONDITION_FAILED);
+
+        // Test load_namespace_metadata after set_namespace_properties
+        let loaded = load_namespace_metadata(
+            State(repo.clone()),
+            Extension(caller()),
+            Path("test".to_string()),
+            HeaderMap::new(),
+        )
+        .await
+        .unwrap();
         assert_eq!(
-            load_namespace_metadata(State(repo.clone()), Path("test".to_string()))
-                .await
-                .unwrap()
-                .name,
-            new_namespace.name
+            loaded.body.unwrap().properties,
+            json!({"property2": "value2"})
         );
 
-        // Test set_namespace_properties
-        let set_namespace_properties_request = Json(SetNamespacePropertiesRequest {
-            removals: vec!["property1".to_string()],
-            updates: serde_json::from_value(json!({"property2": "value2"})).unwrap(),
-        });
+        // Test load_namespace_metadata with a current If-None-Match
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("\"2\""));
+        let loaded = load_namespace_metadata(
+            State(repo.clone()),
+            Extension(caller()),
+            Path("test".to_string()),
+            headers,
+        )
+        .await
+        .unwrap();
+        assert!(loaded.body.is_none());
+
+        // Test drop_namespace on a namespace holding a table
+        let tables = TableRepository::new(db.clone());
+        let orders = TableCreation {
+            name: "orders".to_string(),
+            columns: None,
+            properties: None,
+        };
+        tables
+            .create_table(&NamespaceIdent(vec!["test".to_string()]), &orders, None)
+            .unwrap();
         assert_eq!(
-            set_namespace_properties(
+            drop_namespace(
                 State(repo.clone()),
+                Extension(caller()),
                 Path("test".to_string()),
-                set_namespace_properties_request
+                HeaderMap::new()
             )
             .await
-            .unwrap(),
-            StatusCode::OK
-        );
-
-        // Test load_namespace_metadata after set_namespace_properties
-        assert_eq!(
-            load_namespace_metadata(State(repo.clone()), Path("test".to_string()))
-                .await
-                .unwrap()
-                .name,
-            Json(NamespaceData {
-                name: NamespaceIdent(vec!["test".to_string()]),
-                properties: json!({"property2": "value2"}),
-            })
-            .name
+            .unwrap_err()
+            .0,
+            StatusCode::CONFLICT
         );
+        tables
+            .drop_table(
+                &NamespaceIdent(vec!["test".to_string()]),
+                "orders".to_string(),
+                None,
+            )
+            .unwrap();
 
         // Test drop_namespace
         assert_eq!(
-            drop_namespace(State(repo.clone()), Path("test".to_string()))
-                .await
-                .unwrap(),
+            drop_namespace(
+                State(repo.clone()),
+                Extension(caller()),
+                Path("test".to_string()),
+                if_match_header("\"2\"")
+            )
+            .await
+            .unwrap(),
             StatusCode::NO_CONTENT
         );
 
         // Test namespace_exists after drop_namespace
         assert_eq!(
-            namespace_exists(State(repo.clone()), Path("test".to_string()))
-                .await
-                .unwrap(),
+            namespace_exists(
+                State(repo.clone()),
+                Extension(caller()),
+                Path("test".to_string())
+            )
+            .await
+            .unwrap(),
             StatusCode::NOT_FOUND
         );
     }
@@ -0,0 +1,264 @@
+use crate::config::config::{CasePolicy, NamingConfig};
+use crate::dto::namespace_data::NamespaceIdent;
+use crate::dto::table_data::TableIdent;
+use axum::{
+    extract::{RawPathParams, Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use regex::Regex;
+use std::io::{self, ErrorKind};
+use std::sync::Arc;
+
+/// NamingRules checks namespace and table names against the configured
+/// [`NamingConfig`].
+///
+/// New names must follow every rule. Names of existing objects, as found in
+/// request paths, only have to be well formed, so that tightening the rules
+/// does not make anything unreachable.
+pub struct NamingRules {
+    config: NamingConfig,
+    allowed: Option<Regex>,
+}
+
+impl NamingRules {
+    pub fn new(config: &NamingConfig) -> Result<Self, io::Error> {
+        let allowed = config.allowed_regex().map_err(|e| {
+            io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid naming.allowed_characters: {}", e),
+            )
+        })?;
+        Ok(Self {
+            config: config.clone(),
+            allowed,
+        })
+    }
+
+    /// How names treat case, which the repositories enforce against
+    /// existing names.
+    pub fn case_policy(&self) -> CasePolicy {
+        self.config.case
+    }
+
+    /// Checks the name of a namespace about to be created.
+    pub fn check_new_namespace(&self, namespace: &NamespaceIdent) -> Result<(), String> {
+        self.check_namespace(namespace, true)
+    }
+
+    /// Checks the identifier of a table about to be created.
+    pub fn check_new_table(&self, table: &TableIdent) -> Result<(), String> {
+        self.check_namespace(&table.namespace, false)?;
+        self.check_name("Table name", &table.name, true)
+    }
+
+    /// Checks the identifier of a table that is looked up.
+    pub fn check_table(&self, table: &TableIdent) -> Result<(), String> {
+        self.check_namespace(&table.namespace, false)?;
+        self.check_name("Table name", &table.name, false)
+    }
+
+    fn check_namespace(&self, namespace: &NamespaceIdent, new: bool) -> Result<(), String> {
+        let levels = namespace.0.len();
+        if levels == 0 {
+            return Err("Namespace must have at least one level".to_string());
+        }
+        if levels > self.config.max_depth {
+            return Err(format!(
+                "Namespace has {} levels, at most {} are allowed",
+                levels, self.config.max_depth
+            ));
+        }
+        for (i, level) in namespace.0.iter().enumerate() {
+            self.check_name(&format!("Namespace level {}", i + 1), level, new)?;
+        }
+        Ok(())
+    }
+
+    fn check_name(&self, what: &str, name: &str, new: bool) -> Result<(), String> {
+        if name.is_empty() {
+            return Err(format!("{} is empty", what));
+        }
+        if name.len() > self.config.max_length {
+            return Err(format!(
+                "{} is {} bytes long, at most {} are allowed",
+                what,
+                name.len(),
+                self.config.max_length
+            ));
+        }
+        if let Some(c) = name.chars().find(|c| c.is_control()) {
+            return Err(format!(
+                "{} {:?} contains control character U+{:04X}",
+                what, name, c as u32
+            ));
+        }
+        if !new {
+            return Ok(());
+        }
+        if let Some(allowed) = &self.allowed {
+            let mut buffer = [0; 4];
+            if let Some(c) = name
+                .chars()
+                .find(|c| !allowed.is_match(c.encode_utf8(&mut buffer)))
+            {
+                return Err(format!(
+                    "{} {:?} contains {:?}, only {} are allowed",
+                    what,
+                    name,
+                    c,
+                    self.config
+                        .allowed_characters
+                        .as_deref()
+                        .unwrap_or_default()
+                ));
+            }
+        }
+        if self.config.case == CasePolicy::Lowercase && name.chars().any(char::is_uppercase) {
+            return Err(format!("{} {:?} must be lowercase", what, name));
+        }
+        let lowercase = name.to_lowercase();
+        if self
+            .config
+            .reserved_names
+            .iter()
+            .any(|reserved| reserved.to_lowercase() == lowercase)
+        {
+            return Err(format!("{} {:?} is a reserved name", what, name));
+        }
+        Ok(())
+    }
+}
+
+/// Turns a broken naming rule into the response handlers answer with.
+pub fn invalid_name(message: String) -> (StatusCode, String) {
+    (StatusCode::BAD_REQUEST, format!("Error: {}", message))
+}
+
+/// Rejects requests whose `namespace` or `table` path parameter is not a
+/// well formed name, before they reach a handler.
+pub async fn check_path_names(
+    State(rules): State<Arc<NamingRules>>,
+    params: Option<RawPathParams>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let mut namespace = None;
+    let mut table = None;
+    for (key, value) in params.iter().flatten() {
+        match key {
+            "namespace" => {
+                namespace = Some(NamespaceIdent::new(
+                    value.split('\u{1F}').map(|part| part.to_string()).collect(),
+                ))
+            }
+            "table" => table = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    let result = match (namespace, table) {
+        (Some(namespace), Some(table)) => rules.check_table(&TableIdent::new(namespace, table)),
+        (Some(namespace), None) => rules.check_namespace(&namespace, false),
+        _ => Ok(()),
+    };
+    match result {
+        Ok(()) => next.run(request).await,
+        Err(message) => invalid_name(message).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn namespace(levels: &[&str]) -> NamespaceIdent {
+        NamespaceIdent::new(levels.iter().map(|level| level.to_string()).collect())
+    }
+
+    #[test]
+    fn test_naming_rules() {
+        let rules = NamingRules::new(&NamingConfig {
+            allowed_characters: Some("[a-z0-9_]".to_string()),
+            max_depth: 2,
+            max_length: 8,
+            reserved_names: vec!["system".to_string()],
+            case: CasePolicy::Lowercase,
+        })
+        .unwrap();
+        assert!(rules
+            .check_new_namespace(&namespace(&["sales", "eu"]))
+            .is_ok());
+
+        let cases = [
+            (namespace(&[]), "Namespace must have at least one level"),
+            (
+                namespace(&["a", "b", "c"]),
+                "Namespace has 3 levels, at most 2 are allowed",
+            ),
+            (namespace(&["sales", ""]), "Namespace level 2 is empty"),
+            (
+                namespace(&["warehouse"]),
+                "Namespace level 1 is 9 bytes long, at most 8 are allowed",
+            ),
+            (
+                namespace(&["a\u{7}"]),
+                "Namespace level 1 \"a\\u{7}\" contains control character U+0007",
+            ),
+            (
+                namespace(&["a-b"]),
+                "Namespace level 1 \"a-b\" contains '-', only [a-z0-9_] are allowed",
+            ),
+            (
+                namespace(&["SYSTEM"]),
+                "Namespace level 1 \"SYSTEM\" contains 'S', only [a-z0-9_] are allowed",
+            ),
+            (
+                namespace(&["system"]),
+                "Namespace level 1 \"system\" is a reserved name",
+            ),
+        ];
+        for (namespace, message) in cases {
+            assert_eq!(rules.check_new_namespace(&namespace).unwrap_err(), message);
+        }
+
+        // Existing names only have to be well formed.
+        let legacy = TableIdent::new(namespace(&["Sales"]), "system".to_string());
+        assert!(rules.check_table(&legacy).is_ok());
+        assert_eq!(
+            rules.check_new_table(&legacy).unwrap_err(),
+            "Table name \"system\" is a reserved name"
+        );
+        let unnamed = TableIdent::new(namespace(&["sales"]), String::new());
+        assert_eq!(
+            rules.check_table(&unnamed).unwrap_err(),
+            "Table name is empty"
+        );
+    }
+
+    #[test]
+    fn test_case_policy() {
+        let rules = NamingRules::new(&NamingConfig {
+            case: CasePolicy::Lowercase,
+            reserved_names: vec!["System".to_string()],
+            ..NamingConfig::default()
+        })
+        .unwrap();
+        assert_eq!(
+            rules
+                .check_new_namespace(&namespace(&["Sales"]))
+                .unwrap_err(),
+            "Namespace level 1 \"Sales\" must be lowercase"
+        );
+        assert_eq!(
+            rules
+                .check_new_namespace(&namespace(&["system"]))
+                .unwrap_err(),
+            "Namespace level 1 \"system\" is a reserved name"
+        );
+        assert!(NamingRules::new(&NamingConfig::default())
+            .unwrap()
+            .check_new_namespace(&namespace(&["Sales"]))
+            .is_ok());
+    }
+}
@@ -0,0 +1,79 @@
+use crate::auth::authenticator::Authenticator;
+use crate::dto::oauth::{OAuthError, TokenRequest, TokenResponse};
+use axum::{
+    extract::{Form, Json, State},
+    http::StatusCode,
+};
+use std::io::ErrorKind;
+use std::sync::Arc;
+
+/// Client credentials flow of the Iceberg REST catalog; errors use the
+/// OAuth error body so that clients can report them.
+pub async fn issue_token(
+    State(auth): State<Arc<Authenticator>>,
+    Form(request): Form<TokenRequest>,
+) -> Result<Json<TokenResponse>, (StatusCode, Json<OAuthError>)> {
+    auth.issue_token(&request).map(Json).map_err(|e| {
+        let (status, error) = match e.kind() {
+            ErrorKind::PermissionDenied => (StatusCode::UNAUTHORIZED, "invalid_client"),
+            ErrorKind::Unsupported => (StatusCode::BAD_REQUEST, "unsupported_grant_type"),
+            ErrorKind::InvalidInput => (StatusCode::BAD_REQUEST, "invalid_request"),
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, "server_error"),
+        };
+        (
+            status,
+            Json(OAuthError {
+                error: error.to_string(),
+                error_description: e.to_string(),
+            }),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::config::{AuthConfig, OAuthClient};
+
+    fn request(grant_type: &str, client_secret: &str) -> Form<TokenRequest> {
+        Form(TokenRequest {
+            grant_type: grant_type.to_string(),
+            client_id: "spark".to_string(),
+            client_secret: client_secret.to_string(),
+            scope: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_issue_token() {
+        let auth = Arc::new(Authenticator::new(AuthConfig {
+            enabled: true,
+            jwt_secret: Some("secret".to_string()),
+            clients: vec![OAuthClient {
+                client_id: "spark".to_string(),
+                client_secret: "spark-secret".to_string(),
+            }],
+            ..Default::default()
+        }));
+
+        let response = issue_token(
+            State(auth.clone()),
+            request("client_credentials", "spark-secret"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.token_type, "bearer");
+
+        let (status, error) = issue_token(State(auth.clone()), request("client_credentials", "x"))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(error.error, "invalid_client");
+
+        let (status, error) = issue_token(State(auth), request("password", "spark-secret"))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error.error, "unsupported_grant_type");
+    }
+}
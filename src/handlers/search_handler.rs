@@ -0,0 +1,92 @@
+use crate::auth::access::Caller;
+use crate::dto::acl::{Privilege, Resource};
+use crate::dto::search::{PropertyIndex, SearchQuery, SearchResponse};
+use crate::repository::search::SearchRepository;
+use axum::{
+    extract::{Extension, Json, Path, Query, State},
+    http::StatusCode,
+};
+use serde_json::{json, Value};
+use std::io::ErrorKind;
+use std::sync::Arc;
+use tracing::info;
+
+/// Searches namespaces and tables, see [`SearchQuery`]. Only objects the
+/// caller may read are returned.
+pub async fn search(
+    State(repo): State<Arc<SearchRepository>>,
+    Extension(caller): Extension<Caller>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<SearchResponse>, (StatusCode, String)> {
+    let mut found = repo.search(&query).map_err(|e| match e.kind() {
+        ErrorKind::InvalidInput => (StatusCode::BAD_REQUEST, format!("Error: {}", e)),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e)),
+    })?;
+    found
+        .namespaces
+        .retain(|namespace| caller.allows(Privilege::ReadMetadata, Resource::Namespace(namespace)));
+    found
+        .tables
+        .retain(|table| caller.allows(Privilege::ReadMetadata, Resource::Table(table)));
+    Ok(Json(found))
+}
+
+pub async fn list_indexes(
+    State(repo): State<Arc<SearchRepository>>,
+    Extension(caller): Extension<Caller>,
+) -> Result<Json<Vec<PropertyIndex>>, (StatusCode, String)> {
+    caller.require(Privilege::ReadMetadata, Resource::Catalog)?;
+    repo.list_indexes()
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e)))
+}
+
+/// Declares an index on a property key; the existing namespaces and tables
+/// are indexed before it returns.
+pub async fn create_index(
+    State(repo): State<Arc<SearchRepository>>,
+    Extension(caller): Extension<Caller>,
+    index: Json<PropertyIndex>,
+) -> Result<(StatusCode, Json<PropertyIndex>), (StatusCode, String)> {
+    caller.require(Privilege::ManageCatalog, Resource::Catalog)?;
+    match repo.create_index(&index) {
+        Ok(()) => {
+            info!(target: "audit", principal = %caller.principal, key = %index.key, "created property index");
+            Ok((StatusCode::CREATED, index))
+        }
+        Err(e) => match e.kind() {
+            ErrorKind::AlreadyExists => Err((StatusCode::CONFLICT, format!("Error: {}", e))),
+            ErrorKind::InvalidInput => Err((StatusCode::BAD_REQUEST, format!("Error: {}", e))),
+            _ => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e))),
+        },
+    }
+}
+
+pub async fn drop_index(
+    State(repo): State<Arc<SearchRepository>>,
+    Extension(caller): Extension<Caller>,
+    Path(key): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    caller.require(Privilege::ManageCatalog, Resource::Catalog)?;
+    match repo.drop_index(&key) {
+        Ok(()) => {
+            info!(target: "audit", principal = %caller.principal, key = %key, "dropped property index");
+            Ok(StatusCode::NO_CONTENT)
+        }
+        Err(e) => match e.kind() {
+            ErrorKind::NotFound => Err((StatusCode::NOT_FOUND, format!("Error: {}", e))),
+            _ => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e))),
+        },
+    }
+}
+
+/// Rebuilds the search index from the stored namespaces and tables.
+pub async fn rebuild_indexes(
+    State(repo): State<Arc<SearchRepository>>,
+    Extension(caller): Extension<Caller>,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    caller.require(Privilege::ManageCatalog, Resource::Catalog)?;
+    repo.rebuild_indexes()
+        .map(|terms| Json(json!({ "terms": terms })))
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e)))
+}
@@ -0,0 +1,512 @@
+use crate::auth::access::Caller;
+use crate::dto::acl::{Privilege, Resource};
+use crate::dto::histogram::{
+    ColumnHistogram, EstimateSelectivityRequest, EstimateSelectivityResponse, SetHistogramsRequest,
+};
+use crate::dto::namespace_data::NamespaceIdent;
+use crate::dto::statistics::{
+    ColumnSketch, MergeHllSketchesRequest, MergeHllSketchesResponse, TableStatisticsSummary,
+    UpdateTableStatisticsRequest,
+};
+use crate::dto::table_data::TableIdent;
+use crate::handlers::conditional::{etag_header, if_match};
+use crate::repository::statistics::StatisticsRepository;
+use crate::repository::version::is_version_mismatch;
+use axum::{
+    extract::{Extension, Json, Path, State},
+    http::{HeaderMap, HeaderName, StatusCode},
+};
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::sync::Arc;
+
+pub async fn load_table_statistics(
+    State(repo): State<Arc<StatisticsRepository>>,
+    Extension(caller): Extension<Caller>,
+    Path((namespace, table)): Path<(String, String)>,
+) -> Result<Json<TableStatisticsSummary>, (StatusCode, String)> {
+    let id = NamespaceIdent::new(
+        namespace
+            .split('\u{1F}')
+            .map(|part| part.to_string())
+            .collect(),
+    );
+    let table_id = TableIdent::new(id.clone(), table.clone());
+    caller.require(Privilege::ReadMetadata, Resource::Table(&table_id))?;
+    match repo.load_statistics(&id, table) {
+        Ok(summary) => Ok(Json(summary)),
+        Err(e) => match e.kind() {
+            ErrorKind::NotFound => Err((StatusCode::NOT_FOUND, format!("Error: {}", e))),
+            _ => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e))),
+        },
+    }
+}
+
+pub async fn update_table_statistics(
+    State(repo): State<Arc<StatisticsRepository>>,
+    Extension(caller): Extension<Caller>,
+    Path((namespace, table)): Path<(String, String)>,
+    headers: HeaderMap,
+    request: Json<UpdateTableStatisticsRequest>,
+) -> Result<(StatusCode, [(HeaderName, String); 1]), (StatusCode, String)> {
+    let id = NamespaceIdent::new(
+        namespace
+            .split('\u{1F}')
+            .map(|part| part.to_string())
+            .collect(),
+    );
+    let table_id = TableIdent::new(id.clone(), table.clone());
+    caller.require(Privilege::Commit, Resource::Table(&table_id))?;
+    match repo.update_statistics(&id, table, &request.updates, if_match(&headers).as_deref()) {
+        Ok(version) => Ok((StatusCode::NO_CONTENT, etag_header(version))),
+        Err(e) => match e.kind() {
+            ErrorKind::NotFound => Err((StatusCode::NOT_FOUND, format!("Error: {}", e))),
+            ErrorKind::Other if is_version_mismatch(&e) => {
+                Err((StatusCode::PRECONDITION_FAILED, format!("Error: {}", e)))
+            }
+            _ => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e))),
+        },
+    }
+}
+
+pub async fn load_column_sketch(
+    State(repo): State<Arc<StatisticsRepository>>,
+    Extension(caller): Extension<Caller>,
+    Path((namespace, table, column)): Path<(String, String, String)>,
+) -> Result<Json<ColumnSketch>, (StatusCode, String)> {
+    let id = NamespaceIdent::new(
+        namespace
+            .split('\u{1F}')
+            .map(|part| part.to_string())
+            .collect(),
+    );
+    let table_id = TableIdent::new(id.clone(), table.clone());
+    caller.require(Privilege::ReadMetadata, Resource::Table(&table_id))?;
+    match repo.load_sketch(&id, table, &column) {
+        Ok(Some(sketch)) => Ok(Json(sketch)),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            format!("Sketch for column {} not found", column),
+        )),
+        Err(e) => match e.kind() {
+            ErrorKind::NotFound => Err((StatusCode::NOT_FOUND, format!("Error: {}", e))),
+            _ => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e))),
+        },
+    }
+}
+
+pub async fn set_column_sketch(
+    State(repo): State<Arc<StatisticsRepository>>,
+    Extension(caller): Extension<Caller>,
+    Path((namespace, table, column)): Path<(String, String, String)>,
+    headers: HeaderMap,
+    sketch: Json<ColumnSketch>,
+) -> Result<(StatusCode, [(HeaderName, String); 1]), (StatusCode, String)> {
+    let id = NamespaceIdent::new(
+        namespace
+            .split('\u{1F}')
+            .map(|part| part.to_string())
+            .collect(),
+    );
+    let table_id = TableIdent::new(id.clone(), table.clone());
+    caller.require(Privilege::Commit, Resource::Table(&table_id))?;
+    match repo.set_sketch(&id, table, column, sketch.0, if_match(&headers).as_deref()) {
+        Ok(version) => Ok((StatusCode::NO_CONTENT, etag_header(version))),
+        Err(e) => match e.kind() {
+            ErrorKind::NotFound => Err((StatusCode::NOT_FOUND, format!("Error: {}", e))),
+            ErrorKind::InvalidInput => Err((StatusCode::BAD_REQUEST, format!("Error: {}", e))),
+            ErrorKind::Other if is_version_mismatch(&e) => {
+                Err((StatusCode::PRECONDITION_FAILED, format!("Error: {}", e)))
+            }
+            _ => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e))),
+        },
+    }
+}
+
+pub async fn merge_hll_sketches(
+    State(repo): State<Arc<StatisticsRepository>>,
+    Extension(caller): Extension<Caller>,
+    Path((namespace, table)): Path<(String, String)>,
+    headers: HeaderMap,
+    request: Json<MergeHllSketchesRequest>,
+) -> Result<([(HeaderName, String); 1], Json<MergeHllSketchesResponse>), (StatusCode, String)> {
+    let id = NamespaceIdent::new(
+        namespace
+            .split('\u{1F}')
+            .map(|part| part.to_string())
+            .collect(),
+    );
+    let table_id = TableIdent::new(id.clone(), table.clone());
+    caller.require(Privilege::Commit, Resource::Table(&table_id))?;
+    match repo.merge_hll_sketches(&id, table, &request, if_match(&headers).as_deref()) {
+        Ok((columns, version)) => Ok((
+            etag_header(version),
+            Json(MergeHllSketchesResponse { columns }),
+        )),
+        Err(e) => match e.kind() {
+            ErrorKind::NotFound => Err((StatusCode::NOT_FOUND, format!("Error: {}", e))),
+            ErrorKind::InvalidInput => Err((StatusCode::BAD_REQUEST, format!("Error: {}", e))),
+            ErrorKind::Other if is_version_mismatch(&e) => {
+                Err((StatusCode::PRECONDITION_FAILED, format!("Error: {}", e)))
+            }
+            _ => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e))),
+        },
+    }
+}
+
+pub async fn load_histograms(
+    State(repo): State<Arc<StatisticsRepository>>,
+    Extension(caller): Extension<Caller>,
+    Path((namespace, table)): Path<(String, String)>,
+) -> Result<Json<BTreeMap<String, ColumnHistogram>>, (StatusCode, String)> {
+    let id = NamespaceIdent::new(
+        namespace
+            .split('\u{1F}')
+            .map(|part| part.to_string())
+            .collect(),
+    );
+    let table_id = TableIdent::new(id.clone(), table.clone());
+    caller.require(Privilege::ReadMetadata, Resource::Table(&table_id))?;
+    match repo.load_histograms(&id, table) {
+        Ok(histograms) => Ok(Json(histograms)),
+        Err(e) => match e.kind() {
+            ErrorKind::NotFound => Err((StatusCode::NOT_FOUND, format!("Error: {}", e))),
+            _ => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e))),
+        },
+    }
+}
+
+pub async fn set_histograms(
+    State(repo): State<Arc<StatisticsRepository>>,
+    Extension(caller): Extension<Caller>,
+    Path((namespace, table)): Path<(String, String)>,
+    headers: HeaderMap,
+    request: Json<SetHistogramsRequest>,
+) -> Result<(StatusCode, [(HeaderName, String); 1]), (StatusCode, String)> {
+    let id = NamespaceIdent::new(
+        namespace
+            .split('\u{1F}')
+            .map(|part| part.to_string())
+            .collect(),
+    );
+    let table_id = TableIdent::new(id.clone(), table.clone());
+    caller.require(Privilege::Commit, Resource::Table(&table_id))?;
+    match repo.set_histograms(&id, table, &request.columns, if_match(&headers).as_deref()) {
+        Ok(version) => Ok((StatusCode::NO_CONTENT, etag_header(version))),
+        Err(e) => match e.kind() {
+            ErrorKind::NotFound => Err((StatusCode::NOT_FOUND, format!("Error: {}", e))),
+            ErrorKind::InvalidInput => Err((StatusCode::BAD_REQUEST, format!("Error: {}", e))),
+            ErrorKind::Other if is_version_mismatch(&e) => {
+                Err((StatusCode::PRECONDITION_FAILED, format!("Error: {}", e)))
+            }
+            _ => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e))),
+        },
+    }
+}
+
+pub async fn estimate_selectivity(
+    State(repo): State<Arc<StatisticsRepository>>,
+    Extension(caller): Extension<Caller>,
+    Path((namespace, table)): Path<(String, String)>,
+    request: Json<EstimateSelectivityRequest>,
+) -> Result<Json<EstimateSelectivityResponse>, (StatusCode, String)> {
+    let id = NamespaceIdent::new(
+        namespace
+            .split('\u{1F}')
+            .map(|part| part.to_string())
+            .collect(),
+    );
+    let table_id = TableIdent::new(id.clone(), table.clone());
+    caller.require(Privilege::ReadMetadata, Resource::Table(&table_id))?;
+    match repo.estimate_selectivity(&id, table, &request.filter) {
+        Ok(selectivity) => Ok(Json(EstimateSelectivityResponse { selectivity })),
+        Err(e) => match e.kind() {
+            ErrorKind::NotFound => Err((StatusCode::NOT_FOUND, format!("Error: {}", e))),
+            _ => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e))),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::authenticator::Principal;
+    use crate::database::database::Database;
+    use crate::dto::table_data::TableCreation;
+    use crate::repository::namespace::NamespaceRepository;
+    use crate::repository::table::TableRepository;
+    use axum::http::{header, HeaderValue};
+    use serde_json::json;
+    use std::sync::Mutex;
+    use tempfile::tempdir;
+
+    fn caller() -> Caller {
+        Caller::unrestricted(Principal::anonymous())
+    }
+
+    #[tokio::test]
+    async fn test_statistics_endpoints() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let db = Arc::new(Mutex::new(db));
+        let repo = Arc::new(StatisticsRepository::new(db.clone()));
+
+        // Create a namespace and a table for testing
+        let namespace_ident = NamespaceIdent(vec!["test".to_string()]);
+        NamespaceRepository::new(db.clone())
+            .create_namespace(namespace_ident.clone(), None, None)
+            .unwrap();
+        TableRepository::new(db.clone())
+            .create_table(
+                &namespace_ident,
+                &TableCreation {
+                    name: "table1".to_string(),
+                    columns: None,
+                    properties: None,
+                },
+                None,
+            )
+            .unwrap();
+
+        // Test update_table_statistics
+        let request = Json(
+            serde_json::from_value::<UpdateTableStatisticsRequest>(json!({
+                "updates": [{
+                    "action": "set-snapshot-statistics",
+                    "statistics": {"snapshot_id": 1, "row_count": 10, "total_size_in_bytes": 100}
+                }]
+            }))
+            .unwrap(),
+        );
+        assert_eq!(
+            update_table_statistics(
+                State(repo.clone()),
+                Extension(caller()),
+                Path(("test".to_string(), "table1".to_string())),
+                HeaderMap::new(),
+                request
+            )
+            .await
+            .unwrap()
+            .0,
+            StatusCode::NO_CONTENT
+        );
+
+        // Test load_table_statistics
+        let summary = load_table_statistics(
+            State(repo.clone()),
+            Extension(caller()),
+            Path(("test".to_string(), "table1".to_string())),
+        )
+        .await
+        .unwrap();
+        assert_eq!(summary.snapshot_id, Some(1));
+        assert_eq!(summary.row_count, Some(10));
+
+        // Test set_column_sketch and load_column_sketch
+        let sketch = ColumnSketch {
+            sketch_type: "theta".to_string(),
+            snapshot_id: Some(1),
+            data: vec![7, 8, 9],
+        };
+        assert_eq!(
+            set_column_sketch(
+                State(repo.clone()),
+                Extension(caller()),
+                Path(("test".to_string(), "table1".to_string(), "id".to_string())),
+                HeaderMap::new(),
+                Json(sketch.clone())
+            )
+            .await
+            .unwrap()
+            .0,
+            StatusCode::NO_CONTENT
+        );
+        assert_eq!(
+            load_column_sketch(
+                State(repo.clone()),
+                Extension(caller()),
+                Path(("test".to_string(), "table1".to_string(), "id".to_string())),
+            )
+            .await
+            .unwrap()
+            .0,
+            sketch
+        );
+
+        // Test set_column_sketch with an HLL sketch that does not decode
+        assert_eq!(
+            set_column_sketch(
+                State(repo.clone()),
+                Extension(caller()),
+                Path(("test".to_string(), "table1".to_string(), "name".to_string())),
+                HeaderMap::new(),
+                Json(ColumnSketch {
+                    sketch_type: "hll".to_string(),
+                    snapshot_id: Some(1),
+                    data: vec![1, 2, 3],
+                })
+            )
+            .await
+            .unwrap_err()
+            .0,
+            StatusCode::BAD_REQUEST
+        );
+
+        // Test load_column_sketch with unknown column
+        assert_eq!(
+            load_column_sketch(
+                State(repo.clone()),
+                Extension(caller()),
+                Path(("test".to_string(), "table1".to_string(), "name".to_string())),
+            )
+            .await
+            .unwrap_err()
+            .0,
+            StatusCode::NOT_FOUND
+        );
+
+        // Test merge_hll_sketches
+        let request = Json(
+            serde_json::from_value::<MergeHllSketchesRequest>(json!({
+                "columns": {"name": {"precision": 4, "registers": [1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]}}
+            }))
+            .unwrap(),
+        );
+        let response = merge_hll_sketches(
+            State(repo.clone()),
+            Extension(caller()),
+            Path(("test".to_string(), "table1".to_string())),
+            HeaderMap::new(),
+            request,
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.0, etag_header(4));
+        assert_eq!(response.1.columns["name"], 1);
+
+        // Test merge_hll_sketches with a malformed sketch
+        let request = Json(
+            serde_json::from_value::<MergeHllSketchesRequest>(json!({
+                "columns": {"name": {"precision": 4, "registers": [1, 0]}}
+            }))
+            .unwrap(),
+        );
+        assert_eq!(
+            merge_hll_sketches(
+                State(repo.clone()),
+                Extension(caller()),
+                Path(("test".to_string(), "table1".to_string())),
+                HeaderMap::new(),
+                request,
+            )
+            .await
+            .unwrap_err()
+            .0,
+            StatusCode::BAD_REQUEST
+        );
+
+        // Test set_histograms and load_histograms
+        let request = Json(
+            serde_json::from_value::<SetHistogramsRequest>(json!({
+                "columns": {"id": {"type": "int", "bounds": [0, 10, 20, 30, 40]}}
+            }))
+            .unwrap(),
+        );
+        assert_eq!(
+            set_histograms(
+                State(repo.clone()),
+                Extension(caller()),
+                Path(("test".to_string(), "table1".to_string())),
+                HeaderMap::new(),
+                request,
+            )
+            .await
+            .unwrap()
+            .0,
+            StatusCode::NO_CONTENT
+        );
+        let histograms = load_histograms(
+            State(repo.clone()),
+            Extension(caller()),
+            Path(("test".to_string(), "table1".to_string())),
+        )
+        .await
+        .unwrap();
+        assert_eq!(histograms["id"].bounds.len(), 5);
+
+        // Test set_histograms with a stale If-Match
+        let request = Json(
+            serde_json::from_value::<SetHistogramsRequest>(json!({
+                "columns": {"id": {"type": "int", "bounds": [0, 40]}}
+            }))
+            .unwrap(),
+        );
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_MATCH, HeaderValue::from_static("\"4\""));
+        assert_eq!(
+            set_histograms(
+                State(repo.clone()),
+                Extension(caller()),
+                Path(("test".to_string(), "table1".to_string())),
+                headers,
+                request,
+            )
+            .await
+            .unwrap_err()
+            .0,
+            StatusCode::PRECONDITION_FAILED
+        );
+
+        // Test estimate_selectivity
+        let request = Json(
+            serde_json::from_value::<EstimateSelectivityRequest>(json!({
+                "filter": {"type": "gt-eq", "term": "id", "value": 30}
+            }))
+            .unwrap(),
+        );
+        let response = estimate_selectivity(
+            State(repo.clone()),
+            Extension(caller()),
+            Path(("test".to_string(), "table1".to_string())),
+            request,
+        )
+        .await
+        .unwrap();
+        assert!((response.selectivity - 0.25).abs() < 1e-9);
+
+        // Test set_histograms with an invalid histogram
+        let request = Json(
+            serde_json::from_value::<SetHistogramsRequest>(json!({
+                "columns": {"id": {"type": "int", "bounds": [0]}}
+            }))
+            .unwrap(),
+        );
+        assert_eq!(
+            set_histograms(
+                State(repo.clone()),
+                Extension(caller()),
+                Path(("test".to_string(), "table1".to_string())),
+                HeaderMap::new(),
+                request,
+            )
+            .await
+            .unwrap_err()
+            .0,
+            StatusCode::BAD_REQUEST
+        );
+
+        // Test load_table_statistics with non-existent table
+        assert_eq!(
+            load_table_statistics(
+                State(repo.clone()),
+                Extension(caller()),
+                Path(("test".to_string(), "non_existent".to_string())),
+            )
+            .await
+            .unwrap_err()
+            .0,
+            StatusCode::NOT_FOUND
+        );
+    }
+}
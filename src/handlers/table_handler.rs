@@ -1,16 +1,31 @@
+use crate::auth::access::Caller;
+use crate::dto::acl::{Privilege, Resource};
+use crate::dto::batch::{
+    BatchCreateResponse, BatchCreateTablesRequest, BatchLoadTablesRequest, BatchLoadTablesResponse,
+};
+use crate::dto::constraint::{TableConstraints, TableConstraintsEntry};
+use crate::dto::data_file::{RegisterDataFilesRequest, RegisterDataFilesResponse};
 use crate::dto::namespace_data::NamespaceIdent;
 use crate::dto::rename_request::TableRenameRequest;
-use crate::dto::table_data::{Table, TableCreation, TableIdent};
+use crate::dto::scan_plan::{PlanTableScanRequest, PlanTableScanResponse};
+use crate::dto::table_data::{SetTablePropertiesRequest, Table, TableCreation, TableIdent};
+use crate::handlers::batch::{check_batch_size, create_response, error_status};
+use crate::handlers::conditional::{etag_header, if_match, Versioned};
+use crate::handlers::naming::{invalid_name, NamingRules};
+use crate::repository::quota::QuotaRepository;
 use crate::repository::table::TableRepository;
+use crate::repository::version::is_version_mismatch;
 use axum::{
-    extract::{Json, Path, State},
-    http::StatusCode,
+    extract::{Extension, Json, Path, State},
+    http::{HeaderMap, HeaderName, StatusCode},
 };
 use std::io::ErrorKind;
 use std::sync::Arc;
+use tracing::info;
 
 pub async fn list_tables(
     State(repo): State<Arc<TableRepository>>,
+    Extension(caller): Extension<Caller>,
     Path(namespace): Path<String>,
 ) -> Result<Json<Vec<TableIdent>>, (StatusCode, String)> {
     let id = NamespaceIdent::new(
@@ -20,7 +35,13 @@ pub async fn list_tables(
             .collect(),
     );
     match repo.list_all_tables(&id) {
-        Ok(tables) => Ok(Json(tables.unwrap_or_default())),
+        Ok(tables) => Ok(Json(
+            tables
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|table| caller.allows(Privilege::ReadMetadata, Resource::Table(table)))
+                .collect(),
+        )),
         Err(e) => match e.kind() {
             ErrorKind::NotFound => Err((StatusCode::NOT_FOUND, format!("Error: {}", e))),
             _ => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e))),
@@ -30,6 +51,9 @@ pub async fn list_tables(
 
 pub async fn create_table(
     State(repo): State<Arc<TableRepository>>,
+    Extension(caller): Extension<Caller>,
+    Extension(quotas): Extension<Arc<QuotaRepository>>,
+    Extension(naming): Extension<Arc<NamingRules>>,
     Path(namespace): Path<String>,
     table: Json<TableCreation>,
 ) -> Result<StatusCode, (StatusCode, String)> {
@@ -39,29 +63,84 @@ pub async fn create_table(
             .map(|part| part.to_string())
             .collect(),
     );
-    match repo.create_table(&id, &table) {
+    naming
+        .check_new_table(&TableIdent::new(id.clone(), table.name.clone()))
+        .map_err(invalid_name)?;
+    caller.require(Privilege::CreateTable, Resource::Namespace(&id))?;
+    let owner = quotas.owner(&caller.principal.name);
+    match repo.create_table(&id, &table, Some(&owner)) {
         Ok(_) => Ok(StatusCode::CREATED),
         Err(e) => match e.kind() {
             ErrorKind::NotFound => Err((StatusCode::NOT_FOUND, format!("Error: {}", e))),
             ErrorKind::AlreadyExists => Err((StatusCode::CONFLICT, format!("Error: {}", e))),
+            ErrorKind::PermissionDenied => Err((StatusCode::FORBIDDEN, format!("Error: {}", e))),
             _ => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e))),
         },
     }
 }
 
+/// Creates many tables in one call, see [`BatchCreateTablesRequest`].
+pub async fn create_tables(
+    State(repo): State<Arc<TableRepository>>,
+    Extension(caller): Extension<Caller>,
+    Extension(quotas): Extension<Arc<QuotaRepository>>,
+    Extension(naming): Extension<Arc<NamingRules>>,
+    Json(request): Json<BatchCreateTablesRequest>,
+) -> Result<Json<BatchCreateResponse<TableIdent>>, (StatusCode, String)> {
+    check_batch_size(request.tables.len())?;
+    for creation in &request.tables {
+        naming
+            .check_new_table(&creation.ident())
+            .map_err(invalid_name)?;
+        caller.require(
+            Privilege::CreateTable,
+            Resource::Namespace(&creation.namespace),
+        )?;
+    }
+    let ids: Vec<TableIdent> = request
+        .tables
+        .iter()
+        .map(|creation| creation.ident())
+        .collect();
+    let owner = quotas.owner(&caller.principal.name);
+    repo.create_tables(&request.tables, request.atomic, Some(&owner))
+        .map(|results| Json(create_response(ids, results, StatusCode::CREATED)))
+        .map_err(|e| (error_status(&e), format!("Error: {}", e)))
+}
+
+/// Loads many tables at once, answering null for those that do not exist.
+pub async fn load_tables(
+    State(repo): State<Arc<TableRepository>>,
+    Extension(caller): Extension<Caller>,
+    Extension(naming): Extension<Arc<NamingRules>>,
+    Json(request): Json<BatchLoadTablesRequest>,
+) -> Result<Json<BatchLoadTablesResponse>, (StatusCode, String)> {
+    check_batch_size(request.tables.len())?;
+    for table_id in &request.tables {
+        naming.check_table(table_id).map_err(invalid_name)?;
+        caller.require(Privilege::ReadMetadata, Resource::Table(table_id))?;
+    }
+    repo.load_tables(&request.tables)
+        .map(|tables| Json(BatchLoadTablesResponse { tables }))
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e)))
+}
+
 pub async fn load_table(
     State(repo): State<Arc<TableRepository>>,
+    Extension(caller): Extension<Caller>,
     Path((namespace, table)): Path<(String, String)>,
-) -> Result<Json<Table>, (StatusCode, String)> {
+    headers: HeaderMap,
+) -> Result<Versioned<Table>, (StatusCode, String)> {
     let id = NamespaceIdent::new(
         namespace
             .split('\u{1F}')
             .map(|part| part.to_string())
             .collect(),
     );
-
+    let table_id = TableIdent::new(id.clone(), table.clone());
+    caller.require(Privilege::ReadMetadata, Resource::Table(&table_id))?;
     match repo.load_table(&id, table.clone()) {
-        Ok(Some(table_data)) => Ok(Json(table_data)),
+        Ok(Some(table_data)) => Ok(Versioned::new(&headers, table_data.version, table_data)),
         Ok(None) => Err((
             StatusCode::NOT_FOUND,
             format!("Table {} not found", table.clone()),
@@ -72,7 +151,9 @@ pub async fn load_table(
 
 pub async fn delete_table(
     State(repo): State<Arc<TableRepository>>,
+    Extension(caller): Extension<Caller>,
     Path((namespace, table)): Path<(String, String)>,
+    headers: HeaderMap,
 ) -> Result<StatusCode, (StatusCode, String)> {
     let id = NamespaceIdent::new(
         namespace
@@ -80,10 +161,19 @@ pub async fn delete_table(
             .map(|part| part.to_string())
             .collect(),
     );
-    match repo.drop_table(&id, table) {
-        Ok(_) => Ok(StatusCode::NO_CONTENT),
+    let table_id = TableIdent::new(id.clone(), table.clone());
+    caller.require(Privilege::Drop, Resource::Table(&table_id))?;
+    match repo.drop_table(&id, table.clone(), if_match(&headers).as_deref()) {
+        Ok(_) => {
+            info!(target: "audit", principal = %caller.principal, namespace = %id, table = %table, "dropped table");
+            Ok(StatusCode::NO_CONTENT)
+        }
         Err(e) => match e.kind() {
             ErrorKind::NotFound => Err((StatusCode::NOT_FOUND, format!("Error: {}", e))),
+            ErrorKind::ResourceBusy => Err((StatusCode::CONFLICT, format!("Error: {}", e))),
+            ErrorKind::Other if is_version_mismatch(&e) => {
+                Err((StatusCode::PRECONDITION_FAILED, format!("Error: {}", e)))
+            }
             _ => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e))),
         },
     }
@@ -91,6 +181,7 @@ pub async fn delete_table(
 
 pub async fn table_exists(
     State(repo): State<Arc<TableRepository>>,
+    Extension(caller): Extension<Caller>,
     Path((namespace, table)): Path<(String, String)>,
 ) -> Result<StatusCode, (StatusCode, String)> {
     let id = NamespaceIdent::new(
@@ -99,6 +190,8 @@ pub async fn table_exists(
             .map(|part| part.to_string())
             .collect(),
     );
+    let table_id = TableIdent::new(id.clone(), table.clone());
+    caller.require(Privilege::ReadMetadata, Resource::Table(&table_id))?;
     match repo.table_exists(&id, table) {
         // Ideally this should be FOUND but Iceberg spec says 204
         Ok(true) => Ok(StatusCode::NO_CONTENT),
@@ -109,13 +202,188 @@ pub async fn table_exists(
 
 pub async fn rename_table(
     State(repo): State<Arc<TableRepository>>,
+    Extension(caller): Extension<Caller>,
+    Extension(naming): Extension<Arc<NamingRules>>,
+    headers: HeaderMap,
     request: Json<TableRenameRequest>,
 ) -> Result<StatusCode, (StatusCode, String)> {
-    match repo.rename_table(&request) {
+    naming.check_table(&request.source).map_err(invalid_name)?;
+    naming
+        .check_new_table(&request.destination)
+        .map_err(invalid_name)?;
+    caller.require(Privilege::Drop, Resource::Table(&request.source))?;
+    caller.require(
+        Privilege::CreateTable,
+        Resource::Namespace(&request.destination.namespace),
+    )?;
+    match repo.rename_table(&request, if_match(&headers).as_deref()) {
         Ok(_) => Ok(StatusCode::NO_CONTENT),
         Err(e) => match e.kind() {
             ErrorKind::NotFound => Err((StatusCode::NOT_FOUND, format!("Error: {}", e))),
             ErrorKind::AlreadyExists => Err((StatusCode::CONFLICT, format!("Error: {}", e))),
+            ErrorKind::Other if is_version_mismatch(&e) => {
+                Err((StatusCode::PRECONDITION_FAILED, format!("Error: {}", e)))
+            }
+            _ => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e))),
+        },
+    }
+}
+
+pub async fn register_data_files(
+    State(repo): State<Arc<TableRepository>>,
+    Extension(caller): Extension<Caller>,
+    Path((namespace, table)): Path<(String, String)>,
+    headers: HeaderMap,
+    request: Json<RegisterDataFilesRequest>,
+) -> Result<Json<RegisterDataFilesResponse>, (StatusCode, String)> {
+    let id = NamespaceIdent::new(
+        namespace
+            .split('\u{1F}')
+            .map(|part| part.to_string())
+            .collect(),
+    );
+    let table_id = TableIdent::new(id.clone(), table.clone());
+    caller.require(Privilege::Commit, Resource::Table(&table_id))?;
+    match repo.register_data_files(&id, table, &request, if_match(&headers).as_deref()) {
+        Ok(snapshot_id) => Ok(Json(RegisterDataFilesResponse { snapshot_id })),
+        Err(e) => match e.kind() {
+            ErrorKind::NotFound => Err((StatusCode::NOT_FOUND, format!("Error: {}", e))),
+            ErrorKind::InvalidInput => Err((StatusCode::BAD_REQUEST, format!("Error: {}", e))),
+            ErrorKind::Other if is_version_mismatch(&e) => {
+                Err((StatusCode::PRECONDITION_FAILED, format!("Error: {}", e)))
+            }
+            _ => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e))),
+        },
+    }
+}
+
+pub async fn plan_table_scan(
+    State(repo): State<Arc<TableRepository>>,
+    Extension(caller): Extension<Caller>,
+    Path((namespace, table)): Path<(String, String)>,
+    request: Json<PlanTableScanRequest>,
+) -> Result<Json<PlanTableScanResponse>, (StatusCode, String)> {
+    let id = NamespaceIdent::new(
+        namespace
+            .split('\u{1F}')
+            .map(|part| part.to_string())
+            .collect(),
+    );
+    let table_id = TableIdent::new(id.clone(), table.clone());
+    caller.require(Privilege::ReadMetadata, Resource::Table(&table_id))?;
+    match repo.plan_table_scan(&id, table, &request) {
+        Ok(plan) => Ok(Json(plan)),
+        Err(e) => match e.kind() {
+            ErrorKind::NotFound => Err((StatusCode::NOT_FOUND, format!("Error: {}", e))),
+            ErrorKind::InvalidInput => Err((StatusCode::BAD_REQUEST, format!("Error: {}", e))),
+            _ => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e))),
+        },
+    }
+}
+
+pub async fn load_table_constraints(
+    State(repo): State<Arc<TableRepository>>,
+    Extension(caller): Extension<Caller>,
+    Path((namespace, table)): Path<(String, String)>,
+) -> Result<Json<TableConstraints>, (StatusCode, String)> {
+    let id = NamespaceIdent::new(
+        namespace
+            .split('\u{1F}')
+            .map(|part| part.to_string())
+            .collect(),
+    );
+    let table_id = TableIdent::new(id.clone(), table.clone());
+    caller.require(Privilege::ReadMetadata, Resource::Table(&table_id))?;
+    match repo.load_constraints(&id, table.clone()) {
+        Ok(Some(constraints)) => Ok(Json(constraints)),
+        Ok(None) => Err((StatusCode::NOT_FOUND, format!("Table {} not found", table))),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e))),
+    }
+}
+
+pub async fn set_table_constraints(
+    State(repo): State<Arc<TableRepository>>,
+    Extension(caller): Extension<Caller>,
+    Path((namespace, table)): Path<(String, String)>,
+    headers: HeaderMap,
+    constraints: Json<TableConstraints>,
+) -> Result<(StatusCode, [(HeaderName, String); 1]), (StatusCode, String)> {
+    let id = NamespaceIdent::new(
+        namespace
+            .split('\u{1F}')
+            .map(|part| part.to_string())
+            .collect(),
+    );
+    let table_id = TableIdent::new(id.clone(), table.clone());
+    caller.require(Privilege::Commit, Resource::Table(&table_id))?;
+    match repo.set_constraints(&id, table, &constraints, if_match(&headers).as_deref()) {
+        Ok(version) => Ok((StatusCode::NO_CONTENT, etag_header(version))),
+        Err(e) => match e.kind() {
+            ErrorKind::NotFound => Err((StatusCode::NOT_FOUND, format!("Error: {}", e))),
+            ErrorKind::InvalidInput => Err((StatusCode::BAD_REQUEST, format!("Error: {}", e))),
+            ErrorKind::Other if is_version_mismatch(&e) => {
+                Err((StatusCode::PRECONDITION_FAILED, format!("Error: {}", e)))
+            }
+            _ => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e))),
+        },
+    }
+}
+
+pub async fn set_table_properties(
+    State(repo): State<Arc<TableRepository>>,
+    Extension(caller): Extension<Caller>,
+    Path((namespace, table)): Path<(String, String)>,
+    headers: HeaderMap,
+    request: Json<SetTablePropertiesRequest>,
+) -> Result<(StatusCode, [(HeaderName, String); 1]), (StatusCode, String)> {
+    let id = NamespaceIdent::new(
+        namespace
+            .split('\u{1F}')
+            .map(|part| part.to_string())
+            .collect(),
+    );
+    let table_id = TableIdent::new(id.clone(), table.clone());
+    caller.require(Privilege::Commit, Resource::Table(&table_id))?;
+    match repo.set_properties(
+        &id,
+        table,
+        &request.removals,
+        &request.updates,
+        if_match(&headers).as_deref(),
+    ) {
+        Ok(version) => Ok((StatusCode::OK, etag_header(version))),
+        Err(e) => match e.kind() {
+            ErrorKind::NotFound => Err((StatusCode::NOT_FOUND, format!("Error: {}", e))),
+            ErrorKind::Other if is_version_mismatch(&e) => {
+                Err((StatusCode::PRECONDITION_FAILED, format!("Error: {}", e)))
+            }
+            _ => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e))),
+        },
+    }
+}
+
+pub async fn list_constraints(
+    State(repo): State<Arc<TableRepository>>,
+    Extension(caller): Extension<Caller>,
+    Path(namespace): Path<String>,
+) -> Result<Json<Vec<TableConstraintsEntry>>, (StatusCode, String)> {
+    let id = NamespaceIdent::new(
+        namespace
+            .split('\u{1F}')
+            .map(|part| part.to_string())
+            .collect(),
+    );
+    match repo.list_constraints(&id) {
+        Ok(entries) => Ok(Json(
+            entries
+                .into_iter()
+                .filter(|entry| {
+                    caller.allows(Privilege::ReadMetadata, Resource::Table(&entry.table))
+                })
+                .collect(),
+        )),
+        Err(e) => match e.kind() {
+            ErrorKind::NotFound => Err((StatusCode::NOT_FOUND, format!("Error: {}", e))),
             _ => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Error: {}", e))),
         },
     }
@@ -124,34 +392,56 @@ pub async fn rename_table(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::auth::authenticator::Principal;
     use crate::database::database::Database;
+    use crate::dto::constraint::{ForeignKey, PrimaryKey};
     use crate::dto::table_data::TableCreation;
     use crate::repository::namespace::NamespaceRepository;
-    use axum::http::StatusCode;
+    use axum::http::{header, HeaderValue, StatusCode};
     use std::sync::{Arc, Mutex};
     use tempfile::tempdir;
 
+    fn caller() -> Caller {
+        Caller::unrestricted(Principal::anonymous())
+    }
+
+    fn naming() -> Arc<NamingRules> {
+        Arc::new(NamingRules::new(&Default::default()).unwrap())
+    }
+
+    fn headers(name: HeaderName, value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_static(value));
+        headers
+    }
+
     #[tokio::test]
     async fn test_table_endpoints() {
         let dir = tempdir().unwrap();
         let db = Database::open(dir.path()).unwrap();
         let db = Arc::new(Mutex::new(db));
         let repo = Arc::new(TableRepository::new(db.clone()));
+        let quotas = Arc::new(QuotaRepository::new(db.clone(), Default::default()));
 
         // Create a namespace for testing
         let namespace_ident = NamespaceIdent(vec!["test".to_string()]);
         let namespace_repo = NamespaceRepository::new(db.clone());
         namespace_repo
-            .create_namespace(namespace_ident.clone(), None)
+            .create_namespace(namespace_ident.clone(), None, None)
             .unwrap();
 
         // Test create_table
         let table_creation = Json(TableCreation {
             name: "table1".to_string(),
+            columns: None,
+            properties: None,
         });
         assert_eq!(
             create_table(
                 State(repo.clone()),
+                Extension(caller()),
+                Extension(quotas.clone()),
+                Extension(naming()),
                 Path("test".to_string()),
                 table_creation.clone()
             )
@@ -164,6 +454,9 @@ mod tests {
         assert_eq!(
             create_table(
                 State(repo.clone()),
+                Extension(caller()),
+                Extension(quotas.clone()),
+                Extension(naming()),
                 Path("test".to_string()),
                 table_creation.clone()
             )
@@ -173,10 +466,33 @@ mod tests {
             StatusCode::CONFLICT
         );
 
+        // Test create_table without a name
+        assert_eq!(
+            create_table(
+                State(repo.clone()),
+                Extension(caller()),
+                Extension(quotas.clone()),
+                Extension(naming()),
+                Path("test".to_string()),
+                Json(TableCreation {
+                    name: String::new(),
+                    columns: None,
+                    properties: None,
+                })
+            )
+            .await
+            .unwrap_err(),
+            (
+                StatusCode::BAD_REQUEST,
+                "Error: Table name is empty".to_string()
+            )
+        );
+
         // Test table_exists
         assert_eq!(
             table_exists(
                 State(repo.clone()),
+                Extension(caller()),
                 Path(("test".to_string(), "table1".to_string()))
             )
             .await
@@ -187,11 +503,23 @@ mod tests {
         // Test load_table
         let table = load_table(
             State(repo.clone()),
+            Extension(caller()),
+            Path(("test".to_string(), "table1".to_string())),
+            HeaderMap::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(table.version, 1);
+        assert_eq!(table.body.unwrap().id.name, "table1");
+        let table = load_table(
+            State(repo.clone()),
+            Extension(caller()),
             Path(("test".to_string(), "table1".to_string())),
+            headers(header::IF_NONE_MATCH, "\"1\""),
         )
         .await
         .unwrap();
-        assert_eq!(table.id.name, "table1");
+        assert!(table.body.is_none());
 
         // Test rename_table
         let rename_request = Json(TableRenameRequest {
@@ -199,14 +527,21 @@ mod tests {
             destination: TableIdent::new(namespace_ident.clone(), "table2".to_string()),
         });
         assert_eq!(
-            rename_table(State(repo.clone()), rename_request.clone())
-                .await
-                .unwrap(),
+            rename_table(
+                State(repo.clone()),
+                Extension(caller()),
+                Extension(naming()),
+                HeaderMap::new(),
+                rename_request.clone()
+            )
+            .await
+            .unwrap(),
             StatusCode::NO_CONTENT
         );
         assert_eq!(
             table_exists(
                 State(repo.clone()),
+                Extension(caller()),
                 Path(("test".to_string(), "table1".to_string()))
             )
             .await
@@ -216,6 +551,7 @@ mod tests {
         assert_eq!(
             table_exists(
                 State(repo.clone()),
+                Extension(caller()),
                 Path(("test".to_string(), "table2".to_string()))
             )
             .await
@@ -229,10 +565,16 @@ mod tests {
             destination: TableIdent::new(namespace_ident.clone(), "table3".to_string()),
         });
         assert_eq!(
-            rename_table(State(repo.clone()), rename_request.clone())
-                .await
-                .unwrap_err()
-                .0,
+            rename_table(
+                State(repo.clone()),
+                Extension(caller()),
+                Extension(naming()),
+                HeaderMap::new(),
+                rename_request.clone()
+            )
+            .await
+            .unwrap_err()
+            .0,
             StatusCode::NOT_FOUND
         );
 
@@ -242,18 +584,180 @@ mod tests {
             destination: TableIdent::new(namespace_ident.clone(), "table2".to_string()),
         });
         assert_eq!(
-            rename_table(State(repo.clone()), rename_request.clone())
-                .await
-                .unwrap_err()
-                .0,
+            rename_table(
+                State(repo.clone()),
+                Extension(caller()),
+                Extension(naming()),
+                HeaderMap::new(),
+                rename_request.clone()
+            )
+            .await
+            .unwrap_err()
+            .0,
             StatusCode::CONFLICT
         );
 
+        // Test set_table_constraints and load_table_constraints
+        let constraints: TableConstraints = serde_json::from_value(serde_json::json!({
+            "primary_key": {"columns": ["id"]}
+        }))
+        .unwrap();
+        assert_eq!(
+            set_table_constraints(
+                State(repo.clone()),
+                Extension(caller()),
+                Path(("test".to_string(), "table2".to_string())),
+                headers(header::IF_MATCH, "\"2\""),
+                Json(constraints.clone()),
+            )
+            .await
+            .unwrap(),
+            (
+                StatusCode::NO_CONTENT,
+                [(header::ETAG, "\"3\"".to_string())]
+            )
+        );
+        assert_eq!(
+            load_table_constraints(
+                State(repo.clone()),
+                Extension(caller()),
+                Path(("test".to_string(), "table2".to_string())),
+            )
+            .await
+            .unwrap()
+            .0,
+            constraints
+        );
+
+        // Test set_table_constraints referencing a non-existent table
+        let invalid: TableConstraints = serde_json::from_value(serde_json::json!({
+            "foreign_keys": [{
+                "name": "fk",
+                "columns": ["id"],
+                "referenced_table": {"namespace": ["test"], "name": "non_existent"},
+                "referenced_columns": ["id"]
+            }]
+        }))
+        .unwrap();
+        assert_eq!(
+            set_table_constraints(
+                State(repo.clone()),
+                Extension(caller()),
+                Path(("test".to_string(), "table2".to_string())),
+                HeaderMap::new(),
+                Json(invalid),
+            )
+            .await
+            .unwrap_err()
+            .0,
+            StatusCode::BAD_REQUEST
+        );
+
+        // Test list_constraints
+        let entries = list_constraints(
+            State(repo.clone()),
+            Extension(caller()),
+            Path("test".to_string()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].constraints, constraints);
+
+        // Test register_data_files
+        let request = Json(
+            serde_json::from_value::<RegisterDataFilesRequest>(serde_json::json!({
+                "added_files": [
+                    {"file_path": "a", "record_count": 5, "lower_bounds": {"id": 1}, "upper_bounds": {"id": 5}},
+                    {"file_path": "b", "record_count": 5, "lower_bounds": {"id": 6}, "upper_bounds": {"id": 9}}
+                ]
+            }))
+            .unwrap(),
+        );
+        let snapshot_id = register_data_files(
+            State(repo.clone()),
+            Extension(caller()),
+            Path(("test".to_string(), "table2".to_string())),
+            HeaderMap::new(),
+            request,
+        )
+        .await
+        .unwrap()
+        .snapshot_id;
+
+        // Test plan_table_scan
+        let request = Json(
+            serde_json::from_value::<PlanTableScanRequest>(serde_json::json!({
+                "filter": {"type": "gt", "term": "id", "value": 5}
+            }))
+            .unwrap(),
+        );
+        let plan = plan_table_scan(
+            State(repo.clone()),
+            Extension(caller()),
+            Path(("test".to_string(), "table2".to_string())),
+            request,
+        )
+        .await
+        .unwrap();
+        assert_eq!(plan.snapshot_id, Some(snapshot_id));
+        assert_eq!(plan.data_files.len(), 1);
+        assert_eq!(plan.data_files[0].file_path, "b");
+
+        // Test plan_table_scan with both snapshot_id and ref
+        let request = Json(PlanTableScanRequest {
+            filter: None,
+            snapshot_id: Some(snapshot_id),
+            reference: Some("main".to_string()),
+        });
+        assert_eq!(
+            plan_table_scan(
+                State(repo.clone()),
+                Extension(caller()),
+                Path(("test".to_string(), "table2".to_string())),
+                request,
+            )
+            .await
+            .unwrap_err()
+            .0,
+            StatusCode::BAD_REQUEST
+        );
+
+        // Test plan_table_scan with non-existent table
+        assert_eq!(
+            plan_table_scan(
+                State(repo.clone()),
+                Extension(caller()),
+                Path(("test".to_string(), "non_existent".to_string())),
+                Json(PlanTableScanRequest::default()),
+            )
+            .await
+            .unwrap_err()
+            .0,
+            StatusCode::NOT_FOUND
+        );
+
+        // Test delete_table against a stale version; the snapshot bumped it
+        assert_eq!(
+            delete_table(
+                State(repo.clone()),
+                Extension(caller()),
+                Path(("test".to_string(), "table2".to_string())),
+                headers(header::IF_MATCH, "\"3\"")
+            )
+            .await
+            .unwrap_err()
+            .0,
+            StatusCode::PRECONDITION_FAILED
+        );
+
         // Test delete_table
         assert_eq!(
             delete_table(
                 State(repo.clone()),
-                Path(("test".to_string(), "table2".to_string()))
+                Extension(caller()),
+                Path(("test".to_string(), "table2".to_string())),
+                headers(header::IF_MATCH, "\"4\"")
             )
             .await
             .unwrap(),
@@ -262,6 +766,7 @@ mod tests {
         assert_eq!(
             table_exists(
                 State(repo.clone()),
+                Extension(caller()),
                 Path(("test".to_string(), "table2".to_string()))
             )
             .await
@@ -269,11 +774,55 @@ mod tests {
             StatusCode::NOT_FOUND
         );
 
+        // Test delete_table on a table a foreign key references
+        for name in ["parent", "child"] {
+            let creation = TableCreation {
+                name: name.to_string(),
+                columns: None,
+                properties: None,
+            };
+            repo.create_table(&namespace_ident, &creation, None)
+                .unwrap();
+        }
+        let primary_key = TableConstraints {
+            primary_key: Some(PrimaryKey {
+                columns: vec!["id".to_string()],
+            }),
+            ..Default::default()
+        };
+        repo.set_constraints(&namespace_ident, "parent".to_string(), &primary_key, None)
+            .unwrap();
+        let foreign_key = TableConstraints {
+            foreign_keys: vec![ForeignKey {
+                name: "fk_parent".to_string(),
+                columns: vec!["parent_id".to_string()],
+                referenced_table: TableIdent::new(namespace_ident.clone(), "parent".to_string()),
+                referenced_columns: vec!["id".to_string()],
+            }],
+            ..Default::default()
+        };
+        repo.set_constraints(&namespace_ident, "child".to_string(), &foreign_key, None)
+            .unwrap();
+        assert_eq!(
+            delete_table(
+                State(repo.clone()),
+                Extension(caller()),
+                Path(("test".to_string(), "parent".to_string())),
+                HeaderMap::new()
+            )
+            .await
+            .unwrap_err()
+            .0,
+            StatusCode::CONFLICT
+        );
+
         // Test delete_table with non-existent table
         assert_eq!(
             delete_table(
                 State(repo.clone()),
-                Path(("test".to_string(), "non_existent".to_string()))
+                Extension(caller()),
+                Path(("test".to_string(), "non_existent".to_string())),
+                HeaderMap::new()
             )
             .await
             .unwrap_err()
@@ -0,0 +1,13 @@
+pub mod auth;
+pub mod cli;
+pub mod config;
+pub mod database;
+pub mod dto;
+pub mod handlers;
+pub mod planning;
+pub mod repository;
+pub mod routes;
+pub mod server;
+pub mod sketch;
+pub mod tasks;
+pub mod telemetry;
@@ -1,29 +1,120 @@
-mod config;
-mod database;
-mod dto;
-mod handlers;
-mod repository;
-mod routes;
-
-use config::parameters;
-use database::database::Database;
-use std::sync::{Arc, Mutex};
+use catalog1::config::config::{Cli, Config};
+use catalog1::database::database::Database;
+use catalog1::database::migration;
+use catalog1::repository::admin::AdminRepository;
+use catalog1::routes;
+use catalog1::server::shutdown::{self, Shutdown};
+use catalog1::server::tls::{self, TlsState};
+use catalog1::tasks::{backup, tls_reload};
+use catalog1::telemetry::logging;
+use clap::Parser;
+use std::net::SocketAddr;
+use std::process;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tracing::{error, info, warn};
 
 #[tokio::main]
 async fn main() {
-    parameters::init();
-    let host = format!("0.0.0.0:{}", parameters::get("PORT"));
+    // A .env file is optional; its variables act as environment overrides.
+    dotenv::dotenv().ok();
+    let cli = Cli::parse();
+    let config = Config::load(&cli).unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    });
+    let telemetry = logging::init(&config.log).unwrap_or_else(|e| {
+        eprintln!("Error: cannot set up logging: {}", e);
+        process::exit(1);
+    });
 
     // Create a Database object
-    let db = Database::open("rocksdb").unwrap();
+    let storage = &config.storage;
+    let db = Database::open_with_config(&storage.data_dir, &storage.rocksdb, &storage.encoding)
+        .unwrap_or_else(|e| {
+            error!(data_dir = %storage.data_dir.display(), error = %e, "cannot open database");
+            process::exit(1);
+        });
+
+    // Upgrade data written by older builds before serving it
+    match migration::migrate(&db, false, &config.backup) {
+        Ok(report) if !report.steps.is_empty() => info!(
+            from_version = report.from_version,
+            to_version = report.to_version,
+            "migrated the catalog"
+        ),
+        Ok(_) => {}
+        Err(e) => {
+            error!(error = %e, "cannot migrate the catalog");
+            process::exit(1);
+        }
+    }
 
     // Wrap it in an Arc<Mutex<>> for thread safety
     let db = Arc::new(Mutex::new(db));
 
-    let listener = tokio::net::TcpListener::bind(host).await.unwrap();
+    if let Some(secs) = config.backup.interval_secs {
+        let repo = Arc::new(AdminRepository::new(db.clone(), config.backup.clone()));
+        tokio::spawn(backup::run_backup_schedule(repo, Duration::from_secs(secs)));
+    }
+
+    let listener = tokio::net::TcpListener::bind(config.socket_addr())
+        .await
+        .unwrap_or_else(|e| {
+            error!(address = %config.socket_addr(), error = %e, "cannot bind");
+            process::exit(1);
+        });
 
     // Pass the shared Database object to your routes
-    let app = routes::root::routes(db);
+    let app = routes::root::routes(db.clone(), &config).unwrap_or_else(|e| {
+        error!(error = %e, "invalid configuration");
+        process::exit(1);
+    });
+
+    let shutdown = Shutdown::new();
+    tokio::spawn(shutdown::trigger_on_signal(shutdown.clone()));
+    let timeout = Duration::from_secs(config.server.shutdown_timeout_secs);
+
+    info!(address = %config.socket_addr(), tls = config.tls.enabled, "serving the catalog");
+    let drained = if config.tls.enabled {
+        let state = Arc::new(TlsState::load(&config.tls).unwrap_or_else(|e| {
+            error!(error = %e, "cannot load the TLS certificate");
+            process::exit(1);
+        }));
+        if let Some(secs) = config.tls.reload_interval_secs {
+            tokio::spawn(tls_reload::run_tls_reload_schedule(
+                state.clone(),
+                Duration::from_secs(secs),
+            ));
+        }
+        let server = tls::serve(listener, app, state, shutdown.triggered());
+        shutdown::drain(server, &shutdown, timeout).await
+    } else {
+        let server = axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(shutdown.triggered());
+        let server = async {
+            if let Err(e) = server.await {
+                error!(error = %e, "server failed");
+            }
+        };
+        shutdown::drain(server, &shutdown, timeout).await
+    };
+    if !drained {
+        warn!(
+            timeout_secs = config.server.shutdown_timeout_secs,
+            "in-flight requests did not finish before the shutdown timeout"
+        );
+    }
 
-    axum::serve(listener, app).await.unwrap();
+    // Repositories hold the lock for a whole operation, so taking it waits
+    // for running writes and keeps later ones out while the database closes.
+    let db = db.lock().unwrap_or_else(PoisonError::into_inner);
+    match db.close() {
+        Ok(()) => info!("closed the database"),
+        Err(e) => error!(error = %e, "cannot close the database cleanly"),
+    }
+    telemetry.shutdown();
 }
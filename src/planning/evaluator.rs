@@ -0,0 +1,356 @@
+use crate::dto::data_file::DataFile;
+use crate::dto::expression::Expression;
+use serde_json::Value;
+use std::cmp::Ordering;
+
+/// Returns false only when the file's identity partition values and column
+/// bounds prove that no row of the file can match the expression.
+pub fn might_match(expression: &Expression, file: &DataFile) -> bool {
+    if file.record_count == 0 {
+        return false;
+    }
+    eval(&expression.rewrite_not(), file)
+}
+
+/// Compares two JSON literals of the same kind. Returns None when the values
+/// are not comparable, in which case a predicate cannot prune anything.
+pub fn compare_values(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Number(l), Value::Number(r)) => match (l.as_i64(), r.as_i64()) {
+            (Some(l), Some(r)) => Some(l.cmp(&r)),
+            _ => l.as_f64()?.partial_cmp(&r.as_f64()?),
+        },
+        (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+        (Value::Bool(l), Value::Bool(r)) => Some(l.cmp(r)),
+        _ => None,
+    }
+}
+
+fn eval(expression: &Expression, file: &DataFile) -> bool {
+    match expression {
+        Expression::True => true,
+        Expression::False => false,
+        Expression::And { left, right } => eval(left, file) && eval(right, file),
+        Expression::Or { left, right } => eval(left, file) || eval(right, file),
+        // `rewrite_not` leaves no `Not` node behind, be conservative anyway.
+        Expression::Not { .. } => true,
+        Expression::IsNull { term } => match identity_partition(file, term) {
+            Some(value) => value.is_null(),
+            None => file.null_value_counts.get(term) != Some(&0),
+        },
+        Expression::NotNull { term } => !only_nulls(file, term),
+        Expression::IsNan { term } => file.nan_value_counts.get(term) != Some(&0),
+        Expression::NotNan { term } => file.nan_value_counts.get(term) != Some(&file.record_count),
+        Expression::Lt { term, value } => {
+            !only_nulls(file, term) && !lower_bound_is(file, term, value, |o| o != Ordering::Less)
+        }
+        Expression::LtEq { term, value } => {
+            !only_nulls(file, term)
+                && !lower_bound_is(file, term, value, |o| o == Ordering::Greater)
+        }
+        Expression::Gt { term, value } => {
+            !only_nulls(file, term)
+                && !upper_bound_is(file, term, value, |o| o != Ordering::Greater)
+        }
+        Expression::GtEq { term, value } => {
+            !only_nulls(file, term) && !upper_bound_is(file, term, value, |o| o == Ordering::Less)
+        }
+        Expression::Eq { term, value } => !only_nulls(file, term) && in_bounds(file, term, value),
+        Expression::NotEq { term, value } => {
+            !(lower_bound_is(file, term, value, |o| o == Ordering::Equal)
+                && upper_bound_is(file, term, value, |o| o == Ordering::Equal))
+        }
+        Expression::In { term, values } => {
+            !only_nulls(file, term) && values.iter().any(|value| in_bounds(file, term, value))
+        }
+        Expression::NotIn { .. } => true,
+        Expression::StartsWith { term, value } => {
+            !only_nulls(file, term) && prefix_in_bounds(file, term, value)
+        }
+        Expression::NotStartsWith { .. } => true,
+    }
+}
+
+/// The partition value of the column when the file is partitioned by the
+/// column itself. Values of other transforms, such as a bucket number or a
+/// day, are not values of the column and say nothing about its bounds.
+fn identity_partition<'a>(file: &'a DataFile, term: &str) -> Option<&'a Value> {
+    match file.partition_transforms.get(term) {
+        Some(transform) if transform != "identity" => None,
+        _ => file.partition.get(term),
+    }
+}
+
+/// Identity partition values act as both bounds of the column.
+fn lower_bound<'a>(file: &'a DataFile, term: &str) -> Option<&'a Value> {
+    identity_partition(file, term).or_else(|| file.lower_bounds.get(term))
+}
+
+fn upper_bound<'a>(file: &'a DataFile, term: &str) -> Option<&'a Value> {
+    identity_partition(file, term).or_else(|| file.upper_bounds.get(term))
+}
+
+fn only_nulls(file: &DataFile, term: &str) -> bool {
+    match identity_partition(file, term) {
+        Some(value) => value.is_null(),
+        None => file.null_value_counts.get(term) == Some(&file.record_count),
+    }
+}
+
+fn lower_bound_is(
+    file: &DataFile,
+    term: &str,
+    value: &Value,
+    check: impl Fn(Ordering) -> bool,
+) -> bool {
+    lower_bound(file, term)
+        .and_then(|lower| compare_values(lower, value))
+        .is_some_and(check)
+}
+
+fn upper_bound_is(
+    file: &DataFile,
+    term: &str,
+    value: &Value,
+    check: impl Fn(Ordering) -> bool,
+) -> bool {
+    upper_bound(file, term)
+        .and_then(|upper| compare_values(upper, value))
+        .is_some_and(check)
+}
+
+fn in_bounds(file: &DataFile, term: &str, value: &Value) -> bool {
+    !lower_bound_is(file, term, value, |o| o == Ordering::Greater)
+        && !upper_bound_is(file, term, value, |o| o == Ordering::Less)
+}
+
+fn prefix_in_bounds(file: &DataFile, term: &str, prefix: &str) -> bool {
+    let truncate = |bound: Option<&Value>| {
+        bound
+            .and_then(|b| b.as_str())
+            .map(|b| b.chars().take(prefix.chars().count()).collect::<String>())
+    };
+    if let Some(lower) = truncate(lower_bound(file, term)) {
+        if lower.as_str() > prefix {
+            return false;
+        }
+    }
+    if let Some(upper) = truncate(upper_bound(file, term)) {
+        if upper.as_str() < prefix {
+            return false;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    fn data_file() -> DataFile {
+        DataFile {
+            file_path: "data/0.parquet".to_string(),
+            file_format: None,
+            partition: BTreeMap::from([("region".to_string(), json!("us"))]),
+            partition_transforms: BTreeMap::new(),
+            record_count: 100,
+            file_size_in_bytes: 1024,
+            null_value_counts: BTreeMap::from([
+                ("id".to_string(), 0),
+                ("comment".to_string(), 100),
+            ]),
+            nan_value_counts: BTreeMap::new(),
+            lower_bounds: BTreeMap::from([
+                ("id".to_string(), json!(10)),
+                ("name".to_string(), json!("apple")),
+            ]),
+            upper_bounds: BTreeMap::from([
+                ("id".to_string(), json!(20)),
+                ("name".to_string(), json!("cherry")),
+            ]),
+        }
+    }
+
+    fn parse(expression: serde_json::Value) -> Expression {
+        serde_json::from_value(expression).unwrap()
+    }
+
+    #[test]
+    fn test_range_predicates() {
+        let file = data_file();
+
+        assert!(!might_match(
+            &parse(json!({"type": "lt", "term": "id", "value": 10})),
+            &file
+        ));
+        assert!(might_match(
+            &parse(json!({"type": "lt-eq", "term": "id", "value": 10})),
+            &file
+        ));
+        assert!(!might_match(
+            &parse(json!({"type": "gt", "term": "id", "value": 20})),
+            &file
+        ));
+        assert!(might_match(
+            &parse(json!({"type": "gt-eq", "term": "id", "value": 20.0})),
+            &file
+        ));
+        assert!(might_match(
+            &parse(json!({"type": "eq", "term": "id", "value": 15})),
+            &file
+        ));
+        assert!(!might_match(
+            &parse(json!({"type": "eq", "term": "id", "value": 25})),
+            &file
+        ));
+        assert!(might_match(
+            &parse(json!({"type": "eq", "term": "unknown", "value": 25})),
+            &file
+        ));
+    }
+
+    #[test]
+    fn test_partition_values() {
+        let file = data_file();
+
+        assert!(might_match(
+            &parse(json!({"type": "eq", "term": "region", "value": "us"})),
+            &file
+        ));
+        assert!(!might_match(
+            &parse(json!({"type": "eq", "term": "region", "value": "eu"})),
+            &file
+        ));
+        assert!(!might_match(
+            &parse(json!({"type": "in", "term": "region", "values": ["eu", "ap"]})),
+            &file
+        ));
+        assert!(!might_match(
+            &parse(json!({"type": "not-eq", "term": "region", "value": "us"})),
+            &file
+        ));
+        assert!(!might_match(
+            &parse(json!({"type": "is-null", "term": "region"})),
+            &file
+        ));
+    }
+
+    #[test]
+    fn test_transformed_partition_values() {
+        // Partitioned by a bucket of `id`: 3 is a bucket number, not an id,
+        // so only the column bounds can prune.
+        let mut file = data_file();
+        file.partition = BTreeMap::from([("id".to_string(), json!(3))]);
+        file.partition_transforms = BTreeMap::from([("id".to_string(), "bucket[16]".to_string())]);
+
+        assert!(might_match(
+            &parse(json!({"type": "eq", "term": "id", "value": 15})),
+            &file
+        ));
+        assert!(!might_match(
+            &parse(json!({"type": "eq", "term": "id", "value": 3})),
+            &file
+        ));
+        assert!(might_match(
+            &parse(json!({"type": "not-eq", "term": "id", "value": 3})),
+            &file
+        ));
+
+        // A void transform leaves null behind whatever the column holds.
+        file.partition = BTreeMap::from([("id".to_string(), Value::Null)]);
+        file.partition_transforms = BTreeMap::from([("id".to_string(), "void".to_string())]);
+        assert!(might_match(
+            &parse(json!({"type": "not-null", "term": "id"})),
+            &file
+        ));
+
+        // Declared identity values still prune.
+        file.partition = BTreeMap::from([("id".to_string(), json!(12))]);
+        file.partition_transforms = BTreeMap::from([("id".to_string(), "identity".to_string())]);
+        assert!(!might_match(
+            &parse(json!({"type": "eq", "term": "id", "value": 15})),
+            &file
+        ));
+    }
+
+    #[test]
+    fn test_null_counts() {
+        let file = data_file();
+
+        assert!(!might_match(
+            &parse(json!({"type": "is-null", "term": "id"})),
+            &file
+        ));
+        assert!(!might_match(
+            &parse(json!({"type": "not-null", "term": "comment"})),
+            &file
+        ));
+        assert!(!might_match(
+            &parse(json!({"type": "eq", "term": "comment", "value": "x"})),
+            &file
+        ));
+        assert!(might_match(
+            &parse(json!({"type": "is-null", "term": "comment"})),
+            &file
+        ));
+    }
+
+    #[test]
+    fn test_starts_with() {
+        let file = data_file();
+
+        assert!(might_match(
+            &parse(json!({"type": "starts-with", "term": "name", "value": "b"})),
+            &file
+        ));
+        assert!(might_match(
+            &parse(json!({"type": "starts-with", "term": "name", "value": "ch"})),
+            &file
+        ));
+        assert!(!might_match(
+            &parse(json!({"type": "starts-with", "term": "name", "value": "d"})),
+            &file
+        ));
+        assert!(!might_match(
+            &parse(json!({"type": "starts-with", "term": "name", "value": "aa"})),
+            &file
+        ));
+    }
+
+    #[test]
+    fn test_boolean_connectives() {
+        let file = data_file();
+
+        let expression = parse(json!({
+            "type": "or",
+            "left": {"type": "eq", "term": "region", "value": "eu"},
+            "right": {"type": "lt", "term": "id", "value": 12}
+        }));
+        assert!(might_match(&expression, &file));
+
+        let expression = parse(json!({
+            "type": "and",
+            "left": {"type": "eq", "term": "region", "value": "us"},
+            "right": {"type": "gt", "term": "id", "value": 30}
+        }));
+        assert!(!might_match(&expression, &file));
+
+        let expression = parse(json!({
+            "type": "not",
+            "child": {"type": "lt", "term": "id", "value": 30}
+        }));
+        assert!(!might_match(&expression, &file));
+
+        assert!(!might_match(&Expression::False, &file));
+    }
+
+    #[test]
+    fn test_empty_file_never_matches() {
+        let mut file = data_file();
+        file.record_count = 0;
+
+        assert!(!might_match(&Expression::True, &file));
+    }
+}
@@ -0,0 +1,468 @@
+use crate::dto::expression::Expression;
+use crate::dto::histogram::{ColumnHistogram, HistogramType};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind};
+
+/// Selectivity assumed for an equality predicate on a column without statistics.
+const DEFAULT_EQ_SELECTIVITY: f64 = 0.005;
+/// Selectivity assumed for a range predicate on a column without statistics.
+const DEFAULT_RANGE_SELECTIVITY: f64 = 1.0 / 3.0;
+
+/// Checks that a histogram reported by the engine is well formed.
+pub fn validate(column: &str, histogram: &ColumnHistogram) -> Result<(), Error> {
+    let invalid = |message: String| {
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Invalid histogram for column {}: {}", column, message),
+        ))
+    };
+    if histogram.bounds.len() == 1 {
+        return invalid("a histogram needs at least two bounds".to_string());
+    }
+    let mut previous = None;
+    for bound in &histogram.bounds {
+        let key = match to_key(histogram.histogram_type, bound) {
+            Some(key) => key,
+            None => return invalid(format!("{} is not a valid bound", bound)),
+        };
+        if previous.is_some_and(|previous| previous > key) {
+            return invalid("bounds are not in ascending order".to_string());
+        }
+        previous = Some(key);
+    }
+    for mcv in &histogram.most_common_values {
+        if to_key(histogram.histogram_type, &mcv.value).is_none() {
+            return invalid(format!("{} is not a valid value", mcv.value));
+        }
+        if !(0.0..=1.0).contains(&mcv.frequency) {
+            return invalid(format!("frequency {} is out of range", mcv.frequency));
+        }
+    }
+    let total = histogram.null_fraction + mcv_fraction(histogram);
+    if !(0.0..=1.0).contains(&histogram.null_fraction) || total > 1.0 + f64::EPSILON {
+        return invalid("null and most common value fractions exceed 1".to_string());
+    }
+    Ok(())
+}
+
+/// Estimates the fraction of rows matching the expression, treating
+/// predicates on different columns as independent.
+pub fn estimate(expression: &Expression, histograms: &BTreeMap<String, ColumnHistogram>) -> f64 {
+    estimate_rewritten(&expression.rewrite_not(), histograms).clamp(0.0, 1.0)
+}
+
+fn estimate_rewritten(
+    expression: &Expression,
+    histograms: &BTreeMap<String, ColumnHistogram>,
+) -> f64 {
+    let histogram = |term: &String| histograms.get(term);
+    match expression {
+        Expression::True => 1.0,
+        Expression::False => 0.0,
+        Expression::And { left, right } => {
+            estimate_rewritten(left, histograms) * estimate_rewritten(right, histograms)
+        }
+        Expression::Or { left, right } => {
+            let left = estimate_rewritten(left, histograms);
+            let right = estimate_rewritten(right, histograms);
+            left + right - left * right
+        }
+        // `rewrite_not` leaves no `Not` node behind.
+        Expression::Not { child } => 1.0 - estimate_rewritten(child, histograms),
+        Expression::IsNull { term } => {
+            histogram(term).map_or(DEFAULT_EQ_SELECTIVITY, |h| h.null_fraction)
+        }
+        Expression::NotNull { term } => {
+            histogram(term).map_or(1.0 - DEFAULT_EQ_SELECTIVITY, non_null_fraction)
+        }
+        Expression::IsNan { .. } => 0.0,
+        Expression::NotNan { term } => histogram(term).map_or(1.0, non_null_fraction),
+        Expression::Lt { term, value } => range(histogram(term), value, false, false),
+        Expression::LtEq { term, value } => range(histogram(term), value, true, false),
+        Expression::Gt { term, value } => range(histogram(term), value, false, true),
+        Expression::GtEq { term, value } => range(histogram(term), value, true, true),
+        Expression::Eq { term, value } => equal(histogram(term), value),
+        Expression::NotEq { term, value } => match histogram(term) {
+            Some(h) => non_null_fraction(h) - equal(Some(h), value),
+            None => 1.0 - DEFAULT_EQ_SELECTIVITY,
+        },
+        Expression::In { term, values } => values
+            .iter()
+            .map(|value| equal(histogram(term), value))
+            .sum::<f64>()
+            .min(histogram(term).map_or(1.0, non_null_fraction)),
+        Expression::NotIn { term, values } => {
+            let matching: f64 = values
+                .iter()
+                .map(|value| equal(histogram(term), value))
+                .sum();
+            (histogram(term).map_or(1.0, non_null_fraction) - matching).max(0.0)
+        }
+        Expression::StartsWith { term, value } => starts_with(histogram(term), value),
+        Expression::NotStartsWith { term, value } => match histogram(term) {
+            Some(h) => non_null_fraction(h) - starts_with(Some(h), value),
+            None => 1.0 - DEFAULT_EQ_SELECTIVITY,
+        },
+    }
+}
+
+fn non_null_fraction(histogram: &ColumnHistogram) -> f64 {
+    1.0 - histogram.null_fraction
+}
+
+fn mcv_fraction(histogram: &ColumnHistogram) -> f64 {
+    histogram
+        .most_common_values
+        .iter()
+        .map(|mcv| mcv.frequency)
+        .sum()
+}
+
+/// Fraction of all rows that fall into the histogram buckets.
+fn bucket_fraction(histogram: &ColumnHistogram) -> f64 {
+    (non_null_fraction(histogram) - mcv_fraction(histogram)).max(0.0)
+}
+
+/// Fraction of the bucketed rows below `key`, interpolating linearly inside
+/// the bucket that contains it.
+fn fraction_below(histogram: &ColumnHistogram, key: f64) -> Option<f64> {
+    let bounds: Vec<f64> = histogram
+        .bounds
+        .iter()
+        .filter_map(|bound| to_key(histogram.histogram_type, bound))
+        .collect();
+    if bounds.len() < 2 {
+        return None;
+    }
+    let buckets = (bounds.len() - 1) as f64;
+    if key <= bounds[0] {
+        return Some(0.0);
+    }
+    if key >= bounds[bounds.len() - 1] {
+        return Some(1.0);
+    }
+    let bucket = bounds.windows(2).position(|w| key < w[1]).unwrap_or(0);
+    let (low, high) = (bounds[bucket], bounds[bucket + 1]);
+    let within = if high > low {
+        (key - low) / (high - low)
+    } else {
+        0.5
+    };
+    Some((bucket as f64 + within) / buckets)
+}
+
+/// Estimates `column < value` (or `<=`), or the complement when `above` is set.
+fn range(histogram: Option<&ColumnHistogram>, value: &Value, inclusive: bool, above: bool) -> f64 {
+    let histogram = match histogram {
+        Some(histogram) => histogram,
+        None => return DEFAULT_RANGE_SELECTIVITY,
+    };
+    let key = match to_key(histogram.histogram_type, value) {
+        Some(key) => key,
+        None => return DEFAULT_RANGE_SELECTIVITY,
+    };
+    let mcvs_below: f64 = histogram
+        .most_common_values
+        .iter()
+        .filter_map(|mcv| to_key(histogram.histogram_type, &mcv.value).map(|k| (k, mcv.frequency)))
+        .filter(|(k, _)| {
+            if inclusive != above {
+                *k <= key
+            } else {
+                *k < key
+            }
+        })
+        .map(|(_, frequency)| frequency)
+        .sum();
+    let buckets_below = fraction_below(histogram, key).unwrap_or(DEFAULT_RANGE_SELECTIVITY)
+        * bucket_fraction(histogram);
+    let below = mcvs_below + buckets_below;
+    if above {
+        (non_null_fraction(histogram) - below).max(0.0)
+    } else {
+        below
+    }
+}
+
+fn equal(histogram: Option<&ColumnHistogram>, value: &Value) -> f64 {
+    let histogram = match histogram {
+        Some(histogram) => histogram,
+        None => return DEFAULT_EQ_SELECTIVITY,
+    };
+    let key = match to_key(histogram.histogram_type, value) {
+        Some(key) => key,
+        None => return DEFAULT_EQ_SELECTIVITY,
+    };
+    if let Some(mcv) = histogram
+        .most_common_values
+        .iter()
+        .find(|mcv| to_key(histogram.histogram_type, &mcv.value) == Some(key))
+    {
+        return mcv.frequency;
+    }
+    if let Some(below) = fraction_below(histogram, key) {
+        let first = to_key(histogram.histogram_type, &histogram.bounds[0]);
+        // Values outside the histogram that are not most common never occur.
+        if (below == 0.0 && first != Some(key)) || (below == 1.0 && !is_last_bound(histogram, key))
+        {
+            return 0.0;
+        }
+    }
+    // Remaining rows are spread evenly over the remaining distinct values.
+    match histogram.ndv {
+        Some(ndv) => {
+            let remaining = ndv.saturating_sub(histogram.most_common_values.len() as u64);
+            bucket_fraction(histogram) / remaining.max(1) as f64
+        }
+        None => DEFAULT_EQ_SELECTIVITY.min(bucket_fraction(histogram)),
+    }
+}
+
+fn is_last_bound(histogram: &ColumnHistogram, key: f64) -> bool {
+    histogram
+        .bounds
+        .last()
+        .and_then(|bound| to_key(histogram.histogram_type, bound))
+        == Some(key)
+}
+
+fn starts_with(histogram: Option<&ColumnHistogram>, prefix: &str) -> f64 {
+    let histogram = match histogram {
+        Some(histogram) if histogram.histogram_type == HistogramType::String => histogram,
+        _ => return DEFAULT_EQ_SELECTIVITY,
+    };
+    // Strings with the prefix sort between the prefix and its successor.
+    let upper = match prefix_successor(prefix) {
+        Some(upper) => range(Some(histogram), &Value::String(upper), false, false),
+        None => non_null_fraction(histogram),
+    };
+    let lower = range(
+        Some(histogram),
+        &Value::String(prefix.to_string()),
+        false,
+        false,
+    );
+    (upper - lower).max(0.0)
+}
+
+fn prefix_successor(prefix: &str) -> Option<String> {
+    let mut chars: Vec<char> = prefix.chars().collect();
+    while let Some(last) = chars.pop() {
+        if let Some(next) = char::from_u32(last as u32 + 1) {
+            chars.push(next);
+            return Some(chars.into_iter().collect());
+        }
+    }
+    None
+}
+
+/// Maps a value onto the real line, preserving order within a type.
+fn to_key(histogram_type: HistogramType, value: &Value) -> Option<f64> {
+    match histogram_type {
+        HistogramType::Int => value.as_i64().map(|v| v as f64),
+        HistogramType::Float => value.as_f64(),
+        HistogramType::Date => value.as_str().and_then(days_since_epoch).map(|d| d as f64),
+        HistogramType::String => value.as_str().map(string_key),
+    }
+}
+
+/// Interprets the first bytes of a string as a base-256 fraction so that
+/// strings can be interpolated like numbers.
+fn string_key(value: &str) -> f64 {
+    value
+        .bytes()
+        .take(8)
+        .enumerate()
+        .map(|(i, byte)| byte as f64 / 256f64.powi(i as i32 + 1))
+        .sum()
+}
+
+/// Parses a `YYYY-MM-DD` date into days since 1970-01-01.
+fn days_since_epoch(date: &str) -> Option<i64> {
+    let mut parts = date.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    // Howard Hinnant's days_from_civil
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    Some(era * 146097 + doe - 719468)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dto::histogram::MostCommonValue;
+    use serde_json::json;
+
+    fn int_histogram() -> ColumnHistogram {
+        // 10% nulls, 20% of rows are 7, the rest spread over 0..100
+        ColumnHistogram {
+            histogram_type: HistogramType::Int,
+            bounds: vec![json!(0), json!(25), json!(50), json!(75), json!(100)],
+            most_common_values: vec![MostCommonValue {
+                value: json!(7),
+                frequency: 0.2,
+            }],
+            null_fraction: 0.1,
+            ndv: Some(101),
+        }
+    }
+
+    fn histograms() -> BTreeMap<String, ColumnHistogram> {
+        BTreeMap::from([
+            ("id".to_string(), int_histogram()),
+            (
+                "name".to_string(),
+                ColumnHistogram {
+                    histogram_type: HistogramType::String,
+                    bounds: vec![json!("a"), json!("m"), json!("z")],
+                    most_common_values: vec![],
+                    null_fraction: 0.0,
+                    ndv: None,
+                },
+            ),
+            (
+                "day".to_string(),
+                ColumnHistogram {
+                    histogram_type: HistogramType::Date,
+                    bounds: vec![json!("2024-01-01"), json!("2024-01-11")],
+                    most_common_values: vec![],
+                    null_fraction: 0.0,
+                    ndv: Some(10),
+                },
+            ),
+        ])
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    fn estimate_json(filter: Value) -> f64 {
+        estimate(&serde_json::from_value(filter).unwrap(), &histograms())
+    }
+
+    #[test]
+    fn test_range_selectivity() {
+        // 0.7 of the rows are in buckets, half of them below 50
+        assert_close(
+            estimate_json(json!({"type": "lt", "term": "id", "value": 50})),
+            0.2 + 0.35,
+        );
+        assert_close(
+            estimate_json(json!({"type": "gt-eq", "term": "id", "value": 50})),
+            0.35,
+        );
+        assert_close(
+            estimate_json(json!({"type": "lt", "term": "id", "value": 5})),
+            0.7 * 0.05,
+        );
+        assert_close(
+            estimate_json(json!({"type": "lt", "term": "id", "value": 200})),
+            0.9,
+        );
+        assert_close(
+            estimate_json(json!({"type": "lt", "term": "day", "value": "2024-01-06"})),
+            0.5,
+        );
+    }
+
+    #[test]
+    fn test_equality_selectivity() {
+        assert_close(
+            estimate_json(json!({"type": "eq", "term": "id", "value": 7})),
+            0.2,
+        );
+        assert_close(
+            estimate_json(json!({"type": "eq", "term": "id", "value": 30})),
+            0.7 / 100.0,
+        );
+        assert_close(
+            estimate_json(json!({"type": "eq", "term": "id", "value": 300})),
+            0.0,
+        );
+        assert_close(
+            estimate_json(json!({"type": "in", "term": "id", "values": [7, 30]})),
+            0.2 + 0.007,
+        );
+        assert_close(
+            estimate_json(json!({"type": "not-eq", "term": "id", "value": 7})),
+            0.7,
+        );
+        assert_close(estimate_json(json!({"type": "is-null", "term": "id"})), 0.1);
+        assert_close(
+            estimate_json(json!({"type": "eq", "term": "unknown", "value": 1})),
+            DEFAULT_EQ_SELECTIVITY,
+        );
+    }
+
+    #[test]
+    fn test_starts_with_selectivity() {
+        let selectivity =
+            estimate_json(json!({"type": "starts-with", "term": "name", "value": "b"}));
+        assert!(selectivity > 0.0 && selectivity < 0.1, "{}", selectivity);
+    }
+
+    #[test]
+    fn test_combined_selectivity() {
+        assert_close(
+            estimate_json(json!({
+                "type": "and",
+                "left": {"type": "lt", "term": "id", "value": 50},
+                "right": {"type": "is-null", "term": "id"}
+            })),
+            0.55 * 0.1,
+        );
+        assert_close(
+            estimate_json(json!({
+                "type": "not",
+                "child": {"type": "lt", "term": "id", "value": 50}
+            })),
+            0.35,
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate("id", &int_histogram()).is_ok());
+
+        let mut histogram = int_histogram();
+        histogram.bounds = vec![json!(10), json!(5)];
+        assert!(validate("id", &histogram).is_err());
+
+        let mut histogram = int_histogram();
+        histogram.bounds = vec![json!("x"), json!(5)];
+        assert!(validate("id", &histogram).is_err());
+
+        let mut histogram = int_histogram();
+        histogram.null_fraction = 0.9;
+        assert!(validate("id", &histogram).is_err());
+
+        let mut histogram = int_histogram();
+        histogram.histogram_type = HistogramType::Date;
+        histogram.bounds = vec![json!("2024-13-01"), json!("2024-12-01")];
+        assert!(validate("id", &histogram).is_err());
+    }
+
+    #[test]
+    fn test_days_since_epoch() {
+        assert_eq!(days_since_epoch("1970-01-01"), Some(0));
+        assert_eq!(days_since_epoch("2000-03-01"), Some(11017));
+        assert_eq!(days_since_epoch("1969-12-31"), Some(-1));
+        assert_eq!(days_since_epoch("not a date"), None);
+    }
+}
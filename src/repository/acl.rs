@@ -0,0 +1,223 @@
+use crate::database::database::Database;
+use crate::dto::acl::{Grant, Role};
+use crate::telemetry::metrics::lock_database;
+use serde::{Deserialize, Serialize};
+use std::io::{self, ErrorKind};
+use std::sync::{Arc, Mutex};
+use tracing::instrument;
+
+/// Keys of the `Grants` column family, which holds both roles and the
+/// roles assigned to each principal.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum AclKey {
+    Role(String),
+    Principal(String),
+}
+
+/// AclRepository stores roles, their grants and role assignments.
+pub struct AclRepository {
+    database: Arc<Mutex<Database>>,
+}
+
+fn role_not_found(name: &str) -> io::Error {
+    io::Error::new(ErrorKind::NotFound, format!("Role {} not found", name))
+}
+
+impl AclRepository {
+    pub fn new(database: Arc<Mutex<Database>>) -> Self {
+        Self { database }
+    }
+
+    #[instrument(skip_all)]
+    pub fn list_roles(&self) -> io::Result<Vec<Role>> {
+        let db = lock_database(&self.database, "acl");
+        let mut roles = Vec::new();
+        for key in db.list_all_keys::<AclKey>("Grants")? {
+            if let AclKey::Role(_) = key {
+                if let Some(role) = db.get::<AclKey, Role>("Grants", &key)? {
+                    roles.push(role);
+                }
+            }
+        }
+        Ok(roles)
+    }
+
+    #[instrument(skip_all, fields(role = %name))]
+    pub fn create_role(&self, name: &str) -> io::Result<Role> {
+        let db = lock_database(&self.database, "acl");
+        let key = AclKey::Role(name.to_string());
+        if db.get::<AclKey, Role>("Grants", &key)?.is_some() {
+            return Err(io::Error::new(
+                ErrorKind::AlreadyExists,
+                format!("Role {} already exists", name),
+            ));
+        }
+        let role = Role {
+            name: name.to_string(),
+            grants: Vec::new(),
+        };
+        db.insert("Grants", &key, &role)?;
+        Ok(role)
+    }
+
+    #[instrument(skip_all, fields(role = %name))]
+    pub fn load_role(&self, name: &str) -> io::Result<Option<Role>> {
+        let db = lock_database(&self.database, "acl");
+        db.get("Grants", &AclKey::Role(name.to_string()))
+    }
+
+    /// Deletes a role and unassigns it from every principal, in one batch.
+    #[instrument(skip_all, fields(role = %name))]
+    pub fn drop_role(&self, name: &str) -> io::Result<()> {
+        let db = lock_database(&self.database, "acl");
+        let key = AclKey::Role(name.to_string());
+        if db.get::<AclKey, Role>("Grants", &key)?.is_none() {
+            return Err(role_not_found(name));
+        }
+        let mut changes = vec![("Grants", db.encode_key(&key)?, None)];
+        for principal in db.list_all_keys::<AclKey>("Grants")? {
+            if let AclKey::Principal(_) = principal {
+                let roles: Vec<String> = db.get("Grants", &principal)?.unwrap_or_default();
+                if roles.iter().any(|role| role == name) {
+                    let remaining: Vec<String> =
+                        roles.into_iter().filter(|role| role != name).collect();
+                    changes.push((
+                        "Grants",
+                        db.encode_key(&principal)?,
+                        Some(db.encode_value("Grants", &remaining)?),
+                    ));
+                }
+            }
+        }
+        db.apply_raw(&changes)
+    }
+
+    /// Adds a grant to a role; granting twice is a no-op.
+    #[instrument(skip_all, fields(role = %name))]
+    pub fn grant(&self, name: &str, grant: Grant) -> io::Result<Role> {
+        let db = lock_database(&self.database, "acl");
+        let key = AclKey::Role(name.to_string());
+        let mut role: Role = db
+            .get("Grants", &key)?
+            .ok_or_else(|| role_not_found(name))?;
+        if !role.grants.contains(&grant) {
+            role.grants.push(grant);
+            db.update("Grants", &key, &role)?;
+        }
+        Ok(role)
+    }
+
+    #[instrument(skip_all, fields(role = %name))]
+    pub fn revoke(&self, name: &str, grant: &Grant) -> io::Result<Role> {
+        let db = lock_database(&self.database, "acl");
+        let key = AclKey::Role(name.to_string());
+        let mut role: Role = db
+            .get("Grants", &key)?
+            .ok_or_else(|| role_not_found(name))?;
+        role.grants.retain(|existing| existing != grant);
+        db.update("Grants", &key, &role)?;
+        Ok(role)
+    }
+
+    #[instrument(skip_all, fields(principal = %principal))]
+    pub fn principal_roles(&self, principal: &str) -> io::Result<Vec<String>> {
+        let db = lock_database(&self.database, "acl");
+        Ok(db
+            .get("Grants", &AclKey::Principal(principal.to_string()))?
+            .unwrap_or_default())
+    }
+
+    #[instrument(skip_all, fields(principal = %principal, role = %role))]
+    pub fn assign_role(&self, principal: &str, role: &str) -> io::Result<Vec<String>> {
+        let db = lock_database(&self.database, "acl");
+        if db
+            .get::<AclKey, Role>("Grants", &AclKey::Role(role.to_string()))?
+            .is_none()
+        {
+            return Err(role_not_found(role));
+        }
+        let key = AclKey::Principal(principal.to_string());
+        let mut roles: Vec<String> = db.get("Grants", &key)?.unwrap_or_default();
+        if !roles.iter().any(|existing| existing == role) {
+            roles.push(role.to_string());
+            db.update("Grants", &key, &roles)?;
+        }
+        Ok(roles)
+    }
+
+    #[instrument(skip_all, fields(principal = %principal, role = %role))]
+    pub fn unassign_role(&self, principal: &str, role: &str) -> io::Result<Vec<String>> {
+        let db = lock_database(&self.database, "acl");
+        let key = AclKey::Principal(principal.to_string());
+        let mut roles: Vec<String> = db.get("Grants", &key)?.unwrap_or_default();
+        roles.retain(|existing| existing != role);
+        db.update("Grants", &key, &roles)?;
+        Ok(roles)
+    }
+
+    /// Every grant held by a principal through its roles.
+    #[instrument(skip_all, fields(principal = %principal))]
+    pub fn grants_of(&self, principal: &str) -> io::Result<Vec<Grant>> {
+        let db = lock_database(&self.database, "acl");
+        let roles: Vec<String> = db
+            .get("Grants", &AclKey::Principal(principal.to_string()))?
+            .unwrap_or_default();
+        let mut grants = Vec::new();
+        for name in roles {
+            if let Some(role) = db.get::<AclKey, Role>("Grants", &AclKey::Role(name))? {
+                grants.extend(role.grants);
+            }
+        }
+        Ok(grants)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dto::acl::{GrantScope, Privilege};
+    use tempfile::tempdir;
+
+    fn repository() -> AclRepository {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        AclRepository::new(Arc::new(Mutex::new(db)))
+    }
+
+    fn read_all() -> Grant {
+        Grant {
+            privilege: Privilege::ReadMetadata,
+            scope: GrantScope::Catalog,
+        }
+    }
+
+    #[test]
+    fn test_roles_and_assignments() {
+        let repo = repository();
+        repo.create_role("reader").unwrap();
+        assert_eq!(
+            repo.create_role("reader").unwrap_err().kind(),
+            ErrorKind::AlreadyExists
+        );
+
+        repo.grant("reader", read_all()).unwrap();
+        let role = repo.grant("reader", read_all()).unwrap();
+        assert_eq!(role.grants.len(), 1);
+
+        repo.assign_role("alice", "reader").unwrap();
+        assert_eq!(repo.grants_of("alice").unwrap(), vec![read_all()]);
+        assert!(repo.grants_of("bob").unwrap().is_empty());
+        assert_eq!(
+            repo.assign_role("alice", "writer").unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+
+        repo.revoke("reader", &read_all()).unwrap();
+        assert!(repo.grants_of("alice").unwrap().is_empty());
+
+        repo.drop_role("reader").unwrap();
+        assert!(repo.principal_roles("alice").unwrap().is_empty());
+        assert!(repo.list_roles().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,154 @@
+use crate::config::config::BackupConfig;
+use crate::database::database::Database;
+use crate::database::dump::{self, DumpSummary};
+use crate::database::fsck;
+use crate::database::migration;
+use crate::dto::backup::{BackupInfo, CheckpointResponse};
+use crate::dto::fsck::FsckReport;
+use crate::dto::migration::MigrationReport;
+use crate::telemetry::metrics::lock_database;
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::instrument;
+
+/// AdminRepository serves whole-catalog maintenance operations.
+pub struct AdminRepository {
+    database: Arc<Mutex<Database>>,
+    backup: BackupConfig,
+}
+
+impl AdminRepository {
+    pub fn new(database: Arc<Mutex<Database>>, backup: BackupConfig) -> Self {
+        Self { database, backup }
+    }
+
+    /// Checks the catalog invariants, repairing broken ones if `repair` is set.
+    #[instrument(skip_all, fields(repair = repair))]
+    pub fn fsck(&self, repair: bool) -> io::Result<FsckReport> {
+        let db = lock_database(&self.database, "admin");
+        fsck::fsck(&db, repair)
+    }
+
+    /// Upgrades the storage format, taking a backup first when backups are enabled.
+    #[instrument(skip_all, fields(dry_run = dry_run))]
+    pub fn migrate(&self, dry_run: bool) -> io::Result<MigrationReport> {
+        let db = lock_database(&self.database, "admin");
+        migration::migrate(&db, dry_run, &self.backup)
+    }
+
+    fn backup_dir(&self) -> io::Result<PathBuf> {
+        self.backup.dir.clone().ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::InvalidInput,
+                "Backups are disabled, set backup.dir to enable them",
+            )
+        })
+    }
+
+    #[instrument(skip_all)]
+    pub fn create_backup(&self) -> io::Result<BackupInfo> {
+        let dir = self.backup_dir()?;
+        let db = lock_database(&self.database, "admin");
+        db.create_backup(dir, self.backup.keep)
+    }
+
+    #[instrument(skip_all)]
+    pub fn list_backups(&self) -> io::Result<Vec<BackupInfo>> {
+        Database::list_backups(self.backup_dir()?)
+    }
+
+    /// Creates a checkpoint named after the current time under
+    /// `<backup.dir>/checkpoints`.
+    #[instrument(skip_all)]
+    pub fn create_checkpoint(&self) -> io::Result<CheckpointResponse> {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(io::Error::other)?
+            .as_millis();
+        let path = self
+            .backup_dir()?
+            .join("checkpoints")
+            .join(format!("checkpoint-{}", millis));
+        let db = lock_database(&self.database, "admin");
+        db.create_checkpoint(&path)?;
+        Ok(CheckpointResponse {
+            path: path.display().to_string(),
+        })
+    }
+
+    #[instrument(skip_all)]
+    pub fn export_dump(&self) -> io::Result<Vec<u8>> {
+        let db = lock_database(&self.database, "admin");
+        let mut buffer = Vec::new();
+        dump::write_dump(&db, &mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Validates the dump before taking the lock, then restores it.
+    #[instrument(skip_all)]
+    pub fn import_dump(&self, contents: &[u8]) -> io::Result<DumpSummary> {
+        let dump = dump::read_dump(contents)?;
+        let db = lock_database(&self.database, "admin");
+        dump::restore(&db, &dump)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dto::namespace_data::NamespaceIdent;
+    use crate::repository::namespace::NamespaceRepository;
+    use std::io::ErrorKind;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_export_import_dump() {
+        let source_dir = tempdir().unwrap();
+        let source = Arc::new(Mutex::new(Database::open(source_dir.path()).unwrap()));
+        NamespaceRepository::new(source.clone())
+            .create_namespace(NamespaceIdent(vec!["a".to_string()]), None, None)
+            .unwrap();
+        let contents = AdminRepository::new(source, BackupConfig::default())
+            .export_dump()
+            .unwrap();
+
+        let target_dir = tempdir().unwrap();
+        let target = AdminRepository::new(
+            Arc::new(Mutex::new(Database::open(target_dir.path()).unwrap())),
+            BackupConfig::default(),
+        );
+        let summary = target.import_dump(&contents).unwrap();
+        assert_eq!(summary.entries["NamespaceData"], 1);
+
+        let err = target.import_dump(&contents).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+        let err = target.import_dump(b"not a dump").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_backups() {
+        let dir = tempdir().unwrap();
+        let database = Arc::new(Mutex::new(Database::open(dir.path().join("db")).unwrap()));
+        let disabled = AdminRepository::new(database.clone(), BackupConfig::default());
+        let err = disabled.create_backup().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+        let repo = AdminRepository::new(
+            database,
+            BackupConfig {
+                dir: Some(dir.path().join("backups")),
+                interval_secs: None,
+                keep: 1,
+            },
+        );
+        repo.create_backup().unwrap();
+        let latest = repo.create_backup().unwrap();
+        assert_eq!(repo.list_backups().unwrap(), vec![latest]);
+
+        let checkpoint = repo.create_checkpoint().unwrap();
+        assert!(checkpoint.path.contains("checkpoints"));
+    }
+}
@@ -0,0 +1,96 @@
+use crate::database::database::Database;
+use crate::database::format::FORMAT_VERSION;
+use crate::dto::health::{ReadinessCheck, ReadinessReport, ServerInfo};
+use crate::telemetry::metrics::lock_database;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::instrument;
+
+/// HealthRepository answers the liveness, readiness and info probes.
+pub struct HealthRepository {
+    database: Arc<Mutex<Database>>,
+    started: Instant,
+}
+
+fn check(name: &str, result: io::Result<()>) -> ReadinessCheck {
+    ReadinessCheck {
+        name: name.to_string(),
+        ok: result.is_ok(),
+        detail: result.err().map(|e| e.to_string()),
+    }
+}
+
+impl HealthRepository {
+    pub fn new(database: Arc<Mutex<Database>>) -> Self {
+        Self {
+            database,
+            started: Instant::now(),
+        }
+    }
+
+    /// Ready once the database takes writes and holds the current format.
+    #[instrument(skip_all)]
+    pub fn readiness(&self) -> ReadinessReport {
+        let db = lock_database(&self.database, "health");
+        let checks = vec![
+            check("writable", db.check_writable()),
+            check(
+                "migrated",
+                db.format_version().and_then(|version| match version {
+                    Some(FORMAT_VERSION) => Ok(()),
+                    other => Err(io::Error::other(format!(
+                        "Format version {:?} is not the current version {}",
+                        other, FORMAT_VERSION
+                    ))),
+                }),
+            ),
+        ];
+        ReadinessReport {
+            ready: checks.iter().all(|check| check.ok),
+            checks,
+        }
+    }
+
+    #[instrument(skip_all)]
+    pub fn info(&self) -> io::Result<ServerInfo> {
+        let db = lock_database(&self.database, "health");
+        Ok(ServerInfo {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            format_version: db.format_version()?,
+            latest_format_version: FORMAT_VERSION,
+            uptime_secs: self.started.elapsed().as_secs(),
+            column_families: db.column_family_sizes()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::database::COLUMN_FAMILIES;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_readiness_and_info() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let db = Arc::new(Mutex::new(db));
+        let repo = HealthRepository::new(db.clone());
+
+        let report = repo.readiness();
+        assert!(report.ready, "{:?}", report);
+        let info = repo.info().unwrap();
+        assert_eq!(info.format_version, Some(FORMAT_VERSION));
+        assert_eq!(info.column_families.len(), COLUMN_FAMILIES.len());
+
+        // Data in an older format is not served until it is migrated.
+        db.lock().unwrap().set_format_version(1).unwrap();
+        let report = repo.readiness();
+        assert!(!report.ready);
+        assert!(report
+            .checks
+            .iter()
+            .any(|check| check.name == "migrated" && !check.ok));
+    }
+}
@@ -0,0 +1,38 @@
+use crate::database::database::Database;
+use crate::telemetry::metrics::{lock_database, METRICS};
+use std::io;
+use std::sync::{Arc, Mutex};
+
+/// MetricsRepository samples RocksDB internals for the metrics registry.
+pub struct MetricsRepository {
+    database: Arc<Mutex<Database>>,
+}
+
+impl MetricsRepository {
+    pub fn new(database: Arc<Mutex<Database>>) -> Self {
+        Self { database }
+    }
+
+    /// Refreshes the RocksDB gauges and renders every metric.
+    pub fn render(&self) -> io::Result<String> {
+        let stats = lock_database(&self.database, "metrics").rocksdb_stats()?;
+        METRICS.observe_rocksdb(&stats);
+        METRICS.render()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_render_includes_column_families() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let repo = MetricsRepository::new(Arc::new(Mutex::new(db)));
+        let text = repo.render().unwrap();
+        assert!(text.contains(r#"catalog_rocksdb_memtable_bytes{column_family="TableData"}"#));
+        assert!(text.contains(r#"catalog_database_lock_wait_seconds_count{repository="metrics"}"#));
+    }
+}
@@ -1,2 +1,10 @@
+pub mod acl;
+pub mod admin;
+pub mod health;
+pub mod metrics;
 pub mod namespace;
+pub mod quota;
+pub mod search;
+pub mod statistics;
 pub mod table;
+pub mod version;
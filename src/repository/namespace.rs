@@ -1,60 +1,204 @@
+use crate::config::config::CasePolicy;
 use crate::database::database::Database;
+use crate::database::search_index::IndexUpdate;
 use crate::dto::namespace_data::{NamespaceData, NamespaceIdent};
+use crate::dto::table_data::TableIdent;
+use crate::repository::quota::{Owned, Owner, QuotaUpdate};
+use crate::repository::version::{check_version, first_version, retire, VersionKey};
+use crate::telemetry::metrics::lock_database;
 use serde_json::{json, Map, Value};
+use std::collections::HashSet;
 use std::io::{self, ErrorKind};
 use std::sync::{Arc, Mutex};
+use tracing::instrument;
 
 pub struct NamespaceRepository {
     database: Arc<Mutex<Database>>,
+    case: CasePolicy,
 }
 
 impl NamespaceRepository {
     pub fn new(database: Arc<Mutex<Database>>) -> Self {
-        Self { database }
+        Self {
+            database,
+            case: CasePolicy::default(),
+        }
+    }
+
+    /// Makes new namespaces follow `case`, which by default they do not
+    /// have to.
+    pub fn with_case_policy(mut self, case: CasePolicy) -> Self {
+        self.case = case;
+        self
     }
 
+    #[instrument(skip_all)]
     pub fn list_all_namespaces(&self) -> io::Result<Vec<NamespaceIdent>> {
-        let db = self.database.lock().unwrap();
+        let db = lock_database(&self.database, "namespace");
         db.list_all_keys("NamespaceData")
             .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))
     }
 
+    /// Creates the namespace, counted against the quota of `owner` when
+    /// given.
+    #[instrument(skip_all, fields(namespace = %name))]
     pub fn create_namespace(
         &self,
         name: NamespaceIdent,
         properties: Option<Value>,
+        owner: Option<&Owner>,
     ) -> io::Result<()> {
+        let db = lock_database(&self.database, "namespace");
+        if db
+            .get::<NamespaceIdent, NamespaceData>("NamespaceData", &name)?
+            .is_some()
+        {
+            return Err(io::Error::new(
+                ErrorKind::AlreadyExists,
+                format!("Namespace {} already exists", name),
+            ));
+        }
+        let existing = self.siblings(&db)?;
+        if let Some(other) = clashing(self.case, &name, &existing) {
+            return Err(clash(&name, other));
+        }
         let namespace_data = NamespaceData {
             name: name.clone(),
             properties: properties.unwrap_or_else(|| json!({"last_modified_time": current_time()})),
+            version: first_version(&db, &VersionKey::Namespace(name.clone()))?,
         };
-        let db = self.database.lock().unwrap();
-        db.insert("NamespaceData", &name, &namespace_data)
+        let mut quota = QuotaUpdate::new(&db, owner);
+        quota.created(Owned::Namespace(name.clone()))?;
+        let mut changes = vec![(
+            "NamespaceData",
+            db.encode_key(&name)?,
+            Some(db.encode_value("NamespaceData", &namespace_data)?),
+        )];
+        changes.extend(quota.changes()?);
+        let mut index = IndexUpdate::new(&db)?;
+        index.namespace(None, Some(&namespace_data))?;
+        index.commit(changes)
     }
 
-    pub fn delete_namespace(&self, name: &NamespaceIdent) -> io::Result<()> {
-        let db = self.database.lock().unwrap();
-        db.delete("NamespaceData", name)
+    /// Creates every namespace in one write. With `atomic` the first
+    /// failure is returned and nothing is written; otherwise each namespace
+    /// gets its own result and the others are still created. The namespaces
+    /// created must fit in the quota of `owner` as a whole.
+    #[instrument(skip_all, fields(namespaces = namespaces.len()))]
+    pub fn create_namespaces(
+        &self,
+        namespaces: &[NamespaceData],
+        atomic: bool,
+        owner: Option<&Owner>,
+    ) -> io::Result<Vec<io::Result<()>>> {
+        let db = lock_database(&self.database, "namespace");
+        let names: Vec<&NamespaceIdent> = namespaces.iter().map(|data| &data.name).collect();
+        let existing: Vec<Option<NamespaceData>> = db.multi_get("NamespaceData", &names)?;
+        // Grows with the namespaces of the batch, which may clash as well.
+        let mut siblings = self.siblings(&db)?;
+
+        let mut seen = HashSet::new();
+        let mut results = Vec::with_capacity(namespaces.len());
+        let mut index = IndexUpdate::new(&db)?;
+        let mut quota = QuotaUpdate::new(&db, owner);
+        let mut changes = Vec::new();
+        for (data, existing) in namespaces.iter().zip(existing) {
+            let result = if existing.is_some() || !seen.insert(&data.name) {
+                Err(io::Error::new(
+                    ErrorKind::AlreadyExists,
+                    format!("Namespace {} already exists", data.name),
+                ))
+            } else if let Some(other) = clashing(self.case, &data.name, &siblings) {
+                Err(clash(&data.name, other))
+            } else {
+                if self.case == CasePolicy::Insensitive {
+                    siblings.push(data.name.clone());
+                }
+                let data = NamespaceData {
+                    version: first_version(&db, &VersionKey::Namespace(data.name.clone()))?,
+                    ..data.clone()
+                };
+                index.namespace(None, Some(&data))?;
+                quota.created(Owned::Namespace(data.name.clone()))?;
+                changes.push((
+                    "NamespaceData",
+                    db.encode_key(&data.name)?,
+                    Some(db.encode_value("NamespaceData", &data)?),
+                ));
+                Ok(())
+            };
+            match result {
+                Err(e) if atomic => return Err(e),
+                result => results.push(result),
+            }
+        }
+        changes.extend(quota.changes()?);
+        index.commit(changes)?;
+        Ok(results)
+    }
+
+    /// Deletes the namespace, provided it is at one of the `if_match`
+    /// versions when those are given and holds no tables, and returns it to
+    /// its owner's quota.
+    #[instrument(skip_all, fields(namespace = %name))]
+    pub fn delete_namespace(
+        &self,
+        name: &NamespaceIdent,
+        if_match: Option<&[u64]>,
+    ) -> io::Result<()> {
+        let db = lock_database(&self.database, "namespace");
+        let Some(data) = db.get::<NamespaceIdent, NamespaceData>("NamespaceData", name)? else {
+            return db.delete("NamespaceData", name);
+        };
+        check_version(if_match, data.version)?;
+        let tables = db
+            .get::<NamespaceIdent, Vec<TableIdent>>("TableNamespaceMap", name)?
+            .unwrap_or_default();
+        if !tables.is_empty() {
+            return Err(io::Error::new(
+                ErrorKind::ResourceBusy,
+                format!("Namespace {} still holds {} tables", name, tables.len()),
+            ));
+        }
+        let mut quota = QuotaUpdate::new(&db, None);
+        quota.dropped(&Owned::Namespace(name.clone()))?;
+        let key = db.encode_key(name)?;
+        let mut changes = vec![
+            ("NamespaceData", key.clone(), None),
+            ("TableNamespaceMap", key, None),
+            retire(&db, &VersionKey::Namespace(name.clone()), data.version)?,
+        ];
+        changes.extend(quota.changes()?);
+        let mut index = IndexUpdate::new(&db)?;
+        index.namespace(Some(&data), None)?;
+        index.commit(changes)
     }
 
+    #[instrument(skip_all, fields(namespace = %name))]
     pub fn load_namespace(&self, name: &NamespaceIdent) -> io::Result<Option<NamespaceData>> {
-        let db = self.database.lock().unwrap();
+        let db = lock_database(&self.database, "namespace");
         db.get::<NamespaceIdent, NamespaceData>("NamespaceData", &name)
     }
 
+    #[instrument(skip_all, fields(namespace = %name))]
     pub fn namespace_exists(&self, name: &NamespaceIdent) -> io::Result<bool> {
-        let db = self.database.lock().unwrap();
+        let db = lock_database(&self.database, "namespace");
         db.get::<NamespaceIdent, NamespaceData>("NamespaceData", &name)
             .map(|data| data.is_some())
     }
 
+    /// Applies the removals and updates and returns the new version of the
+    /// namespace, provided it is at one of the `if_match` versions when
+    /// those are given.
+    #[instrument(skip_all, fields(namespace = %name))]
     pub fn set_namespace_properties(
         &self,
         name: NamespaceIdent,
         removals: Vec<String>,
         updates: Map<String, Value>,
-    ) -> io::Result<()> {
-        let db = self.database.lock().unwrap();
+        if_match: Option<&[u64]>,
+    ) -> io::Result<u64> {
+        let db = lock_database(&self.database, "namespace");
         // Get the current properties
         let namespace_data: NamespaceData = match db.get("NamespaceData", &name)? {
             Some(data) => data,
@@ -65,6 +209,7 @@ impl NamespaceRepository {
                 ))
             }
         };
+        check_version(if_match, namespace_data.version)?;
 
         // Convert the properties to a mutable Map
         let mut p = namespace_data.get_properties().clone();
@@ -83,18 +228,52 @@ impl NamespaceRepository {
         }
         let props = Value::Object(properties.clone());
         let name_copy = name.clone();
-        // Save the updated properties
-        db.update(
+        let version = namespace_data.version + 1;
+        let updated = NamespaceData {
+            name: name_copy,
+            properties: props,
+            version,
+        };
+        // Save the updated properties together with their index entries
+        let mut index = IndexUpdate::new(&db)?;
+        index.namespace(Some(&namespace_data), Some(&updated))?;
+        index.commit(vec![(
             "NamespaceData",
-            &name,
-            &NamespaceData {
-                name: name_copy,
-                properties: props,
-            },
-        )?;
-
-        Ok(())
+            db.encode_key(&name)?,
+            Some(db.encode_value("NamespaceData", &updated)?),
+        )])?;
+
+        Ok(version)
     }
+
+    /// The namespaces a new one may clash with, which are only looked up
+    /// when the case policy asks for it.
+    fn siblings(&self, db: &Database) -> io::Result<Vec<NamespaceIdent>> {
+        match self.case {
+            CasePolicy::Insensitive => db.list_all_keys("NamespaceData"),
+            _ => Ok(Vec::new()),
+        }
+    }
+}
+
+/// Finds the namespace among `existing` that `name` differs from only in
+/// case, when `case` forbids that.
+fn clashing<'a>(
+    case: CasePolicy,
+    name: &NamespaceIdent,
+    existing: &'a [NamespaceIdent],
+) -> Option<&'a NamespaceIdent> {
+    let joined = name.0.join("\u{1F}");
+    existing
+        .iter()
+        .find(|other| case.clashes(&joined, &other.0.join("\u{1F}")))
+}
+
+fn clash(name: &NamespaceIdent, other: &NamespaceIdent) -> io::Error {
+    io::Error::new(
+        ErrorKind::AlreadyExists,
+        format!("Namespace {} differs only in case from {}", name, other),
+    )
 }
 
 fn current_time() -> String {
@@ -104,6 +283,9 @@ fn current_time() -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::dto::table_data::TableCreation;
+    use crate::repository::table::TableRepository;
+    use crate::repository::version::is_version_mismatch;
     use std::sync::{Arc, Mutex};
     use tempfile::tempdir;
 
@@ -117,7 +299,7 @@ mod tests {
         // Test create_namespace
         let namespace_ident = NamespaceIdent(vec!["test".to_string()]);
         let properties = Some(json!({"property1": "value1"}));
-        repo.create_namespace(namespace_ident.clone(), properties)
+        repo.create_namespace(namespace_ident.clone(), properties, None)
             .unwrap();
 
         // Test namespace_exists
@@ -132,18 +314,161 @@ mod tests {
         let removals = vec!["property1".to_string()];
         let mut updates = Map::new();
         updates.insert("property2".to_string(), json!("value2"));
-        repo.set_namespace_properties(namespace_ident.clone(), removals, updates)
+        assert_eq!(namespace_data.version, 1);
+        let version = repo
+            .set_namespace_properties(namespace_ident.clone(), removals, updates, Some(&[1]))
             .unwrap();
+        assert_eq!(version, 2);
 
         let updated_namespace_data = repo.load_namespace(&namespace_ident).unwrap().unwrap();
         assert_eq!(
             updated_namespace_data.properties,
             json!({"property2": "value2"})
         );
+        assert_eq!(updated_namespace_data.version, 2);
+
+        // Writes against a stale version are refused.
+        let err = repo
+            .set_namespace_properties(namespace_ident.clone(), vec![], Map::new(), Some(&[1]))
+            .unwrap_err();
+        assert!(is_version_mismatch(&err));
+        let err = repo
+            .delete_namespace(&namespace_ident, Some(&[1]))
+            .unwrap_err();
+        assert!(is_version_mismatch(&err));
 
         // Test delete_namespace
-        repo.delete_namespace(&namespace_ident).unwrap();
+        repo.delete_namespace(&namespace_ident, None).unwrap();
         assert!(!repo.namespace_exists(&namespace_ident).unwrap());
+
+        // A namespace created again under the name keeps counting, so tags
+        // of the dropped one do not match it.
+        repo.create_namespace(namespace_ident.clone(), None, None)
+            .unwrap();
+        let recreated = repo.load_namespace(&namespace_ident).unwrap().unwrap();
+        assert_eq!(recreated.version, 3);
+        let err = repo
+            .delete_namespace(&namespace_ident, Some(&[1]))
+            .unwrap_err();
+        assert!(is_version_mismatch(&err));
+    }
+
+    #[test]
+    fn test_delete_namespace_with_tables() {
+        let dir = tempdir().unwrap();
+        let db = Arc::new(Mutex::new(Database::open(dir.path()).unwrap()));
+        let repo = NamespaceRepository::new(db.clone());
+        let tables = TableRepository::new(db.clone());
+        let sales = NamespaceIdent(vec!["sales".to_string()]);
+        repo.create_namespace(sales.clone(), None, None).unwrap();
+        let orders = TableCreation {
+            name: "orders".to_string(),
+            columns: None,
+            properties: None,
+        };
+        tables.create_table(&sales, &orders, None).unwrap();
+
+        // A namespace holding tables stays.
+        let err = repo.delete_namespace(&sales, None).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ResourceBusy);
+        assert!(repo.namespace_exists(&sales).unwrap());
+
+        // Once emptied it drops, and a namespace created again under the
+        // name starts without tables.
+        tables
+            .drop_table(&sales, "orders".to_string(), None)
+            .unwrap();
+        repo.delete_namespace(&sales, None).unwrap();
+        assert!(db
+            .lock()
+            .unwrap()
+            .get::<NamespaceIdent, Vec<TableIdent>>("TableNamespaceMap", &sales)
+            .unwrap()
+            .is_none());
+        repo.create_namespace(sales.clone(), None, None).unwrap();
+        assert_eq!(tables.list_all_tables(&sales).unwrap(), None);
+        assert!(!tables.table_exists(&sales, "orders".to_string()).unwrap());
+    }
+
+    #[test]
+    fn test_create_namespaces() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let repo = NamespaceRepository::new(Arc::new(Mutex::new(db)));
+        let namespace = |name: &str| NamespaceData {
+            name: NamespaceIdent(vec![name.to_string()]),
+            properties: json!({}),
+            version: 0,
+        };
+        repo.create_namespace(namespace("sales").name, None, None)
+            .unwrap();
+
+        // Atomic batches write nothing when one namespace fails.
+        let batch = [namespace("marketing"), namespace("sales")];
+        let err = repo.create_namespaces(&batch, true, None).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+        assert!(!repo.namespace_exists(&batch[0].name).unwrap());
+
+        let batch = [
+            namespace("marketing"),
+            namespace("sales"),
+            namespace("marketing"),
+        ];
+        let results = repo.create_namespaces(&batch, false, None).unwrap();
+        assert!(results[0].is_ok());
+        assert_eq!(
+            results[1].as_ref().unwrap_err().kind(),
+            ErrorKind::AlreadyExists
+        );
+        assert_eq!(
+            results[2].as_ref().unwrap_err().kind(),
+            ErrorKind::AlreadyExists
+        );
+        let created = repo.load_namespace(&batch[0].name).unwrap().unwrap();
+        assert_eq!(created.version, 1);
+    }
+
+    #[test]
+    fn test_case_insensitive_namespaces() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let repo = NamespaceRepository::new(Arc::new(Mutex::new(db)))
+            .with_case_policy(CasePolicy::Insensitive);
+        let namespace = |levels: &[&str]| NamespaceData {
+            name: NamespaceIdent(levels.iter().map(|level| level.to_string()).collect()),
+            properties: json!({}),
+            version: 0,
+        };
+        repo.create_namespace(namespace(&["Sales"]).name, None, None)
+            .unwrap();
+
+        let err = repo
+            .create_namespace(namespace(&["sales"]).name, None, None)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+        assert_eq!(
+            err.to_string(),
+            "Namespace sales differs only in case from Sales"
+        );
+        // Names only clash with namespaces at the same level.
+        repo.create_namespace(namespace(&["Sales", "sales"]).name, None, None)
+            .unwrap();
+
+        // Names in the batch clash with each other as well.
+        let batch = [
+            namespace(&["Marketing"]),
+            namespace(&["marketing"]),
+            namespace(&["SALES"]),
+        ];
+        let results = repo.create_namespaces(&batch, false, None).unwrap();
+        assert!(results[0].is_ok());
+        for result in &results[1..] {
+            assert_eq!(
+                result.as_ref().unwrap_err().kind(),
+                ErrorKind::AlreadyExists
+            );
+        }
+        assert!(!repo.namespace_exists(&batch[1].name).unwrap());
     }
 
     #[test]
@@ -167,7 +492,7 @@ mod tests {
         let mut updates = Map::new();
         updates.insert("property2".to_string(), json!("value2"));
         assert!(repo
-            .set_namespace_properties(non_existent_namespace.clone(), vec![], updates)
+            .set_namespace_properties(non_existent_namespace.clone(), vec![], updates, None)
             .is_err());
     }
 }
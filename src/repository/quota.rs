@@ -0,0 +1,299 @@
+use crate::config::config::LimitsConfig;
+use crate::database::database::{Database, RawChange};
+use crate::dto::namespace_data::NamespaceIdent;
+use crate::dto::table_data::TableIdent;
+use crate::telemetry::metrics::lock_database;
+use serde::{Deserialize, Serialize};
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::io::{self, ErrorKind};
+use std::sync::{Arc, Mutex};
+
+/// An object counted against the quota of the principal that created it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Owned {
+    Namespace(NamespaceIdent),
+    Table(TableIdent),
+}
+
+/// Objects owned by a principal.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Usage {
+    pub namespaces: u64,
+    pub tables: u64,
+}
+
+impl Usage {
+    fn count(&mut self, object: &Owned) -> &mut u64 {
+        match object {
+            Owned::Namespace(_) => &mut self.namespaces,
+            Owned::Table(_) => &mut self.tables,
+        }
+    }
+}
+
+/// Keys of the `Ownership` column family: the principal owning an object,
+/// and the [`Usage`] of a principal.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OwnershipKey {
+    Owner(Owned),
+    Usage(String),
+}
+
+/// Usage of every principal owning one of `owners`, counted from scratch.
+pub fn count_usage<'a>(
+    owners: impl IntoIterator<Item = (&'a Owned, &'a String)>,
+) -> BTreeMap<String, Usage> {
+    let mut usage: BTreeMap<String, Usage> = BTreeMap::new();
+    for (object, principal) in owners {
+        *usage.entry(principal.clone()).or_default().count(object) += 1;
+    }
+    usage
+}
+
+/// QuotaRepository records who created each namespace and table and caps
+/// how many a principal may own.
+pub struct QuotaRepository {
+    database: Arc<Mutex<Database>>,
+    limits: LimitsConfig,
+}
+
+impl QuotaRepository {
+    pub fn new(database: Arc<Mutex<Database>>, limits: LimitsConfig) -> Self {
+        Self { database, limits }
+    }
+
+    pub fn usage(&self, principal: &str) -> io::Result<Usage> {
+        let db = lock_database(&self.database, "quota");
+        Ok(db
+            .get("Ownership", &OwnershipKey::Usage(principal.to_string()))?
+            .unwrap_or_default())
+    }
+
+    /// The owner objects created on behalf of `principal` are counted
+    /// against.
+    pub fn owner<'a>(&'a self, principal: &'a str) -> Owner<'a> {
+        Owner {
+            principal,
+            limits: &self.limits,
+        }
+    }
+}
+
+/// A principal creating objects, with the limits that apply to them.
+pub struct Owner<'a> {
+    principal: &'a str,
+    limits: &'a LimitsConfig,
+}
+
+impl Owner<'_> {
+    fn check(&self, usage: &Usage) -> io::Result<()> {
+        let checks = [
+            (
+                usage.namespaces,
+                self.limits.max_namespaces_per_principal,
+                "namespaces",
+            ),
+            (usage.tables, self.limits.max_tables_per_principal, "tables"),
+        ];
+        for (count, limit, kind) in checks {
+            if let Some(limit) = limit.filter(|limit| count > *limit) {
+                return Err(io::Error::new(
+                    ErrorKind::PermissionDenied,
+                    format!(
+                        "Quota of {} {} for {} exceeded",
+                        limit, kind, self.principal
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// QuotaUpdate collects the ownership and usage changes that go with
+/// creating, dropping and renaming namespaces and tables, so that they land
+/// in the same batch as the objects.
+pub struct QuotaUpdate<'a> {
+    db: &'a Database,
+    owner: Option<&'a Owner<'a>>,
+    owners: Vec<(Owned, Option<String>)>,
+    usage: BTreeMap<String, Usage>,
+}
+
+impl<'a> QuotaUpdate<'a> {
+    /// Objects created through the update are counted against `owner`, or
+    /// have no owner when there is none.
+    pub fn new(db: &'a Database, owner: Option<&'a Owner<'a>>) -> Self {
+        Self {
+            db,
+            owner,
+            owners: Vec::new(),
+            usage: BTreeMap::new(),
+        }
+    }
+
+    fn usage(&mut self, principal: &str) -> io::Result<&mut Usage> {
+        Ok(match self.usage.entry(principal.to_string()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let key = OwnershipKey::Usage(entry.key().clone());
+                entry.insert(self.db.get("Ownership", &key)?.unwrap_or_default())
+            }
+        })
+    }
+
+    fn owner_of(&self, object: &Owned) -> io::Result<Option<String>> {
+        self.db
+            .get("Ownership", &OwnershipKey::Owner(object.clone()))
+    }
+
+    /// Records `object` as created by the owner.
+    pub fn created(&mut self, object: Owned) -> io::Result<()> {
+        let Some(owner) = self.owner else {
+            return Ok(());
+        };
+        *self.usage(owner.principal)?.count(&object) += 1;
+        self.owners
+            .push((object, Some(owner.principal.to_string())));
+        Ok(())
+    }
+
+    /// Returns a dropped object to its owner's quota. Objects created before
+    /// ownership was recorded have no owner and are ignored.
+    pub fn dropped(&mut self, object: &Owned) -> io::Result<()> {
+        let Some(owner) = self.owner_of(object)? else {
+            return Ok(());
+        };
+        let count = self.usage(&owner)?.count(object);
+        *count = count.saturating_sub(1);
+        self.owners.push((object.clone(), None));
+        Ok(())
+    }
+
+    /// Moves the ownership record of a renamed object.
+    pub fn renamed(&mut self, from: &Owned, to: &Owned) -> io::Result<()> {
+        if let Some(owner) = self.owner_of(from)? {
+            self.owners.push((from.clone(), None));
+            self.owners.push((to.clone(), Some(owner)));
+        }
+        Ok(())
+    }
+
+    /// Returns the changes to write, failing with `PermissionDenied` when
+    /// the objects created take the owner past a limit. A batch of creates
+    /// must fit in the quota as a whole.
+    pub fn changes(self) -> io::Result<Vec<RawChange<'static>>> {
+        if let Some(owner) = self.owner {
+            if let Some(usage) = self.usage.get(owner.principal) {
+                owner.check(usage)?;
+            }
+        }
+        let mut changes = Vec::with_capacity(self.owners.len() + self.usage.len());
+        for (object, owner) in self.owners {
+            let value = owner
+                .map(|owner| self.db.encode_value("Ownership", &owner))
+                .transpose()?;
+            changes.push((
+                "Ownership",
+                self.db.encode_key(&OwnershipKey::Owner(object))?,
+                value,
+            ));
+        }
+        for (principal, usage) in self.usage {
+            changes.push((
+                "Ownership",
+                self.db.encode_key(&OwnershipKey::Usage(principal))?,
+                Some(self.db.encode_value("Ownership", &usage)?),
+            ));
+        }
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::{tempdir, TempDir};
+
+    /// The repository, with the directory its database lives in.
+    fn repository(max_tables: Option<u64>) -> (QuotaRepository, TempDir) {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let repo = QuotaRepository::new(
+            Arc::new(Mutex::new(db)),
+            LimitsConfig {
+                max_tables_per_principal: max_tables,
+                ..Default::default()
+            },
+        );
+        (repo, dir)
+    }
+
+    fn table(name: &str) -> Owned {
+        Owned::Table(TableIdent::new(
+            NamespaceIdent(vec!["sales".to_string()]),
+            name.to_string(),
+        ))
+    }
+
+    /// Writes what `edit` records, with creates counted against `principal`.
+    fn update(
+        repo: &QuotaRepository,
+        principal: Option<&str>,
+        edit: impl FnOnce(&mut QuotaUpdate) -> io::Result<()>,
+    ) -> io::Result<()> {
+        let owner = principal.map(|principal| repo.owner(principal));
+        let db = lock_database(&repo.database, "quota");
+        let mut quota = QuotaUpdate::new(&db, owner.as_ref());
+        edit(&mut quota)?;
+        db.apply_raw(&quota.changes()?)
+    }
+
+    #[test]
+    fn test_quota_is_charged_and_released() {
+        let (repo, _dir) = repository(Some(1));
+        update(&repo, Some("alice"), |quota| quota.created(table("orders"))).unwrap();
+        let err = update(&repo, Some("alice"), |quota| quota.created(table("items"))).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+        assert_eq!(repo.usage("alice").unwrap().tables, 1);
+        // Quotas are per principal.
+        update(&repo, Some("bob"), |quota| quota.created(table("items"))).unwrap();
+
+        update(&repo, None, |quota| {
+            quota.renamed(&table("orders"), &table("orders_v2"))
+        })
+        .unwrap();
+        update(&repo, None, |quota| quota.dropped(&table("orders_v2"))).unwrap();
+        assert_eq!(repo.usage("alice").unwrap(), Usage::default());
+        update(&repo, Some("alice"), |quota| {
+            quota.created(table("items_v2"))
+        })
+        .unwrap();
+
+        // Tables without an owner record are ignored.
+        update(&repo, None, |quota| quota.dropped(&table("legacy"))).unwrap();
+        assert_eq!(repo.usage("bob").unwrap().tables, 1);
+    }
+
+    #[test]
+    fn test_batches_must_fit_the_quota() {
+        let (repo, _dir) = repository(Some(2));
+        let err = update(&repo, Some("alice"), |quota| {
+            for name in ["a", "b", "c"] {
+                quota.created(table(name))?;
+            }
+            Ok(())
+        })
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+        assert_eq!(repo.usage("alice").unwrap().tables, 0);
+
+        update(&repo, Some("alice"), |quota| quota.created(table("a"))).unwrap();
+        assert_eq!(repo.usage("alice").unwrap().tables, 1);
+        update(&repo, None, |quota| quota.dropped(&table("a"))).unwrap();
+        assert_eq!(repo.usage("alice").unwrap().tables, 0);
+    }
+}
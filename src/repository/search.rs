@@ -0,0 +1,491 @@
+use crate::database::database::Database;
+use crate::database::search_index::{
+    self, lookup, named_with_prefix, property_postings, with_property,
+};
+use crate::dto::namespace_data::NamespaceIdent;
+use crate::dto::search::{
+    CatalogObject, PropertyIndex, SearchKind, SearchQuery, SearchResponse, SearchTerm,
+};
+use crate::dto::table_data::TableIdent;
+use crate::telemetry::metrics::lock_database;
+use regex::{Regex, RegexBuilder};
+use std::collections::HashSet;
+use std::io::{Error, ErrorKind};
+use std::sync::{Arc, Mutex};
+use tracing::instrument;
+
+/// Largest compiled pattern a search may use, so that a hostile pattern
+/// cannot exhaust memory.
+const PATTERN_SIZE_LIMIT: usize = 1 << 20;
+
+pub struct SearchRepository {
+    database: Arc<Mutex<Database>>,
+}
+
+impl SearchRepository {
+    pub fn new(database: Arc<Mutex<Database>>) -> Self {
+        Self { database }
+    }
+
+    /// Finds the namespaces and tables passing every filter of the query.
+    /// Property and column filters are answered from the search index, as
+    /// are name globs starting with literal text. Other name patterns are
+    /// checked against every name.
+    #[instrument(skip_all)]
+    pub fn search(&self, query: &SearchQuery) -> Result<SearchResponse, Error> {
+        let mut patterns = Vec::new();
+        if let Some(glob) = &query.name {
+            patterns.push(compile(&glob_to_regex(glob))?);
+        }
+        let name_prefix = query
+            .name
+            .as_deref()
+            .map(literal_prefix)
+            .filter(|prefix| !prefix.is_empty());
+        if let Some(regex) = &query.regex {
+            patterns.push(compile(regex)?);
+        }
+        let matches = |name: String| patterns.iter().all(|pattern| pattern.is_match(&name));
+        let property = query.property.as_deref().map(parse_property).transpose()?;
+
+        let db = lock_database(&self.database, "search");
+        // Namespaces and tables carrying the property, None when not
+        // filtering on one.
+        let mut namespaces_with: Option<HashSet<NamespaceIdent>> = None;
+        let mut tables_with: HashSet<TableIdent> = HashSet::new();
+        if let Some((key, value)) = property {
+            let mut namespaces = HashSet::new();
+            for object in with_property(&db, key, value)? {
+                match object {
+                    CatalogObject::Namespace(namespace) => namespaces.insert(namespace),
+                    CatalogObject::Table(table) => tables_with.insert(table),
+                };
+            }
+            namespaces_with = Some(namespaces);
+        }
+        // Namespaces and tables whose name starts like the glob, None when
+        // it does not start with literal text.
+        let named = name_prefix
+            .map(|prefix| named_with_prefix(&db, prefix))
+            .transpose()?;
+
+        let mut response = SearchResponse::default();
+        if query.kind != Some(SearchKind::Table) && query.column.is_none() {
+            let candidates: Vec<NamespaceIdent> = match (&namespaces_with, &named) {
+                (Some(namespaces), _) => namespaces.iter().cloned().collect(),
+                (None, Some(named)) => named
+                    .iter()
+                    .filter_map(|object| match object {
+                        CatalogObject::Namespace(namespace) => Some(namespace.clone()),
+                        CatalogObject::Table(_) => None,
+                    })
+                    .collect(),
+                (None, None) => db.list_all_keys("NamespaceData")?,
+            };
+            response.namespaces = candidates
+                .into_iter()
+                .filter(|namespace| matches(namespace.to_string()))
+                .collect();
+            response.namespaces.sort();
+        }
+        if query.kind != Some(SearchKind::Namespace) {
+            let candidates: Vec<TableIdent> = match (&query.column, named) {
+                (Some(column), _) => tables(lookup(&db, &SearchTerm::Column(column.clone()))?),
+                (None, Some(named)) => tables(named),
+                (None, None) => db.list_all_keys("TableData")?,
+            };
+            response.tables = candidates
+                .into_iter()
+                .filter(|table| {
+                    namespaces_with.as_ref().is_none_or(|namespaces| {
+                        tables_with.contains(table) || namespaces.contains(&table.namespace)
+                    })
+                })
+                .filter(|table| matches(table.to_string()))
+                .collect();
+            response
+                .tables
+                .sort_by(|a, b| (&a.namespace, &a.name).cmp(&(&b.namespace, &b.name)));
+        }
+        Ok(response)
+    }
+
+    pub fn list_indexes(&self) -> Result<Vec<PropertyIndex>, Error> {
+        let db = lock_database(&self.database, "search");
+        let keys: Vec<String> = db.list_all_keys("PropertyIndexes")?;
+        Ok(db
+            .multi_get("PropertyIndexes", &keys)?
+            .into_iter()
+            .flatten()
+            .collect())
+    }
+
+    /// Declares an index on a property key and indexes the namespaces and
+    /// tables already carrying it, in one write.
+    #[instrument(skip_all, fields(key = %index.key))]
+    pub fn create_index(&self, index: &PropertyIndex) -> Result<(), Error> {
+        if index.key.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "An index needs a property key",
+            ));
+        }
+        let db = lock_database(&self.database, "search");
+        if db
+            .get::<String, PropertyIndex>("PropertyIndexes", &index.key)?
+            .is_some()
+        {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("Property {} is already indexed", index.key),
+            ));
+        }
+        let (namespaces, tables) = search_index::load_all(&db)?;
+        let declared = HashSet::from([index.key.clone()]);
+        let mut changes = vec![(
+            "PropertyIndexes",
+            db.encode_key(&index.key)?,
+            Some(db.encode_value("PropertyIndexes", index)?),
+        )];
+        for posting in search_index::build(&namespaces, &tables, &declared) {
+            if let SearchTerm::Property { .. } = posting.0 {
+                changes.push(search_index::put(&db, &posting)?);
+            }
+        }
+        db.apply_raw(&changes)
+    }
+
+    /// Drops the declaration of an index together with its entries.
+    #[instrument(skip_all, fields(key = %key))]
+    pub fn drop_index(&self, key: &str) -> Result<(), Error> {
+        let key = key.to_string();
+        let db = lock_database(&self.database, "search");
+        if db
+            .get::<String, PropertyIndex>("PropertyIndexes", &key)?
+            .is_none()
+        {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("Property {} is not indexed", key),
+            ));
+        }
+        let mut changes = vec![("PropertyIndexes", db.encode_key(&key)?, None)];
+        for posting in property_postings(&db, &key)? {
+            changes.push(("SearchIndex", db.encode_key(&posting)?, None));
+        }
+        db.apply_raw(&changes)
+    }
+
+    /// Rebuilds every index from the stored namespaces and tables and
+    /// returns the number of entries indexed.
+    #[instrument(skip_all)]
+    pub fn rebuild_indexes(&self) -> Result<usize, Error> {
+        let db = lock_database(&self.database, "search");
+        search_index::rebuild(&db)
+    }
+}
+
+fn tables(objects: Vec<CatalogObject>) -> Vec<TableIdent> {
+    objects
+        .into_iter()
+        .filter_map(|object| match object {
+            CatalogObject::Table(table) => Some(table),
+            CatalogObject::Namespace(_) => None,
+        })
+        .collect()
+}
+
+/// The text every name matching the glob starts with.
+fn literal_prefix(glob: &str) -> &str {
+    glob.find(['*', '?']).map_or(glob, |end| &glob[..end])
+}
+
+/// Translates a glob into an anchored regular expression.
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+fn compile(pattern: &str) -> Result<Regex, Error> {
+    RegexBuilder::new(pattern)
+        .size_limit(PATTERN_SIZE_LIMIT)
+        .build()
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid pattern {:?}: {}", pattern, e),
+            )
+        })
+}
+
+/// Parses a `key=value` property filter.
+fn parse_property(filter: &str) -> Result<(&str, &str), Error> {
+    filter.split_once('=').ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("Property filter {:?} is not of the form key=value", filter),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dto::column_data::ColumnData;
+    use crate::dto::rename_request::TableRenameRequest;
+    use crate::dto::table_data::TableCreation;
+    use crate::repository::namespace::NamespaceRepository;
+    use crate::repository::table::TableRepository;
+    use serde_json::{json, Map};
+    use tempfile::tempdir;
+
+    fn column(name: &str) -> ColumnData {
+        ColumnData {
+            name: name.to_string(),
+            aggregates: json!({}),
+            value_range: (0, 0),
+            is_strong_key: false,
+            is_weak_key: false,
+            primary_key_col_name: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_glob_to_regex() {
+        assert_eq!(glob_to_regex("sales.*"), r"^sales\..*$");
+        assert_eq!(glob_to_regex("t?"), "^t.$");
+    }
+
+    #[test]
+    fn test_search() {
+        let dir = tempdir().unwrap();
+        let db = Arc::new(Mutex::new(Database::open(dir.path()).unwrap()));
+        let namespaces = NamespaceRepository::new(db.clone());
+        let tables = TableRepository::new(db.clone());
+        let search = SearchRepository::new(db);
+        let sales = NamespaceIdent(vec!["sales".to_string()]);
+        let marketing = NamespaceIdent(vec!["marketing".to_string()]);
+        namespaces
+            .create_namespace(sales.clone(), Some(json!({"owner": "teamA"})), None)
+            .unwrap();
+        namespaces
+            .create_namespace(marketing.clone(), Some(json!({"owner": "teamB"})), None)
+            .unwrap();
+        for (namespace, name, columns) in [
+            (&sales, "orders", vec![column("id"), column("amount")]),
+            (&sales, "customers", vec![column("id")]),
+            (&marketing, "campaigns", vec![column("id")]),
+        ] {
+            tables
+                .create_table(
+                    namespace,
+                    &TableCreation {
+                        name: name.to_string(),
+                        columns: Some(columns),
+                        properties: None,
+                    },
+                    None,
+                )
+                .unwrap();
+        }
+        let table = |namespace: &NamespaceIdent, name: &str| {
+            TableIdent::new(namespace.clone(), name.to_string())
+        };
+
+        let query = SearchQuery {
+            name: Some("sales.*".to_string()),
+            ..Default::default()
+        };
+        let found = search.search(&query).unwrap();
+        assert_eq!(found.namespaces, vec![]);
+        assert_eq!(
+            found.tables,
+            vec![table(&sales, "customers"), table(&sales, "orders")]
+        );
+
+        let query = SearchQuery {
+            regex: Some(r"\.c[a-z]+$".to_string()),
+            column: Some("id".to_string()),
+            ..Default::default()
+        };
+        let found = search.search(&query).unwrap();
+        assert_eq!(
+            found.tables,
+            vec![table(&marketing, "campaigns"), table(&sales, "customers")]
+        );
+
+        let query = SearchQuery {
+            property: Some("owner=teamA".to_string()),
+            ..Default::default()
+        };
+        let found = search.search(&query).unwrap();
+        assert_eq!(found.namespaces, vec![sales.clone()]);
+        assert_eq!(found.tables.len(), 2);
+
+        // The index follows property changes, renames and drops.
+        let mut updates = Map::new();
+        updates.insert("owner".to_string(), json!("teamB"));
+        namespaces
+            .set_namespace_properties(sales.clone(), vec![], updates, None)
+            .unwrap();
+        let found = search.search(&query).unwrap();
+        assert_eq!(found, SearchResponse::default());
+
+        tables
+            .rename_table(
+                &TableRenameRequest {
+                    source: table(&sales, "orders"),
+                    destination: table(&sales, "purchases"),
+                },
+                None,
+            )
+            .unwrap();
+        tables
+            .drop_table(&sales, "customers".to_string(), None)
+            .unwrap();
+        let query = SearchQuery {
+            column: Some("amount".to_string()),
+            kind: Some(SearchKind::Table),
+            ..Default::default()
+        };
+        assert_eq!(
+            search.search(&query).unwrap().tables,
+            vec![table(&sales, "purchases")]
+        );
+        let query = SearchQuery {
+            column: Some("id".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(search.search(&query).unwrap().tables.len(), 2);
+
+        let query = SearchQuery {
+            regex: Some("(".to_string()),
+            ..Default::default()
+        };
+        let err = search.search(&query).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let query = SearchQuery {
+            property: Some("owner".to_string()),
+            ..Default::default()
+        };
+        let err = search.search(&query).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_property_indexes() {
+        let dir = tempdir().unwrap();
+        let db = Arc::new(Mutex::new(Database::open(dir.path()).unwrap()));
+        let namespaces = NamespaceRepository::new(db.clone());
+        let tables = TableRepository::new(db.clone());
+        let search = SearchRepository::new(db.clone());
+        let analytics = NamespaceIdent(vec!["analytics".to_string()]);
+        let sales = NamespaceIdent(vec!["sales".to_string()]);
+        namespaces
+            .create_namespace(analytics.clone(), Some(json!({"owner": "analytics"})), None)
+            .unwrap();
+        namespaces
+            .create_namespace(sales.clone(), Some(json!({"owner": "sales"})), None)
+            .unwrap();
+        let owner = |value: &str| SearchTerm::Property {
+            key: "owner".to_string(),
+            value: value.to_string(),
+        };
+        let indexed = |term: &SearchTerm| lookup(&db.lock().unwrap(), term).unwrap();
+
+        // Declaring an index covers the existing namespaces.
+        let index = PropertyIndex {
+            key: "owner".to_string(),
+        };
+        search.create_index(&index).unwrap();
+        assert_eq!(
+            search.create_index(&index).unwrap_err().kind(),
+            ErrorKind::AlreadyExists
+        );
+        assert_eq!(search.list_indexes().unwrap(), vec![index.clone()]);
+        assert_eq!(
+            indexed(&owner("analytics")),
+            vec![CatalogObject::Namespace(analytics.clone())]
+        );
+
+        // Table properties are indexed on create and on change.
+        tables
+            .create_table(
+                &sales,
+                &TableCreation {
+                    name: "events".to_string(),
+                    columns: None,
+                    properties: Some([("owner".to_string(), "analytics".to_string())].into()),
+                },
+                None,
+            )
+            .unwrap();
+        let events = TableIdent::new(sales.clone(), "events".to_string());
+        assert_eq!(indexed(&owner("analytics")).len(), 2);
+        let query = SearchQuery {
+            property: Some("owner=analytics".to_string()),
+            ..Default::default()
+        };
+        let found = search.search(&query).unwrap();
+        assert_eq!(found.namespaces, vec![analytics.clone()]);
+        assert_eq!(found.tables, vec![events.clone()]);
+
+        let version = tables
+            .set_properties(
+                &sales,
+                "events".to_string(),
+                &[],
+                &[("owner".to_string(), "sales".to_string())].into(),
+                Some(&[1]),
+            )
+            .unwrap();
+        assert_eq!(version, 2);
+        assert_eq!(indexed(&owner("analytics")).len(), 1);
+        let mut updates = Map::new();
+        updates.insert("owner".to_string(), json!("finance"));
+        namespaces
+            .set_namespace_properties(analytics.clone(), vec![], updates, None)
+            .unwrap();
+        assert_eq!(indexed(&owner("analytics")), vec![]);
+        assert_eq!(
+            indexed(&owner("sales")),
+            vec![
+                CatalogObject::Namespace(sales.clone()),
+                CatalogObject::Table(events.clone())
+            ]
+        );
+
+        // Dropping the index leaves searches to scan.
+        search.drop_index("owner").unwrap();
+        assert_eq!(
+            search.drop_index("owner").unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+        assert_eq!(indexed(&owner("sales")), vec![]);
+        let query = SearchQuery {
+            property: Some("owner=finance".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(search.search(&query).unwrap().namespaces, vec![analytics]);
+
+        search.create_index(&index).unwrap();
+        db.lock()
+            .unwrap()
+            .delete(
+                "SearchIndex",
+                &(owner("sales"), CatalogObject::Namespace(sales.clone())),
+            )
+            .unwrap();
+        // Three names and three owners.
+        assert_eq!(search.rebuild_indexes().unwrap(), 6);
+        assert_eq!(indexed(&owner("sales")).len(), 2);
+    }
+}
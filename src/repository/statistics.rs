@@ -0,0 +1,781 @@
+use crate::database::database::Database;
+use crate::dto::column_data::ColumnData;
+use crate::dto::data_file::FileRegistry;
+use crate::dto::expression::Expression;
+use crate::dto::histogram::ColumnHistogram;
+use crate::dto::namespace_data::NamespaceIdent;
+use crate::dto::statistics::{
+    ColumnSketch, ColumnStatistics, MergeHllSketchesRequest, TableStatistics,
+    TableStatisticsSummary, TableStatisticsUpdate,
+};
+use crate::dto::table_data::{Table, TableIdent};
+use crate::planning::selectivity;
+use crate::repository::version::check_version;
+use crate::sketch::hll::HyperLogLog;
+use crate::telemetry::metrics::lock_database;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind};
+use std::sync::{Arc, Mutex};
+use tracing::instrument;
+
+/// Sketch type of the HLL sketches merged by the catalog, stored as the
+/// precision byte followed by the registers.
+pub const HLL: &str = "hll";
+
+pub struct StatisticsRepository {
+    database: Arc<Mutex<Database>>,
+}
+
+impl StatisticsRepository {
+    pub fn new(database: Arc<Mutex<Database>>) -> Self {
+        Self { database }
+    }
+
+    #[instrument(skip_all, fields(namespace = %namespace, table = %table_name))]
+    pub fn update_statistics(
+        &self,
+        namespace: &NamespaceIdent,
+        table_name: String,
+        updates: &[TableStatisticsUpdate],
+        if_match: Option<&[u64]>,
+    ) -> Result<u64, Error> {
+        let table_id = TableIdent::new(namespace.clone(), table_name.clone());
+        let db = lock_database(&self.database, "statistics");
+        let table = table_for_write(&db, &table_id, if_match)?;
+
+        let mut statistics = db
+            .get::<TableIdent, TableStatistics>("TableStatistics", &table_id)?
+            .unwrap_or_default();
+        for update in updates {
+            match update {
+                TableStatisticsUpdate::SetSnapshotStatistics { statistics: s } => {
+                    statistics
+                        .snapshots
+                        .retain(|existing| existing.snapshot_id != s.snapshot_id);
+                    statistics.snapshots.push(s.clone());
+                }
+                TableStatisticsUpdate::SetStatistics { statistics: s } => {
+                    statistics
+                        .statistics_files
+                        .retain(|existing| existing.snapshot_id != s.snapshot_id);
+                    statistics.statistics_files.push(s.clone());
+                }
+                TableStatisticsUpdate::RemoveStatistics { snapshot_id } => {
+                    statistics
+                        .statistics_files
+                        .retain(|existing| existing.snapshot_id != *snapshot_id);
+                }
+                TableStatisticsUpdate::SetPartitionStatistics {
+                    partition_statistics: s,
+                } => {
+                    statistics
+                        .partition_statistics_files
+                        .retain(|existing| existing.snapshot_id != s.snapshot_id);
+                    statistics.partition_statistics_files.push(s.clone());
+                }
+                TableStatisticsUpdate::RemovePartitionStatistics { snapshot_id } => {
+                    statistics
+                        .partition_statistics_files
+                        .retain(|existing| existing.snapshot_id != *snapshot_id);
+                }
+            }
+        }
+        commit(&db, table, "TableStatistics", &statistics)
+    }
+
+    /// Merges the reported snapshot statistics, the NDV estimates of the
+    /// attached statistics files and the file registry into one summary.
+    #[instrument(skip_all, fields(namespace = %namespace, table = %table_name))]
+    pub fn load_statistics(
+        &self,
+        namespace: &NamespaceIdent,
+        table_name: String,
+    ) -> Result<TableStatisticsSummary, Error> {
+        let table_id = TableIdent::new(namespace.clone(), table_name.clone());
+        let db = lock_database(&self.database, "statistics");
+        check_table_exists(&db, &table_id)?;
+
+        let statistics = db
+            .get::<TableIdent, TableStatistics>("TableStatistics", &table_id)?
+            .unwrap_or_default();
+        let registry = db
+            .get::<TableIdent, FileRegistry>("TableFiles", &table_id)?
+            .unwrap_or_default();
+
+        // Prefer the current snapshot, fall back to the latest reported one.
+        let snapshot_id = registry.current_snapshot_id.or_else(|| {
+            statistics
+                .snapshots
+                .iter()
+                .map(|s| s.snapshot_id)
+                .chain(statistics.statistics_files.iter().map(|f| f.snapshot_id))
+                .max()
+        });
+        let snapshot = snapshot_id.and_then(|id| {
+            statistics
+                .snapshots
+                .iter()
+                .find(|s| s.snapshot_id == id)
+                .cloned()
+        });
+
+        let mut columns: BTreeMap<String, ColumnStatistics> = snapshot
+            .as_ref()
+            .map(|s| s.columns.clone())
+            .unwrap_or_default();
+        for file in statistics
+            .statistics_files
+            .iter()
+            .filter(|f| Some(f.snapshot_id) == snapshot_id)
+        {
+            for blob in file.blob_metadata.iter().filter(|b| b.columns.len() == 1) {
+                if let Some(ndv) = blob.properties.get("ndv").and_then(|n| n.parse().ok()) {
+                    columns.entry(blob.columns[0].clone()).or_default().ndv = Some(ndv);
+                }
+            }
+        }
+
+        // The HLL sketches merged across commits carry the running estimate.
+        // One that does not decode, stored before sketches were checked,
+        // leaves its column to the other sources.
+        let sketches = db
+            .get::<TableIdent, BTreeMap<String, ColumnSketch>>("TableSketches", &table_id)?
+            .unwrap_or_default();
+        for (column, sketch) in sketches.iter().filter(|(_, s)| s.sketch_type == HLL) {
+            if let Ok(hll) = HyperLogLog::from_bytes(&sketch.data) {
+                columns.entry(column.clone()).or_default().ndv = Some(hll.estimate());
+            }
+        }
+
+        let live_files = snapshot_id.map(|id| registry.files_in(id));
+        let row_count = snapshot.as_ref().map(|s| s.row_count).or_else(|| {
+            live_files
+                .as_ref()
+                .map(|files| files.iter().map(|f| f.record_count).sum())
+        });
+        let total_size_in_bytes = snapshot
+            .as_ref()
+            .map(|s| s.total_size_in_bytes)
+            .or_else(|| {
+                live_files
+                    .as_ref()
+                    .map(|files| files.iter().map(|f| f.file_size_in_bytes).sum())
+            });
+
+        Ok(TableStatisticsSummary {
+            table: table_id,
+            snapshot_id,
+            row_count,
+            total_size_in_bytes,
+            columns: columns
+                .into_iter()
+                .map(|(name, column)| to_column_data(name, column))
+                .collect(),
+            statistics_files: statistics.statistics_files,
+            partition_statistics_files: statistics.partition_statistics_files,
+        })
+    }
+
+    /// Stores the sketch of a column, replacing an earlier one. HLL
+    /// sketches must decode, since statistics and merges read them.
+    #[instrument(skip_all, fields(namespace = %namespace, table = %table_name, column = %column))]
+    pub fn set_sketch(
+        &self,
+        namespace: &NamespaceIdent,
+        table_name: String,
+        column: String,
+        sketch: ColumnSketch,
+        if_match: Option<&[u64]>,
+    ) -> Result<u64, Error> {
+        if sketch.sketch_type == HLL {
+            HyperLogLog::from_bytes(&sketch.data).map_err(|e| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Invalid HLL sketch for column {}: {}", column, e),
+                )
+            })?;
+        }
+        let table_id = TableIdent::new(namespace.clone(), table_name.clone());
+        let db = lock_database(&self.database, "statistics");
+        let table = table_for_write(&db, &table_id, if_match)?;
+
+        let mut sketches = db
+            .get::<TableIdent, BTreeMap<String, ColumnSketch>>("TableSketches", &table_id)?
+            .unwrap_or_default();
+        sketches.insert(column, sketch);
+        commit(&db, table, "TableSketches", &sketches)
+    }
+
+    /// Merges the HLL sketches reported by one writer into the stored sketch
+    /// of each column and returns the merged NDV estimates with the new
+    /// version of the table.
+    #[instrument(skip_all, fields(namespace = %namespace, table = %table_name))]
+    pub fn merge_hll_sketches(
+        &self,
+        namespace: &NamespaceIdent,
+        table_name: String,
+        request: &MergeHllSketchesRequest,
+        if_match: Option<&[u64]>,
+    ) -> Result<(BTreeMap<String, u64>, u64), Error> {
+        let table_id = TableIdent::new(namespace.clone(), table_name.clone());
+        let db = lock_database(&self.database, "statistics");
+        let table = table_for_write(&db, &table_id, if_match)?;
+
+        let mut sketches = db
+            .get::<TableIdent, BTreeMap<String, ColumnSketch>>("TableSketches", &table_id)?
+            .unwrap_or_default();
+        let mut estimates = BTreeMap::new();
+        for (column, reported) in &request.columns {
+            let mut merged =
+                HyperLogLog::from_registers(reported.precision, reported.registers.clone())?;
+            if let Some(existing) = sketches.get(column) {
+                if existing.sketch_type != HLL {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "Column {} has a {} sketch, cannot merge an HLL sketch into it",
+                            column, existing.sketch_type
+                        ),
+                    ));
+                }
+                merged.merge(&HyperLogLog::from_bytes(&existing.data)?);
+            }
+            estimates.insert(column.clone(), merged.estimate());
+            sketches.insert(
+                column.clone(),
+                ColumnSketch {
+                    sketch_type: HLL.to_string(),
+                    snapshot_id: request.snapshot_id,
+                    data: merged.to_bytes(),
+                },
+            );
+        }
+        let version = commit(&db, table, "TableSketches", &sketches)?;
+        Ok((estimates, version))
+    }
+
+    /// Stores the histograms of the given columns, replacing earlier ones.
+    #[instrument(skip_all, fields(namespace = %namespace, table = %table_name))]
+    pub fn set_histograms(
+        &self,
+        namespace: &NamespaceIdent,
+        table_name: String,
+        columns: &BTreeMap<String, ColumnHistogram>,
+        if_match: Option<&[u64]>,
+    ) -> Result<u64, Error> {
+        for (column, histogram) in columns {
+            selectivity::validate(column, histogram)?;
+        }
+        let table_id = TableIdent::new(namespace.clone(), table_name.clone());
+        let db = lock_database(&self.database, "statistics");
+        let table = table_for_write(&db, &table_id, if_match)?;
+
+        let mut histograms = db
+            .get::<TableIdent, BTreeMap<String, ColumnHistogram>>("TableHistograms", &table_id)?
+            .unwrap_or_default();
+        histograms.extend(columns.clone());
+        commit(&db, table, "TableHistograms", &histograms)
+    }
+
+    #[instrument(skip_all, fields(namespace = %namespace, table = %table_name))]
+    pub fn load_histograms(
+        &self,
+        namespace: &NamespaceIdent,
+        table_name: String,
+    ) -> Result<BTreeMap<String, ColumnHistogram>, Error> {
+        let table_id = TableIdent::new(namespace.clone(), table_name.clone());
+        let db = lock_database(&self.database, "statistics");
+        check_table_exists(&db, &table_id)?;
+
+        Ok(db
+            .get::<TableIdent, BTreeMap<String, ColumnHistogram>>("TableHistograms", &table_id)?
+            .unwrap_or_default())
+    }
+
+    #[instrument(skip_all, fields(namespace = %namespace, table = %table_name))]
+    pub fn estimate_selectivity(
+        &self,
+        namespace: &NamespaceIdent,
+        table_name: String,
+        filter: &Expression,
+    ) -> Result<f64, Error> {
+        let histograms = self.load_histograms(namespace, table_name)?;
+        Ok(selectivity::estimate(filter, &histograms))
+    }
+
+    #[instrument(skip_all, fields(namespace = %namespace, table = %table_name, column = %column))]
+    pub fn load_sketch(
+        &self,
+        namespace: &NamespaceIdent,
+        table_name: String,
+        column: &str,
+    ) -> Result<Option<ColumnSketch>, Error> {
+        let table_id = TableIdent::new(namespace.clone(), table_name.clone());
+        let db = lock_database(&self.database, "statistics");
+        check_table_exists(&db, &table_id)?;
+
+        let sketches = db
+            .get::<TableIdent, BTreeMap<String, ColumnSketch>>("TableSketches", &table_id)?
+            .unwrap_or_default();
+        Ok(sketches.get(column).cloned())
+    }
+}
+
+fn check_table_exists(db: &Database, table_id: &TableIdent) -> Result<(), Error> {
+    match db.get::<TableIdent, Table>("TableData", table_id)? {
+        Some(_) => Ok(()),
+        None => Err(Error::new(
+            ErrorKind::NotFound,
+            format!("Table {} not found", table_id.name),
+        )),
+    }
+}
+
+/// Loads the table a statistics write goes to, provided it is at one of
+/// the `if_match` versions when those are given.
+fn table_for_write(
+    db: &Database,
+    table_id: &TableIdent,
+    if_match: Option<&[u64]>,
+) -> Result<Table, Error> {
+    let table = db
+        .get::<TableIdent, Table>("TableData", table_id)?
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("Table {} not found", table_id.name),
+            )
+        })?;
+    check_version(if_match, table.version)?;
+    Ok(table)
+}
+
+/// Stores the table's entry in `cf` and bumps the table's version in the
+/// same batch, returning the new version.
+fn commit<V: Serialize>(
+    db: &Database,
+    mut table: Table,
+    cf: &str,
+    value: &V,
+) -> Result<u64, Error> {
+    table.version += 1;
+    let key = db.encode_key(&table.id)?;
+    db.apply_raw(&[
+        (cf, key.clone(), Some(db.encode_value(cf, value)?)),
+        (
+            "TableData",
+            key,
+            Some(db.encode_value("TableData", &table)?),
+        ),
+    ])?;
+    Ok(table.version)
+}
+
+fn to_column_data(name: String, column: ColumnStatistics) -> ColumnData {
+    ColumnData {
+        name,
+        aggregates: json!({
+            "ndv": column.ndv,
+            "null_count": column.null_count,
+        }),
+        // An unknown range covers the whole domain.
+        value_range: column.value_range.unwrap_or((i32::MIN, i32::MAX)),
+        is_strong_key: false,
+        is_weak_key: false,
+        primary_key_col_name: String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dto::data_file::RegisterDataFilesRequest;
+    use crate::dto::statistics::{HllSketch, UpdateTableStatisticsRequest};
+    use crate::dto::table_data::TableCreation;
+    use crate::repository::namespace::NamespaceRepository;
+    use crate::repository::table::TableRepository;
+    use crate::repository::version::is_version_mismatch;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_statistics_repository() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let db = Arc::new(Mutex::new(db));
+        let repo = StatisticsRepository::new(db.clone());
+
+        let namespace_ident = NamespaceIdent(vec!["test".to_string()]);
+        NamespaceRepository::new(db.clone())
+            .create_namespace(namespace_ident.clone(), None, None)
+            .unwrap();
+        let table_repo = TableRepository::new(db.clone());
+        table_repo
+            .create_table(
+                &namespace_ident,
+                &TableCreation {
+                    name: "table1".to_string(),
+                    columns: None,
+                    properties: None,
+                },
+                None,
+            )
+            .unwrap();
+
+        // Test load_statistics falls back to the file registry
+        let request: RegisterDataFilesRequest = serde_json::from_value(json!({
+            "added_files": [
+                {"file_path": "a", "record_count": 10, "file_size_in_bytes": 100},
+                {"file_path": "b", "record_count": 5, "file_size_in_bytes": 50}
+            ]
+        }))
+        .unwrap();
+        let snapshot_id = table_repo
+            .register_data_files(&namespace_ident, "table1".to_string(), &request, None)
+            .unwrap();
+        let summary = repo
+            .load_statistics(&namespace_ident, "table1".to_string())
+            .unwrap();
+        assert_eq!(summary.snapshot_id, Some(snapshot_id));
+        assert_eq!(summary.row_count, Some(15));
+        assert_eq!(summary.total_size_in_bytes, Some(150));
+        assert!(summary.columns.is_empty());
+
+        // Test update_statistics
+        let request: UpdateTableStatisticsRequest = serde_json::from_value(json!({
+            "updates": [
+                {
+                    "action": "set-snapshot-statistics",
+                    "statistics": {
+                        "snapshot_id": snapshot_id,
+                        "row_count": 20,
+                        "total_size_in_bytes": 200,
+                        "columns": {"id": {"ndv": 18, "value_range": [1, 20]}, "name": {"ndv": 3}}
+                    }
+                },
+                {
+                    "action": "set-statistics",
+                    "statistics": {
+                        "snapshot_id": snapshot_id,
+                        "statistics_path": "stats.puffin",
+                        "file_size_in_bytes": 10,
+                        "file_footer_size_in_bytes": 2,
+                        "blob_metadata": [{
+                            "type": "apache-datasketches-theta-v1",
+                            "snapshot_id": snapshot_id,
+                            "sequence_number": 1,
+                            "columns": ["name"],
+                            "properties": {"ndv": "4"}
+                        }]
+                    }
+                }
+            ]
+        }))
+        .unwrap();
+        repo.update_statistics(
+            &namespace_ident,
+            "table1".to_string(),
+            &request.updates,
+            None,
+        )
+        .unwrap();
+
+        let summary = repo
+            .load_statistics(&namespace_ident, "table1".to_string())
+            .unwrap();
+        assert_eq!(summary.row_count, Some(20));
+        assert_eq!(summary.total_size_in_bytes, Some(200));
+        assert_eq!(summary.statistics_files.len(), 1);
+        assert_eq!(summary.columns[0].name, "id");
+        assert_eq!(summary.columns[0].aggregates["ndv"], json!(18));
+        assert_eq!(summary.columns[0].value_range, (1, 20));
+        assert_eq!(summary.columns[1].name, "name");
+        assert_eq!(summary.columns[1].aggregates["ndv"], json!(4));
+
+        // Test remove-statistics
+        repo.update_statistics(
+            &namespace_ident,
+            "table1".to_string(),
+            &[TableStatisticsUpdate::RemoveStatistics { snapshot_id }],
+            None,
+        )
+        .unwrap();
+        let summary = repo
+            .load_statistics(&namespace_ident, "table1".to_string())
+            .unwrap();
+        assert!(summary.statistics_files.is_empty());
+        assert_eq!(summary.columns[1].aggregates["ndv"], json!(3));
+
+        // Test set_sketch rejects an HLL sketch that does not decode
+        let err = repo
+            .set_sketch(
+                &namespace_ident,
+                "table1".to_string(),
+                "id".to_string(),
+                ColumnSketch {
+                    sketch_type: "hll".to_string(),
+                    snapshot_id: Some(snapshot_id),
+                    data: vec![1, 2, 3],
+                },
+                None,
+            )
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+        // Test set_sketch and load_sketch
+        let sketch = ColumnSketch {
+            sketch_type: "hll".to_string(),
+            snapshot_id: Some(snapshot_id),
+            data: HyperLogLog::new(4).unwrap().to_bytes(),
+        };
+        repo.set_sketch(
+            &namespace_ident,
+            "table1".to_string(),
+            "id".to_string(),
+            sketch.clone(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            repo.load_sketch(&namespace_ident, "table1".to_string(), "id")
+                .unwrap(),
+            Some(sketch.clone())
+        );
+        assert_eq!(
+            repo.load_sketch(&namespace_ident, "table1".to_string(), "name")
+                .unwrap(),
+            None
+        );
+
+        // Test load_statistics skips a stored HLL sketch that does not decode
+        let corrupt = ColumnSketch {
+            data: vec![1, 2, 3],
+            ..sketch
+        };
+        db.lock()
+            .unwrap()
+            .update(
+                "TableSketches",
+                &TableIdent::new(namespace_ident.clone(), "table1".to_string()),
+                &BTreeMap::from([("id".to_string(), corrupt)]),
+            )
+            .unwrap();
+        let summary = repo
+            .load_statistics(&namespace_ident, "table1".to_string())
+            .unwrap();
+        assert_eq!(summary.columns[1].aggregates["ndv"], json!(3));
+
+        // Test statistics are dropped together with the table
+        table_repo
+            .drop_table(&namespace_ident, "table1".to_string(), None)
+            .unwrap();
+        let db = db.lock().unwrap();
+        let table_id = TableIdent::new(namespace_ident.clone(), "table1".to_string());
+        assert!(db
+            .get::<TableIdent, TableStatistics>("TableStatistics", &table_id)
+            .unwrap()
+            .is_none());
+        assert!(db
+            .get::<TableIdent, BTreeMap<String, ColumnSketch>>("TableSketches", &table_id)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_merge_hll_sketches() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let db = Arc::new(Mutex::new(db));
+        let repo = StatisticsRepository::new(db.clone());
+
+        let namespace_ident = NamespaceIdent(vec!["test".to_string()]);
+        NamespaceRepository::new(db.clone())
+            .create_namespace(namespace_ident.clone(), None, None)
+            .unwrap();
+        TableRepository::new(db.clone())
+            .create_table(
+                &namespace_ident,
+                &TableCreation {
+                    name: "table1".to_string(),
+                    columns: None,
+                    properties: None,
+                },
+                None,
+            )
+            .unwrap();
+
+        // Two writers each report a sketch with a single distinct value
+        // landing in different registers.
+        let mut first = vec![0; 16];
+        first[0] = 1;
+        let mut second = vec![0; 16];
+        second[5] = 1;
+        for registers in [first.clone(), second] {
+            let request = MergeHllSketchesRequest {
+                snapshot_id: None,
+                columns: BTreeMap::from([(
+                    "id".to_string(),
+                    HllSketch {
+                        precision: 4,
+                        registers,
+                    },
+                )]),
+            };
+            repo.merge_hll_sketches(&namespace_ident, "table1".to_string(), &request, None)
+                .unwrap();
+        }
+
+        // Every merge is a change of the table, which conditional merges
+        // must name the version of.
+        let request = MergeHllSketchesRequest {
+            snapshot_id: None,
+            columns: BTreeMap::new(),
+        };
+        let err = repo
+            .merge_hll_sketches(&namespace_ident, "table1".to_string(), &request, Some(&[2]))
+            .unwrap_err();
+        assert!(is_version_mismatch(&err));
+        let (_, version) = repo
+            .merge_hll_sketches(&namespace_ident, "table1".to_string(), &request, Some(&[3]))
+            .unwrap();
+        assert_eq!(version, 4);
+
+        let stored = repo
+            .load_sketch(&namespace_ident, "table1".to_string(), "id")
+            .unwrap()
+            .unwrap();
+        let merged = HyperLogLog::from_bytes(&stored.data).unwrap();
+        let mut expected = first;
+        expected[5] = 1;
+        assert_eq!(merged, HyperLogLog::from_registers(4, expected).unwrap());
+
+        // Test the merged estimate is exposed through load_statistics
+        let summary = repo
+            .load_statistics(&namespace_ident, "table1".to_string())
+            .unwrap();
+        assert_eq!(summary.columns[0].name, "id");
+        assert_eq!(summary.columns[0].aggregates["ndv"], json!(2));
+
+        // Test merging into a sketch of another type and invalid sketches
+        repo.set_sketch(
+            &namespace_ident,
+            "table1".to_string(),
+            "name".to_string(),
+            ColumnSketch {
+                sketch_type: "theta".to_string(),
+                snapshot_id: None,
+                data: vec![1],
+            },
+            None,
+        )
+        .unwrap();
+        for (column, precision, registers) in [
+            ("name", 4, vec![0; 16]),
+            ("id", 4, vec![0; 3]),
+            ("id", 2, vec![0; 4]),
+        ] {
+            let request = MergeHllSketchesRequest {
+                snapshot_id: None,
+                columns: BTreeMap::from([(
+                    column.to_string(),
+                    HllSketch {
+                        precision,
+                        registers,
+                    },
+                )]),
+            };
+            let err = repo
+                .merge_hll_sketches(&namespace_ident, "table1".to_string(), &request, None)
+                .unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn test_histograms() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let db = Arc::new(Mutex::new(db));
+        let repo = StatisticsRepository::new(db.clone());
+
+        let namespace_ident = NamespaceIdent(vec!["test".to_string()]);
+        NamespaceRepository::new(db.clone())
+            .create_namespace(namespace_ident.clone(), None, None)
+            .unwrap();
+        TableRepository::new(db.clone())
+            .create_table(
+                &namespace_ident,
+                &TableCreation {
+                    name: "table1".to_string(),
+                    columns: None,
+                    properties: None,
+                },
+                None,
+            )
+            .unwrap();
+
+        // Test set_histograms and load_histograms
+        let columns: BTreeMap<String, ColumnHistogram> = serde_json::from_value(json!({
+            "id": {"type": "int", "bounds": [0, 50, 100]},
+            "price": {"type": "float", "bounds": [0.0, 1.5], "null_fraction": 0.5}
+        }))
+        .unwrap();
+        repo.set_histograms(&namespace_ident, "table1".to_string(), &columns, None)
+            .unwrap();
+        assert_eq!(
+            repo.load_histograms(&namespace_ident, "table1".to_string())
+                .unwrap(),
+            columns
+        );
+
+        // Test estimate_selectivity
+        let filter: Expression = serde_json::from_value(json!({
+            "type": "and",
+            "left": {"type": "lt", "term": "id", "value": 25},
+            "right": {"type": "not-null", "term": "price"}
+        }))
+        .unwrap();
+        let selectivity = repo
+            .estimate_selectivity(&namespace_ident, "table1".to_string(), &filter)
+            .unwrap();
+        assert!((selectivity - 0.125).abs() < 1e-9);
+
+        // Test set_histograms with an invalid histogram keeps the old ones
+        let invalid: BTreeMap<String, ColumnHistogram> = serde_json::from_value(json!({
+            "id": {"type": "int", "bounds": [100, 0]}
+        }))
+        .unwrap();
+        let err = repo
+            .set_histograms(&namespace_ident, "table1".to_string(), &invalid, None)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert_eq!(
+            repo.load_histograms(&namespace_ident, "table1".to_string())
+                .unwrap(),
+            columns
+        );
+    }
+
+    #[test]
+    fn test_statistics_repository_negative() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let db = Arc::new(Mutex::new(db));
+        let repo = StatisticsRepository::new(db.clone());
+
+        let namespace_ident = NamespaceIdent(vec!["test".to_string()]);
+        let err = repo
+            .load_statistics(&namespace_ident, "non_existent".to_string())
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        let err = repo
+            .update_statistics(&namespace_ident, "non_existent".to_string(), &[], None)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        let err = repo
+            .load_sketch(&namespace_ident, "non_existent".to_string(), "id")
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+    }
+}
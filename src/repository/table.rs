@@ -1,25 +1,60 @@
+use crate::config::config::CasePolicy;
 use crate::database::database::Database;
+use crate::database::search_index::{self, IndexUpdate};
+use crate::dto::batch::BatchTableCreation;
+use crate::dto::column_data::ColumnData;
+use crate::dto::constraint::{TableConstraints, TableConstraintsEntry};
+use crate::dto::data_file::{FileRegistry, RegisterDataFilesRequest, RegisteredDataFile};
 use crate::dto::namespace_data::{NamespaceData, NamespaceIdent};
 use crate::dto::rename_request::TableRenameRequest;
+use crate::dto::scan_plan::{PlanTableScanRequest, PlanTableScanResponse};
 use crate::dto::table_data::{Table, TableCreation, TableIdent, TableMetadata};
+use crate::planning::evaluator;
+use crate::repository::quota::{Owned, Owner, QuotaUpdate};
+use crate::repository::version::{check_version, first_version, retire, VersionKey};
+use crate::telemetry::metrics::lock_database;
+use serde_json::Value;
+use std::collections::HashMap;
 use std::io::{Error, ErrorKind};
 use std::sync::{Arc, Mutex};
+use tracing::instrument;
 use uuid::Uuid;
 
+/// Column families holding per-table data keyed by `TableIdent`, which
+/// follows the table when it is renamed and goes away when it is dropped.
+pub const TABLE_DATA_CFS: [&str; 4] = [
+    "TableFiles",
+    "TableStatistics",
+    "TableSketches",
+    "TableHistograms",
+];
+
 pub struct TableRepository {
     database: Arc<Mutex<Database>>,
+    case: CasePolicy,
 }
 
 impl TableRepository {
     pub fn new(database: Arc<Mutex<Database>>) -> Self {
-        Self { database }
+        Self {
+            database,
+            case: CasePolicy::default(),
+        }
     }
 
+    /// Makes new table names follow `case`, which by default they do not
+    /// have to.
+    pub fn with_case_policy(mut self, case: CasePolicy) -> Self {
+        self.case = case;
+        self
+    }
+
+    #[instrument(skip_all, fields(namespace = %namespace))]
     pub fn list_all_tables(
         &self,
         namespace: &NamespaceIdent,
     ) -> Result<Option<Vec<TableIdent>>, Error> {
-        let db = self.database.lock().unwrap();
+        let db = lock_database(&self.database, "table");
         let _: NamespaceData = match db.get("NamespaceData", namespace)? {
             Some(data) => data,
             None => {
@@ -32,12 +67,15 @@ impl TableRepository {
         db.get::<NamespaceIdent, Vec<TableIdent>>("TableNamespaceMap", namespace)
     }
 
+    /// Creates the table, counted against the quota of `owner` when given.
+    #[instrument(skip_all, fields(namespace = %namespace, table = %table_creation.name))]
     pub fn create_table(
         &self,
         namespace: &NamespaceIdent,
         table_creation: &TableCreation,
+        owner: Option<&Owner>,
     ) -> Result<(), Error> {
-        let db = self.database.lock().unwrap();
+        let db = lock_database(&self.database, "table");
         let _: NamespaceData = match db.get("NamespaceData", namespace)? {
             Some(data) => data,
             None => {
@@ -51,11 +89,15 @@ impl TableRepository {
         let table_id = TableIdent::new(namespace.clone(), table_creation.name.clone());
         let table_uuid = Uuid::new_v4().to_string();
 
-        let table_metadata = TableMetadata { table_uuid };
+        let table_metadata = TableMetadata {
+            table_uuid,
+            columns: table_creation.columns.clone(),
+            constraints: TableConstraints::default(),
+            properties: table_creation.properties.clone(),
+        };
 
         let mut tables = db
-            .get::<NamespaceIdent, Vec<TableIdent>>("TableNamespaceMap", namespace)
-            .unwrap()
+            .get::<NamespaceIdent, Vec<TableIdent>>("TableNamespaceMap", namespace)?
             .unwrap_or_else(|| vec![]);
 
         if tables.contains(&table_id) {
@@ -68,36 +110,161 @@ impl TableRepository {
                 ),
             ));
         }
+        if let Some(other) = clashing(self.case, &table_id, &tables) {
+            return Err(clash(&table_id, other));
+        }
 
-        db.insert(
-            "TableData",
-            &table_id,
-            &Table {
-                id: table_id.clone(),
-                metadata: table_metadata,
-            },
-        )?;
+        let table = Table {
+            id: table_id.clone(),
+            metadata: table_metadata,
+            version: first_version(&db, &VersionKey::Table(table_id.clone()))?,
+        };
         tables.push(table_id.clone());
-        let r_val = db.insert("TableNamespaceMap", namespace, &tables);
-        r_val
+        let mut quota = QuotaUpdate::new(&db, owner);
+        quota.created(Owned::Table(table_id.clone()))?;
+        let mut changes = vec![
+            (
+                "TableData",
+                db.encode_key(&table_id)?,
+                Some(db.encode_value("TableData", &table)?),
+            ),
+            (
+                "TableNamespaceMap",
+                db.encode_key(namespace)?,
+                Some(db.encode_value("TableNamespaceMap", &tables)?),
+            ),
+        ];
+        changes.extend(quota.changes()?);
+        let mut index = IndexUpdate::new(&db)?;
+        index.table(None, Some(&table))?;
+        index.commit(changes)
     }
 
+    /// Creates every table in one write. With `atomic` the first failure
+    /// is returned and nothing is written; otherwise each table gets its own
+    /// result and the others are still created. The tables created must fit
+    /// in the quota of `owner` as a whole.
+    #[instrument(skip_all, fields(tables = tables.len()))]
+    pub fn create_tables(
+        &self,
+        tables: &[BatchTableCreation],
+        atomic: bool,
+        owner: Option<&Owner>,
+    ) -> Result<Vec<Result<(), Error>>, Error> {
+        let db = lock_database(&self.database, "table");
+        // Table lists of the namespaces the batch touches, None for those
+        // that do not exist.
+        let mut listed: HashMap<NamespaceIdent, Option<Vec<TableIdent>>> = HashMap::new();
+        for creation in tables {
+            if !listed.contains_key(&creation.namespace) {
+                let exists = db
+                    .get::<NamespaceIdent, NamespaceData>("NamespaceData", &creation.namespace)?
+                    .is_some();
+                let list = exists.then(|| {
+                    db.get::<NamespaceIdent, Vec<TableIdent>>(
+                        "TableNamespaceMap",
+                        &creation.namespace,
+                    )
+                });
+                listed.insert(
+                    creation.namespace.clone(),
+                    list.transpose()?.map(Option::unwrap_or_default),
+                );
+            }
+        }
+
+        let mut results = Vec::with_capacity(tables.len());
+        let mut index = IndexUpdate::new(&db)?;
+        let mut quota = QuotaUpdate::new(&db, owner);
+        let mut changes = Vec::new();
+        for creation in tables {
+            let table_id = creation.ident();
+            let result = match listed.get_mut(&creation.namespace).and_then(Option::as_mut) {
+                None => Err(Error::new(
+                    ErrorKind::NotFound,
+                    format!("Namespace {} not found", creation.namespace),
+                )),
+                Some(list) if list.contains(&table_id) => Err(Error::new(
+                    ErrorKind::AlreadyExists,
+                    format!("Table {} already exists", table_id),
+                )),
+                Some(list) => match clashing(self.case, &table_id, list) {
+                    Some(other) => Err(clash(&table_id, other)),
+                    None => {
+                        let table = Table {
+                            id: table_id.clone(),
+                            metadata: TableMetadata {
+                                table_uuid: Uuid::new_v4().to_string(),
+                                columns: creation.table.columns.clone(),
+                                constraints: TableConstraints::default(),
+                                properties: creation.table.properties.clone(),
+                            },
+                            version: first_version(&db, &VersionKey::Table(table_id.clone()))?,
+                        };
+                        index.table(None, Some(&table))?;
+                        quota.created(Owned::Table(table_id.clone()))?;
+                        changes.push((
+                            "TableData",
+                            db.encode_key(&table_id)?,
+                            Some(db.encode_value("TableData", &table)?),
+                        ));
+                        list.push(table_id);
+                        Ok(())
+                    }
+                },
+            };
+            match result {
+                Err(e) if atomic => return Err(e),
+                result => results.push(result),
+            }
+        }
+        for (namespace, list) in &listed {
+            if let Some(list) = list {
+                changes.push((
+                    "TableNamespaceMap",
+                    db.encode_key(namespace)?,
+                    Some(db.encode_value("TableNamespaceMap", list)?),
+                ));
+            }
+        }
+        changes.extend(quota.changes()?);
+        index.commit(changes)?;
+        Ok(results)
+    }
+
+    /// Loads many tables at once, in the order of `table_ids`.
+    #[instrument(skip_all, fields(tables = table_ids.len()))]
+    pub fn load_tables(&self, table_ids: &[TableIdent]) -> Result<Vec<Option<Table>>, Error> {
+        let db = lock_database(&self.database, "table");
+        db.multi_get("TableData", table_ids)
+    }
+
+    #[instrument(skip_all, fields(namespace = %namespace, table = %table_name))]
     pub fn load_table(
         &self,
         namespace: &NamespaceIdent,
         table_name: String,
     ) -> Result<Option<Table>, Error> {
         let table_id = TableIdent::new(namespace.clone(), table_name.clone());
-        let db = self.database.lock().unwrap();
+        let db = lock_database(&self.database, "table");
         // If the table is in the namespace, get the table data
         db.get::<TableIdent, Table>("TableData", &table_id)
     }
 
-    pub fn drop_table(&self, namespace: &NamespaceIdent, table_name: String) -> Result<(), Error> {
-        let db = self.database.lock().unwrap();
+    /// Drops the table, provided it is at one of the `if_match` versions
+    /// when those are given and no foreign key of another table references
+    /// it, and returns it to its owner's quota.
+    #[instrument(skip_all, fields(namespace = %namespace, table = %table_name))]
+    pub fn drop_table(
+        &self,
+        namespace: &NamespaceIdent,
+        table_name: String,
+        if_match: Option<&[u64]>,
+    ) -> Result<(), Error> {
+        let db = lock_database(&self.database, "table");
         let table_id = TableIdent::new(namespace.clone(), table_name.clone());
 
-        let _: Table = match db.get::<TableIdent, Table>("TableData", &table_id)? {
+        let table: Table = match db.get::<TableIdent, Table>("TableData", &table_id)? {
             Some(data) => data,
             None => {
                 return Err(std::io::Error::new(
@@ -106,16 +273,46 @@ impl TableRepository {
                 ))
             }
         };
+        check_version(if_match, table.version)?;
+        if let Some(referencing) = search_index::referencing(&db, &table_id)?.first() {
+            return Err(Error::new(
+                ErrorKind::ResourceBusy,
+                format!(
+                    "Table {} is referenced by a foreign key of table {}",
+                    table_id, referencing
+                ),
+            ));
+        }
 
-        db.delete("TableData", &table_id)?;
-        let mut tables = db
-            .get::<NamespaceIdent, Vec<TableIdent>>("TableNamespaceMap", namespace)
-            .unwrap()
-            .unwrap();
-        tables.retain(|id| id.name != table_name);
-        db.insert("TableNamespaceMap", namespace, &tables)
+        let key = db.encode_key(&table_id)?;
+        let mut changes = vec![
+            ("TableData", key.clone(), None),
+            retire(&db, &VersionKey::Table(table_id.clone()), table.version)?,
+        ];
+        for cf in TABLE_DATA_CFS {
+            changes.push((cf, key.clone(), None));
+        }
+        // A table missing from its namespace's list, which fsck reports,
+        // still drops.
+        if let Some(mut tables) =
+            db.get::<NamespaceIdent, Vec<TableIdent>>("TableNamespaceMap", namespace)?
+        {
+            tables.retain(|id| id.name != table_name);
+            changes.push((
+                "TableNamespaceMap",
+                db.encode_key(namespace)?,
+                Some(db.encode_value("TableNamespaceMap", &tables)?),
+            ));
+        }
+        let mut quota = QuotaUpdate::new(&db, None);
+        quota.dropped(&Owned::Table(table_id))?;
+        changes.extend(quota.changes()?);
+        let mut index = IndexUpdate::new(&db)?;
+        index.table(Some(&table), None)?;
+        index.commit(changes)
     }
 
+    #[instrument(skip_all, fields(namespace = %namespace, table = %table_name))]
     pub fn table_exists(
         &self,
         namespace: &NamespaceIdent,
@@ -125,40 +322,546 @@ impl TableRepository {
         Ok(table.is_some())
     }
 
-    pub fn rename_table(&self, rename_request: &TableRenameRequest) -> Result<(), Error> {
-        let source = rename_request.source.clone();
-        let destination = rename_request.destination.clone();
-        let namespace = source.namespace.clone();
+    /// Renames the table, provided the source is at one of the `if_match`
+    /// versions when those are given. The table keeps counting versions
+    /// and its owner under its new name, and the foreign keys referencing it
+    /// follow it.
+    #[instrument(skip_all, fields(source = %rename_request.source, destination = %rename_request.destination))]
+    pub fn rename_table(
+        &self,
+        rename_request: &TableRenameRequest,
+        if_match: Option<&[u64]>,
+    ) -> Result<(), Error> {
+        let source = &rename_request.source;
+        let destination = &rename_request.destination;
+        let db = lock_database(&self.database, "table");
 
-        let table = self
-            .load_table(&namespace, source.name.clone())?
+        let table = db
+            .get::<TableIdent, Table>("TableData", source)?
             .ok_or_else(|| Error::new(ErrorKind::NotFound, "Source table not found"))?;
-
-        if self.table_exists(&destination.namespace, destination.name.clone())? {
+        check_version(if_match, table.version)?;
+        if db
+            .get::<NamespaceIdent, NamespaceData>("NamespaceData", &destination.namespace)?
+            .is_none()
+        {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("Namespace {} not found", destination.namespace),
+            ));
+        }
+        if db
+            .get::<TableIdent, Table>("TableData", destination)?
+            .is_some()
+        {
             return Err(Error::new(
                 ErrorKind::AlreadyExists,
                 "Destination table already exists",
             ));
         }
 
-        let mut new_table = table.clone();
-        new_table.id = destination.clone();
+        // Keep the uuid, schema and constraints of the source table.
+        let mut renamed = table.clone();
+        renamed.id = destination.clone();
+        redirect_foreign_keys(&mut renamed.metadata.constraints, source, destination);
+        // Past both the source's version and any tag an earlier table under
+        // the destination name handed out.
+        renamed.version =
+            (table.version + 1).max(first_version(&db, &VersionKey::Table(destination.clone()))?);
 
-        self.create_table(
-            &destination.namespace.clone(),
-            &TableCreation {
-                name: destination.name.clone(),
-            },
+        let source_key = db.encode_key(source)?;
+        let destination_key = db.encode_key(destination)?;
+        let mut changes = vec![
+            ("TableData", source_key.clone(), None),
+            retire(&db, &VersionKey::Table(source.clone()), table.version)?,
+            (
+                "TableData",
+                destination_key.clone(),
+                Some(db.encode_value("TableData", &renamed)?),
+            ),
+        ];
+        for cf in TABLE_DATA_CFS {
+            if let Some(value) = db.get::<TableIdent, Value>(cf, source)? {
+                changes.push((cf, source_key.clone(), None));
+                changes.push((
+                    cf,
+                    destination_key.clone(),
+                    Some(db.encode_value(cf, &value)?),
+                ));
+            }
+        }
+
+        // Both names may be in the same namespace, so the lists are edited
+        // before they are written.
+        let mut lists: HashMap<&NamespaceIdent, Vec<TableIdent>> = HashMap::new();
+        for namespace in [&source.namespace, &destination.namespace] {
+            if !lists.contains_key(namespace) {
+                let list = db
+                    .get::<NamespaceIdent, Vec<TableIdent>>("TableNamespaceMap", namespace)?
+                    .unwrap_or_default();
+                lists.insert(namespace, list);
+            }
+        }
+        if let Some(list) = lists.get_mut(&source.namespace) {
+            list.retain(|id| id != source);
+        }
+        if let Some(list) = lists.get_mut(&destination.namespace) {
+            // The source is out of the list already, so a table may be
+            // renamed to its own name in another case.
+            if let Some(other) = clashing(self.case, destination, list) {
+                return Err(clash(destination, other));
+            }
+            list.push(destination.clone());
+        }
+        for (namespace, list) in &lists {
+            changes.push((
+                "TableNamespaceMap",
+                db.encode_key(namespace)?,
+                Some(db.encode_value("TableNamespaceMap", list)?),
+            ));
+        }
+
+        let mut index = IndexUpdate::new(&db)?;
+        for id in search_index::referencing(&db, source)? {
+            let Some(referencing) = db.get::<TableIdent, Table>("TableData", &id)? else {
+                continue;
+            };
+            let mut redirected = referencing.clone();
+            redirect_foreign_keys(&mut redirected.metadata.constraints, source, destination);
+            redirected.version += 1;
+            changes.push((
+                "TableData",
+                db.encode_key(&id)?,
+                Some(db.encode_value("TableData", &redirected)?),
+            ));
+            index.table(Some(&referencing), Some(&redirected))?;
+        }
+
+        let mut quota = QuotaUpdate::new(&db, None);
+        quota.renamed(
+            &Owned::Table(source.clone()),
+            &Owned::Table(destination.clone()),
         )?;
-        self.drop_table(&namespace, source.name.clone())
+        changes.extend(quota.changes()?);
+        index.table(Some(&table), None)?;
+        index.table(None, Some(&renamed))?;
+        index.commit(changes)
+    }
+
+    /// Replaces the constraints of a table after checking that every column
+    /// they mention exists and that foreign keys reference a key of an
+    /// existing table. Key flags of the table's columns follow the constraints.
+    /// Returns the new version of the table.
+    #[instrument(skip_all, fields(namespace = %namespace, table = %table_name))]
+    pub fn set_constraints(
+        &self,
+        namespace: &NamespaceIdent,
+        table_name: String,
+        constraints: &TableConstraints,
+        if_match: Option<&[u64]>,
+    ) -> Result<u64, Error> {
+        let table_id = TableIdent::new(namespace.clone(), table_name.clone());
+        let db = lock_database(&self.database, "table");
+        let mut table = db
+            .get::<TableIdent, Table>("TableData", &table_id)?
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::NotFound,
+                    format!("Table {} not found", table_name),
+                )
+            })?;
+        check_version(if_match, table.version)?;
+
+        let own_columns = table.metadata.columns.as_deref();
+        let declared = constraints
+            .primary_key
+            .iter()
+            .map(|pk| &pk.columns)
+            .chain(constraints.unique_keys.iter().map(|uk| &uk.columns))
+            .chain(constraints.foreign_keys.iter().map(|fk| &fk.columns))
+            .chain(
+                constraints
+                    .functional_dependencies
+                    .iter()
+                    .flat_map(|fd| [&fd.determinant, &fd.dependent]),
+            );
+        for columns in declared {
+            check_columns(&table_id, own_columns, columns)?;
+        }
+
+        for foreign_key in &constraints.foreign_keys {
+            if foreign_key.columns.len() != foreign_key.referenced_columns.len() {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Foreign key {} has {} columns but references {}",
+                        foreign_key.name,
+                        foreign_key.columns.len(),
+                        foreign_key.referenced_columns.len()
+                    ),
+                ));
+            }
+            let referenced = if foreign_key.referenced_table == table_id {
+                Table {
+                    id: table_id.clone(),
+                    metadata: TableMetadata {
+                        constraints: constraints.clone(),
+                        ..table.metadata.clone()
+                    },
+                    version: table.version,
+                }
+            } else {
+                db.get::<TableIdent, Table>("TableData", &foreign_key.referenced_table)?
+                    .ok_or_else(|| {
+                        Error::new(
+                            ErrorKind::InvalidInput,
+                            format!(
+                                "Foreign key {} references table {} which does not exist",
+                                foreign_key.name, foreign_key.referenced_table.name
+                            ),
+                        )
+                    })?
+            };
+            check_columns(
+                &referenced.id,
+                referenced.metadata.columns.as_deref(),
+                &foreign_key.referenced_columns,
+            )?;
+            if !referenced
+                .metadata
+                .constraints
+                .is_key(&foreign_key.referenced_columns)
+            {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Foreign key {} does not reference a primary or unique key of table {}",
+                        foreign_key.name, referenced.id.name
+                    ),
+                ));
+            }
+        }
+
+        let old = table.clone();
+        if let Some(columns) = table.metadata.columns.as_mut() {
+            apply_key_flags(columns, constraints);
+        }
+        table.metadata.constraints = constraints.clone();
+        table.version += 1;
+        let mut index = IndexUpdate::new(&db)?;
+        index.table(Some(&old), Some(&table))?;
+        index.commit(vec![(
+            "TableData",
+            db.encode_key(&table_id)?,
+            Some(db.encode_value("TableData", &table)?),
+        )])?;
+        Ok(table.version)
+    }
+
+    /// Applies the removals and updates to the properties of a table and
+    /// returns its new version, provided it is at one of the `if_match`
+    /// versions when those are given.
+    #[instrument(skip_all, fields(namespace = %namespace, table = %table_name))]
+    pub fn set_properties(
+        &self,
+        namespace: &NamespaceIdent,
+        table_name: String,
+        removals: &[String],
+        updates: &HashMap<String, String>,
+        if_match: Option<&[u64]>,
+    ) -> Result<u64, Error> {
+        let table_id = TableIdent::new(namespace.clone(), table_name.clone());
+        let db = lock_database(&self.database, "table");
+        let table = db
+            .get::<TableIdent, Table>("TableData", &table_id)?
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::NotFound,
+                    format!("Table {} not found", table_name),
+                )
+            })?;
+        check_version(if_match, table.version)?;
+
+        let mut updated = table.clone();
+        let properties = updated.metadata.properties.get_or_insert_with(HashMap::new);
+        for key in removals {
+            properties.remove(key);
+        }
+        properties.extend(updates.clone());
+        updated.version += 1;
+
+        let mut index = IndexUpdate::new(&db)?;
+        index.table(Some(&table), Some(&updated))?;
+        index.commit(vec![(
+            "TableData",
+            db.encode_key(&table_id)?,
+            Some(db.encode_value("TableData", &updated)?),
+        )])?;
+        Ok(updated.version)
+    }
+
+    #[instrument(skip_all, fields(namespace = %namespace, table = %table_name))]
+    pub fn load_constraints(
+        &self,
+        namespace: &NamespaceIdent,
+        table_name: String,
+    ) -> Result<Option<TableConstraints>, Error> {
+        Ok(self
+            .load_table(namespace, table_name)?
+            .map(|table| table.metadata.constraints))
+    }
+
+    /// Returns the constraints of every table in the namespace.
+    #[instrument(skip_all, fields(namespace = %namespace))]
+    pub fn list_constraints(
+        &self,
+        namespace: &NamespaceIdent,
+    ) -> Result<Vec<TableConstraintsEntry>, Error> {
+        let tables = self.list_all_tables(namespace)?.unwrap_or_default();
+        let db = lock_database(&self.database, "table");
+        let mut entries = Vec::new();
+        for table_id in tables {
+            if let Some(table) = db.get::<TableIdent, Table>("TableData", &table_id)? {
+                entries.push(TableConstraintsEntry {
+                    table: table_id,
+                    constraints: table.metadata.constraints,
+                });
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Commits a snapshot adding and removing data files, provided the
+    /// table is at one of the `if_match` versions when those are given.
+    #[instrument(skip_all, fields(namespace = %namespace, table = %table_name))]
+    pub fn register_data_files(
+        &self,
+        namespace: &NamespaceIdent,
+        table_name: String,
+        request: &RegisterDataFilesRequest,
+        if_match: Option<&[u64]>,
+    ) -> Result<i64, Error> {
+        let table_id = TableIdent::new(namespace.clone(), table_name.clone());
+        let db = lock_database(&self.database, "table");
+        let mut table = db
+            .get::<TableIdent, Table>("TableData", &table_id)?
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::NotFound,
+                    format!("Table {} not found", table_name),
+                )
+            })?;
+        check_version(if_match, table.version)?;
+
+        let mut registry = db
+            .get::<TableIdent, FileRegistry>("TableFiles", &table_id)?
+            .unwrap_or_default();
+        let snapshot_id = registry.snapshot_ids.last().map_or(1, |id| id + 1);
+
+        for path in &request.removed_file_paths {
+            let file = registry
+                .files
+                .iter_mut()
+                .find(|file| {
+                    file.removed_snapshot_id.is_none() && &file.data_file.file_path == path
+                })
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Data file {} is not part of the table", path),
+                    )
+                })?;
+            file.removed_snapshot_id = Some(snapshot_id);
+        }
+        for data_file in &request.added_files {
+            registry.files.push(RegisteredDataFile {
+                added_snapshot_id: snapshot_id,
+                removed_snapshot_id: None,
+                data_file: data_file.clone(),
+            });
+        }
+
+        registry.snapshot_ids.push(snapshot_id);
+        registry.current_snapshot_id = Some(snapshot_id);
+        registry.refs.insert("main".to_string(), snapshot_id);
+        // A new snapshot is a change of the table as a whole.
+        table.version += 1;
+        let key = db.encode_key(&table_id)?;
+        db.apply_raw(&[
+            (
+                "TableFiles",
+                key.clone(),
+                Some(db.encode_value("TableFiles", &registry)?),
+            ),
+            (
+                "TableData",
+                key,
+                Some(db.encode_value("TableData", &table)?),
+            ),
+        ])?;
+        Ok(snapshot_id)
     }
+
+    #[instrument(skip_all, fields(namespace = %namespace, table = %table_name))]
+    pub fn plan_table_scan(
+        &self,
+        namespace: &NamespaceIdent,
+        table_name: String,
+        request: &PlanTableScanRequest,
+    ) -> Result<PlanTableScanResponse, Error> {
+        let table_id = TableIdent::new(namespace.clone(), table_name.clone());
+        let registry = {
+            let db = lock_database(&self.database, "table");
+            if db
+                .get::<TableIdent, Table>("TableData", &table_id)?
+                .is_none()
+            {
+                return Err(Error::new(
+                    ErrorKind::NotFound,
+                    format!("Table {} not found", table_name),
+                ));
+            }
+            db.get::<TableIdent, FileRegistry>("TableFiles", &table_id)?
+                .unwrap_or_default()
+        };
+
+        let snapshot_id = match (request.snapshot_id, &request.reference) {
+            (Some(_), Some(_)) => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "Only one of snapshot_id and ref can be given",
+                ))
+            }
+            (Some(snapshot_id), None) => {
+                if !registry.snapshot_ids.contains(&snapshot_id) {
+                    return Err(Error::new(
+                        ErrorKind::NotFound,
+                        format!("Snapshot {} not found", snapshot_id),
+                    ));
+                }
+                Some(snapshot_id)
+            }
+            (None, Some(reference)) => match registry.refs.get(reference) {
+                Some(snapshot_id) => Some(*snapshot_id),
+                // An empty table has no snapshots yet, `main` still resolves.
+                None if reference == "main" => None,
+                None => {
+                    return Err(Error::new(
+                        ErrorKind::NotFound,
+                        format!("Ref {} not found", reference),
+                    ))
+                }
+            },
+            (None, None) => registry.current_snapshot_id,
+        };
+
+        let data_files = match snapshot_id {
+            Some(snapshot_id) => registry
+                .files_in(snapshot_id)
+                .into_iter()
+                .filter(|file| match &request.filter {
+                    Some(filter) => evaluator::might_match(filter, file),
+                    None => true,
+                })
+                .collect(),
+            None => vec![],
+        };
+
+        Ok(PlanTableScanResponse {
+            snapshot_id,
+            data_files,
+        })
+    }
+}
+
+fn check_columns(
+    table_id: &TableIdent,
+    known: Option<&[ColumnData]>,
+    columns: &[String],
+) -> Result<(), Error> {
+    if columns.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Constraint on table {} has no columns", table_id.name),
+        ));
+    }
+    for (i, column) in columns.iter().enumerate() {
+        if columns[..i].contains(column) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Column {} is listed twice in a constraint", column),
+            ));
+        }
+        // Tables created without a schema cannot be checked.
+        if known.is_some_and(|known| !known.iter().any(|c| &c.name == column)) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Column {} not found in table {}", column, table_id.name),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn apply_key_flags(columns: &mut [ColumnData], constraints: &TableConstraints) {
+    for column in columns.iter_mut() {
+        column.is_strong_key = constraints
+            .primary_key
+            .as_ref()
+            .is_some_and(|pk| pk.columns.contains(&column.name));
+        column.is_weak_key = constraints
+            .unique_keys
+            .iter()
+            .any(|uk| uk.columns.contains(&column.name));
+        column.primary_key_col_name = constraints
+            .foreign_keys
+            .iter()
+            .find_map(|fk| {
+                fk.columns
+                    .iter()
+                    .position(|c| c == &column.name)
+                    .map(|i| fk.referenced_columns[i].clone())
+            })
+            .unwrap_or_default();
+    }
+}
+
+/// Points the foreign keys referencing `from` at `to`.
+fn redirect_foreign_keys(constraints: &mut TableConstraints, from: &TableIdent, to: &TableIdent) {
+    for foreign_key in &mut constraints.foreign_keys {
+        if foreign_key.referenced_table == *from {
+            foreign_key.referenced_table = to.clone();
+        }
+    }
+}
+
+/// Finds the table among `tables`, all in the namespace of `table`, that
+/// `table` differs from only in name case, when `case` forbids that.
+fn clashing<'a>(
+    case: CasePolicy,
+    table: &TableIdent,
+    tables: &'a [TableIdent],
+) -> Option<&'a TableIdent> {
+    tables
+        .iter()
+        .find(|other| case.clashes(&table.name, &other.name))
+}
+
+fn clash(table: &TableIdent, other: &TableIdent) -> Error {
+    Error::new(
+        ErrorKind::AlreadyExists,
+        format!("Table {} differs only in case from {}", table, other),
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::config::LimitsConfig;
     use crate::dto::table_data::TableCreation;
     use crate::repository::namespace::NamespaceRepository;
+    use crate::repository::quota::QuotaRepository;
+    use crate::repository::version::is_version_mismatch;
+    use serde_json::json;
     use std::sync::{Arc, Mutex};
     use tempfile::tempdir;
 
@@ -173,14 +876,16 @@ mod tests {
         let namespace_ident = NamespaceIdent(vec!["test".to_string()]);
         let namespace_repo = NamespaceRepository::new(db.clone());
         namespace_repo
-            .create_namespace(namespace_ident.clone(), None)
+            .create_namespace(namespace_ident.clone(), None, None)
             .unwrap();
 
         // Test create_table
         let table_creation = TableCreation {
             name: "table1".to_string(),
+            columns: None,
+            properties: None,
         };
-        repo.create_table(&namespace_ident, &table_creation)
+        repo.create_table(&namespace_ident, &table_creation, None)
             .unwrap();
 
         // Test table_exists
@@ -194,13 +899,16 @@ mod tests {
             .unwrap()
             .unwrap();
         assert_eq!(table.id.name, "table1");
+        assert_eq!(table.version, 1);
 
         // Test rename_table
         let rename_request = TableRenameRequest {
             source: TableIdent::new(namespace_ident.clone(), "table1".to_string()),
             destination: TableIdent::new(namespace_ident.clone(), "table2".to_string()),
         };
-        repo.rename_table(&rename_request).unwrap();
+        let err = repo.rename_table(&rename_request, Some(&[2])).unwrap_err();
+        assert!(is_version_mismatch(&err));
+        repo.rename_table(&rename_request, Some(&[1])).unwrap();
         assert!(!repo
             .table_exists(&namespace_ident, "table1".to_string())
             .unwrap());
@@ -208,12 +916,154 @@ mod tests {
             .table_exists(&namespace_ident, "table2".to_string())
             .unwrap());
 
+        let table = repo
+            .load_table(&namespace_ident, "table2".to_string())
+            .unwrap()
+            .unwrap();
+        assert_eq!(table.version, 2);
+
         // Test drop_table
-        repo.drop_table(&namespace_ident, "table2".to_string())
+        let err = repo
+            .drop_table(&namespace_ident, "table2".to_string(), Some(&[1]))
+            .unwrap_err();
+        assert!(is_version_mismatch(&err));
+        repo.drop_table(&namespace_ident, "table2".to_string(), Some(&[2]))
             .unwrap();
         assert!(!repo
             .table_exists(&namespace_ident, "table2".to_string())
             .unwrap());
+
+        // Versions keep counting for tables created again under a name.
+        repo.create_table(&namespace_ident, &table_creation, None)
+            .unwrap();
+        let table = repo
+            .load_table(&namespace_ident, "table1".to_string())
+            .unwrap()
+            .unwrap();
+        assert_eq!(table.version, 2);
+        repo.create_table(
+            &namespace_ident,
+            &TableCreation::builder().name("table2".to_string()).build(),
+            None,
+        )
+        .unwrap();
+        let table = repo
+            .load_table(&namespace_ident, "table2".to_string())
+            .unwrap()
+            .unwrap();
+        assert_eq!(table.version, 3);
+    }
+
+    #[test]
+    fn test_create_and_load_tables() {
+        let dir = tempdir().unwrap();
+        let db = Arc::new(Mutex::new(Database::open(dir.path()).unwrap()));
+        let repo = TableRepository::new(db.clone());
+        let sales = NamespaceIdent(vec!["sales".to_string()]);
+        NamespaceRepository::new(db)
+            .create_namespace(sales.clone(), None, None)
+            .unwrap();
+        let creation = |namespace: &NamespaceIdent, name: &str| BatchTableCreation {
+            namespace: namespace.clone(),
+            table: TableCreation::builder().name(name.to_string()).build(),
+        };
+        let missing = NamespaceIdent(vec!["missing".to_string()]);
+
+        let batch = [creation(&sales, "orders"), creation(&missing, "items")];
+        let err = repo.create_tables(&batch, true, None).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        assert!(!repo.table_exists(&sales, "orders".to_string()).unwrap());
+
+        let batch = [
+            creation(&sales, "orders"),
+            creation(&missing, "items"),
+            creation(&sales, "orders"),
+            creation(&sales, "items"),
+        ];
+        let results = repo.create_tables(&batch, false, None).unwrap();
+        let kinds: Vec<Option<ErrorKind>> = results
+            .iter()
+            .map(|result| result.as_ref().err().map(Error::kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                None,
+                Some(ErrorKind::NotFound),
+                Some(ErrorKind::AlreadyExists),
+                None
+            ]
+        );
+        assert_eq!(repo.list_all_tables(&sales).unwrap().unwrap().len(), 2);
+
+        let tables = repo
+            .load_tables(&[batch[3].ident(), batch[1].ident(), batch[0].ident()])
+            .unwrap();
+        assert_eq!(tables[0].as_ref().unwrap().id, batch[3].ident());
+        assert!(tables[1].is_none());
+        assert_eq!(tables[2].as_ref().unwrap().version, 1);
+    }
+
+    #[test]
+    fn test_case_insensitive_tables() {
+        let dir = tempdir().unwrap();
+        let db = Arc::new(Mutex::new(Database::open(dir.path()).unwrap()));
+        let repo = TableRepository::new(db.clone()).with_case_policy(CasePolicy::Insensitive);
+        let sales = NamespaceIdent(vec!["sales".to_string()]);
+        let archive = NamespaceIdent(vec!["archive".to_string()]);
+        let namespaces = NamespaceRepository::new(db);
+        for namespace in [&sales, &archive] {
+            namespaces
+                .create_namespace(namespace.clone(), None, None)
+                .unwrap();
+        }
+        let creation = |name: &str| TableCreation::builder().name(name.to_string()).build();
+        repo.create_table(&sales, &creation("Orders"), None)
+            .unwrap();
+
+        let err = repo
+            .create_table(&sales, &creation("orders"), None)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+        assert_eq!(
+            err.to_string(),
+            "Table sales.orders differs only in case from sales.Orders"
+        );
+        // Tables in other namespaces are not siblings.
+        repo.create_table(&archive, &creation("orders"), None)
+            .unwrap();
+
+        let batch = [
+            BatchTableCreation {
+                namespace: sales.clone(),
+                table: creation("Items"),
+            },
+            BatchTableCreation {
+                namespace: sales.clone(),
+                table: creation("ITEMS"),
+            },
+        ];
+        let results = repo.create_tables(&batch, false, None).unwrap();
+        assert!(results[0].is_ok());
+        assert_eq!(
+            results[1].as_ref().unwrap_err().kind(),
+            ErrorKind::AlreadyExists
+        );
+
+        // A table may change the case of its own name, but not take one
+        // differing only in case from another table.
+        let rename = |source: &str, destination: &str| TableRenameRequest {
+            source: TableIdent::new(sales.clone(), source.to_string()),
+            destination: TableIdent::new(sales.clone(), destination.to_string()),
+        };
+        let err = repo
+            .rename_table(&rename("Items", "orders"), None)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+        assert!(repo.table_exists(&sales, "Items".to_string()).unwrap());
+        repo.rename_table(&rename("Orders", "orders"), None)
+            .unwrap();
+        assert!(repo.table_exists(&sales, "orders".to_string()).unwrap());
     }
 
     #[test]
@@ -227,24 +1077,26 @@ mod tests {
         let non_existent_namespace = NamespaceIdent(vec!["non_existent".to_string()]);
         let table_creation = TableCreation {
             name: "table1".to_string(),
+            columns: None,
+            properties: None,
         };
         assert!(repo
-            .create_table(&non_existent_namespace, &table_creation)
+            .create_table(&non_existent_namespace, &table_creation, None)
             .is_err());
         assert!(repo
-            .drop_table(&non_existent_namespace, "table1".to_string())
+            .drop_table(&non_existent_namespace, "table1".to_string(), None)
             .is_err());
 
         // Test with existing table
         let namespace_ident = NamespaceIdent(vec!["test".to_string()]);
         let namespace_repo = NamespaceRepository::new(db.clone());
         namespace_repo
-            .create_namespace(namespace_ident.clone(), None)
+            .create_namespace(namespace_ident.clone(), None, None)
             .unwrap();
-        repo.create_table(&namespace_ident, &table_creation)
+        repo.create_table(&namespace_ident, &table_creation, None)
             .unwrap();
         assert!(repo
-            .create_table(&namespace_ident, &table_creation)
+            .create_table(&namespace_ident, &table_creation, None)
             .is_err());
 
         // Test rename_table with non-existent source table
@@ -252,13 +1104,491 @@ mod tests {
             source: TableIdent::new(namespace_ident.clone(), "non_existent".to_string()),
             destination: TableIdent::new(namespace_ident.clone(), "table2".to_string()),
         };
-        assert!(repo.rename_table(&rename_request).is_err());
+        assert!(repo.rename_table(&rename_request, None).is_err());
 
         // Test rename_table with existing destination table
         let rename_request = TableRenameRequest {
             source: TableIdent::new(namespace_ident.clone(), "table1".to_string()),
             destination: TableIdent::new(namespace_ident.clone(), "table1".to_string()),
         };
-        assert!(repo.rename_table(&rename_request).is_err());
+        assert!(repo.rename_table(&rename_request, None).is_err());
+    }
+
+    #[test]
+    fn test_drop_table_missing_from_namespace_list() {
+        let dir = tempdir().unwrap();
+        let db = Arc::new(Mutex::new(Database::open(dir.path()).unwrap()));
+        let repo = TableRepository::new(db.clone());
+        let namespace_ident = NamespaceIdent(vec!["test".to_string()]);
+        NamespaceRepository::new(db.clone())
+            .create_namespace(namespace_ident.clone(), None, None)
+            .unwrap();
+        repo.create_table(
+            &namespace_ident,
+            &TableCreation::builder().name("table1".to_string()).build(),
+            None,
+        )
+        .unwrap();
+        db.lock()
+            .unwrap()
+            .delete("TableNamespaceMap", &namespace_ident)
+            .unwrap();
+
+        repo.drop_table(&namespace_ident, "table1".to_string(), None)
+            .unwrap();
+        assert!(!repo
+            .table_exists(&namespace_ident, "table1".to_string())
+            .unwrap());
+    }
+
+    #[test]
+    fn test_rename_table_moves_data() {
+        let dir = tempdir().unwrap();
+        let db = Arc::new(Mutex::new(Database::open(dir.path()).unwrap()));
+        let repo = TableRepository::new(db.clone());
+        let sales = NamespaceIdent(vec!["sales".to_string()]);
+        let archive = NamespaceIdent(vec!["archive".to_string()]);
+        let namespaces = NamespaceRepository::new(db.clone());
+        namespaces
+            .create_namespace(sales.clone(), None, None)
+            .unwrap();
+        repo.create_table(
+            &sales,
+            &TableCreation::builder().name("orders".to_string()).build(),
+            None,
+        )
+        .unwrap();
+        let request: RegisterDataFilesRequest = serde_json::from_value(json!({
+            "added_files": [{"file_path": "a", "record_count": 10, "partition": {}}]
+        }))
+        .unwrap();
+        repo.register_data_files(&sales, "orders".to_string(), &request, None)
+            .unwrap();
+
+        // Nothing moves when the destination namespace is missing.
+        let rename = TableRenameRequest {
+            source: TableIdent::new(sales.clone(), "orders".to_string()),
+            destination: TableIdent::new(archive.clone(), "orders".to_string()),
+        };
+        let err = repo.rename_table(&rename, None).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        assert!(repo.table_exists(&sales, "orders".to_string()).unwrap());
+
+        namespaces
+            .create_namespace(archive.clone(), None, None)
+            .unwrap();
+        repo.rename_table(&rename, Some(&[2])).unwrap();
+        assert_eq!(repo.list_all_tables(&sales).unwrap(), Some(vec![]));
+        assert_eq!(
+            repo.list_all_tables(&archive).unwrap(),
+            Some(vec![rename.destination.clone()])
+        );
+        let plan = repo
+            .plan_table_scan(
+                &archive,
+                "orders".to_string(),
+                &PlanTableScanRequest::default(),
+            )
+            .unwrap();
+        assert_eq!(plan.data_files.len(), 1);
+        let db = db.lock().unwrap();
+        let files: Option<FileRegistry> = db.get("TableFiles", &rename.source).unwrap();
+        assert!(files.is_none());
+    }
+
+    #[test]
+    fn test_tables_are_counted_against_their_owner() {
+        let dir = tempdir().unwrap();
+        let db = Arc::new(Mutex::new(Database::open(dir.path()).unwrap()));
+        let repo = TableRepository::new(db.clone());
+        let quotas = QuotaRepository::new(
+            db.clone(),
+            LimitsConfig {
+                max_tables_per_principal: Some(1),
+                ..Default::default()
+            },
+        );
+        let owner = quotas.owner("alice");
+        let sales = NamespaceIdent(vec!["sales".to_string()]);
+        NamespaceRepository::new(db.clone())
+            .create_namespace(sales.clone(), None, None)
+            .unwrap();
+        let creation = |name: &str| TableCreation::builder().name(name.to_string()).build();
+
+        repo.create_table(&sales, &creation("orders"), Some(&owner))
+            .unwrap();
+        // A failed create is not counted.
+        let err = repo
+            .create_table(&sales, &creation("orders"), Some(&owner))
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+        let err = repo
+            .create_table(&sales, &creation("items"), Some(&owner))
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+        assert!(!repo.table_exists(&sales, "items".to_string()).unwrap());
+        assert_eq!(quotas.usage("alice").unwrap().tables, 1);
+
+        // The owner follows the table and is released with it.
+        let rename = TableRenameRequest {
+            source: TableIdent::new(sales.clone(), "orders".to_string()),
+            destination: TableIdent::new(sales.clone(), "orders_v2".to_string()),
+        };
+        repo.rename_table(&rename, None).unwrap();
+        repo.drop_table(&sales, "orders_v2".to_string(), None)
+            .unwrap();
+        assert_eq!(quotas.usage("alice").unwrap().tables, 0);
+        repo.create_table(&sales, &creation("items"), Some(&owner))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_plan_table_scan() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let db = Arc::new(Mutex::new(db));
+        let repo = TableRepository::new(db.clone());
+
+        let namespace_ident = NamespaceIdent(vec!["test".to_string()]);
+        NamespaceRepository::new(db.clone())
+            .create_namespace(namespace_ident.clone(), None, None)
+            .unwrap();
+        repo.create_table(
+            &namespace_ident,
+            &TableCreation {
+                name: "table1".to_string(),
+                columns: None,
+                properties: None,
+            },
+            None,
+        )
+        .unwrap();
+
+        // Test plan_table_scan on a table without files
+        let plan = repo
+            .plan_table_scan(
+                &namespace_ident,
+                "table1".to_string(),
+                &PlanTableScanRequest::default(),
+            )
+            .unwrap();
+        assert_eq!(plan.snapshot_id, None);
+        assert!(plan.data_files.is_empty());
+
+        // Test register_data_files
+        let request: RegisterDataFilesRequest = serde_json::from_value(json!({
+            "added_files": [
+                {"file_path": "a", "record_count": 10, "partition": {"region": "us"}},
+                {"file_path": "b", "record_count": 10, "partition": {"region": "eu"}}
+            ]
+        }))
+        .unwrap();
+        let first = repo
+            .register_data_files(&namespace_ident, "table1".to_string(), &request, None)
+            .unwrap();
+        let request: RegisterDataFilesRequest = serde_json::from_value(json!({
+            "added_files": [
+                {"file_path": "c", "record_count": 10, "partition": {"region": "us"}}
+            ],
+            "removed_file_paths": ["a"]
+        }))
+        .unwrap();
+        let second = repo
+            .register_data_files(&namespace_ident, "table1".to_string(), &request, None)
+            .unwrap();
+        assert!(second > first);
+        // Every snapshot bumps the table version in the same write.
+        let table = repo
+            .load_table(&namespace_ident, "table1".to_string())
+            .unwrap()
+            .unwrap();
+        assert_eq!(table.version, 3);
+
+        // Test plan_table_scan with a filter against the current snapshot
+        let request: PlanTableScanRequest = serde_json::from_value(json!({
+            "filter": {"type": "eq", "term": "region", "value": "us"}
+        }))
+        .unwrap();
+        let plan = repo
+            .plan_table_scan(&namespace_ident, "table1".to_string(), &request)
+            .unwrap();
+        assert_eq!(plan.snapshot_id, Some(second));
+        let paths: Vec<String> = plan.data_files.into_iter().map(|f| f.file_path).collect();
+        assert_eq!(paths, vec!["c".to_string()]);
+
+        // Test plan_table_scan against an older snapshot
+        let request = PlanTableScanRequest {
+            snapshot_id: Some(first),
+            ..Default::default()
+        };
+        let plan = repo
+            .plan_table_scan(&namespace_ident, "table1".to_string(), &request)
+            .unwrap();
+        let paths: Vec<String> = plan.data_files.into_iter().map(|f| f.file_path).collect();
+        assert_eq!(paths, vec!["a".to_string(), "b".to_string()]);
+
+        // Test plan_table_scan with unknown snapshot, ref and both given
+        let request = PlanTableScanRequest {
+            snapshot_id: Some(42),
+            ..Default::default()
+        };
+        let err = repo
+            .plan_table_scan(&namespace_ident, "table1".to_string(), &request)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        let request = PlanTableScanRequest {
+            reference: Some("dev".to_string()),
+            ..Default::default()
+        };
+        let err = repo
+            .plan_table_scan(&namespace_ident, "table1".to_string(), &request)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        let request = PlanTableScanRequest {
+            snapshot_id: Some(first),
+            reference: Some("main".to_string()),
+            ..Default::default()
+        };
+        let err = repo
+            .plan_table_scan(&namespace_ident, "table1".to_string(), &request)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+        // Test removing a file that is not registered
+        let request: RegisterDataFilesRequest =
+            serde_json::from_value(json!({"removed_file_paths": ["a"]})).unwrap();
+        let err = repo
+            .register_data_files(&namespace_ident, "table1".to_string(), &request, None)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_constraints() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let db = Arc::new(Mutex::new(db));
+        let repo = TableRepository::new(db.clone());
+
+        let namespace_ident = NamespaceIdent(vec!["test".to_string()]);
+        NamespaceRepository::new(db.clone())
+            .create_namespace(namespace_ident.clone(), None, None)
+            .unwrap();
+        let column = |name: &str| -> ColumnData {
+            serde_json::from_value(json!({
+                "name": name,
+                "aggregates": {},
+                "value_range": [0, 0],
+                "is_strong_key": false,
+                "is_weak_key": false,
+                "primary_key_col_name": ""
+            }))
+            .unwrap()
+        };
+        repo.create_table(
+            &namespace_ident,
+            &TableCreation {
+                name: "customers".to_string(),
+                columns: Some(vec![column("id"), column("email")]),
+                properties: None,
+            },
+            None,
+        )
+        .unwrap();
+        repo.create_table(
+            &namespace_ident,
+            &TableCreation {
+                name: "orders".to_string(),
+                columns: Some(vec![column("id"), column("customer_id")]),
+                properties: None,
+            },
+            None,
+        )
+        .unwrap();
+
+        // Test set_constraints with a primary and a unique key
+        let constraints: TableConstraints = serde_json::from_value(json!({
+            "primary_key": {"columns": ["id"]},
+            "unique_keys": [{"name": "uk_email", "columns": ["email"]}],
+            "functional_dependencies": [{"determinant": ["id"], "dependent": ["email"]}]
+        }))
+        .unwrap();
+        repo.set_constraints(
+            &namespace_ident,
+            "customers".to_string(),
+            &constraints,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            repo.load_constraints(&namespace_ident, "customers".to_string())
+                .unwrap(),
+            Some(constraints)
+        );
+        let customers = repo
+            .load_table(&namespace_ident, "customers".to_string())
+            .unwrap()
+            .unwrap();
+        let columns = customers.metadata.columns.unwrap();
+        assert!(columns[0].is_strong_key);
+        assert!(columns[1].is_weak_key);
+
+        // Test set_constraints with a foreign key
+        let constraints: TableConstraints = serde_json::from_value(json!({
+            "primary_key": {"columns": ["id"]},
+            "foreign_keys": [{
+                "name": "fk_customer",
+                "columns": ["customer_id"],
+                "referenced_table": {"namespace": ["test"], "name": "customers"},
+                "referenced_columns": ["id"]
+            }]
+        }))
+        .unwrap();
+        repo.set_constraints(&namespace_ident, "orders".to_string(), &constraints, None)
+            .unwrap();
+        let orders = repo
+            .load_table(&namespace_ident, "orders".to_string())
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            orders.metadata.columns.unwrap()[1].primary_key_col_name,
+            "id"
+        );
+
+        // Test list_constraints
+        let entries = repo.list_constraints(&namespace_ident).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].table.name, "orders");
+        assert_eq!(entries[1].constraints, constraints);
+
+        // Test rename_table keeps the constraints
+        repo.rename_table(
+            &TableRenameRequest {
+                source: TableIdent::new(namespace_ident.clone(), "orders".to_string()),
+                destination: TableIdent::new(namespace_ident.clone(), "purchases".to_string()),
+            },
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            repo.load_constraints(&namespace_ident, "purchases".to_string())
+                .unwrap(),
+            Some(constraints.clone())
+        );
+
+        // Test drop_table refuses a table a foreign key references
+        let err = repo
+            .drop_table(&namespace_ident, "customers".to_string(), None)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ResourceBusy);
+
+        // Test rename_table points the referencing foreign keys at the new name
+        let purchases = TableIdent::new(namespace_ident.clone(), "purchases".to_string());
+        let version = |repo: &TableRepository| {
+            repo.load_table(&namespace_ident, "purchases".to_string())
+                .unwrap()
+                .unwrap()
+                .version
+        };
+        let before = version(&repo);
+        let customers = TableIdent::new(namespace_ident.clone(), "customers".to_string());
+        let clients = TableIdent::new(namespace_ident.clone(), "clients".to_string());
+        repo.rename_table(
+            &TableRenameRequest {
+                source: customers.clone(),
+                destination: clients.clone(),
+            },
+            None,
+        )
+        .unwrap();
+        let redirected = repo
+            .load_constraints(&namespace_ident, "purchases".to_string())
+            .unwrap()
+            .unwrap();
+        assert_eq!(redirected.foreign_keys[0].referenced_table, clients);
+        assert_eq!(version(&repo), before + 1);
+        {
+            let db = repo.database.lock().unwrap();
+            assert!(search_index::referencing(&db, &customers)
+                .unwrap()
+                .is_empty());
+            assert_eq!(
+                search_index::referencing(&db, &clients).unwrap(),
+                vec![purchases]
+            );
+        }
+        repo.rename_table(
+            &TableRenameRequest {
+                source: clients,
+                destination: customers,
+            },
+            None,
+        )
+        .unwrap();
+
+        // Test invalid constraints
+        for invalid in [
+            json!({"primary_key": {"columns": ["missing"]}}),
+            json!({"primary_key": {"columns": []}}),
+            json!({"unique_keys": [{"name": "uk", "columns": ["id", "id"]}]}),
+            json!({"foreign_keys": [{
+                "name": "fk",
+                "columns": ["customer_id"],
+                "referenced_table": {"namespace": ["test"], "name": "missing"},
+                "referenced_columns": ["id"]
+            }]}),
+            json!({"foreign_keys": [{
+                "name": "fk",
+                "columns": ["customer_id"],
+                "referenced_table": {"namespace": ["test"], "name": "customers"},
+                "referenced_columns": ["missing"]
+            }]}),
+            json!({"foreign_keys": [{
+                "name": "fk",
+                "columns": ["id", "customer_id"],
+                "referenced_table": {"namespace": ["test"], "name": "customers"},
+                "referenced_columns": ["id"]
+            }]}),
+        ] {
+            let constraints: TableConstraints = serde_json::from_value(invalid).unwrap();
+            let err = repo
+                .set_constraints(
+                    &namespace_ident,
+                    "purchases".to_string(),
+                    &constraints,
+                    None,
+                )
+                .unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        }
+
+        // Test a foreign key that does not reference a key
+        repo.set_constraints(
+            &namespace_ident,
+            "customers".to_string(),
+            &TableConstraints::default(),
+            None,
+        )
+        .unwrap();
+        let err = repo
+            .set_constraints(
+                &namespace_ident,
+                "purchases".to_string(),
+                &constraints,
+                None,
+            )
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+        // Test set_constraints on a non-existent table
+        let err = repo
+            .set_constraints(
+                &namespace_ident,
+                "non_existent".to_string(),
+                &TableConstraints::default(),
+                None,
+            )
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
     }
 }
@@ -0,0 +1,109 @@
+use crate::database::database::{Database, RawChange, RawEntry};
+use crate::database::format::META_CF;
+use crate::dto::namespace_data::NamespaceIdent;
+use crate::dto::table_data::TableIdent;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// VersionMismatch is the error of a conditional write made against a
+/// version that is no longer current.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VersionMismatch {
+    pub current: u64,
+}
+
+impl fmt::Display for VersionMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Version {} does not match the expected version",
+            self.current
+        )
+    }
+}
+
+impl Error for VersionMismatch {}
+
+/// Fails with a [`VersionMismatch`] unless `current` is one of `expected`.
+/// No expected versions means the write is unconditional.
+pub fn check_version(expected: Option<&[u64]>, current: u64) -> io::Result<()> {
+    match expected {
+        Some(expected) if !expected.contains(&current) => {
+            Err(io::Error::other(VersionMismatch { current }))
+        }
+        _ => Ok(()),
+    }
+}
+
+pub fn is_version_mismatch(e: &io::Error) -> bool {
+    e.get_ref()
+        .is_some_and(|inner| inner.is::<VersionMismatch>())
+}
+
+/// Identifier whose versions keep counting when the object is dropped and
+/// another one is created under the same name, so that a tag of the old
+/// object never matches the new one.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionKey {
+    Namespace(NamespaceIdent),
+    Table(TableIdent),
+}
+
+/// Key in [`META_CF`] of the last version an identifier was dropped at.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum MetaKey {
+    LastVersion(VersionKey),
+}
+
+/// Version an object created under `key` starts at: 1, or one past the
+/// version the last object of that name was dropped at.
+pub fn first_version(db: &Database, key: &VersionKey) -> io::Result<u64> {
+    let last: Option<u64> = db.get(META_CF, &MetaKey::LastVersion(key.clone()))?;
+    Ok(last.map_or(1, |version| version + 1))
+}
+
+/// Whether `key` is the key of a version record in [`META_CF`].
+pub fn is_version_record(key: &[u8]) -> bool {
+    serde_json::from_slice::<MetaKey>(key).is_ok()
+}
+
+/// The version records in [`META_CF`], which dumps carry so that a restored
+/// catalog keeps counting where the dumped one stopped.
+pub fn version_records(db: &Database) -> io::Result<Vec<RawEntry>> {
+    Ok(db
+        .list_raw(META_CF)?
+        .into_iter()
+        .filter(|(key, _)| is_version_record(key))
+        .collect())
+}
+
+/// Change recording that the object under `key` goes away at `version`, to
+/// be written in the same batch as the drop.
+pub fn retire(db: &Database, key: &VersionKey, version: u64) -> io::Result<RawChange<'static>> {
+    Ok((
+        META_CF,
+        db.encode_key(&MetaKey::LastVersion(key.clone()))?,
+        Some(db.encode_value(META_CF, &version)?),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_version() {
+        assert!(check_version(None, 3).is_ok());
+        assert!(check_version(Some(&[2, 3]), 3).is_ok());
+
+        let err = check_version(Some(&[2]), 3).unwrap_err();
+        assert!(is_version_mismatch(&err));
+        assert!(!is_version_mismatch(&io::Error::other("x")));
+        // A tag that could not be parsed matches no version.
+        assert!(check_version(Some(&[]), 0).is_err());
+    }
+}
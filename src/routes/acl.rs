@@ -0,0 +1,39 @@
+use crate::database::database::Database;
+use crate::handlers::acl_handler;
+use crate::repository::acl::AclRepository;
+use axum::{
+    routing::{delete, get, post, put},
+    Router,
+};
+use std::sync::{Arc, Mutex};
+
+pub fn routes(db: Arc<Mutex<Database>>) -> Router {
+    let repo = Arc::new(AclRepository::new(db));
+
+    Router::new()
+        .route("/acl/roles", get(acl_handler::list_roles))
+        .route("/acl/roles", post(acl_handler::create_role))
+        .route("/acl/roles/:role", get(acl_handler::load_role))
+        .route("/acl/roles/:role", delete(acl_handler::drop_role))
+        .route(
+            "/acl/roles/:role/grants",
+            post(acl_handler::grant_privilege),
+        )
+        .route(
+            "/acl/roles/:role/revoke",
+            post(acl_handler::revoke_privilege),
+        )
+        .route(
+            "/acl/principals/:principal/roles",
+            get(acl_handler::list_principal_roles),
+        )
+        .route(
+            "/acl/principals/:principal/roles/:role",
+            put(acl_handler::assign_role),
+        )
+        .route(
+            "/acl/principals/:principal/roles/:role",
+            delete(acl_handler::unassign_role),
+        )
+        .with_state(repo)
+}
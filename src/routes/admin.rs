@@ -0,0 +1,27 @@
+use crate::config::config::BackupConfig;
+use crate::database::database::Database;
+use crate::handlers::admin_handler;
+use crate::repository::admin::AdminRepository;
+use axum::{
+    extract::DefaultBodyLimit,
+    routing::{get, post},
+    Router,
+};
+use std::sync::{Arc, Mutex};
+
+pub fn routes(db: Arc<Mutex<Database>>, backup: BackupConfig) -> Router {
+    let repo = Arc::new(AdminRepository::new(db, backup));
+    Router::new()
+        .route("/admin/export", get(admin_handler::export_catalog))
+        .route(
+            "/admin/import",
+            // Dumps of large catalogs exceed the default request body limit.
+            post(admin_handler::import_catalog).layer(DefaultBodyLimit::disable()),
+        )
+        .route("/admin/fsck", get(admin_handler::check_catalog))
+        .route("/admin/fsck", post(admin_handler::repair_catalog))
+        .route("/admin/backups", get(admin_handler::list_backups))
+        .route("/admin/backups", post(admin_handler::create_backup))
+        .route("/admin/checkpoints", post(admin_handler::create_checkpoint))
+        .with_state(repo)
+}
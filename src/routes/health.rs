@@ -0,0 +1,15 @@
+use crate::database::database::Database;
+use crate::handlers::health_handler;
+use crate::repository::health::HealthRepository;
+use axum::{routing::get, Router};
+use std::sync::{Arc, Mutex};
+
+pub fn routes(db: Arc<Mutex<Database>>) -> Router {
+    let repo = Arc::new(HealthRepository::new(db));
+
+    Router::new()
+        .route("/health", get(health_handler::health))
+        .route("/ready", get(health_handler::ready))
+        .route("/info", get(health_handler::info))
+        .with_state(repo)
+}
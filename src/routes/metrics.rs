@@ -0,0 +1,13 @@
+use crate::database::database::Database;
+use crate::handlers::metrics_handler;
+use crate::repository::metrics::MetricsRepository;
+use axum::{routing::get, Router};
+use std::sync::{Arc, Mutex};
+
+pub fn routes(db: Arc<Mutex<Database>>) -> Router {
+    let repo = Arc::new(MetricsRepository::new(db));
+
+    Router::new()
+        .route("/metrics", get(metrics_handler::metrics))
+        .with_state(repo)
+}
@@ -1,3 +1,10 @@
+pub mod acl;
+pub mod admin;
+pub mod health;
+pub mod metrics;
 pub mod namespace;
+pub mod oauth;
 pub mod root;
+pub mod search;
+pub mod statistics;
 pub mod table;
@@ -1,3 +1,4 @@
+use crate::config::config::CasePolicy;
 use crate::database::database::Database;
 use crate::handlers::namespace_handler;
 use axum::{
@@ -8,12 +9,16 @@ use std::sync::{Arc, Mutex};
 
 use crate::repository::namespace::NamespaceRepository;
 
-pub fn routes(db: Arc<Mutex<Database>>) -> Router {
-    let repo = Arc::new(NamespaceRepository::new(db));
+pub fn routes(db: Arc<Mutex<Database>>, case: CasePolicy) -> Router {
+    let repo = Arc::new(NamespaceRepository::new(db).with_case_policy(case));
 
     let router = Router::new()
         .route("/namespaces", get(namespace_handler::list_namespaces))
         .route("/namespaces", post(namespace_handler::create_namespace))
+        .route(
+            "/batch/namespaces",
+            post(namespace_handler::create_namespaces),
+        )
         .route(
             "/namespaces/:namespace",
             get(namespace_handler::load_namespace_metadata),
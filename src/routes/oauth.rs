@@ -0,0 +1,10 @@
+use crate::auth::authenticator::Authenticator;
+use crate::handlers::oauth_handler;
+use axum::{routing::post, Router};
+use std::sync::Arc;
+
+pub fn routes(auth: Arc<Authenticator>) -> Router {
+    Router::new()
+        .route("/v1/oauth/tokens", post(oauth_handler::issue_token))
+        .with_state(auth)
+}
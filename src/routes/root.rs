@@ -1,13 +1,604 @@
+use crate::auth::access::AccessControl;
+use crate::auth::authenticator::Authenticator;
+use crate::auth::middleware::authenticate;
+use crate::config::config::Config;
 use crate::database::database::Database;
-use crate::routes::{namespace, table};
-use axum::Router;
+use crate::handlers::naming::{check_path_names, NamingRules};
+use crate::repository::quota::QuotaRepository;
+use crate::routes::{acl, admin, health, metrics, namespace, oauth, search, statistics, table};
+use crate::server::rate_limit::{rate_limit, rate_limit_address, RateLimiter};
+use crate::telemetry::logging::trace_requests;
+use crate::telemetry::metrics::track_requests;
+use axum::{middleware, Extension, Router};
+use std::io;
 use std::sync::{Arc, Mutex};
 
-pub fn routes(db: Arc<Mutex<Database>>) -> Router {
+pub fn routes(db: Arc<Mutex<Database>>, config: &Config) -> Result<Router, io::Error> {
+    let auth = Arc::new(Authenticator::new(config.auth.clone()));
+    let access = Arc::new(AccessControl::new(&config.auth, db.clone()));
+
+    let quotas = Arc::new(QuotaRepository::new(db.clone(), config.limits.clone()));
+    let naming = Arc::new(NamingRules::new(&config.naming)?);
+
     // Pass the shared Database object to your routes
-    let app_router = Router::new()
-        .nest("/", table::routes(db.clone()))
-        .nest("/", namespace::routes(db.clone()));
+    let mut protected = Router::new()
+        .nest("/", table::routes(db.clone(), config.naming.case))
+        .nest("/", namespace::routes(db.clone(), config.naming.case))
+        .nest("/", statistics::routes(db.clone()))
+        .nest("/", admin::routes(db.clone(), config.backup.clone()))
+        .nest("/", acl::routes(db.clone()))
+        .nest("/", search::routes(db.clone()))
+        .layer(Extension(quotas))
+        .layer(Extension(naming.clone()))
+        .layer(middleware::from_fn_with_state(naming, check_path_names));
+    if config.limits.rate_limit {
+        // Applied inside authentication so clients are told apart by principal.
+        let limiter = Arc::new(RateLimiter::new(config.limits.clone()));
+        protected = protected.layer(middleware::from_fn_with_state(limiter, rate_limit));
+    }
+    let mut public = protected
+        .layer(middleware::from_fn_with_state(
+            (auth.clone(), access),
+            authenticate,
+        ))
+        // Clients fetch their token here, so it stays reachable without one.
+        .merge(oauth::routes(auth));
+    if config.limits.rate_limit {
+        // Applied before authentication so that guessing credentials and
+        // requesting tokens are limited as well.
+        let limiter = Arc::new(RateLimiter::per_address(config.limits.clone()));
+        public = public.layer(middleware::from_fn_with_state(limiter, rate_limit_address));
+    }
+    let app_router = public
+        // Scrapers and orchestrators rarely hold catalog credentials.
+        .merge(metrics::routes(db.clone()))
+        .merge(health::routes(db))
+        .layer(middleware::from_fn(track_requests))
+        .layer(middleware::from_fn(trace_requests));
+
+    Ok(app_router)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::config::{ApiToken, AuthConfig, LimitsConfig, NamingConfig};
+    use crate::server::tls::ClientCertificate;
+    use axum::body::Body;
+    use axum::extract::ConnectInfo;
+    use axum::http::{header, Request, StatusCode};
+    use std::net::SocketAddr;
+    use tempfile::{tempdir, TempDir};
+    use tower::ServiceExt;
+
+    /// The routes, with the directory their database lives in.
+    fn app(auth: AuthConfig) -> (Router, TempDir) {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let config = Config {
+            auth,
+            ..Default::default()
+        };
+        (routes(Arc::new(Mutex::new(db)), &config).unwrap(), dir)
+    }
+
+    fn get(uri: &str, token: Option<&str>) -> Request<Body> {
+        let mut request = Request::get(uri);
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        request.body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_routes_require_token_when_auth_is_enabled() {
+        let (app, _dir) = app(AuthConfig {
+            enabled: true,
+            tokens: vec![ApiToken {
+                name: "ci".to_string(),
+                token: "secret".to_string(),
+            }],
+            ..Default::default()
+        });
+
+        let response = app.clone().oneshot(get("/namespaces", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()[header::WWW_AUTHENTICATE], "Bearer");
+
+        let response = app
+            .clone()
+            .oneshot(get("/namespaces", Some("wrong")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app
+            .clone()
+            .oneshot(get("/namespaces", Some("secret")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let token_request = Request::post("/v1/oauth/tokens")
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from(
+                "grant_type=client_credentials&client_id=spark&client_secret=x",
+            ))
+            .unwrap();
+        let response = app.oneshot(token_request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_routes_enforce_grants_when_rbac_is_enabled() {
+        let (app, _dir) = app(AuthConfig {
+            enabled: true,
+            rbac: true,
+            tokens: vec![
+                ApiToken {
+                    name: "admin".to_string(),
+                    token: "admin-secret".to_string(),
+                },
+                ApiToken {
+                    name: "alice".to_string(),
+                    token: "alice-secret".to_string(),
+                },
+            ],
+            admins: vec!["admin".to_string()],
+            ..Default::default()
+        });
+        let send = |method: &str, uri: &str, token: &str, body: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(get("/namespaces/sales", Some("alice-secret")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        for request in [
+            send(
+                "POST",
+                "/namespaces",
+                "admin-secret",
+                r#"{"name": ["sales"], "properties": {}}"#,
+            ),
+            send(
+                "POST",
+                "/acl/roles",
+                "admin-secret",
+                r#"{"name": "reader"}"#,
+            ),
+            send(
+                "POST",
+                "/acl/roles/reader/grants",
+                "admin-secret",
+                r#"{"privilege": "READ_METADATA", "scope": {"type": "namespace", "namespace": ["sales"]}}"#,
+            ),
+            send(
+                "PUT",
+                "/acl/principals/alice/roles/reader",
+                "admin-secret",
+                "",
+            ),
+        ] {
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert!(response.status().is_success());
+        }
+
+        let response = app
+            .clone()
+            .oneshot(get("/namespaces/sales", Some("alice-secret")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app
+            .oneshot(send("DELETE", "/namespaces/sales", "alice-secret", ""))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_client_certificate_authenticates_caller() {
+        let (app, _dir) = app(AuthConfig {
+            enabled: true,
+            rbac: true,
+            tokens: vec![ApiToken {
+                name: "ci".to_string(),
+                token: "secret".to_string(),
+            }],
+            admins: vec!["spark".to_string()],
+            ..Default::default()
+        });
+        let mut request = get("/admin/fsck", None);
+        request.extensions_mut().insert(ClientCertificate {
+            subject: "CN=spark".to_string(),
+            common_name: Some("spark".to_string()),
+        });
+
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.oneshot(get("/admin/fsck", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_are_public_and_labelled_by_route() {
+        let (app, _dir) = app(AuthConfig {
+            enabled: true,
+            tokens: vec![ApiToken {
+                name: "ci".to_string(),
+                token: "secret".to_string(),
+            }],
+            ..Default::default()
+        });
+        let response = app
+            .clone()
+            .oneshot(get("/namespaces/metrics_probe", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app.oneshot(get("/metrics", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        assert!(text.contains(
+            r#"catalog_http_requests_total{method="GET",route="/namespaces/:namespace",status="401"}"#
+        ));
+        assert!(!text.contains("metrics_probe"));
+    }
+
+    #[tokio::test]
+    async fn test_probes_do_not_require_a_token() {
+        let (app, _dir) = app(AuthConfig {
+            enabled: true,
+            tokens: vec![ApiToken {
+                name: "ci".to_string(),
+                token: "secret".to_string(),
+            }],
+            ..Default::default()
+        });
+
+        for uri in ["/health", "/ready", "/info"] {
+            let response = app.clone().oneshot(get(uri, None)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_requests_over_the_rate_are_rejected() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let config = Config {
+            limits: LimitsConfig {
+                rate_limit: true,
+                reads_per_second: 0.5,
+                read_burst: 2,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let app = routes(Arc::new(Mutex::new(db)), &config).unwrap();
+
+        for _ in 0..2 {
+            let response = app.clone().oneshot(get("/namespaces", None)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let response = app.clone().oneshot(get("/namespaces", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "2");
+        // Probes are never limited.
+        let response = app.oneshot(get("/health", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_addresses_are_limited_before_authentication() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let config = Config {
+            auth: AuthConfig {
+                enabled: true,
+                ..Default::default()
+            },
+            limits: LimitsConfig {
+                rate_limit: true,
+                address_requests_per_second: 0.5,
+                address_burst: 2,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let app = routes(Arc::new(Mutex::new(db)), &config).unwrap();
+        let from = |address: &str, mut request: Request<Body>| {
+            let addr: SocketAddr = address.parse().unwrap();
+            request.extensions_mut().insert(ConnectInfo(addr));
+            request
+        };
+        let token_request = || {
+            Request::post("/v1/oauth/tokens")
+                .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(Body::from(
+                    "grant_type=client_credentials&client_id=spark&client_secret=x",
+                ))
+                .unwrap()
+        };
+
+        for _ in 0..2 {
+            let request = from("10.0.0.1:1000", get("/namespaces", Some("wrong")));
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+        // Failed attempts used up the address's bucket, token requests too.
+        let response = app
+            .clone()
+            .oneshot(from("10.0.0.1:1001", token_request()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let response = app
+            .clone()
+            .oneshot(from("10.0.0.2:1000", token_request()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        // Probes are never limited.
+        let response = app
+            .oneshot(from("10.0.0.1:1002", get("/health", None)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_namespace_quota_is_enforced() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let config = Config {
+            limits: LimitsConfig {
+                max_namespaces_per_principal: Some(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let app = routes(Arc::new(Mutex::new(db)), &config).unwrap();
+        let create = |name: &str| {
+            Request::post("/namespaces")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(format!(
+                    r#"{{"name": ["{}"], "properties": {{}}}}"#,
+                    name
+                )))
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(create("sales")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.clone().oneshot(create("marketing")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let request = Request::delete("/namespaces/sales")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let response = app.oneshot(create("marketing")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_batch_endpoints() {
+        let (app, _dir) = app(AuthConfig::default());
+        let post = |uri: &str, body: &str| {
+            Request::post(uri)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let json = |response: axum::response::Response| async {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(post(
+                "/batch/namespaces",
+                r#"{"namespaces": [
+                    {"name": ["sales"], "properties": {}},
+                    {"name": ["sales"], "properties": {}}
+                ]}"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let statuses: Vec<u64> = json(response).await["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|result| result["status"].as_u64().unwrap())
+            .collect();
+        assert_eq!(statuses, vec![200, 409]);
+
+        let response = app
+            .clone()
+            .oneshot(post(
+                "/batch/tables",
+                r#"{"atomic": true, "tables": [
+                    {"namespace": ["sales"], "name": "orders"},
+                    {"namespace": ["missing"], "name": "items"}
+                ]}"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = app
+            .clone()
+            .oneshot(post(
+                "/batch/tables",
+                r#"{"atomic": true, "tables": [{"namespace": ["sales"], "name": "orders"}]}"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app
+            .oneshot(post(
+                "/batch/tables/load",
+                r#"{"tables": [
+                    {"namespace": ["sales"], "name": "items"},
+                    {"namespace": ["sales"], "name": "orders"}
+                ]}"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let tables = json(response).await["tables"].clone();
+        assert!(tables[0].is_null());
+        assert_eq!(tables[1]["id"]["name"], "orders");
+    }
+
+    #[tokio::test]
+    async fn test_search_endpoint() {
+        let (app, _dir) = app(AuthConfig::default());
+        let response = app
+            .clone()
+            .oneshot(
+                Request::post("/namespaces")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(
+                        r#"{"name": ["sales"], "properties": {"owner": "teamA"}}"#,
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app
+            .clone()
+            .oneshot(get("/search?property=owner%3DteamA&name=sal*", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let found: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(found["namespaces"], serde_json::json!([["sales"]]));
+
+        let response = app
+            .clone()
+            .oneshot(get("/search?regex=%28", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::post("/indexes")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(r#"{"key": "owner"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let response = app
+            .clone()
+            .oneshot(
+                Request::post("/indexes/rebuild")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app
+            .oneshot(
+                Request::delete("/indexes/owner")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn test_names_are_validated() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let config = Config {
+            naming: NamingConfig {
+                reserved_names: vec!["system".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let app = routes(Arc::new(Mutex::new(db)), &config).unwrap();
+        let post = |uri: &str, body: &str| {
+            Request::post(uri)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let long = format!("/namespaces/{}", "a".repeat(300));
+        let cases = [
+            (
+                post("/namespaces", r#"{"name": [], "properties": {}}"#),
+                "Error: Namespace must have at least one level",
+            ),
+            (
+                post("/namespaces/sales/tables", r#"{"name": "system"}"#),
+                "Error: Table name \"system\" is a reserved name",
+            ),
+            (
+                get("/namespaces/sales%1F", None),
+                "Error: Namespace level 2 is empty",
+            ),
+            (
+                get("/namespaces/a%07/tables/orders", None),
+                "Error: Namespace level 1 \"a\\u{7}\" contains control character U+0007",
+            ),
+            (
+                get(&long, None),
+                "Error: Namespace level 1 is 300 bytes long, at most 255 are allowed",
+            ),
+        ];
+        for (request, message) in cases {
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            assert_eq!(body, message);
+        }
+
+        let response = app.oneshot(get("/namespaces/sales", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_routes_are_open_when_auth_is_disabled() {
+        let (app, _dir) = app(AuthConfig::default());
+        let response = app.oneshot(get("/namespaces", None)).await.unwrap();
 
-    app_router
+        assert_eq!(response.status(), StatusCode::OK);
+    }
 }
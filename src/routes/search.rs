@@ -0,0 +1,19 @@
+use crate::database::database::Database;
+use crate::handlers::search_handler;
+use crate::repository::search::SearchRepository;
+use axum::{
+    routing::{delete, get, post},
+    Router,
+};
+use std::sync::{Arc, Mutex};
+
+pub fn routes(db: Arc<Mutex<Database>>) -> Router {
+    let repo = Arc::new(SearchRepository::new(db));
+    Router::new()
+        .route("/search", get(search_handler::search))
+        .route("/indexes", get(search_handler::list_indexes))
+        .route("/indexes", post(search_handler::create_index))
+        .route("/indexes/rebuild", post(search_handler::rebuild_indexes))
+        .route("/indexes/:key", delete(search_handler::drop_index))
+        .with_state(repo)
+}
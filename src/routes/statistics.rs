@@ -0,0 +1,46 @@
+use crate::database::database::Database;
+use crate::handlers::statistics_handler;
+use crate::repository::statistics::StatisticsRepository;
+use axum::{
+    routing::{get, post, put},
+    Router,
+};
+use std::sync::{Arc, Mutex};
+
+pub fn routes(db: Arc<Mutex<Database>>) -> Router {
+    let repo = Arc::new(StatisticsRepository::new(db));
+    Router::new()
+        .route(
+            "/namespaces/:namespace/tables/:table/statistics",
+            get(statistics_handler::load_table_statistics),
+        )
+        .route(
+            "/namespaces/:namespace/tables/:table/statistics",
+            post(statistics_handler::update_table_statistics),
+        )
+        .route(
+            "/namespaces/:namespace/tables/:table/statistics/hll",
+            post(statistics_handler::merge_hll_sketches),
+        )
+        .route(
+            "/namespaces/:namespace/tables/:table/statistics/histograms",
+            get(statistics_handler::load_histograms),
+        )
+        .route(
+            "/namespaces/:namespace/tables/:table/statistics/histograms",
+            post(statistics_handler::set_histograms),
+        )
+        .route(
+            "/namespaces/:namespace/tables/:table/statistics/selectivity",
+            post(statistics_handler::estimate_selectivity),
+        )
+        .route(
+            "/namespaces/:namespace/tables/:table/statistics/sketches/:column",
+            get(statistics_handler::load_column_sketch),
+        )
+        .route(
+            "/namespaces/:namespace/tables/:table/statistics/sketches/:column",
+            put(statistics_handler::set_column_sketch),
+        )
+        .with_state(repo)
+}
@@ -1,14 +1,15 @@
+use crate::config::config::CasePolicy;
 use crate::database::database::Database;
 use crate::handlers::table_handler;
 use crate::repository::table::TableRepository;
 use axum::{
-    routing::{delete, get, head, post},
+    routing::{delete, get, head, post, put},
     Router,
 };
 use std::sync::{Arc, Mutex};
 
-pub fn routes(db: Arc<Mutex<Database>>) -> Router {
-    let repo = Arc::new(TableRepository::new(db));
+pub fn routes(db: Arc<Mutex<Database>>, case: CasePolicy) -> Router {
+    let repo = Arc::new(TableRepository::new(db).with_case_policy(case));
     let router = Router::new()
         .route(
             "/namespaces/:namespace/tables",
@@ -30,7 +31,33 @@ pub fn routes(db: Arc<Mutex<Database>>) -> Router {
             "/namespaces/:namespace/tables/:table",
             head(table_handler::table_exists),
         )
+        .route(
+            "/namespaces/:namespace/tables/:table/constraints",
+            get(table_handler::load_table_constraints),
+        )
+        .route(
+            "/namespaces/:namespace/tables/:table/constraints",
+            put(table_handler::set_table_constraints),
+        )
+        .route(
+            "/namespaces/:namespace/tables/:table/properties",
+            post(table_handler::set_table_properties),
+        )
+        .route(
+            "/namespaces/:namespace/constraints",
+            get(table_handler::list_constraints),
+        )
+        .route(
+            "/namespaces/:namespace/tables/:table/files",
+            post(table_handler::register_data_files),
+        )
+        .route(
+            "/namespaces/:namespace/tables/:table/plan",
+            post(table_handler::plan_table_scan),
+        )
         .route("/tables/rename", post(table_handler::rename_table))
+        .route("/batch/tables", post(table_handler::create_tables))
+        .route("/batch/tables/load", post(table_handler::load_tables))
         .with_state(repo);
 
     return router;
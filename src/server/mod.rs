@@ -0,0 +1,3 @@
+pub mod rate_limit;
+pub mod shutdown;
+pub mod tls;
@@ -0,0 +1,236 @@
+use crate::auth::access::Caller;
+use crate::auth::authenticator::AuthMethod;
+use crate::config::config::LimitsConfig;
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Buckets kept before full ones, which carry no state, are dropped.
+const MAX_IDLE_BUCKETS: usize = 10_000;
+
+/// RouteClass separates cheap reads from writes, which contend for the
+/// database lock for longer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RouteClass {
+    Read,
+    Write,
+}
+
+impl RouteClass {
+    pub fn of(method: &Method) -> Self {
+        if method == Method::GET || method == Method::HEAD {
+            RouteClass::Read
+        } else {
+            RouteClass::Write
+        }
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// RateLimiter keeps a token bucket per client and route class.
+pub struct RateLimiter {
+    /// Rate and burst of reads.
+    read: (f64, f64),
+    /// Rate and burst of writes, or None when they share the read buckets.
+    write: Option<(f64, f64)>,
+    buckets: Mutex<HashMap<(String, RouteClass), Bucket>>,
+}
+
+impl RateLimiter {
+    /// Limits each principal at the configured read and write rates.
+    pub fn new(config: LimitsConfig) -> Self {
+        Self::with_rates(
+            (config.reads_per_second, config.read_burst as f64),
+            Some((config.writes_per_second, config.write_burst as f64)),
+        )
+    }
+
+    /// Limits each client address at the configured address rate, with one
+    /// bucket for reads and writes alike.
+    pub fn per_address(config: LimitsConfig) -> Self {
+        Self::with_rates(
+            (
+                config.address_requests_per_second,
+                config.address_burst as f64,
+            ),
+            None,
+        )
+    }
+
+    fn with_rates(read: (f64, f64), write: Option<(f64, f64)>) -> Self {
+        Self {
+            read,
+            write,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn rate(&self, class: RouteClass) -> (f64, f64) {
+        match class {
+            RouteClass::Read => self.read,
+            RouteClass::Write => self.write.unwrap_or(self.read),
+        }
+    }
+
+    /// Takes a token for the client, or returns how long until one is free.
+    pub fn acquire(&self, client: &str, class: RouteClass) -> Result<(), Duration> {
+        self.acquire_at(client, class, Instant::now())
+    }
+
+    fn acquire_at(&self, client: &str, class: RouteClass, now: Instant) -> Result<(), Duration> {
+        let class = match self.write {
+            Some(_) => class,
+            None => RouteClass::Read,
+        };
+        let (rate, burst) = self.rate(class);
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() > MAX_IDLE_BUCKETS {
+            buckets.retain(|(_, class), bucket| {
+                let (rate, burst) = self.rate(*class);
+                bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * rate < burst
+            });
+        }
+        let bucket = buckets
+            .entry((client.to_string(), class))
+            .or_insert(Bucket {
+                tokens: burst,
+                updated: now,
+            });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(burst);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+        }
+    }
+}
+
+/// Identifies the client by its principal, or by its address when it is
+/// anonymous.
+fn client_of(request: &Request) -> String {
+    if let Some(caller) = request.extensions().get::<Caller>() {
+        if caller.principal.method != AuthMethod::Anonymous {
+            return format!("principal:{}", caller.principal.name);
+        }
+    }
+    address_of(request)
+}
+
+fn address_of(request: &Request) -> String {
+    match request.extensions().get::<ConnectInfo<SocketAddr>>() {
+        Some(ConnectInfo(addr)) => format!("ip:{}", addr.ip()),
+        None => "unknown".to_string(),
+    }
+}
+
+/// Answers 429 with `Retry-After` once a client exceeds its request rate.
+/// Runs after authentication, which tells clients apart by principal.
+pub async fn rate_limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let client = client_of(&request);
+    limit(&limiter, &client, request, next).await
+}
+
+/// Like [`rate_limit`] for every request from a client address. Runs
+/// before authentication, so that failed attempts and token requests are
+/// limited too.
+pub async fn rate_limit_address(
+    State(limiter): State<Arc<RateLimiter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let client = address_of(&request);
+    limit(&limiter, &client, request, next).await
+}
+
+async fn limit(limiter: &RateLimiter, client: &str, request: Request, next: Next) -> Response {
+    let class = RouteClass::of(request.method());
+    match limiter.acquire(client, class) {
+        Ok(()) => next.run(request).await,
+        Err(wait) => (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(
+                header::RETRY_AFTER,
+                (wait.as_secs_f64().ceil() as u64).max(1).to_string(),
+            )],
+            "Error: Too many requests".to_string(),
+        )
+            .into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter() -> RateLimiter {
+        RateLimiter::new(LimitsConfig {
+            rate_limit: true,
+            reads_per_second: 10.0,
+            read_burst: 2,
+            writes_per_second: 1.0,
+            write_burst: 1,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_buckets_refill_per_client_and_class() {
+        let limiter = limiter();
+        let start = Instant::now();
+
+        assert!(limiter.acquire_at("a", RouteClass::Read, start).is_ok());
+        assert!(limiter.acquire_at("a", RouteClass::Read, start).is_ok());
+        let wait = limiter
+            .acquire_at("a", RouteClass::Read, start)
+            .unwrap_err();
+        assert_eq!(wait, Duration::from_millis(100));
+
+        // Other clients and writes have buckets of their own.
+        assert!(limiter.acquire_at("b", RouteClass::Read, start).is_ok());
+        assert!(limiter.acquire_at("a", RouteClass::Write, start).is_ok());
+        assert!(limiter.acquire_at("a", RouteClass::Write, start).is_err());
+
+        let later = start + Duration::from_millis(100);
+        assert!(limiter.acquire_at("a", RouteClass::Read, later).is_ok());
+        assert!(limiter.acquire_at("a", RouteClass::Read, later).is_err());
+    }
+
+    #[test]
+    fn test_addresses_share_one_bucket() {
+        let limiter = RateLimiter::per_address(LimitsConfig {
+            address_requests_per_second: 1.0,
+            address_burst: 2,
+            ..Default::default()
+        });
+        let start = Instant::now();
+        assert!(limiter.acquire_at("a", RouteClass::Read, start).is_ok());
+        assert!(limiter.acquire_at("a", RouteClass::Write, start).is_ok());
+        assert!(limiter.acquire_at("a", RouteClass::Write, start).is_err());
+        assert!(limiter.acquire_at("a", RouteClass::Read, start).is_err());
+    }
+
+    #[test]
+    fn test_route_class() {
+        assert_eq!(RouteClass::of(&Method::HEAD), RouteClass::Read);
+        assert_eq!(RouteClass::of(&Method::POST), RouteClass::Write);
+        assert_eq!(RouteClass::of(&Method::DELETE), RouteClass::Write);
+    }
+}
@@ -0,0 +1,117 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{error, info};
+
+/// Shutdown is triggered once, by a signal or by hand, and wakes every task
+/// waiting for it.
+#[derive(Clone)]
+pub struct Shutdown {
+    sender: Arc<watch::Sender<bool>>,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self {
+            sender: Arc::new(watch::channel(false).0),
+        }
+    }
+
+    pub fn trigger(&self) {
+        self.sender.send_replace(true);
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.sender.borrow()
+    }
+
+    /// Resolves once shutdown is triggered, at once if it already was.
+    pub fn triggered(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut receiver = self.sender.subscribe();
+        async move {
+            let _ = receiver.wait_for(|triggered| *triggered).await;
+        }
+    }
+}
+
+/// Triggers `shutdown` on SIGINT or SIGTERM.
+pub async fn trigger_on_signal(shutdown: Shutdown) {
+    let interrupt = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!(error = %e, "cannot listen for SIGINT");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(e) => {
+                error!(error = %e, "cannot listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => info!("received SIGINT, shutting down"),
+        _ = terminate => info!("received SIGTERM, shutting down"),
+    }
+    shutdown.trigger();
+}
+
+/// Runs `server`, which must itself stop accepting connections on
+/// `shutdown`, and gives its in-flight requests at most `timeout` once
+/// shutdown is triggered. Returns false when requests were cut off.
+pub async fn drain<F>(server: F, shutdown: &Shutdown, timeout: Duration) -> bool
+where
+    F: Future<Output = ()>,
+{
+    tokio::pin!(server);
+    tokio::select! {
+        _ = &mut server => return true,
+        _ = shutdown.triggered() => {}
+    }
+    tokio::time::timeout(timeout, server).await.is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_triggered_wakes_waiters() {
+        let shutdown = Shutdown::new();
+        let waiter = tokio::spawn(shutdown.triggered());
+        assert!(!shutdown.is_triggered());
+
+        shutdown.trigger();
+        waiter.await.unwrap();
+        assert!(shutdown.is_triggered());
+        // Waiting after the fact returns at once.
+        shutdown.triggered().await;
+    }
+
+    #[tokio::test]
+    async fn test_drain_waits_for_requests_up_to_the_timeout() {
+        let shutdown = Shutdown::new();
+        shutdown.trigger();
+        let finishes = tokio::time::sleep(Duration::from_millis(10));
+        assert!(drain(finishes, &shutdown, Duration::from_secs(5)).await);
+
+        let hangs = std::future::pending::<()>();
+        assert!(!drain(hangs, &shutdown, Duration::from_millis(10)).await);
+    }
+}
@@ -0,0 +1,426 @@
+use crate::config::config::TlsConfig;
+use axum::{extract::ConnectInfo, http::Request, Router};
+use hyper::body::Incoming;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto::Builder;
+use hyper_util::server::graceful::GracefulShutdown;
+use hyper_util::service::TowerToHyperService;
+use rustls::crypto::ring;
+use rustls::pki_types::CertificateDer;
+use rustls::server::WebPkiClientVerifier;
+use rustls::{RootCertStore, ServerConfig};
+use std::fs;
+use std::future::Future;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+use tower::ServiceExt;
+use tracing::{debug, error, warn};
+
+/// ClientCertificate is the verified certificate a client presented during
+/// the TLS handshake, available to handlers as a request extension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientCertificate {
+    /// Distinguished name of the certificate subject.
+    pub subject: String,
+    pub common_name: Option<String>,
+}
+
+impl ClientCertificate {
+    pub fn from_der(der: &[u8]) -> io::Result<Self> {
+        let (_, certificate) = x509_parser::parse_x509_certificate(der).map_err(|e| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid client certificate: {}", e),
+            )
+        })?;
+        let subject = certificate.subject();
+        let common_name = subject
+            .iter_common_name()
+            .next()
+            .and_then(|cn| cn.as_str().ok())
+            .map(str::to_string);
+        Ok(Self {
+            subject: subject.to_string(),
+            common_name,
+        })
+    }
+
+    /// Name of the principal the certificate authenticates: its common name,
+    /// or the whole subject when it has none.
+    pub fn principal_name(&self) -> &str {
+        self.common_name.as_deref().unwrap_or(&self.subject)
+    }
+}
+
+fn read_file(name: &str, path: &Option<PathBuf>) -> io::Result<Vec<u8>> {
+    let path = path.as_ref().ok_or_else(|| {
+        io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("{} is required when TLS is enabled", name),
+        )
+    })?;
+    read_path(path)
+}
+
+fn read_path(path: &Path) -> io::Result<Vec<u8>> {
+    fs::read(path).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))
+}
+
+fn parse_certificates(pem: &[u8]) -> io::Result<Vec<CertificateDer<'static>>> {
+    let certificates = rustls_pemfile::certs(&mut &pem[..]).collect::<Result<Vec<_>, _>>()?;
+    if certificates.is_empty() {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "No certificate found in PEM file",
+        ));
+    }
+    Ok(certificates)
+}
+
+/// The certificate, key and client CA files a configuration was built from.
+#[derive(PartialEq)]
+struct Files {
+    cert: Vec<u8>,
+    key: Vec<u8>,
+    client_ca: Option<Vec<u8>>,
+}
+
+impl Files {
+    fn read(config: &TlsConfig) -> io::Result<Self> {
+        Ok(Self {
+            cert: read_file("tls.cert_path", &config.cert_path)?,
+            key: read_file("tls.key_path", &config.key_path)?,
+            client_ca: config
+                .client_ca_path
+                .as_deref()
+                .map(read_path)
+                .transpose()?,
+        })
+    }
+
+    fn server_config(&self) -> io::Result<ServerConfig> {
+        let provider = Arc::new(ring::default_provider());
+        let builder = ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(|e| io::Error::new(ErrorKind::InvalidInput, e))?;
+        let builder = match &self.client_ca {
+            Some(pem) => {
+                let mut roots = RootCertStore::empty();
+                for certificate in parse_certificates(pem)? {
+                    roots
+                        .add(certificate)
+                        .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+                }
+                // Clients without a certificate may still use bearer tokens.
+                let verifier =
+                    WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                        .allow_unauthenticated()
+                        .build()
+                        .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+                builder.with_client_cert_verifier(verifier)
+            }
+            None => builder.with_no_client_auth(),
+        };
+        let key = rustls_pemfile::private_key(&mut &self.key[..])?.ok_or_else(|| {
+            io::Error::new(ErrorKind::InvalidData, "No private key found in PEM file")
+        })?;
+        let mut config = builder
+            .with_single_cert(parse_certificates(&self.cert)?, key)
+            .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        Ok(config)
+    }
+}
+
+/// TlsState holds the server's TLS configuration and reloads it when the
+/// certificate files change.
+pub struct TlsState {
+    config: TlsConfig,
+    current: RwLock<(Files, Arc<ServerConfig>)>,
+}
+
+impl TlsState {
+    pub fn load(config: &TlsConfig) -> io::Result<Self> {
+        let files = Files::read(config)?;
+        let server_config = Arc::new(files.server_config()?);
+        Ok(Self {
+            config: config.clone(),
+            current: RwLock::new((files, server_config)),
+        })
+    }
+
+    /// Acceptor for a new connection, using the current certificate.
+    pub fn acceptor(&self) -> TlsAcceptor {
+        TlsAcceptor::from(self.current.read().unwrap().1.clone())
+    }
+
+    /// Reloads the certificate files if they changed, returning whether they
+    /// did. Invalid files are reported and the current certificate is kept.
+    pub fn reload(&self) -> io::Result<bool> {
+        let files = Files::read(&self.config)?;
+        if self.current.read().unwrap().0 == files {
+            return Ok(false);
+        }
+        let server_config = Arc::new(files.server_config()?);
+        *self.current.write().unwrap() = (files, server_config);
+        Ok(true)
+    }
+}
+
+/// Serves the router over TLS until `signal` resolves, then stops accepting
+/// connections and waits for open ones to finish their requests. A verified
+/// client certificate is attached to each request of its connection as a
+/// [`ClientCertificate`].
+pub async fn serve<F>(listener: TcpListener, app: Router, tls: Arc<TlsState>, signal: F)
+where
+    F: Future<Output = ()>,
+{
+    let graceful = GracefulShutdown::new();
+    tokio::pin!(signal);
+    loop {
+        let (stream, peer) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(connection) => connection,
+                Err(e) => {
+                    error!(error = %e, "cannot accept connection");
+                    continue;
+                }
+            },
+            _ = &mut signal => break,
+        };
+        let acceptor = tls.acceptor();
+        let app = app.clone();
+        // Taken before the handshake so that shutdown waits for it too.
+        let watcher = graceful.watcher();
+        tokio::spawn(async move {
+            let stream = match acceptor.accept(stream).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    warn!(%peer, error = %e, "TLS handshake failed");
+                    return;
+                }
+            };
+            let certificate = match stream.get_ref().1.peer_certificates() {
+                Some([certificate, ..]) => match ClientCertificate::from_der(certificate) {
+                    Ok(certificate) => Some(certificate),
+                    Err(e) => {
+                        warn!(%peer, error = %e, "rejected client certificate");
+                        return;
+                    }
+                },
+                _ => None,
+            };
+            let service = app.map_request(move |mut request: Request<Incoming>| {
+                if let Some(certificate) = &certificate {
+                    request.extensions_mut().insert(certificate.clone());
+                }
+                request.extensions_mut().insert(ConnectInfo(peer));
+                request
+            });
+            let builder = Builder::new(TokioExecutor::new());
+            let connection =
+                builder.serve_connection(TokioIo::new(stream), TowerToHyperService::new(service));
+            if let Err(e) = watcher.watch(connection).await {
+                debug!(%peer, error = %e, "connection closed with an error");
+            }
+        });
+    }
+    drop(listener);
+    graceful.shutdown().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Extension};
+    use rcgen::{BasicConstraints, CertificateParams, DnType, IsCa, KeyPair};
+    use rustls::pki_types::{PrivateKeyDer, ServerName};
+    use rustls::ClientConfig;
+    use std::net::SocketAddr;
+    use tempfile::{tempdir, TempDir};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_rustls::TlsConnector;
+
+    struct Authority {
+        certificate: rcgen::Certificate,
+        key: KeyPair,
+    }
+
+    impl Authority {
+        fn new() -> Self {
+            let key = KeyPair::generate().unwrap();
+            let mut params = CertificateParams::new(Vec::<String>::new()).unwrap();
+            params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            params
+                .distinguished_name
+                .push(DnType::CommonName, "Test CA");
+            let certificate = params.self_signed(&key).unwrap();
+            Self { certificate, key }
+        }
+
+        /// Issues a certificate for localhost with the given common name.
+        fn issue(&self, common_name: &str) -> (rcgen::Certificate, KeyPair) {
+            let key = KeyPair::generate().unwrap();
+            let mut params = CertificateParams::new(vec!["localhost".to_string()]).unwrap();
+            params
+                .distinguished_name
+                .push(DnType::CommonName, common_name);
+            let certificate = params
+                .signed_by(&key, &self.certificate, &self.key)
+                .unwrap();
+            (certificate, key)
+        }
+    }
+
+    fn write_server_files(dir: &TempDir, ca: &Authority, common_name: &str) -> TlsConfig {
+        let (certificate, key) = ca.issue(common_name);
+        let config = TlsConfig {
+            enabled: true,
+            cert_path: Some(dir.path().join("server.pem")),
+            key_path: Some(dir.path().join("server.key")),
+            client_ca_path: Some(dir.path().join("ca.pem")),
+            reload_interval_secs: None,
+        };
+        fs::write(config.cert_path.as_ref().unwrap(), certificate.pem()).unwrap();
+        fs::write(config.key_path.as_ref().unwrap(), key.serialize_pem()).unwrap();
+        fs::write(
+            config.client_ca_path.as_ref().unwrap(),
+            ca.certificate.pem(),
+        )
+        .unwrap();
+        config
+    }
+
+    fn connector(ca: &Authority, client: Option<(rcgen::Certificate, KeyPair)>) -> TlsConnector {
+        let mut roots = RootCertStore::empty();
+        roots.add(ca.certificate.der().clone()).unwrap();
+        let builder = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots);
+        let config = match client {
+            Some((certificate, key)) => builder
+                .with_client_auth_cert(
+                    vec![certificate.der().clone()],
+                    PrivateKeyDer::try_from(key.serialize_der()).unwrap(),
+                )
+                .unwrap(),
+            None => builder.with_no_client_auth(),
+        };
+        TlsConnector::from(Arc::new(config))
+    }
+
+    async fn start(tls: Arc<TlsState>) -> SocketAddr {
+        let app = Router::new().route(
+            "/whoami",
+            get(
+                |certificate: Option<Extension<ClientCertificate>>| async move {
+                    match certificate {
+                        Some(Extension(certificate)) => certificate.principal_name().to_string(),
+                        None => "nobody".to_string(),
+                    }
+                },
+            ),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener, app, tls, std::future::pending()));
+        addr
+    }
+
+    /// Sends `GET /whoami` and returns the server certificate and the body.
+    async fn whoami(
+        addr: SocketAddr,
+        connector: &TlsConnector,
+    ) -> (CertificateDer<'static>, String) {
+        let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let mut stream = connector
+            .connect(ServerName::try_from("localhost").unwrap(), stream)
+            .await
+            .unwrap();
+        stream
+            .write_all(b"GET /whoami HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        let server_certificate = stream.get_ref().1.peer_certificates().unwrap()[0].clone();
+        assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+        let body = response.split("\r\n\r\n").nth(1).unwrap().to_string();
+        (server_certificate, body)
+    }
+
+    #[tokio::test]
+    async fn test_client_certificate_identifies_caller() {
+        let dir = tempdir().unwrap();
+        let ca = Authority::new();
+        let config = write_server_files(&dir, &ca, "catalog");
+        let addr = start(Arc::new(TlsState::load(&config).unwrap())).await;
+
+        let (_, body) = whoami(addr, &connector(&ca, Some(ca.issue("spark")))).await;
+        assert_eq!(body, "spark");
+        let (_, body) = whoami(addr, &connector(&ca, None)).await;
+        assert_eq!(body, "nobody");
+
+        // Certificates from another authority are rejected.
+        let stranger = Authority::new();
+        let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let mut stream = connector(&ca, Some(stranger.issue("mallory")))
+            .connect(ServerName::try_from("localhost").unwrap(), stream)
+            .await
+            .unwrap();
+        let _ = stream.write_all(b"GET /whoami HTTP/1.1\r\n\r\n").await;
+        let mut response = Vec::new();
+        assert!(stream.read_to_end(&mut response).await.is_err() || response.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_serve_stops_on_signal() {
+        let dir = tempdir().unwrap();
+        let ca = Authority::new();
+        let config = write_server_files(&dir, &ca, "catalog");
+        let tls = Arc::new(TlsState::load(&config).unwrap());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().route("/whoami", get(|| async { "nobody" }));
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::spawn(serve(listener, app, tls, async {
+            let _ = stopped.await;
+        }));
+
+        let (_, body) = whoami(addr, &connector(&ca, None)).await;
+        assert_eq!(body, "nobody");
+        stop.send(()).unwrap();
+        tokio::time::timeout(std::time::Duration::from_secs(5), server)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(tokio::net::TcpStream::connect(addr).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_certificate_reload() {
+        let dir = tempdir().unwrap();
+        let ca = Authority::new();
+        let config = write_server_files(&dir, &ca, "catalog");
+        let tls = Arc::new(TlsState::load(&config).unwrap());
+        let addr = start(tls.clone()).await;
+        let client = connector(&ca, None);
+        let (before, _) = whoami(addr, &client).await;
+        assert!(!tls.reload().unwrap());
+
+        write_server_files(&dir, &ca, "catalog-renewed");
+        assert!(tls.reload().unwrap());
+        let (after, _) = whoami(addr, &client).await;
+        assert_ne!(before, after);
+
+        // A broken certificate is reported and the current one kept.
+        fs::write(config.cert_path.as_ref().unwrap(), "garbage").unwrap();
+        assert!(tls.reload().is_err());
+        let (current, _) = whoami(addr, &client).await;
+        assert_eq!(current, after);
+    }
+}
@@ -0,0 +1,234 @@
+use std::io::{Error, ErrorKind};
+
+pub const MIN_PRECISION: u8 = 4;
+pub const MAX_PRECISION: u8 = 18;
+
+/// HyperLogLog is a dense HLL sketch with `2^precision` 6-bit registers,
+/// each stored in one byte.
+///
+/// The register index is taken from the top `precision` bits of a 64-bit
+/// hash and the register value is the position of the leftmost set bit in
+/// the remaining bits, which is the layout folding between precisions
+/// relies on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HyperLogLog {
+    precision: u8,
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    pub fn new(precision: u8) -> Result<Self, Error> {
+        check_precision(precision)?;
+        Ok(Self {
+            precision,
+            registers: vec![0; 1 << precision],
+        })
+    }
+
+    pub fn from_registers(precision: u8, registers: Vec<u8>) -> Result<Self, Error> {
+        check_precision(precision)?;
+        if registers.len() != 1 << precision {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "HLL of precision {} needs {} registers, got {}",
+                    precision,
+                    1 << precision,
+                    registers.len()
+                ),
+            ));
+        }
+        let max_rank = 64 - precision + 1;
+        if let Some(rank) = registers.iter().find(|rank| **rank > max_rank) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("HLL register value {} exceeds {}", rank, max_rank),
+            ));
+        }
+        Ok(Self {
+            precision,
+            registers,
+        })
+    }
+
+    /// Decodes the storage format: the precision byte followed by the registers.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        match bytes.split_first() {
+            Some((precision, registers)) => Self::from_registers(*precision, registers.to_vec()),
+            None => Err(Error::new(ErrorKind::InvalidData, "Empty HLL sketch")),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.registers.len() + 1);
+        bytes.push(self.precision);
+        bytes.extend_from_slice(&self.registers);
+        bytes
+    }
+
+    pub fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    pub fn add_hash(&mut self, hash: u64) {
+        let index = (hash >> (64 - self.precision)) as usize;
+        let rest = hash << self.precision;
+        let rank = (rest.leading_zeros() as u8 + 1).min(64 - self.precision + 1);
+        if rank > self.registers[index] {
+            self.registers[index] = rank;
+        }
+    }
+
+    /// Merges another sketch into this one by taking the register-wise
+    /// maximum. A sketch of different precision is first folded down to the
+    /// lower of the two precisions.
+    pub fn merge(&mut self, other: &HyperLogLog) {
+        if other.precision < self.precision {
+            *self = self.fold(other.precision);
+        }
+        let other = if other.precision > self.precision {
+            other.fold(self.precision)
+        } else {
+            other.clone()
+        };
+        for (register, rank) in self.registers.iter_mut().zip(other.registers) {
+            if rank > *register {
+                *register = rank;
+            }
+        }
+    }
+
+    /// Returns the equivalent sketch of a lower precision.
+    pub fn fold(&self, precision: u8) -> HyperLogLog {
+        let shift = self.precision - precision;
+        let mut folded = vec![0; 1 << precision];
+        for (index, rank) in self.registers.iter().enumerate() {
+            if *rank == 0 {
+                continue;
+            }
+            // The low `shift` bits of the old index move into the rank bits.
+            let low = index & ((1 << shift) - 1);
+            let rank = if low == 0 {
+                rank + shift
+            } else {
+                (shift - (usize::BITS - low.leading_zeros()) as u8) + 1
+            };
+            let target = &mut folded[index >> shift];
+            if rank > *target {
+                *target = rank;
+            }
+        }
+        HyperLogLog {
+            precision,
+            registers: folded,
+        }
+    }
+
+    /// Estimated number of distinct values, with linear counting for small
+    /// cardinalities.
+    pub fn estimate(&self) -> u64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|rank| 2f64.powi(-(*rank as i32)))
+            .sum();
+        let estimate = alpha * m * m / sum;
+        let zeros = self.registers.iter().filter(|rank| **rank == 0).count();
+        if estimate <= 2.5 * m && zeros > 0 {
+            (m * (m / zeros as f64).ln()).round() as u64
+        } else {
+            estimate.round() as u64
+        }
+    }
+}
+
+fn check_precision(precision: u8) -> Result<(), Error> {
+    if !(MIN_PRECISION..=MAX_PRECISION).contains(&precision) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "HLL precision must be between {} and {}, got {}",
+                MIN_PRECISION, MAX_PRECISION, precision
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // splitmix64, a cheap well-mixed hash for the tests
+    fn hash(value: u64) -> u64 {
+        let mut z = value.wrapping_add(0x9E3779B97F4A7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    fn sketch(precision: u8, values: std::ops::Range<u64>) -> HyperLogLog {
+        let mut hll = HyperLogLog::new(precision).unwrap();
+        for value in values {
+            hll.add_hash(hash(value));
+        }
+        hll
+    }
+
+    fn assert_close(estimate: u64, expected: u64) {
+        let error = (estimate as f64 - expected as f64).abs() / expected as f64;
+        assert!(error < 0.05, "estimate {} for {}", estimate, expected);
+    }
+
+    #[test]
+    fn test_estimate() {
+        assert_eq!(HyperLogLog::new(12).unwrap().estimate(), 0);
+        assert_close(sketch(12, 0..100).estimate(), 100);
+        assert_close(sketch(12, 0..100_000).estimate(), 100_000);
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut left = sketch(12, 0..60_000);
+        let right = sketch(12, 40_000..100_000);
+        left.merge(&right);
+
+        assert_eq!(left, sketch(12, 0..100_000));
+        assert_close(left.estimate(), 100_000);
+    }
+
+    #[test]
+    fn test_merge_different_precisions() {
+        let mut left = sketch(14, 0..60_000);
+        let right = sketch(10, 40_000..100_000);
+        left.merge(&right);
+
+        assert_eq!(left.precision(), 10);
+        assert_eq!(left, sketch(10, 0..100_000));
+        assert_eq!(sketch(14, 0..5_000).fold(10), sketch(10, 0..5_000));
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let hll = sketch(8, 0..1_000);
+        let decoded = HyperLogLog::from_bytes(&hll.to_bytes()).unwrap();
+
+        assert_eq!(decoded, hll);
+    }
+
+    #[test]
+    fn test_invalid_sketches() {
+        assert!(HyperLogLog::new(3).is_err());
+        assert!(HyperLogLog::new(19).is_err());
+        assert!(HyperLogLog::from_registers(4, vec![0; 15]).is_err());
+        assert!(HyperLogLog::from_registers(4, vec![70; 16]).is_err());
+        assert!(HyperLogLog::from_bytes(&[]).is_err());
+    }
+}
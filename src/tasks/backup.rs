@@ -0,0 +1,48 @@
+use crate::repository::admin::AdminRepository;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info};
+
+/// Creates a backup every `interval`; a failed backup is reported and the
+/// schedule carries on.
+pub async fn run_backup_schedule(repo: Arc<AdminRepository>, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    // The first tick completes immediately; the first backup waits a full interval.
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        let repo = repo.clone();
+        match tokio::task::spawn_blocking(move || repo.create_backup()).await {
+            Ok(Ok(backup)) => info!(backup_id = backup.backup_id, "created backup"),
+            Ok(Err(e)) => error!(error = %e, "scheduled backup failed"),
+            Err(e) => error!(error = %e, "scheduled backup failed"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::config::BackupConfig;
+    use crate::database::database::Database;
+    use std::sync::Mutex;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_backup_schedule() {
+        let dir = tempdir().unwrap();
+        let repo = Arc::new(AdminRepository::new(
+            Arc::new(Mutex::new(Database::open(dir.path().join("db")).unwrap())),
+            BackupConfig {
+                dir: Some(dir.path().join("backups")),
+                interval_secs: None,
+                keep: 2,
+            },
+        ));
+
+        let schedule = run_backup_schedule(repo.clone(), Duration::from_millis(20));
+        let _ = tokio::time::timeout(Duration::from_millis(150), schedule).await;
+
+        assert_eq!(repo.list_backups().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,19 @@
+use crate::server::tls::TlsState;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info};
+
+/// Checks the TLS certificate files every `interval` and reloads them when
+/// they change, so that renewed certificates apply without a restart.
+pub async fn run_tls_reload_schedule(tls: Arc<TlsState>, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        match tls.reload() {
+            Ok(true) => info!("reloaded the TLS certificate"),
+            Ok(false) => {}
+            Err(e) => error!(error = %e, "TLS certificate reload failed"),
+        }
+    }
+}
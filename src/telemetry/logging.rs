@@ -0,0 +1,206 @@
+use crate::config::config::{LogConfig, LogFormat};
+use axum::{
+    body::{to_bytes, Body},
+    extract::{MatchedPath, Request},
+    http::{HeaderMap, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use opentelemetry::global;
+use opentelemetry::propagation::Extractor;
+use opentelemetry::trace::{TraceContextExt, TracerProvider};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
+use std::io::{self, ErrorKind};
+use std::time::Instant;
+use tracing::{error, field, info, info_span, warn, Instrument};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+use uuid::Uuid;
+
+/// Header carrying the request ID, taken from the client when present and
+/// echoed on every response.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Telemetry owns the span exporter; shut it down to flush pending spans.
+pub struct Telemetry {
+    provider: Option<SdkTracerProvider>,
+}
+
+impl Telemetry {
+    pub fn shutdown(self) {
+        if let Some(provider) = self.provider {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Error: cannot flush spans: {}", e);
+            }
+        }
+    }
+}
+
+/// Installs the global subscriber, writing `config.format` lines at
+/// `config.level` and exporting spans when an OTLP endpoint is set.
+pub fn init(config: &LogConfig) -> io::Result<Telemetry> {
+    let filter = EnvFilter::try_new(&config.level)
+        .map_err(|e| io::Error::new(ErrorKind::InvalidInput, e))?;
+    let output = match config.format {
+        LogFormat::Text => tracing_subscriber::fmt::layer().boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .boxed(),
+    };
+    let provider = match &config.otlp_endpoint {
+        Some(endpoint) => Some(tracer_provider(endpoint, &config.service_name)?),
+        None => None,
+    };
+    let export = provider
+        .as_ref()
+        .map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer("catalog1")));
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(output)
+        .with(export)
+        .try_init()
+        .map_err(|e| io::Error::new(ErrorKind::AlreadyExists, e))?;
+    Ok(Telemetry { provider })
+}
+
+fn tracer_provider(endpoint: &str, service_name: &str) -> io::Result<SdkTracerProvider> {
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .build()
+        .map_err(io::Error::other)?;
+    // Continue the traces of callers that send a W3C traceparent header.
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(
+            Resource::builder()
+                .with_service_name(service_name.to_string())
+                .build(),
+        )
+        .build())
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+/// Reuses a well-formed request ID sent by the client, else generates one.
+fn request_id(headers: &HeaderMap) -> String {
+    headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| !id.is_empty() && id.len() <= 128)
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string())
+}
+
+/// Runs every request in a span carrying its request ID and logs its
+/// outcome, including the error message of failed requests.
+pub async fn trace_requests(request: Request, next: Next) -> Response {
+    let start = Instant::now();
+    let request_id = request_id(request.headers());
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let span = info_span!(
+        "request",
+        method = %request.method(),
+        route = %route,
+        request_id = %request_id,
+        status = field::Empty,
+    );
+    let parent = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(request.headers()))
+    });
+    if parent.span().span_context().is_valid() {
+        let _ = span.set_parent(parent);
+    }
+
+    let response = next.run(request).instrument(span.clone()).await;
+    let status = response.status();
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+    span.record("status", status.as_u16());
+
+    let (mut parts, body) = response.into_parts();
+    let body = if status.is_client_error() || status.is_server_error() {
+        // Handlers report errors as short text bodies.
+        let bytes = to_bytes(body, usize::MAX).await.unwrap_or_default();
+        let message = String::from_utf8_lossy(&bytes);
+        span.in_scope(|| {
+            if status.is_server_error() {
+                error!(status = status.as_u16(), latency_ms, error = %message, "request failed");
+            } else {
+                warn!(status = status.as_u16(), latency_ms, error = %message, "request rejected");
+            }
+        });
+        Body::from(bytes)
+    } else {
+        span.in_scope(|| info!(status = status.as_u16(), latency_ms, "request completed"));
+        body
+    };
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        parts.headers.insert(REQUEST_ID_HEADER, value);
+    }
+    Response::from_parts(parts, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use axum::{middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route("/ok", get(|| async { "ok" }))
+            .route(
+                "/missing",
+                get(|| async { (StatusCode::NOT_FOUND, "Error: missing") }),
+            )
+            .layer(middleware::from_fn(trace_requests))
+    }
+
+    #[tokio::test]
+    async fn test_request_id_is_propagated() {
+        let request = Request::get("/ok")
+            .header(REQUEST_ID_HEADER, "abc-123")
+            .body(Body::empty())
+            .unwrap();
+        let response = app().oneshot(request).await.unwrap();
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "abc-123");
+
+        let request = Request::get("/ok").body(Body::empty()).unwrap();
+        let response = app().oneshot(request).await.unwrap();
+        let generated = response.headers()[REQUEST_ID_HEADER].to_str().unwrap();
+        assert!(Uuid::parse_str(generated).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_error_body_is_kept() {
+        let request = Request::get("/missing").body(Body::empty()).unwrap();
+        let response = app().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"Error: missing");
+    }
+}
@@ -0,0 +1,239 @@
+use crate::database::database::{Database, RocksDbStats};
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use prometheus::{
+    Encoder, Gauge, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use std::io::{self, ErrorKind};
+use std::sync::{LazyLock, Mutex, MutexGuard};
+use std::time::Instant;
+
+/// Metrics of this process, exported by `/metrics` in the Prometheus text
+/// format.
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    lock_wait: HistogramVec,
+    block_cache_hits: IntGauge,
+    block_cache_misses: IntGauge,
+    block_cache_hit_ratio: Gauge,
+    block_cache_usage: IntGauge,
+    memtable_bytes: IntGaugeVec,
+    pending_compaction_bytes: IntGaugeVec,
+    sst_files: IntGaugeVec,
+}
+
+fn register<T: prometheus::core::Collector + Clone + 'static>(registry: &Registry, metric: T) -> T {
+    registry
+        .register(Box::new(metric.clone()))
+        .expect("metric names are unique");
+    metric
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry =
+            Registry::new_custom(Some("catalog".to_string()), None).expect("the prefix is valid");
+        let r = &registry;
+        Self {
+            http_requests: register(
+                r,
+                IntCounterVec::new(
+                    Opts::new("http_requests_total", "HTTP requests by route and status"),
+                    &["method", "route", "status"],
+                )
+                .unwrap(),
+            ),
+            http_request_duration: register(
+                r,
+                HistogramVec::new(
+                    HistogramOpts::new(
+                        "http_request_duration_seconds",
+                        "HTTP request latency by route",
+                    ),
+                    &["method", "route"],
+                )
+                .unwrap(),
+            ),
+            lock_wait: register(
+                r,
+                HistogramVec::new(
+                    HistogramOpts::new(
+                        "database_lock_wait_seconds",
+                        "Time spent waiting for the database lock",
+                    )
+                    .buckets(vec![
+                        0.00001, 0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0,
+                    ]),
+                    &["repository"],
+                )
+                .unwrap(),
+            ),
+            block_cache_hits: register(
+                r,
+                IntGauge::new("rocksdb_block_cache_hits", "Block cache hits").unwrap(),
+            ),
+            block_cache_misses: register(
+                r,
+                IntGauge::new("rocksdb_block_cache_misses", "Block cache misses").unwrap(),
+            ),
+            block_cache_hit_ratio: register(
+                r,
+                Gauge::new(
+                    "rocksdb_block_cache_hit_ratio",
+                    "Share of block reads served by the block cache",
+                )
+                .unwrap(),
+            ),
+            block_cache_usage: register(
+                r,
+                IntGauge::new(
+                    "rocksdb_block_cache_usage_bytes",
+                    "Memory held by the block cache",
+                )
+                .unwrap(),
+            ),
+            memtable_bytes: register(
+                r,
+                IntGaugeVec::new(
+                    Opts::new("rocksdb_memtable_bytes", "Size of all memtables"),
+                    &["column_family"],
+                )
+                .unwrap(),
+            ),
+            pending_compaction_bytes: register(
+                r,
+                IntGaugeVec::new(
+                    Opts::new(
+                        "rocksdb_pending_compaction_bytes",
+                        "Estimated bytes compaction needs to rewrite",
+                    ),
+                    &["column_family"],
+                )
+                .unwrap(),
+            ),
+            sst_files: register(
+                r,
+                IntGaugeVec::new(
+                    Opts::new("rocksdb_sst_files", "Live SST files"),
+                    &["column_family"],
+                )
+                .unwrap(),
+            ),
+            registry,
+        }
+    }
+
+    pub fn observe_request(&self, method: &str, route: &str, status: u16, seconds: f64) {
+        self.http_requests
+            .with_label_values(&[method, route, &status.to_string()])
+            .inc();
+        self.http_request_duration
+            .with_label_values(&[method, route])
+            .observe(seconds);
+    }
+
+    pub fn observe_rocksdb(&self, stats: &RocksDbStats) {
+        self.block_cache_hits.set(stats.block_cache_hits as i64);
+        self.block_cache_misses.set(stats.block_cache_misses as i64);
+        let reads = stats.block_cache_hits + stats.block_cache_misses;
+        if reads > 0 {
+            self.block_cache_hit_ratio
+                .set(stats.block_cache_hits as f64 / reads as f64);
+        }
+        self.block_cache_usage.set(stats.block_cache_usage as i64);
+        for cf in &stats.column_families {
+            let labels = [cf.name.as_str()];
+            self.memtable_bytes
+                .with_label_values(&labels)
+                .set(cf.memtable_bytes as i64);
+            self.pending_compaction_bytes
+                .with_label_values(&labels)
+                .set(cf.pending_compaction_bytes as i64);
+            self.sst_files
+                .with_label_values(&labels)
+                .set(cf.sst_files as i64);
+        }
+    }
+
+    /// Encodes every metric in the Prometheus text exposition format.
+    pub fn render(&self) -> io::Result<String> {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .map_err(io::Error::other)?;
+        String::from_utf8(buffer).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+    }
+}
+
+/// Locks the shared database, recording how long the repository waited.
+pub fn lock_database<'a>(
+    database: &'a Mutex<Database>,
+    repository: &str,
+) -> MutexGuard<'a, Database> {
+    let start = Instant::now();
+    let guard = database.lock().unwrap();
+    METRICS
+        .lock_wait
+        .with_label_values(&[repository])
+        .observe(start.elapsed().as_secs_f64());
+    guard
+}
+
+/// Counts every request and records its latency under the route it matched.
+pub async fn track_requests(request: Request, next: Next) -> Response {
+    let start = Instant::now();
+    let method = request.method().to_string();
+    // Unmatched paths share one label so that scanners cannot blow up the
+    // number of series.
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let response = next.run(request).await;
+    METRICS.observe_request(
+        &method,
+        &route,
+        response.status().as_u16(),
+        start.elapsed().as_secs_f64(),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::database::ColumnFamilyStats;
+
+    #[test]
+    fn test_render_metrics() {
+        METRICS.observe_request("GET", "/test/render", 404, 0.002);
+        METRICS.observe_rocksdb(&RocksDbStats {
+            block_cache_hits: 3,
+            block_cache_misses: 1,
+            block_cache_usage: 4096,
+            column_families: vec![ColumnFamilyStats {
+                name: "TableData".to_string(),
+                memtable_bytes: 1024,
+                pending_compaction_bytes: 0,
+                sst_files: 2,
+            }],
+        });
+
+        let text = METRICS.render().unwrap();
+        assert!(text.contains(
+            r#"catalog_http_requests_total{method="GET",route="/test/render",status="404"} 1"#
+        ));
+        assert!(text.contains("catalog_http_request_duration_seconds_bucket"));
+        assert!(text.contains("catalog_rocksdb_block_cache_hit_ratio 0.75"));
+        assert!(text.contains(r#"catalog_rocksdb_sst_files{column_family="TableData"} 2"#));
+    }
+}